@@ -1,7 +1,10 @@
 use clap::{crate_version, Parser, Subcommand};
 use std::path::PathBuf;
 #[cfg(feature = "lint")]
-pub use taplo_cli::args::LintCommand;
+pub use taplo_cli::args::{
+    CodegenCommand, CodegenLanguage, ConceptSchemaCommand, DocCommand, DocFormat, FixtureCommand,
+    LintCommand, MigrateCommand, PromptTagStyle, PromptTextFormat, RenderPromptCommand,
+};
 pub use taplo_cli::args::{Colors, FormatCommand, GeneralArgs, GetCommand, OutputFormat};
 #[cfg(feature = "lsp")]
 pub use taplo_cli::args::{LspCommand, LspCommandIo};
@@ -56,6 +59,36 @@ pub enum PlxtCommand {
     /// Extract a value from the given TOML document.
     Get(GetCommand),
 
+    /// Explain a diagnostic code, or list every code.
+    #[cfg(feature = "lint")]
+    Explain(ExplainCommand),
+
+    /// Print the concepts of an MTHDS bundle as a JSON Schema.
+    #[cfg(feature = "lint")]
+    ConceptSchema(ConceptSchemaCommand),
+
+    /// Print Python or TypeScript types for the concepts of an MTHDS bundle.
+    #[cfg(feature = "lint")]
+    Codegen(CodegenCommand),
+
+    /// Write documentation pages for the MTHDS bundles of a directory.
+    #[cfg(feature = "lint")]
+    Doc(DocCommand),
+
+    /// Print a sample input fixture for a pipe, or validate one.
+    #[cfg(feature = "lint")]
+    Fixture(FixtureCommand),
+
+    /// Render the prompts of a pipe with sample inputs.
+    #[cfg(feature = "lint")]
+    RenderPrompt(RenderPromptCommand),
+
+    /// Migrate MTHDS bundles from one MTHDS version to another.
+    ///
+    /// Files are modified in-place, keeping their comments and formatting.
+    #[cfg(feature = "lint")]
+    Migrate(MigrateCommand),
+
     /// Generate completions for plxt CLI
     #[cfg(feature = "completions")]
     Completions { shell: String },
@@ -87,6 +120,16 @@ pub struct PlxtLintCommand {
     pub schema_path: Option<PathBuf>,
 }
 
+/// Print the long-form description of a diagnostic code.
+#[cfg(feature = "lint")]
+#[derive(Clone, clap::Args)]
+pub struct ExplainCommand {
+    /// The code to explain, e.g. `mthds/undefined-pipe` or `schema/required`.
+    ///
+    /// Every code is listed when omitted.
+    pub code: Option<String>,
+}
+
 /// Pipelex-specific GeneralArgs.
 #[derive(Clone, clap::Args)]
 pub struct PlxtGeneralArgs {
@@ -0,0 +1,36 @@
+use anyhow::anyhow;
+use pipelex_common::tools::{
+    codes::{all_codes, code_description, find_code},
+    rules::find_rule,
+};
+use taplo_common::environment::Environment;
+
+use crate::{args::ExplainCommand, PlxtCli};
+
+impl<E: Environment> PlxtCli<E> {
+    pub fn execute_explain(&self, cmd: ExplainCommand) -> Result<(), anyhow::Error> {
+        let Some(code) = cmd.code else {
+            for info in all_codes() {
+                println!("{:<36} {}", info.code, info.summary);
+            }
+            return Ok(());
+        };
+
+        let info = find_code(&code).ok_or_else(|| {
+            anyhow!("unknown diagnostic code `{code}`, run `plxt explain` to list every code")
+        })?;
+
+        println!("{}: {}\n", info.code, info.summary);
+        println!("{}\n", info.explanation);
+        if let Some(rule) = find_rule(info.code) {
+            println!(
+                "Default severity: {}. Set it under [lint.rules] in plxt.toml (\"off\", \"error\", \"warning\" or \"info\").",
+                rule.default_severity.as_str()
+            );
+        }
+        if let Some(url) = code_description(info.code) {
+            println!("Documentation: {url}");
+        }
+        Ok(())
+    }
+}
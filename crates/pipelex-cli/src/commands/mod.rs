@@ -7,6 +7,8 @@ use crate::{
 };
 
 mod config;
+#[cfg(feature = "lint")]
+mod explain;
 #[cfg(feature = "lsp")]
 mod lsp;
 
@@ -46,6 +48,18 @@ impl<E: Environment> PlxtCli<E> {
             crate::args::Colors::Never => false,
         };
 
+        // Commands implemented by taplo-cli run under the global options.
+        // The MTHDS commands (`migrate`, `concept-schema`, `codegen`,
+        // `fixture`, `render-prompt`, `doc`) live there next to `lint`, whose
+        // config loading, file collection and diagnostic printing they share;
+        // their logic is in `pipelex_common::tools`.
+        let taplo_args = |cmd| TaploArgs {
+            colors: args.colors,
+            verbose: args.verbose,
+            log_spans: args.log_spans,
+            cmd,
+        };
+
         match args.cmd {
             #[cfg(feature = "completions")]
             PlxtCommand::Completions { shell } => {
@@ -63,27 +77,36 @@ impl<E: Environment> PlxtCli<E> {
                 );
                 Ok(())
             }
+            #[cfg(feature = "lint")]
+            PlxtCommand::Codegen(cmd) => {
+                self.inner
+                    .execute(taplo_args(TaploCommand::Codegen(cmd)))
+                    .await
+            }
+            #[cfg(feature = "lint")]
+            PlxtCommand::ConceptSchema(cmd) => {
+                self.inner
+                    .execute(taplo_args(TaploCommand::ConceptSchema(cmd)))
+                    .await
+            }
             PlxtCommand::Config { cmd } => self.execute_config(cmd).await,
-            PlxtCommand::Format(cmd) => {
-                // Delegate to taplo's execute_format via execute()
-                let taplo_args = TaploArgs {
-                    colors: args.colors,
-                    verbose: args.verbose,
-                    log_spans: args.log_spans,
-                    cmd: TaploCommand::Format(cmd),
-                };
-                self.inner.execute(taplo_args).await
+            #[cfg(feature = "lint")]
+            PlxtCommand::Doc(cmd) => self.inner.execute(taplo_args(TaploCommand::Doc(cmd))).await,
+            #[cfg(feature = "lint")]
+            PlxtCommand::Fixture(cmd) => {
+                self.inner
+                    .execute(taplo_args(TaploCommand::Fixture(cmd)))
+                    .await
             }
-            PlxtCommand::Get(cmd) => {
-                let taplo_args = TaploArgs {
-                    colors: args.colors,
-                    verbose: args.verbose,
-                    log_spans: args.log_spans,
-                    cmd: TaploCommand::Get(cmd),
-                };
-                self.inner.execute(taplo_args).await
+            PlxtCommand::Format(cmd) => {
+                self.inner
+                    .execute(taplo_args(TaploCommand::Format(cmd)))
+                    .await
             }
             #[cfg(feature = "lint")]
+            PlxtCommand::Explain(cmd) => self.execute_explain(cmd),
+            PlxtCommand::Get(cmd) => self.inner.execute(taplo_args(TaploCommand::Get(cmd))).await,
+            #[cfg(feature = "lint")]
             PlxtCommand::Lint(cmd) => {
                 let mut lint_cmd = cmd.inner;
                 if let Some(schema_path) = cmd.schema_path {
@@ -91,13 +114,21 @@ impl<E: Environment> PlxtCli<E> {
                 }
                 // Enable compact one-line error output for plxt when not verbose
                 self.inner.set_compact(!args.verbose);
-                let taplo_args = TaploArgs {
-                    colors: args.colors,
-                    verbose: args.verbose,
-                    log_spans: args.log_spans,
-                    cmd: TaploCommand::Lint(lint_cmd),
-                };
-                self.inner.execute(taplo_args).await
+                self.inner
+                    .execute(taplo_args(TaploCommand::Lint(lint_cmd)))
+                    .await
+            }
+            #[cfg(feature = "lint")]
+            PlxtCommand::Migrate(cmd) => {
+                self.inner
+                    .execute(taplo_args(TaploCommand::Migrate(cmd)))
+                    .await
+            }
+            #[cfg(feature = "lint")]
+            PlxtCommand::RenderPrompt(cmd) => {
+                self.inner
+                    .execute(taplo_args(TaploCommand::RenderPrompt(cmd)))
+                    .await
             }
             #[cfg(feature = "lsp")]
             PlxtCommand::Lsp { cmd } => self.execute_lsp(cmd).await,
@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A bundle whose concept references one defined in a sibling file.
+const REVIEWS: &str = r#"domain = "reviews"
+
+[concept.Review]
+description = "A customer review"
+
+[concept.Review.structure]
+rating = { type = "integer", description = "From 1 to 5", required = true }
+author = { type = "concept", concept_ref = "people.Person", description = "Who wrote it" }
+"#;
+
+const PEOPLE: &str = r#"domain = "people"
+
+[concept.Person]
+description = "A person"
+
+[concept.Person.structure]
+name = { type = "text", description = "Full name", required = true }
+"#;
+
+fn bundle_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("plxt-codegen-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("reviews.mthds"), REVIEWS).unwrap();
+    std::fs::write(dir.join("people.mthds"), PEOPLE).unwrap();
+    dir
+}
+
+fn plxt_codegen(dir: &PathBuf, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .current_dir(dir)
+        .args(["codegen", "--colors", "never", "--no-auto-config"])
+        .args(args)
+        .output()
+        .expect("failed to run plxt")
+}
+
+#[test]
+fn generates_pydantic_models() {
+    let dir = bundle_dir("python");
+    let output = plxt_codegen(&dir, &["--lang", "python", "reviews.mthds"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let python = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        python.starts_with("# Generated by `plxt codegen` from `reviews.mthds`."),
+        "got: {python}"
+    );
+    let person = python.find("class Person(BaseModel):").expect("no Person");
+    let review = python.find("class Review(BaseModel):").expect("no Review");
+    assert!(person < review, "got: {python}");
+    assert!(
+        python.contains(
+            "    author: Optional[Person] = Field(default=None, description=\"Who wrote it\")\n"
+        ),
+        "got: {python}"
+    );
+
+    // The output is deterministic.
+    let again = plxt_codegen(&dir, &["--lang", "python", "reviews.mthds"]);
+    assert_eq!(output.stdout, again.stdout);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn generates_typescript_interfaces() {
+    let dir = bundle_dir("typescript");
+    let output = plxt_codegen(&dir, &["--lang", "typescript", "reviews.mthds"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let typescript = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        typescript.contains("export interface Person {\n  /** Full name */\n  name: string;\n}\n"),
+        "got: {typescript}"
+    );
+    assert!(
+        typescript.contains("  author?: Person | null;\n"),
+        "got: {typescript}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
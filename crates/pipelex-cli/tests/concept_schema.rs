@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A bundle whose concept references one defined in a sibling file.
+const REVIEWS: &str = r#"domain = "reviews"
+
+[concept.Review]
+description = "A customer review"
+
+[concept.Review.structure]
+rating = { type = "integer", description = "From 1 to 5", required = true }
+author = { type = "concept", concept_ref = "people.Person", description = "Who wrote it" }
+"#;
+
+const PEOPLE: &str = r#"domain = "people"
+
+[concept.Person]
+description = "A person"
+
+[concept.Person.structure]
+name = { type = "text", description = "Full name", required = true }
+"#;
+
+fn bundle_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("plxt-concept-schema-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("reviews.mthds"), REVIEWS).unwrap();
+    std::fs::write(dir.join("people.mthds"), PEOPLE).unwrap();
+    dir
+}
+
+fn plxt_concept_schema(dir: &PathBuf, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .current_dir(dir)
+        .args(["concept-schema", "--colors", "never", "--no-auto-config"])
+        .args(args)
+        .output()
+        .expect("failed to run plxt")
+}
+
+#[test]
+fn prints_the_json_schema_of_a_concept() {
+    let dir = bundle_dir("concept");
+    let output = plxt_concept_schema(&dir, &["reviews.mthds", "--concept", "Review"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["$ref"], "#/$defs/Review");
+    assert_eq!(
+        schema["$defs"]["Review"]["properties"]["author"]["$ref"],
+        "#/$defs/people.Person"
+    );
+    assert_eq!(
+        schema["$defs"]["people.Person"]["required"],
+        serde_json::json!(["name"])
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unknown_concepts_are_an_error() {
+    let dir = bundle_dir("unknown");
+    let output = plxt_concept_schema(&dir, &["reviews.mthds", "--concept", "Missing"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("no concept `Missing` in the bundle"),
+        "got: {stderr}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
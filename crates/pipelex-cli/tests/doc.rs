@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const REVIEWS: &str = r#"domain = "reviews"
+description = "Review analysis"
+main_pipe = "analyze"
+
+[concept.Review]
+description = "A customer review"
+
+[pipe.analyze]
+type = "PipeSequence"
+description = "Analyze a review"
+inputs = { review = "Review" }
+output = "Text"
+steps = [{ pipe = "summarize", result = "summary" }]
+
+[pipe.summarize]
+type = "PipeLLM"
+description = "Summarize a review"
+inputs = { review = "Review" }
+output = "Text"
+prompt = "Summarize @review"
+"#;
+
+const NOTIFY: &str = r#"domain = "notify"
+
+[pipe.send]
+type = "PipeFunc"
+description = "Send a notification"
+output = "Text"
+function_name = "send"
+"#;
+
+fn bundle_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("plxt-doc-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("methods/notify")).unwrap();
+    std::fs::write(dir.join("methods/reviews.mthds"), REVIEWS).unwrap();
+    std::fs::write(dir.join("methods/notify/notify.mthds"), NOTIFY).unwrap();
+    dir
+}
+
+fn plxt_doc(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .current_dir(dir)
+        .args(["doc", "--colors", "never", "--no-auto-config"])
+        .args(args)
+        .output()
+        .expect("failed to run plxt")
+}
+
+#[test]
+fn writes_markdown_pages() {
+    let dir = bundle_dir("markdown");
+    let output = plxt_doc(&dir, &["methods", "--output", "site/docs"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let mut pages: Vec<String> = std::fs::read_dir(dir.join("site/docs"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    pages.sort();
+    assert_eq!(pages, ["index.md", "notify.md", "reviews.md"]);
+
+    let reviews = std::fs::read_to_string(dir.join("site/docs/reviews.md")).unwrap();
+    assert!(
+        reviews.contains("**Sources:** [`reviews.mthds`](../../methods/reviews.mthds)"),
+        "{reviews}"
+    );
+    assert!(reviews.contains("```mermaid\nflowchart LR\n"), "{reviews}");
+    assert!(
+        reviews.contains("Defined in [reviews.mthds:8](../../methods/reviews.mthds#L8)"),
+        "{reviews}"
+    );
+    let notify = std::fs::read_to_string(dir.join("site/docs/notify.md")).unwrap();
+    assert!(
+        notify.contains("[`notify/notify.mthds`](../../methods/notify/notify.mthds)"),
+        "{notify}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn writes_html_pages() {
+    let dir = bundle_dir("html");
+    let output = plxt_doc(&dir, &["methods", "--format", "html", "-o", "out"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let index = std::fs::read_to_string(dir.join("out/index.html")).unwrap();
+    assert!(index.starts_with("<!DOCTYPE html>"), "{index}");
+    assert!(
+        index.contains("<a href=\"reviews.html\"><code>reviews</code></a>"),
+        "{index}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,46 @@
+use std::process::Command;
+
+fn plxt_explain(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .arg("explain")
+        .args(args)
+        .output()
+        .expect("failed to run plxt")
+}
+
+#[test]
+fn explain_prints_the_long_form_description() {
+    let output = plxt_explain(&["mthds/unused-concept"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("mthds/unused-concept: A concept is defined but never referenced"),
+        "got: {stdout}"
+    );
+    assert!(stdout.contains("Default severity: info."), "got: {stdout}");
+    assert!(
+        stdout.contains("diagnostic-codes.md#mthdsunused-concept"),
+        "got: {stdout}"
+    );
+}
+
+#[test]
+fn explain_without_a_code_lists_every_stage() {
+    let output = plxt_explain(&[]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for code in [
+        "syntax/parse-error",
+        "semantic/conflicting-keys",
+        "schema/required",
+        "mthds/undefined-pipe",
+    ] {
+        assert!(stdout.contains(code), "{code} missing from: {stdout}");
+    }
+}
+
+#[test]
+fn explain_rejects_unknown_codes() {
+    let output = plxt_explain(&["--quiet", "mthds/nope"]);
+    assert!(!output.status.success());
+}
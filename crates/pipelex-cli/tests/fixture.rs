@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const BUNDLE: &str = r#"domain = "reviews"
+
+[concept.Review]
+description = "A customer review"
+
+[concept.Review.structure]
+rating = { type = "integer", description = "From 1 to 5", required = true }
+sentiment = { type = "text", choices = ["positive", "negative"] }
+
+[pipe.analyze]
+type = "PipeLLM"
+description = "Analyze a review and its photos"
+inputs = { review = "Review", photos = "Image[]" }
+output = "Text"
+"#;
+
+fn bundle_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("plxt-fixture-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("reviews.mthds"), BUNDLE).unwrap();
+    dir
+}
+
+fn plxt_fixture(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .current_dir(dir)
+        .args(["fixture", "--colors", "never", "--no-auto-config"])
+        .args(args)
+        .output()
+        .expect("failed to run plxt")
+}
+
+#[test]
+fn prints_a_fixture_that_validates() {
+    let dir = bundle_dir("roundtrip");
+    let output = plxt_fixture(&dir, &["reviews.mthds", "--pipe", "analyze"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let fixture: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(fixture["review"]["concept"], "reviews.Review");
+    assert_eq!(fixture["review"]["content"]["sentiment"], "positive");
+    assert_eq!(fixture["photos"]["concept"], "Image");
+    assert!(fixture["photos"]["content"][0]["url"].is_string());
+
+    std::fs::write(dir.join("inputs.json"), &output.stdout).unwrap();
+    let output = plxt_fixture(
+        &dir,
+        &[
+            "reviews.mthds",
+            "--pipe",
+            "analyze",
+            "--validate",
+            "inputs.json",
+        ],
+    );
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn reports_mismatching_fixtures() {
+    let dir = bundle_dir("mismatch");
+    std::fs::write(
+        dir.join("inputs.json"),
+        r#"{ "review": { "concept": "reviews.Review", "content": { "rating": "five" } } }"#,
+    )
+    .unwrap();
+    let output = plxt_fixture(
+        &dir,
+        &[
+            "reviews.mthds",
+            "--pipe",
+            "analyze",
+            "--validate",
+            "inputs.json",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("expected an integer"), "got: {stderr}");
+    assert!(stderr.contains("missing input `photos`"), "got: {stderr}");
+    assert!(
+        stderr.contains("the fixture does not match the inputs of pipe `analyze`"),
+        "got: {stderr}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
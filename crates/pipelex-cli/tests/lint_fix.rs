@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+const BUNDLE: &str = r#"domain = "d"
+
+[concept.Summary]
+description = "A summary"
+
+[pipe.review]
+type = "PipeSequence"
+description = "Review"
+output = "Sumary"
+steps = [{ pipe = "sumarize" }, { pipe = "translate" }]
+
+[pipe.summarize]
+type = "PipeLLM"
+description = "Summarize"
+output = "Summary"
+"#;
+
+/// A fresh directory holding `bundle.mthds`, so the file is a bundle of its own.
+fn bundle_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("plxt-lint-fix-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("bundle.mthds"), BUNDLE).unwrap();
+    dir
+}
+
+fn plxt_lint(dir: &PathBuf, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .current_dir(dir)
+        .args(["lint", "--quiet", "--no-auto-config"])
+        .args(args)
+        .output()
+        .expect("failed to run plxt")
+}
+
+#[test]
+fn fix_rewrites_misspelled_references_and_reports_the_rest() {
+    let dir = bundle_dir("apply");
+    let output = plxt_lint(&dir, &["--fix", "bundle.mthds"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+
+    let fixed = std::fs::read_to_string(dir.join("bundle.mthds")).unwrap();
+    assert!(fixed.contains(r#"output = "Summary""#), "got: {fixed}");
+    assert!(fixed.contains(r#"{ pipe = "summarize" }"#), "got: {fixed}");
+
+    // No close match, so no fix: still reported.
+    assert!(
+        stderr.contains(r#"warning[mthds/undefined-pipe]: pipe "translate""#),
+        "got: {stderr}"
+    );
+    assert!(!stderr.contains("sumarize"), "got: {stderr}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn lint_without_fix_leaves_the_file_alone() {
+    let dir = bundle_dir("report");
+    let output = plxt_lint(&dir, &["bundle.mthds"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(r#"pipe "sumarize""#), "got: {stderr}");
+    assert_eq!(
+        std::fs::read_to_string(dir.join("bundle.mthds")).unwrap(),
+        BUNDLE
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fix_rejects_standard_input() {
+    let dir = bundle_dir("stdin");
+    let output = plxt_lint(&dir, &["--fix", "-"]);
+    assert!(!output.status.success());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,128 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A bundle written for MTHDS 0.9, with comments the migration must keep.
+const LEGACY_BUNDLE: &str = r#"mthds_version = "0.9"
+domain = "d"
+
+[pipe.summarize]
+type = "PipeLLM"
+description = "Summarize"
+output = "Text"
+# Kept as written.
+prompt_template = "Summarize this."
+"#;
+
+/// The same bundle, migrated to MTHDS 1.0.
+const MIGRATED_BUNDLE: &str = r#"mthds_version = "1.0"
+domain = "d"
+
+[pipe.summarize]
+type = "PipeLLM"
+description = "Summarize"
+output = "Text"
+# Kept as written.
+prompt = "Summarize this."
+"#;
+
+/// A fresh directory holding `bundle.mthds` with `source`.
+fn bundle_dir(name: &str, source: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("plxt-migrate-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("bundle.mthds"), source).unwrap();
+    dir
+}
+
+fn plxt_migrate(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .current_dir(dir)
+        .args(["migrate", "--colors", "never", "--no-auto-config"])
+        .args(args)
+        .output()
+        .expect("failed to run plxt")
+}
+
+#[test]
+fn migrates_bundles_in_place() {
+    let dir = bundle_dir("in-place", LEGACY_BUNDLE);
+    let output = plxt_migrate(&dir, &[]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("bundle.mthds")).unwrap(),
+        MIGRATED_BUNDLE
+    );
+
+    // Migrating again changes nothing.
+    let output = plxt_migrate(&dir, &["--check"]);
+    assert!(output.status.success());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn check_reports_without_writing() {
+    let dir = bundle_dir("check", LEGACY_BUNDLE);
+    let output = plxt_migrate(&dir, &["--check", "--diff", "bundle.mthds"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("some files need migrating to MTHDS `1.0`"),
+        "got: {stderr}"
+    );
+    assert!(
+        stdout.contains("-prompt_template = \"Summarize this.\""),
+        "got: {stdout}"
+    );
+    assert!(
+        stdout.contains("+prompt = \"Summarize this.\""),
+        "got: {stdout}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("bundle.mthds")).unwrap(),
+        LEGACY_BUNDLE
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn undeclared_bundles_lose_deprecated_names() {
+    let undeclared = LEGACY_BUNDLE.replace("mthds_version = \"0.9\"\n", "");
+    let dir = bundle_dir("undeclared", &undeclared);
+    let output = plxt_migrate(&dir, &["--check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("some files need migrating to MTHDS `1.0`"),
+        "got: {stderr}"
+    );
+
+    let output = plxt_migrate(&dir, &[]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("bundle.mthds")).unwrap(),
+        MIGRATED_BUNDLE.replace("mthds_version = \"1.0\"\n", "")
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn downgrades_are_refused() {
+    let dir = bundle_dir("downgrade", MIGRATED_BUNDLE);
+    let output = plxt_migrate(&dir, &["--to", "0.9"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("no migration from MTHDS `1.0` to `0.9`"),
+        "got: {stderr}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,120 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A bundle written for MTHDS 0.9, which names the prompt `prompt_template`.
+const LEGACY_BUNDLE: &str = r#"domain = "d"
+
+[pipe.summarize]
+type = "PipeLLM"
+description = "Summarize"
+output = "Text"
+prompt_template = "Summarize this."
+"#;
+
+/// A fresh directory holding `bundle.mthds` with `source`.
+fn bundle_dir(name: &str, source: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("plxt-mthds-version-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("bundle.mthds"), source).unwrap();
+    dir
+}
+
+fn plxt_lint(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .current_dir(dir)
+        .args(["lint", "--quiet", "--no-auto-config"])
+        .args(args)
+        .output()
+        .expect("failed to run plxt")
+}
+
+#[test]
+fn undeclared_bundles_use_the_latest_schema() {
+    let dir = bundle_dir("latest", LEGACY_BUNDLE);
+    let output = plxt_lint(&dir, &["bundle.mthds"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // The latest schema deprecates the old field name, which only warns.
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(
+        stderr.contains(
+            "warning[schema/deprecated]: `prompt_template` is deprecated: \
+             renamed to `prompt` in MTHDS 1.0"
+        ),
+        "got: {stderr}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fix_renames_deprecated_fields() {
+    let dir = bundle_dir("fix", LEGACY_BUNDLE);
+    let output = plxt_lint(&dir, &["--fix", "bundle.mthds"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(!stderr.contains("schema/deprecated"), "got: {stderr}");
+    assert_eq!(
+        std::fs::read_to_string(dir.join("bundle.mthds")).unwrap(),
+        LEGACY_BUNDLE.replace("prompt_template", "prompt")
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn declared_version_selects_its_schema_and_is_reported_deprecated() {
+    let source = format!("mthds_version = \"0.9\"\n{LEGACY_BUNDLE}");
+    let dir = bundle_dir("declared", &source);
+    let output = plxt_lint(&dir, &["bundle.mthds"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(
+        stderr.contains("warning[mthds/deprecated-version]: MTHDS 0.9 is deprecated"),
+        "got: {stderr}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn mthds_version_flag_pins_every_bundle() {
+    let dir = bundle_dir("pinned", LEGACY_BUNDLE);
+    let output = plxt_lint(&dir, &["--mthds-version", "0.9", "bundle.mthds"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(!stderr.contains("schema/deprecated"), "got: {stderr}");
+
+    // The flag wins over the bundle's own declaration.
+    std::fs::write(
+        dir.join("bundle.mthds"),
+        format!("mthds_version = \"0.9\"\n{LEGACY_BUNDLE}"),
+    )
+    .unwrap();
+    let output = plxt_lint(&dir, &["--mthds-version", "1.0", "bundle.mthds"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("schema/deprecated"), "got: {stderr}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unknown_mthds_version_is_an_error() {
+    let dir = bundle_dir("unknown", LEGACY_BUNDLE);
+    // Without `--quiet`, which hides the error.
+    let output = Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .current_dir(&dir)
+        .args([
+            "lint",
+            "--no-auto-config",
+            "--mthds-version",
+            "7.0",
+            "bundle.mthds",
+        ])
+        .output()
+        .expect("failed to run plxt");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("unknown MTHDS version `7.0`, expected one of: 1.0, 0.9"),
+        "got: {stderr}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
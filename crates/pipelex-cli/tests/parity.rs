@@ -18,10 +18,11 @@
 //!   rule without the other drifts and fails here.
 //! - **lint:** `plxt lint --schema pipelex://mthds.schema.json -` validates stdin
 //!   against the same embedded MTHDS schema the binding uses, fully offline. The
-//!   CLI's compact one-line diagnostics (`-:L:C: error[kind]: msg (in loc)`) are
-//!   parsed back into structured form and compared as a sorted multiset against
-//!   the binding's diagnostics — coordinates, kind, message, and instance
-//!   location. This also pins the shared dedup semantics (syntax by range, schema
+//!   CLI's compact one-line diagnostics (`-:L:C: error[code]: msg (in loc)`,
+//!   e.g. `error[schema/required]` or `warning[mthds/undefined-pipe]`) are parsed
+//!   back into structured form and compared as a sorted multiset against the
+//!   binding's diagnostics — coordinates, severity, diagnostic code, message,
+//!   and instance location. This also pins the shared dedup semantics (syntax by range, schema
 //!   by coords+msg+location, semantic not deduped).
 //!
 //!   The CLI is pointed at the **builtin URL**, not at the schema *file* via
@@ -36,7 +37,7 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
-use pipelex_tools::diagnostic::{Diagnostic, DiagnosticKind};
+use pipelex_tools::diagnostic::{Diagnostic, Severity};
 use pipelex_tools::format::format_mthds_impl;
 use pipelex_tools::lint::lint_mthds_impl;
 use taplo_common::schema::builtins::MTHDS_SCHEMA_URL;
@@ -133,28 +134,31 @@ fn plxt_fmt(content: &str) -> String {
     String::from_utf8(output.stdout).expect("plxt fmt stdout is utf-8")
 }
 
-/// A diagnostic in the CLI's compact projection: position, kind keyword, and the
-/// trailing text (`msg` plus the schema `(in location)` suffix). Both the parsed
-/// CLI output and the binding's diagnostics are normalized into this shape so
-/// they can be compared directly.
+/// A diagnostic in the CLI's compact projection: position, severity, stable
+/// diagnostic code, and the trailing text (`msg` plus
+/// the schema `(in location)` suffix). Both the parsed CLI output and the
+/// binding's diagnostics are normalized into this shape so they can be compared
+/// directly.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 struct CompactDiag {
     line: usize,
     col: usize,
-    kind: String,
+    severity: String,
+    code: String,
     rest: String,
 }
 
-/// Parse one compact line `-:L:C: error[kind]: rest`. Returns `None` for the
+/// Parse one compact line `-:L:C: severity[code]: rest`. Returns `None` for the
 /// trailing `Found N error(s) in -` summary and any non-diagnostic line.
 fn parse_compact_line(line: &str) -> Option<CompactDiag> {
-    let marker = ": error[";
-    let marker_at = line.find(marker)?;
+    let (marker_at, severity) = ["error", "warning", "info"]
+        .into_iter()
+        .find_map(|severity| Some((line.find(&format!(": {severity}["))?, severity)))?;
     let (location, after) = line.split_at(marker_at);
-    let after = &after[marker.len()..];
-    let kind_end = after.find("]: ")?;
-    let kind = after[..kind_end].to_owned();
-    let rest = after[kind_end + "]: ".len()..].to_owned();
+    let after = &after[": [".len() + severity.len()..];
+    let code_end = after.find("]: ")?;
+    let code = after[..code_end].to_owned();
+    let rest = after[code_end + "]: ".len()..].to_owned();
 
     // `location` is `-:L:C` — the `-` is the stdin display path.
     let coords = location.strip_prefix("-:")?;
@@ -165,13 +169,14 @@ fn parse_compact_line(line: &str) -> Option<CompactDiag> {
     Some(CompactDiag {
         line: line_no,
         col: col_no,
-        kind,
+        severity: severity.to_owned(),
+        code,
         rest,
     })
 }
 
 /// Parse every compact diagnostic out of `plxt lint`'s stderr. Each diagnostic
-/// opens with a `-:L:C: error[kind]: ` prefix; a message that itself spans
+/// opens with a `-:L:C: error[code]: ` prefix; a message that itself spans
 /// multiple lines is emitted across several physical lines, so any line that
 /// neither opens a new diagnostic nor is the `Found N error(s)` trailer is folded
 /// back into the previous diagnostic's `rest` — keeping it equal to the binding's
@@ -194,10 +199,11 @@ fn parse_compact_diags(stderr: &str) -> Vec<CompactDiag> {
     diags
 }
 
-/// The compact trailer `Found N error(s) in <path>`, which must not be folded
-/// into the preceding diagnostic's message.
+/// The compact trailer `Found N error(s) in <path>` (or `Found N warning(s),
+/// M info(s) in <path>` after the rules stage), which must not be folded into
+/// the preceding diagnostic's message.
 fn is_summary_line(line: &str) -> bool {
-    line.starts_with("Found ") && line.contains(" error(s) in ")
+    line.starts_with("Found ") && line.contains("(s) in ")
 }
 
 /// Run `plxt lint --schema <builtin URL> -` on `content`, offline, and return the
@@ -224,8 +230,9 @@ fn plxt_lint(content: &str) -> Vec<CompactDiag> {
 
     let stderr = String::from_utf8(output.stderr).expect("plxt lint stderr is utf-8");
     let mut diags = parse_compact_diags(&stderr);
-    // A clean lint exits 0 with no diagnostics; any diagnostic means a non-zero
-    // exit. If the CLI exits non-zero yet we parsed nothing, the fault is in the
+    // A clean lint exits 0 with no diagnostics; any error-severity diagnostic
+    // means a non-zero exit (rule warnings and infos don't fail the file). If
+    // the CLI exits non-zero yet we parsed nothing, the fault is in the
     // CLI itself (the schema waterfall erroring out, a log line leaking past
     // `--quiet`, …), not a lint-parity diff — surface its stderr verbatim instead
     // of a bare boolean mismatch that would mask the cause.
@@ -236,7 +243,7 @@ fn plxt_lint(content: &str) -> Vec<CompactDiag> {
         );
     }
     assert_eq!(
-        diags.is_empty(),
+        !diags.iter().any(|diag| diag.severity == "error"),
         output.status.success(),
         "plxt lint exit/diagnostic mismatch (exit {:?}); stderr:\n{stderr}",
         output.status.code()
@@ -247,17 +254,17 @@ fn plxt_lint(content: &str) -> Vec<CompactDiag> {
 
 /// Project a binding [`Diagnostic`] into the CLI's compact shape so the two can
 /// be compared. Mirrors the compact printers in `taplo-cli/src/printing.rs`:
-/// rangeless errors render at `1:1` (the CLI fabricates that coordinate), and
-/// schema errors append ` (in <location>)`.
+/// rangeless errors render at `1:1` (the CLI fabricates that coordinate),
+/// and schema errors append ` (in <location>)`.
 fn binding_to_compact(diag: &Diagnostic) -> CompactDiag {
     let (line, col) = match &diag.range {
         Some(range) => (range.start_line, range.start_col),
         None => (1, 1),
     };
-    let kind = match diag.kind {
-        DiagnosticKind::Syntax => "syntax",
-        DiagnosticKind::Semantic => "semantic",
-        DiagnosticKind::Schema => "schema",
+    let severity = match diag.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "info",
     }
     .to_owned();
     let rest = match &diag.location {
@@ -267,7 +274,8 @@ fn binding_to_compact(diag: &Diagnostic) -> CompactDiag {
     CompactDiag {
         line,
         col,
-        kind,
+        severity,
+        code: diag.code.to_owned(),
         rest,
     }
 }
@@ -323,7 +331,7 @@ fn lint_matches_cli_on_inline_syntax_error() {
     let content = "key = ";
     let binding = binding_lint_compact(content);
     assert_eq!(binding, plxt_lint(content));
-    assert!(binding.iter().all(|diag| diag.kind == "syntax"));
+    assert!(binding.iter().all(|diag| diag.code == "syntax/parse-error"));
     assert!(!binding.is_empty());
 }
 
@@ -333,10 +341,44 @@ fn lint_matches_cli_on_inline_semantic_error() {
     let content = "a = 1\na = 2\n";
     let binding = binding_lint_compact(content);
     assert_eq!(binding, plxt_lint(content));
-    assert!(binding.iter().all(|diag| diag.kind == "semantic"));
+    assert!(binding
+        .iter()
+        .all(|diag| diag.code == "semantic/conflicting-keys"));
     assert!(!binding.is_empty());
 }
 
+#[test]
+fn lint_matches_cli_on_inline_rule_violations() {
+    // Schema-valid, but references a pipe and a concept that are not defined —
+    // both sides run the MTHDS rules stage at default severities.
+    let content = "domain = \"d\"\n\n[concept.Unused]\ndescription = \"u\"\n\n[pipe.p]\ntype = \"PipeSequence\"\ndescription = \"p\"\noutput = \"Summary\"\nsteps = [{ pipe = \"missing\", result = \"r\" }]\n";
+    let binding = binding_lint_compact(content);
+    assert_eq!(binding, plxt_lint(content));
+    let codes: Vec<&str> = binding.iter().map(|diag| diag.code.as_str()).collect();
+    assert_eq!(
+        codes,
+        [
+            "mthds/unused-concept",
+            "mthds/undefined-concept",
+            "mthds/undefined-pipe"
+        ]
+    );
+}
+
+#[test]
+fn lint_matches_cli_on_inline_suppression_comments() {
+    // The unused concept is suppressed for its table, the undefined pipe for
+    // the whole file; the stale suppression above `output` is reported.
+    let content = "# plxt-ignore-file: mthds/undefined-pipe\ndomain = \"d\"\n\n# plxt-ignore: mthds/unused-concept\n[concept.Unused]\ndescription = \"u\"\n\n[pipe.p]\ntype = \"PipeSequence\"\ndescription = \"p\"\n# plxt-ignore: mthds/unused-concept\noutput = \"Summary\"\nsteps = [{ pipe = \"missing\", result = \"r\" }]\n";
+    let binding = binding_lint_compact(content);
+    assert_eq!(binding, plxt_lint(content));
+    let codes: Vec<&str> = binding.iter().map(|diag| diag.code.as_str()).collect();
+    assert_eq!(
+        codes,
+        ["mthds/unused-suppression", "mthds/undefined-concept"]
+    );
+}
+
 #[test]
 fn format_matches_cli_when_canonicalizing_unformatted_input() {
     // A hermetic case independent of the fixtures: an unaligned pair becomes the
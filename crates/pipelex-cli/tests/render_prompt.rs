@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const BUNDLE: &str = r#"domain = "reviews"
+
+[concept.Review]
+description = "A customer review"
+
+[concept.Review.structure]
+rating = { type = "integer", description = "From 1 to 5", required = true }
+body = { type = "text", description = "The review", required = true }
+
+[pipe.analyze]
+type = "PipeLLM"
+description = "Analyze a review"
+inputs = { review = "Review" }
+output = "Text"
+system_prompt = "You analyze reviews."
+prompt = "Rated {{ review.rating }}/5: @review.body {{ review.author }}"
+"#;
+
+fn bundle_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("plxt-render-prompt-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("reviews.mthds"), BUNDLE).unwrap();
+    dir
+}
+
+fn plxt_render_prompt(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_plxt"))
+        .current_dir(dir)
+        .args(["render-prompt", "--colors", "never", "--no-auto-config"])
+        .args(args)
+        .output()
+        .expect("failed to run plxt")
+}
+
+#[test]
+fn renders_with_inputs_and_reports_undefined_fields() {
+    let dir = bundle_dir("inputs");
+    std::fs::write(
+        dir.join("inputs.json"),
+        r#"{ "review": { "concept": "reviews.Review", "content": { "rating": 4, "body": "Solid" } } }"#,
+    )
+    .unwrap();
+    let output = plxt_render_prompt(
+        &dir,
+        &[
+            "reviews.mthds",
+            "--pipe",
+            "analyze",
+            "--inputs",
+            "inputs.json",
+            "--tag-style",
+            "xml",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert_eq!(
+        stdout,
+        "--- prompt ---\nRated 4/5: <review.body>\nSolid\n</review.body> \n\n\
+         --- system_prompt ---\nYou analyze reviews.\n"
+    );
+    assert!(stderr.contains("no field `author`"), "got: {stderr}");
+    assert!(stderr.contains("reviews.mthds:16:63"), "got: {stderr}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn renders_with_placeholder_inputs() {
+    let dir = bundle_dir("placeholders");
+    std::fs::write(
+        dir.join("reviews.mthds"),
+        BUNDLE.replace(" {{ review.author }}", ""),
+    )
+    .unwrap();
+    let output = plxt_render_prompt(&dir, &["reviews.mthds", "--pipe", "analyze"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("--- prompt ---\nRated 1/5: review.body: ```\n"),
+        "got: {stdout}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
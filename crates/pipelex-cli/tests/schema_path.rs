@@ -48,7 +48,7 @@ fn schema_path_invalid_file_fails_with_schema_error() {
     assert!(!output.status.success(), "expected non-zero exit");
     let stderr = String::from_utf8_lossy(&output.stderr);
     assert!(
-        stderr.contains("error[schema]"),
+        stderr.contains("error[schema/"),
         "expected schema diagnostic in stderr, got: {stderr}"
     );
 }
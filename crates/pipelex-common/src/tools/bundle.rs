@@ -0,0 +1,511 @@
+//! A flat index of the pipe/concept definitions and references in one MTHDS
+//! document.
+//!
+//! The lint rules work on this index rather than on the raw DOM: a bundle is
+//! every `.mthds` file that shares a directory, so cross-file checks simply
+//! merge the indexes of the sibling documents. Ranges are rowan `TextRange`s
+//! into the document the index was built from.
+
+use taplo::{
+    dom::{
+        node::{DomNode, Key},
+        Node,
+    },
+    rowan::{TextRange, TextSize},
+    syntax::SyntaxKind,
+};
+use taplo_common::schema::builtins::{MthdsSchemaVersion, MTHDS_VERSION_KEY};
+
+use crate::tools::jinja::{self, pipe_templates, TemplateError, TemplateSource};
+use crate::tools::models::ModelKind;
+
+/// What a definition or reference points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RefKind {
+    Pipe,
+    Concept,
+}
+
+/// A `[pipe.<name>]` or `[concept.<name>]` definition.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub kind: RefKind,
+    pub name: String,
+    /// The range of the defining key (e.g. `name` in `[pipe.name]`).
+    pub range: TextRange,
+}
+
+/// A string value that names a pipe or a concept.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub kind: RefKind,
+    /// The string value as written (e.g. `"legal.Contract[]"`).
+    pub raw: String,
+    /// The bare name, without domain qualifier or multiplicity.
+    pub name: String,
+    /// The domain qualifier, if any (`"legal"` for `"legal.Contract[]"`).
+    pub domain: Option<String>,
+    /// The range of the string token, quotes included.
+    pub range: TextRange,
+    /// The range of the bare name inside the string, when the string is
+    /// written on one line without escapes (so `raw` maps onto it byte for
+    /// byte).
+    pub name_range: Option<TextRange>,
+}
+
+/// The `model` or `model_to_structure` of a pipe, or the `model` of the
+/// setting table it holds.
+#[derive(Debug, Clone)]
+pub struct ModelReference {
+    /// The reference as written (e.g. `"$writing-factual"`).
+    pub value: String,
+    /// The kind of model the pipe's `type` runs.
+    pub kind: Option<ModelKind>,
+    /// The range of the string token, quotes included.
+    pub range: TextRange,
+    /// The range of the value inside the quotes, when the string is written
+    /// on one line without escapes.
+    pub value_range: Option<TextRange>,
+}
+
+/// The MTHDS version a document declares: with a top-level `mthds_version`,
+/// or with a `#:schema` directive naming an embedded MTHDS schema.
+#[derive(Debug, Clone)]
+pub struct VersionDeclaration {
+    /// The version as written.
+    pub version: String,
+    /// The range of the `mthds_version` string or of the directive comment.
+    pub range: TextRange,
+}
+
+/// Every definition and reference found in one document, its model
+/// references and the syntax errors of its pipes' templates.
+#[derive(Debug, Clone, Default)]
+pub struct BundleIndex {
+    /// The document's `domain`, if declared and non-empty.
+    pub domain: Option<String>,
+    pub mthds_version: Option<VersionDeclaration>,
+    pub definitions: Vec<Definition>,
+    pub references: Vec<Reference>,
+    pub model_references: Vec<ModelReference>,
+    /// Errors found by [`jinja::parse`], with ranges into the document.
+    pub template_errors: Vec<TemplateError>,
+}
+
+impl BundleIndex {
+    /// Index the definitions and references of an MTHDS document.
+    ///
+    /// Values of an unexpected type are skipped silently: shape errors are the
+    /// schema stage's job.
+    #[must_use]
+    pub fn from_dom(dom: &Node) -> Self {
+        let mut index = Self {
+            domain: dom
+                .get("domain")
+                .as_str()
+                .map(|s| s.value().to_string())
+                .filter(|domain| !domain.is_empty()),
+            mthds_version: version_declaration(dom),
+            ..Self::default()
+        };
+
+        index.push_reference(RefKind::Pipe, &dom.get("main_pipe"));
+
+        if let Some(pipes) = dom.get("pipe").as_table() {
+            for (key, pipe) in pipes.entries().read().iter() {
+                index.push_definition(RefKind::Pipe, key);
+                index.index_pipe(pipe);
+            }
+        }
+
+        if let Some(concepts) = dom.get("concept").as_table() {
+            for (key, concept) in concepts.entries().read().iter() {
+                index.push_definition(RefKind::Concept, key);
+                index.index_concept(concept);
+            }
+        }
+
+        index
+    }
+
+    /// Whether `kind`/`name` is defined in this document.
+    #[must_use]
+    pub fn defines(&self, kind: RefKind, name: &str) -> bool {
+        self.definitions
+            .iter()
+            .any(|definition| definition.kind == kind && definition.name == name)
+    }
+
+    /// Whether any reference in this document points at `kind`/`name`.
+    #[must_use]
+    pub fn references(&self, kind: RefKind, name: &str) -> bool {
+        self.references
+            .iter()
+            .any(|reference| reference.kind == kind && reference.name == name)
+    }
+
+    /// The references to the pipes `pipe` calls: its steps and branches, its
+    /// batch branch and its outcomes.
+    #[must_use]
+    pub fn pipe_calls(pipe: &Node) -> Vec<Reference> {
+        let mut index = Self::default();
+        index.index_pipe(pipe);
+        index
+            .references
+            .into_iter()
+            .filter(|reference| reference.kind == RefKind::Pipe)
+            .collect()
+    }
+
+    fn index_pipe(&mut self, pipe: &Node) {
+        if pipe.as_table().is_none() {
+            return;
+        }
+
+        self.push_reference(RefKind::Concept, &pipe.get("output"));
+
+        if let Some(inputs) = pipe.get("inputs").as_table() {
+            for (_, input) in inputs.entries().read().iter() {
+                self.push_reference(RefKind::Concept, input);
+            }
+        }
+
+        for key in ["steps", "branches"] {
+            if let Some(items) = pipe.get(key).as_array() {
+                for item in items.items().read().iter() {
+                    self.push_reference(RefKind::Pipe, &item.get("pipe"));
+                }
+            }
+        }
+
+        self.push_reference(RefKind::Pipe, &pipe.get("branch_pipe_code"));
+
+        if let Some(outcomes) = pipe.get("outcomes").as_table() {
+            for (_, outcome) in outcomes.entries().read().iter() {
+                self.push_pipe_outcome(outcome);
+            }
+        }
+
+        self.push_pipe_outcome(&pipe.get("default_outcome"));
+
+        let kind = pipe
+            .get("type")
+            .as_str()
+            .and_then(|pipe_type| ModelKind::of_pipe_type(pipe_type.value()));
+        for key in ["model", "model_to_structure"] {
+            let model = pipe.get(key);
+            if model.as_table().is_some() {
+                self.push_model_reference(kind, &model.get("model"));
+            } else {
+                self.push_model_reference(kind, &model);
+            }
+        }
+
+        for (_, template) in pipe_templates(pipe) {
+            let Some(source) = TemplateSource::from_node(&template) else {
+                continue;
+            };
+            self.template_errors
+                .extend(
+                    jinja::parse(&source.text)
+                        .errors
+                        .into_iter()
+                        .map(|error| TemplateError {
+                            range: source.document_range(error.range),
+                            ..error
+                        }),
+                );
+        }
+    }
+
+    fn index_concept(&mut self, concept: &Node) {
+        if concept.as_table().is_none() {
+            // `Name = "description"` shorthand — nothing references out.
+            return;
+        }
+
+        self.push_reference(RefKind::Concept, &concept.get("refines"));
+
+        if let Some(structure) = concept.get("structure").as_table() {
+            for (_, field) in structure.entries().read().iter() {
+                self.push_reference(RefKind::Concept, &field.get("concept_ref"));
+                self.push_reference(RefKind::Concept, &field.get("item_concept_ref"));
+            }
+        }
+    }
+
+    /// Outcomes name a pipe, except for the `fail`/`continue` keywords.
+    fn push_pipe_outcome(&mut self, outcome: &Node) {
+        let is_keyword = outcome
+            .as_str()
+            .is_some_and(|s| matches!(s.value(), "fail" | "continue"));
+        if !is_keyword {
+            self.push_reference(RefKind::Pipe, outcome);
+        }
+    }
+
+    fn push_model_reference(&mut self, kind: Option<ModelKind>, node: &Node) {
+        let Some(value) = node.as_str() else {
+            return;
+        };
+        let Some(range) = node.text_ranges(false).next() else {
+            return;
+        };
+        let value = value.value().to_string();
+        let value_range = is_verbatim(node, &value).then(|| {
+            TextRange::at(
+                range.start() + TextSize::from(1),
+                TextSize::from(value.len() as u32),
+            )
+        });
+        self.model_references.push(ModelReference {
+            value,
+            kind,
+            range,
+            value_range,
+        });
+    }
+
+    fn push_definition(&mut self, kind: RefKind, key: &Key) {
+        let Some(range) = key.text_ranges().next() else {
+            return;
+        };
+        self.definitions.push(Definition {
+            kind,
+            name: key.value().to_string(),
+            range,
+        });
+    }
+
+    fn push_reference(&mut self, kind: RefKind, node: &Node) {
+        let Some(value) = node.as_str() else {
+            return;
+        };
+        let Some(range) = node.text_ranges(false).next() else {
+            return;
+        };
+        let raw = value.value().to_string();
+        let (domain, name) = match kind {
+            RefKind::Concept => split_concept_ref(&raw),
+            RefKind::Pipe => split_domain(&raw),
+        };
+        if name.is_empty() {
+            return;
+        }
+        let name_range = is_verbatim(node, &raw).then(|| {
+            let offset = 1 + domain.map_or(0, |domain| domain.len() + 1);
+            TextRange::at(
+                range.start() + TextSize::from(offset as u32),
+                TextSize::from(name.len() as u32),
+            )
+        });
+        self.references.push(Reference {
+            kind,
+            domain: domain.map(str::to_string),
+            name: name.to_string(),
+            raw,
+            range,
+            name_range,
+        });
+    }
+}
+
+/// The version declaration of `dom`: its first `#:schema` directive above
+/// any entry, when it names an embedded MTHDS schema, or else its top-level
+/// `mthds_version` string.
+fn version_declaration(dom: &Node) -> Option<VersionDeclaration> {
+    let root = dom.syntax()?.as_node()?.clone();
+    let directive = root
+        .children_with_tokens()
+        .take_while(|element| element.as_token().is_some())
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.kind() == SyntaxKind::COMMENT)
+        .find_map(|token| {
+            let mut words = token.text().strip_prefix("#:schema")?.split_whitespace();
+            let version = MthdsSchemaVersion::by_url(words.next()?)?;
+            Some(VersionDeclaration {
+                version: version.version.to_string(),
+                range: token.text_range(),
+            })
+        });
+    directive.or_else(|| {
+        let value = dom.get(MTHDS_VERSION_KEY);
+        Some(VersionDeclaration {
+            version: value.as_str()?.value().to_string(),
+            range: value.syntax()?.text_range(),
+        })
+    })
+}
+
+/// Whether the string `node` is written on one line as `value` between its
+/// quotes, so offsets in `value` map onto the document byte for byte.
+fn is_verbatim(node: &Node, value: &str) -> bool {
+    node.syntax().is_some_and(|syntax| {
+        let text = syntax.to_string();
+        matches!(
+            syntax.kind(),
+            SyntaxKind::STRING | SyntaxKind::STRING_LITERAL
+        ) && text.get(1..text.len().saturating_sub(1)) == Some(value)
+    })
+}
+
+/// The value of `node` when it is a string.
+#[must_use]
+pub fn string_of(node: &Node) -> Option<String> {
+    node.as_str().map(|s| s.value().to_string())
+}
+
+/// Strip domain prefix and multiplicity suffix from a concept reference string.
+///
+/// Examples:
+/// - `"Slide[]"` → `"Slide"`
+/// - `"Page[5]"` → `"Page"`
+/// - `"images.Photo"` → `"Photo"`
+/// - `"legal.Contract[]"` → `"Contract"`
+/// - `"Analysis"` → `"Analysis"` (unchanged)
+#[must_use]
+pub fn strip_concept_qualifiers(name: &str) -> String {
+    split_concept_ref(name).1.to_string()
+}
+
+/// Split a concept reference into its optional domain and its bare name.
+fn split_concept_ref(name: &str) -> (Option<&str>, &str) {
+    // Strip multiplicity suffix: everything from '[' onwards
+    let without_mult = match name.find('[') {
+        Some(pos) => &name[..pos],
+        None => name,
+    };
+    split_domain(without_mult)
+}
+
+/// Split `domain.name` at the last `.`.
+fn split_domain(name: &str) -> (Option<&str>, &str) {
+    match name.rfind('.') {
+        Some(pos) => (Some(&name[..pos]), &name[pos + 1..]),
+        None => (None, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taplo::parser;
+
+    fn index(source: &str) -> BundleIndex {
+        BundleIndex::from_dom(&parser::parse(source).into_dom())
+    }
+
+    #[test]
+    fn strip_bare_brackets() {
+        assert_eq!(strip_concept_qualifiers("[]"), "");
+    }
+
+    #[test]
+    fn strip_lone_dot() {
+        assert_eq!(strip_concept_qualifiers("."), "");
+    }
+
+    #[test]
+    fn strip_specific_count_only() {
+        assert_eq!(strip_concept_qualifiers("[5]"), "");
+    }
+
+    #[test]
+    fn strip_domain_and_brackets() {
+        assert_eq!(strip_concept_qualifiers("domain.[]"), "");
+    }
+
+    #[test]
+    fn strip_normal_concept_unchanged() {
+        assert_eq!(strip_concept_qualifiers("Analysis"), "Analysis");
+    }
+
+    #[test]
+    fn strip_indefinite_multiplicity() {
+        assert_eq!(strip_concept_qualifiers("Slide[]"), "Slide");
+    }
+
+    #[test]
+    fn strip_specific_multiplicity() {
+        assert_eq!(strip_concept_qualifiers("Page[5]"), "Page");
+    }
+
+    #[test]
+    fn strip_domain_prefix() {
+        assert_eq!(strip_concept_qualifiers("images.Photo"), "Photo");
+    }
+
+    #[test]
+    fn strip_domain_prefix_and_multiplicity() {
+        assert_eq!(strip_concept_qualifiers("legal.Contract[]"), "Contract");
+    }
+
+    #[test]
+    fn indexes_definitions_and_references() {
+        let source = r#"
+domain = "legal"
+main_pipe = "review"
+
+[concept.Contract]
+description = "A contract"
+refines = "Document"
+
+[pipe.review]
+type = "PipeSequence"
+inputs = { contract = "legal.Contract" }
+output = "Text[]"
+steps = [{ pipe = "summarize", result = "summary" }]
+
+[pipe.route]
+type = "PipeCondition"
+outcomes = { yes = "review", no = "fail" }
+default_outcome = "continue"
+"#;
+        let index = index(source);
+
+        assert_eq!(index.domain.as_deref(), Some("legal"));
+        assert!(index.defines(RefKind::Pipe, "review"));
+        assert!(index.defines(RefKind::Concept, "Contract"));
+        assert!(index.references(RefKind::Pipe, "summarize"));
+        assert!(index.references(RefKind::Concept, "Document"));
+        assert!(!index.references(RefKind::Pipe, "fail"));
+        assert!(!index.references(RefKind::Pipe, "continue"));
+
+        let contract = index
+            .references
+            .iter()
+            .find(|r| r.name == "Contract")
+            .unwrap();
+        assert_eq!(contract.domain.as_deref(), Some("legal"));
+        assert_eq!(contract.raw, "legal.Contract");
+
+        let output = index.references.iter().find(|r| r.raw == "Text[]").unwrap();
+        assert_eq!(output.name, "Text");
+        assert_eq!(output.domain, None);
+
+        assert_eq!(
+            contract.name_range.map(|range| &source[range]),
+            Some("Contract")
+        );
+    }
+
+    #[test]
+    fn indexes_the_version_declaration() {
+        let source = "mthds_version = \"0.9\"\ndomain = \"d\"\n";
+        let declared = index(source).mthds_version.unwrap();
+        assert_eq!(declared.version, "0.9");
+        assert_eq!(&source[declared.range], "\"0.9\"");
+
+        let source = "# Legacy bundle.\n#:schema pipelex://mthds-0.9.schema.json\n\nmthds_version = \"1.0\"\n";
+        let declared = index(source).mthds_version.unwrap();
+        assert_eq!(declared.version, "0.9");
+        assert_eq!(
+            &source[declared.range],
+            "#:schema pipelex://mthds-0.9.schema.json"
+        );
+
+        assert!(index("#:schema ./other.json\ndomain = \"d\"\n")
+            .mthds_version
+            .is_none());
+    }
+}
@@ -0,0 +1,690 @@
+//! Python and TypeScript types generated from MTHDS concepts.
+//!
+//! [`codegen`] turns the concepts of a bundle into Pydantic `BaseModel`
+//! classes or TypeScript interfaces, so that the code calling a method works
+//! with typed inputs and outputs.
+//!
+//! Each concept becomes one class, its `structure` fields typed from their
+//! `type` (`concept_ref` and `item_concept_ref` naming the referenced
+//! concept's class), `choices` narrowing them to a `Literal` union, and
+//! fields that are not `required` made optional. A concept inherits from the
+//! class of what it `refines`, the native `Text` when it has neither
+//! `refines` nor `structure`. The concepts the bundle's own concepts
+//! reference are generated too, native ones as `{Name}Content`, and the ones
+//! whose fields are unknown accept any extra field.
+//!
+//! The output only depends on the bundle, classes being emitted in document
+//! order after what they depend on, so it can be checked in and compared.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+use taplo::dom::Node;
+
+use super::{
+    concepts::{ConceptDef, ConceptGraph, Field, FieldType, Target},
+    native_concepts::NativeConcepts,
+};
+
+/// The language to generate types in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    /// Pydantic `BaseModel` classes.
+    Python,
+    /// TypeScript interfaces.
+    Typescript,
+}
+
+/// The types of the concepts of `document`, and of those they reference, in
+/// `language`. `siblings` are the other documents of the bundle, searched for
+/// referenced concepts; `source` names the bundle in the generated header.
+#[must_use]
+pub fn codegen(
+    document: &Node,
+    siblings: &[Node],
+    language: Language,
+    natives: &NativeConcepts,
+    source: &str,
+) -> String {
+    let graph = ConceptGraph::new(document, siblings, natives);
+    let classes = classes(&graph);
+    match language {
+        Language::Python => Python::default().module(&classes, source),
+        Language::Typescript => typescript(&classes, source),
+    }
+}
+
+/// A generated class.
+struct Class {
+    target: Target,
+    name: String,
+    concept: ConceptDef,
+    /// The class it inherits from, dropped when `refines` loops.
+    base: Option<Target>,
+}
+
+/// The classes to generate, each after its base and the classes its fields
+/// reference, unless they reference each other.
+fn classes(graph: &ConceptGraph) -> Vec<Class> {
+    let mut collector = Collector {
+        graph,
+        visited: Vec::new(),
+        classes: Vec::new(),
+    };
+    for index in 0..graph.exported {
+        collector.visit(&Target::Bundle(index));
+    }
+
+    let mut classes = collector.classes;
+    let mut taken: Vec<String> = Vec::new();
+    for class in &mut classes {
+        let (domain, name) = match &class.target {
+            Target::Bundle(index) => {
+                let concept = &graph.concepts[*index];
+                (concept.domain.clone(), concept.name.clone())
+            }
+            Target::Native(name) => (Some("native".into()), format!("{name}Content")),
+            Target::Foreign(reference) => match reference.rsplit_once('.') {
+                Some((domain, name)) => (Some(domain.to_string()), name.to_string()),
+                None => (None, reference.clone()),
+            },
+        };
+
+        let mut name = identifier(&name);
+        if taken.contains(&name) {
+            if let Some(domain) = domain {
+                name = format!("{}{name}", pascal_case(&domain));
+            }
+        }
+        let base = name.clone();
+        let mut suffix = 2;
+        while taken.contains(&name) {
+            name = format!("{base}{suffix}");
+            suffix += 1;
+        }
+        taken.push(name.clone());
+        class.name = name;
+    }
+    classes
+}
+
+struct Collector<'g, 'a> {
+    graph: &'g ConceptGraph<'a>,
+    visited: Vec<Target>,
+    classes: Vec<Class>,
+}
+
+impl Collector<'_, '_> {
+    fn visit(&mut self, target: &Target) {
+        if self.visited.contains(target) {
+            return;
+        }
+        self.visited.push(target.clone());
+
+        let concept = self.graph.concept(target);
+        let base = concept.parent.clone().filter(|parent| {
+            self.visit(parent);
+            self.classes.iter().any(|class| class.target == *parent)
+        });
+        for field in concept.fields.iter().flatten() {
+            self.visit_type(&field.ty);
+        }
+
+        self.classes.push(Class {
+            target: target.clone(),
+            name: String::new(),
+            concept,
+            base,
+        });
+    }
+
+    fn visit_type(&mut self, ty: &FieldType) {
+        match ty {
+            FieldType::Concept(target) => self.visit(target),
+            FieldType::List(Some(item)) => self.visit_type(item),
+            FieldType::Dict(key, value) => {
+                for ty in [key, value].into_iter().flatten() {
+                    self.visit_type(ty);
+                }
+            }
+            FieldType::Union(variants) => {
+                for variant in variants {
+                    self.visit_type(variant);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn class_name<'c>(classes: &'c [Class], target: &Target) -> &'c str {
+    classes
+        .iter()
+        .find(|class| class.target == *target)
+        .map_or("Any", |class| class.name.as_str())
+}
+
+/// The description of a class, explaining the ones the bundle does not
+/// define.
+fn class_description(class: &Class) -> Option<String> {
+    match &class.target {
+        Target::Foreign(reference) => Some(format!("`{reference}` is not defined in this bundle.")),
+        _ => class.concept.description.clone(),
+    }
+}
+
+/// A string literal, in the syntax both Python and TypeScript accept.
+fn string_literal(value: &str) -> String {
+    Value::from(value).to_string()
+}
+
+/// `name` with the characters identifiers do not allow replaced.
+fn identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// A Python module, collecting the imports its classes use.
+#[derive(Default)]
+struct Python {
+    /// The names imported from each module.
+    imports: BTreeMap<&'static str, BTreeSet<&'static str>>,
+}
+
+impl Python {
+    fn module(mut self, classes: &[Class], source: &str) -> String {
+        let body: Vec<String> = classes
+            .iter()
+            .map(|class| self.class(classes, class))
+            .collect();
+
+        let mut module = format!("# Generated by `plxt codegen` from `{source}`. Do not edit.\n\n");
+        module.push_str("from __future__ import annotations\n\n");
+        let (pydantic, others): (Vec<_>, Vec<_>) = self
+            .imports
+            .iter()
+            .partition(|(module, _)| **module == "pydantic");
+        for imports in [others, pydantic] {
+            if imports.is_empty() {
+                continue;
+            }
+            for (module_name, names) in imports {
+                let names: Vec<&str> = names.iter().copied().collect();
+                module.push_str(&format!("from {module_name} import {}\n", names.join(", ")));
+            }
+            module.push('\n');
+        }
+        module.push('\n');
+        module.push_str(&body.join("\n\n"));
+        module
+    }
+
+    fn import(&mut self, module: &'static str, name: &'static str) {
+        self.imports.entry(module).or_default().insert(name);
+    }
+
+    fn class(&mut self, classes: &[Class], class: &Class) -> String {
+        let base = match &class.base {
+            Some(base) => class_name(classes, base).to_string(),
+            None => {
+                self.import("pydantic", "BaseModel");
+                "BaseModel".into()
+            }
+        };
+
+        let mut lines = Vec::new();
+        if let Some(description) = class_description(class) {
+            lines.push(format!("\"\"\"{}\"\"\"", docstring(&description)));
+        }
+        if class.concept.fields.is_none() {
+            self.import("pydantic", "ConfigDict");
+            lines.push("model_config = ConfigDict(extra=\"allow\")".into());
+        }
+        let fields: Vec<String> = class
+            .concept
+            .fields
+            .iter()
+            .flatten()
+            .map(|field| self.field(classes, field))
+            .collect();
+        if !lines.is_empty() && !fields.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(fields);
+        if lines.is_empty() {
+            lines.push("pass".into());
+        }
+
+        let mut source = format!("class {}({base}):\n", class.name);
+        for line in lines {
+            if !line.is_empty() {
+                source.push_str("    ");
+                source.push_str(&line);
+            }
+            source.push('\n');
+        }
+        source
+    }
+
+    fn field(&mut self, classes: &[Class], field: &Field) -> String {
+        let mut annotation = if field.choices.is_empty() {
+            self.annotation(classes, &field.ty)
+        } else {
+            self.import("typing", "Literal");
+            let choices: Vec<String> = field.choices.iter().map(|c| string_literal(c)).collect();
+            let literal = format!("Literal[{}]", choices.join(", "));
+            match field.ty {
+                FieldType::List(_) => format!("list[{literal}]"),
+                _ => literal,
+            }
+        };
+
+        let mut arguments = Vec::new();
+        if field.required {
+            if let Some(default) = &field.default {
+                arguments.push(format!("default={}", python_literal(default)));
+            }
+        } else {
+            self.import("typing", "Optional");
+            annotation = format!("Optional[{annotation}]");
+            let default = field.default.as_ref().map_or("None".into(), python_literal);
+            arguments.push(format!("default={default}"));
+        }
+
+        let mut name = identifier(&field.name);
+        if PYTHON_KEYWORDS.contains(&name.as_str()) {
+            name.push('_');
+        }
+        if name != field.name {
+            arguments.push(format!("alias={}", string_literal(&field.name)));
+        }
+        if let Some(description) = &field.description {
+            arguments.push(format!("description={}", string_literal(description)));
+        }
+
+        if arguments.is_empty() {
+            format!("{name}: {annotation}")
+        } else {
+            self.import("pydantic", "Field");
+            format!("{name}: {annotation} = Field({})", arguments.join(", "))
+        }
+    }
+
+    fn annotation(&mut self, classes: &[Class], ty: &FieldType) -> String {
+        match ty {
+            FieldType::Text => "str".into(),
+            FieldType::Integer => "int".into(),
+            FieldType::Number => "float".into(),
+            FieldType::Boolean => "bool".into(),
+            FieldType::Date => {
+                self.import("datetime", "date");
+                "date".into()
+            }
+            FieldType::DateTime => {
+                self.import("datetime", "datetime");
+                "datetime".into()
+            }
+            FieldType::Time => {
+                self.import("datetime", "time");
+                "time".into()
+            }
+            FieldType::Concept(target) => class_name(classes, target).to_string(),
+            FieldType::List(item) => {
+                let item = match item {
+                    Some(item) => self.annotation(classes, item),
+                    None => self.annotation(classes, &FieldType::Any),
+                };
+                format!("list[{item}]")
+            }
+            FieldType::Dict(key, value) => {
+                let key = match key {
+                    Some(key) => self.annotation(classes, key),
+                    None => "str".into(),
+                };
+                let value = match value {
+                    Some(value) => self.annotation(classes, value),
+                    None => self.annotation(classes, &FieldType::Any),
+                };
+                format!("dict[{key}, {value}]")
+            }
+            FieldType::Union(variants) => {
+                self.import("typing", "Union");
+                let variants: Vec<String> = variants
+                    .iter()
+                    .map(|variant| self.annotation(classes, variant))
+                    .collect();
+                format!("Union[{}]", variants.join(", "))
+            }
+            FieldType::Any => {
+                self.import("typing", "Any");
+                "Any".into()
+            }
+        }
+    }
+}
+
+/// `text` escaped for a triple-quoted docstring.
+fn docstring(text: &str) -> String {
+    let mut text = text.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\"");
+    if text.ends_with('"') {
+        text.pop();
+        text.push_str("\\\"");
+    }
+    text
+}
+
+fn python_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "None".into(),
+        Value::Bool(true) => "True".into(),
+        Value::Bool(false) => "False".into(),
+        Value::Number(number) => number.to_string(),
+        Value::String(string) => string_literal(string),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(python_literal).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", string_literal(key), python_literal(value)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+fn typescript(classes: &[Class], source: &str) -> String {
+    let mut module = format!("// Generated by `plxt codegen` from `{source}`. Do not edit.\n");
+    for class in classes {
+        module.push('\n');
+        if let Some(description) = class_description(class) {
+            module.push_str(&jsdoc(&[description], ""));
+        }
+        module.push_str(&format!("export interface {}", class.name));
+        if let Some(base) = &class.base {
+            module.push_str(&format!(" extends {}", class_name(classes, base)));
+        }
+        let open = class.concept.fields.is_none();
+        let fields = class.concept.fields.as_deref().unwrap_or_default();
+        if fields.is_empty() && !open {
+            module.push_str(" {}\n");
+            continue;
+        }
+        module.push_str(" {\n");
+
+        for field in fields {
+            let mut doc = Vec::new();
+            doc.extend(field.description.clone());
+            if let Some(default) = &field.default {
+                doc.push(format!("@default {default}"));
+            }
+            module.push_str(&jsdoc(&doc, "  "));
+
+            let ty = if field.choices.is_empty() {
+                typescript_type(classes, &field.ty)
+            } else {
+                let choices: Vec<String> =
+                    field.choices.iter().map(|c| string_literal(c)).collect();
+                match field.ty {
+                    FieldType::List(_) => format!("({})[]", choices.join(" | ")),
+                    _ => choices.join(" | "),
+                }
+            };
+            let name = if identifier(&field.name) == field.name {
+                field.name.clone()
+            } else {
+                string_literal(&field.name)
+            };
+            if field.required {
+                module.push_str(&format!("  {name}: {ty};\n"));
+            } else {
+                module.push_str(&format!("  {name}?: {ty} | null;\n"));
+            }
+        }
+        if open {
+            module.push_str("  [key: string]: unknown;\n");
+        }
+        module.push_str("}\n");
+    }
+    module
+}
+
+fn typescript_type(classes: &[Class], ty: &FieldType) -> String {
+    match ty {
+        FieldType::Text | FieldType::Date | FieldType::DateTime | FieldType::Time => {
+            "string".into()
+        }
+        FieldType::Integer | FieldType::Number => "number".into(),
+        FieldType::Boolean => "boolean".into(),
+        FieldType::Concept(target) => class_name(classes, target).to_string(),
+        FieldType::List(item) => match item.as_deref() {
+            Some(item @ FieldType::Union(_)) => format!("({})[]", typescript_type(classes, item)),
+            Some(item) => format!("{}[]", typescript_type(classes, item)),
+            None => "unknown[]".into(),
+        },
+        FieldType::Dict(_, value) => match value {
+            Some(value) => format!("Record<string, {}>", typescript_type(classes, value)),
+            None => "Record<string, unknown>".into(),
+        },
+        FieldType::Union(variants) => {
+            let variants: Vec<String> = variants
+                .iter()
+                .map(|variant| typescript_type(classes, variant))
+                .collect();
+            variants.join(" | ")
+        }
+        FieldType::Any => "unknown".into(),
+    }
+}
+
+/// A JSDoc comment of `paragraphs`, on one line when it fits.
+fn jsdoc(paragraphs: &[String], indent: &str) -> String {
+    let paragraphs: Vec<String> = paragraphs
+        .iter()
+        .map(|paragraph| paragraph.replace("*/", "*\\/"))
+        .collect();
+    match paragraphs.as_slice() {
+        [] => String::new(),
+        [line] if !line.contains('\n') => format!("{indent}/** {line} */\n"),
+        _ => {
+            let mut doc = format!("{indent}/**\n");
+            for (index, paragraph) in paragraphs.iter().enumerate() {
+                if index > 0 {
+                    doc.push_str(&format!("{indent} *\n"));
+                }
+                for line in paragraph.lines() {
+                    doc.push_str(format!("{indent} * {line}").trim_end());
+                    doc.push('\n');
+                }
+            }
+            doc.push_str(&format!("{indent} */\n"));
+            doc
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taplo::parser;
+
+    const BUNDLE: &str = r#"domain = "reviews"
+
+[concept.Review]
+description = "A customer review"
+
+[concept.Review.structure]
+rating = { type = "integer", description = "From 1 to 5", required = true }
+sentiment = { type = "text", description = "Overall tone", choices = ["positive", "negative"], default_value = "positive" }
+published = { type = "date", description = "Publication date" }
+author = { type = "concept", concept_ref = "Author", required = true }
+photos = { type = "list", item_concept_ref = "native.Image" }
+class = { type = "text", description = "A reserved word" }
+
+[concept.Author]
+description = "The author of a review"
+refines = "Text"
+"#;
+
+    fn generate(source: &str, language: Language) -> String {
+        codegen(
+            &parser::parse(source).into_dom(),
+            &[],
+            language,
+            NativeConcepts::embedded(),
+            "reviews.mthds",
+        )
+    }
+
+    #[test]
+    fn python_models() {
+        assert_eq!(
+            generate(BUNDLE, Language::Python),
+            r#"# Generated by `plxt codegen` from `reviews.mthds`. Do not edit.
+
+from __future__ import annotations
+
+from datetime import date
+from typing import Literal, Optional
+
+from pydantic import BaseModel, Field
+
+
+class TextContent(BaseModel):
+    """Plain text content."""
+
+    text: str
+
+
+class Author(TextContent):
+    """The author of a review"""
+
+
+class ImageContent(BaseModel):
+    """An image with URL and optional metadata."""
+
+    url: str
+    filename: Optional[str] = Field(default=None)
+    caption: Optional[str] = Field(default=None)
+    mime_type: Optional[str] = Field(default=None)
+
+
+class Review(BaseModel):
+    """A customer review"""
+
+    rating: int = Field(description="From 1 to 5")
+    sentiment: Optional[Literal["positive", "negative"]] = Field(default="positive", description="Overall tone")
+    published: Optional[date] = Field(default=None, description="Publication date")
+    author: Author
+    photos: Optional[list[ImageContent]] = Field(default=None)
+    class_: Optional[str] = Field(default=None, alias="class", description="A reserved word")
+"#
+        );
+    }
+
+    #[test]
+    fn typescript_interfaces() {
+        assert_eq!(
+            generate(BUNDLE, Language::Typescript),
+            r#"// Generated by `plxt codegen` from `reviews.mthds`. Do not edit.
+
+/** Plain text content. */
+export interface TextContent {
+  text: string;
+}
+
+/** The author of a review */
+export interface Author extends TextContent {}
+
+/** An image with URL and optional metadata. */
+export interface ImageContent {
+  url: string;
+  filename?: string | null;
+  caption?: string | null;
+  mime_type?: string | null;
+}
+
+/** A customer review */
+export interface Review {
+  /** From 1 to 5 */
+  rating: number;
+  /**
+   * Overall tone
+   *
+   * @default "positive"
+   */
+  sentiment?: "positive" | "negative" | null;
+  /** Publication date */
+  published?: string | null;
+  author: Author;
+  photos?: ImageContent[] | null;
+  /** A reserved word */
+  class?: string | null;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn unknown_concepts_accept_any_field_and_names_stay_unique() {
+        let bundle = r#"domain = "shop"
+
+[concept.Order]
+description = "An order"
+
+[concept.Order.structure]
+buyer = { type = "concept", concept_ref = "crm.Customer", required = true }
+seller = { type = "concept", concept_ref = "billing.Customer", required = true }
+
+[concept.Loop]
+refines = "Cycle"
+
+[concept.Cycle]
+refines = "Loop"
+"#;
+        let python = generate(bundle, Language::Python);
+        assert!(python.contains("class Customer(BaseModel):\n    \"\"\"`crm.Customer` is not defined in this bundle.\"\"\"\n    model_config = ConfigDict(extra=\"allow\")\n"));
+        assert!(python.contains("class BillingCustomer(BaseModel):"));
+        assert!(python.contains("    buyer: Customer\n    seller: BillingCustomer\n"));
+        // A `refines` loop is cut rather than inheriting from itself.
+        assert!(python.contains("class Cycle(BaseModel):"));
+        assert!(python.contains("class Loop(Cycle):"));
+
+        let typescript = generate(bundle, Language::Typescript);
+        assert!(typescript
+            .contains("export interface BillingCustomer {\n  [key: string]: unknown;\n}\n"));
+    }
+}
@@ -0,0 +1,222 @@
+//! The registry of stable diagnostic codes and their long-form explanations.
+//!
+//! Every [`Diagnostic`](super::diagnostic::Diagnostic) carries one of these
+//! codes, prefixed by the stage that produced it:
+//!
+//! - `syntax/…` — taplo's parser ([`taplo::parser::Error::CODE`]);
+//! - `semantic/…` — DOM validation ([`taplo::dom::Error::code`]);
+//! - `schema/…` — the failed JSON Schema keyword
+//!   ([`NodeValidationError::code`](taplo_common::schema::NodeValidationError::code));
+//! - `mthds/…` — the ids of the [`rules`](super::rules) registry.
+//!
+//! A code never changes meaning once published, so clients can match on it
+//! instead of on the message text. Each one has a section in
+//! `docs/guide/diagnostic-codes.md`, which is what [`code_description`] links
+//! to; `plxt explain <code>` prints the same explanation offline.
+
+use crate::tools::rules::RULES;
+
+/// Where every code is documented, one heading per code.
+pub const DOCS_URL: &str =
+    "https://github.com/Pipelex/vscode-pipelex/blob/main/docs/guide/diagnostic-codes.md";
+
+/// A documented diagnostic code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeInfo {
+    pub code: &'static str,
+    /// One-line description.
+    pub summary: &'static str,
+    /// Long-form description, as printed by `plxt explain`.
+    pub explanation: &'static str,
+}
+
+/// The codes of the syntax, semantic and schema stages. Rule codes come from
+/// the rule registry.
+pub static STAGE_CODES: &[CodeInfo] = &[
+    CodeInfo {
+        code: "syntax/parse-error",
+        summary: "The document is not well-formed TOML.",
+        explanation: "The parser could not read the document as TOML: a missing `=` or closing \
+bracket, an unterminated string, an invalid escape or number, and so on. The message says what \
+was expected. Nothing else is checked until the document parses.",
+    },
+    CodeInfo {
+        code: "semantic/unexpected-syntax",
+        summary: "A syntax element appears where TOML does not allow it.",
+        explanation: "The document parsed, but contains a construct that cannot be turned into a \
+TOML value at that position.",
+    },
+    CodeInfo {
+        code: "semantic/invalid-escape-sequence",
+        summary: "A string contains an invalid escape sequence.",
+        explanation: "Basic strings only allow the TOML escapes (`\\b`, `\\t`, `\\n`, `\\f`, \
+`\\r`, `\\\"`, `\\\\`, `\\uXXXX`, `\\UXXXXXXXX`). Use a literal string ('…') to keep backslashes \
+as written.",
+    },
+    CodeInfo {
+        code: "semantic/conflicting-keys",
+        summary: "The same key is defined twice.",
+        explanation: "A key, or a table header, was defined more than once in the same table. \
+TOML forbids redefinition: merge the two definitions into one.",
+    },
+    CodeInfo {
+        code: "semantic/expected-table",
+        summary: "A key is used as a table but holds another value.",
+        explanation: "A dotted key or table header extends a key that was already given a \
+non-table value, e.g. `a = 1` followed by `[a.b]`.",
+    },
+    CodeInfo {
+        code: "semantic/expected-array-of-tables",
+        summary: "A key is used as an array of tables but holds another value.",
+        explanation: "An `[[array]]` header extends a key that was already defined as a \
+regular table or value.",
+    },
+    CodeInfo {
+        code: "semantic/invalid-query",
+        summary: "A key could not be resolved in the document.",
+        explanation: "An internal lookup into the document failed. This should not happen \
+for a parsed document; please report it with the file that triggers it.",
+    },
+    CodeInfo {
+        code: "schema/type",
+        summary: "A value has the wrong type.",
+        explanation: "The schema expects a different type here, e.g. a string where a table \
+was given. The message names the expected type.",
+    },
+    CodeInfo {
+        code: "schema/enum",
+        summary: "A value is not one of the allowed values.",
+        explanation: "The schema only allows a fixed set of values here, such as the pipe \
+`type`. The message lists them.",
+    },
+    CodeInfo {
+        code: "schema/required",
+        summary: "A required key is missing.",
+        explanation: "The table is missing a key the schema requires, e.g. a pipe without an \
+`output`.",
+    },
+    CodeInfo {
+        code: "schema/additional-properties",
+        summary: "A key is not allowed here.",
+        explanation: "The schema does not define this key for the table, which is most often \
+a typo or a key that belongs to another pipe type.",
+    },
+    CodeInfo {
+        code: "schema/no-match",
+        summary: "A value matches none of the allowed shapes.",
+        explanation: "The schema accepts several shapes here (for instance the different pipe \
+types) and the value matches none of them. When one shape is clearly intended, its specific \
+errors are reported instead.",
+    },
+    CodeInfo {
+        code: "schema/pattern",
+        summary: "A string does not match the required pattern.",
+        explanation: "The schema constrains this string with a regular expression, such as a \
+naming convention. The message shows the pattern.",
+    },
+    CodeInfo {
+        code: "schema/format",
+        summary: "A string is not in the required format.",
+        explanation: "The schema expects a specific string format here, such as a date, a URI \
+or a semantic version.",
+    },
+    CodeInfo {
+        code: "schema/bounds",
+        summary: "A value is outside its allowed bounds.",
+        explanation: "A number, string length, array length or table size is outside the \
+minimum or maximum the schema allows, or an array that must hold unique items has duplicates.",
+    },
+    CodeInfo {
+        code: "schema/invalid",
+        summary: "A value violates the schema.",
+        explanation: "A schema constraint without a more specific code failed. The message \
+names the constraint.",
+    },
+    CodeInfo {
+        code: "schema/deprecated",
+        summary: "A key or value is deprecated.",
+        explanation: "The schema still accepts this key or value but marks it deprecated, \
+usually because it was renamed. The message says what to use instead, and a fix renames it \
+when the schema names a replacement. Deprecations are warnings: they do not fail `plxt lint`.",
+    },
+    CodeInfo {
+        code: "schema/validation-failed",
+        summary: "Schema validation could not be completed.",
+        explanation: "The validator failed, or produced errors that could not be mapped back \
+to positions in the document. The message carries the underlying error.",
+    },
+];
+
+/// Look up a code, among the stage codes and the rule ids.
+#[must_use]
+pub fn find_code(code: &str) -> Option<CodeInfo> {
+    all_codes().find(|info| info.code == code)
+}
+
+/// Every documented code: the stage codes, then the rules in registry order.
+pub fn all_codes() -> impl Iterator<Item = CodeInfo> {
+    STAGE_CODES
+        .iter()
+        .copied()
+        .chain(RULES.iter().map(|rule| CodeInfo {
+            code: rule.id,
+            summary: rule.summary,
+            explanation: rule.explanation,
+        }))
+}
+
+/// The documentation URL of a registered code.
+#[must_use]
+pub fn code_description(code: &str) -> Option<String> {
+    find_code(code).map(|info| format!("{DOCS_URL}#{}", anchor(info.code)))
+}
+
+/// GitHub's anchor for a `` ### `code` `` heading: lowercased, with
+/// punctuation other than `-` and `_` dropped.
+fn anchor(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn codes_are_unique_and_prefixed_by_stage() {
+        let mut seen = HashSet::new();
+        for info in all_codes() {
+            assert!(seen.insert(info.code), "{} is registered twice", info.code);
+            let (stage, _) = info.code.split_once('/').unwrap();
+            assert!(
+                ["syntax", "semantic", "schema", "mthds"].contains(&stage),
+                "{} has an unknown stage prefix",
+                info.code
+            );
+        }
+    }
+
+    #[test]
+    fn every_code_is_documented() {
+        let docs = include_str!("../../../../docs/guide/diagnostic-codes.md");
+        for info in all_codes() {
+            assert!(
+                docs.contains(&format!("### `{}`\n", info.code)),
+                "{} has no section in docs/guide/diagnostic-codes.md",
+                info.code
+            );
+        }
+    }
+
+    #[test]
+    fn code_description_links_to_the_heading() {
+        assert_eq!(
+            code_description("mthds/undefined-pipe").as_deref(),
+            Some(&*format!("{DOCS_URL}#mthdsundefined-pipe"))
+        );
+        assert_eq!(code_description("mthds/nope"), None);
+    }
+}
@@ -0,0 +1,356 @@
+//! JSON Schema export of MTHDS concepts.
+//!
+//! Consumers of a method's outputs outside of Pipelex need formal types:
+//! [`concept_schema`] translates the concepts of a bundle into a JSON Schema
+//! (draft 2020-12) document with one `$defs` entry per concept.
+//!
+//! A `[concept.X.structure]` becomes a closed object: each field's `type`
+//! maps to a JSON type (`date`, `datetime` and `time` to formatted strings),
+//! `choices` to an `enum`, `default_value` to a `default`, `required` fields
+//! are listed, `concept_ref` and `item_concept_ref` point at the referenced
+//! concept's definition, and `list` / `dict` fields carry their item and
+//! value types. A concept without a structure has the fields of what it
+//! `refines` (the native `Text` when it refines nothing), and a structure on
+//! top of `refines` extends them. Native concepts are translated from the
+//! [`NativeConcepts`] registry. Concepts the bundle does not define are left
+//! open.
+
+use anyhow::bail;
+use serde_json::{json, Map, Value};
+use taplo::{dom::Node, parser};
+
+use super::{
+    bundle::string_of,
+    concepts::{ConceptGraph, FieldType, Target, MAX_REFINES_DEPTH},
+    native_concepts::NativeConcepts,
+};
+
+/// The JSON Schema dialect of the exported documents.
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// The JSON Schema of the concepts of `document`, or only of `concept` and
+/// the concepts it references. `siblings` are the other documents of the
+/// bundle, searched for referenced concepts.
+///
+/// # Errors
+///
+/// If `concept` is neither defined in the bundle nor a native concept.
+pub fn concept_schema(
+    document: &Node,
+    siblings: &[Node],
+    concept: Option<&str>,
+    natives: &NativeConcepts,
+) -> anyhow::Result<Value> {
+    let mut exporter = Exporter {
+        graph: ConceptGraph::new(document, siblings, natives),
+        defs: Map::new(),
+        queue: Vec::new(),
+    };
+
+    let mut schema = Map::new();
+    schema.insert("$schema".into(), JSON_SCHEMA_DIALECT.into());
+    match concept {
+        Some(name) => {
+            let target = exporter
+                .graph
+                .resolve(name, exporter.graph.domain.as_deref());
+            if matches!(target, Target::Foreign(_)) {
+                bail!("no concept `{name}` in the bundle");
+            }
+            if let Value::Object(reference) = exporter.reference(target) {
+                schema.extend(reference);
+            }
+        }
+        None => {
+            if let Some(domain) = &exporter.graph.domain {
+                schema.insert("title".into(), domain.clone().into());
+            }
+            if let Some(description) = string_of(&document.get("description")) {
+                schema.insert("description".into(), description.into());
+            }
+            for index in 0..exporter.graph.exported {
+                exporter.reference(Target::Bundle(index));
+            }
+        }
+    }
+
+    exporter.define_queued();
+    schema.insert("$defs".into(), Value::Object(exporter.defs));
+    Ok(Value::Object(schema))
+}
+
+/// [`concept_schema`] over a single document with the embedded native
+/// concepts, for the bindings.
+///
+/// # Errors
+///
+/// If `content` has syntax errors or `concept` is not defined.
+pub fn concept_schema_impl(content: &str, concept: Option<&str>) -> anyhow::Result<Value> {
+    let parse = parser::parse(content);
+    if let Some(error) = parse.errors.first() {
+        bail!("the document has syntax errors: {}", error.message);
+    }
+    concept_schema(&parse.into_dom(), &[], concept, NativeConcepts::embedded())
+}
+
+struct Exporter<'a> {
+    graph: ConceptGraph<'a>,
+    defs: Map<String, Value>,
+    /// The referenced concepts, in order, defined or still to define.
+    queue: Vec<Target>,
+}
+
+/// The properties of an object schema being built.
+#[derive(Default)]
+struct Fields {
+    properties: Map<String, Value>,
+    required: Vec<String>,
+    /// Whether the concept may hold fields its schema does not list.
+    open: bool,
+}
+
+impl Exporter<'_> {
+    /// A `$ref` to the definition of `target`, queued to be defined.
+    fn reference(&mut self, target: Target) -> Value {
+        let name = self.graph.qualified_name(&target);
+        if !self.queue.contains(&target) {
+            self.queue.push(target);
+        }
+        json!({ "$ref": format!("#/$defs/{name}") })
+    }
+
+    /// Define every queued concept, including those their definitions
+    /// reference in turn.
+    fn define_queued(&mut self) {
+        let mut next = 0;
+        while let Some(target) = self.queue.get(next).cloned() {
+            let name = self.graph.qualified_name(&target);
+            let definition = self.definition(&target);
+            self.defs.insert(name, definition);
+            next += 1;
+        }
+    }
+
+    fn definition(&mut self, target: &Target) -> Value {
+        let concept = self.graph.concept(target);
+        let description = match target {
+            Target::Foreign(reference) => {
+                Some(format!("`{reference}` is not defined in this bundle."))
+            }
+            _ => concept.description,
+        };
+
+        let mut fields = Fields::default();
+        self.inherit(target, &mut fields, 0);
+
+        let mut definition = Map::new();
+        definition.insert("title".into(), concept.name.into());
+        if let Some(description) = description {
+            definition.insert("description".into(), description.into());
+        }
+        if fields.open && fields.properties.is_empty() {
+            return Value::Object(definition);
+        }
+        definition.insert("type".into(), "object".into());
+        definition.insert("properties".into(), Value::Object(fields.properties));
+        if !fields.required.is_empty() {
+            definition.insert("required".into(), fields.required.into());
+        }
+        if !fields.open {
+            definition.insert("additionalProperties".into(), false.into());
+        }
+        Value::Object(definition)
+    }
+
+    /// Add the fields of `target` to `fields`: those of what it refines
+    /// first, then its own.
+    fn inherit(&mut self, target: &Target, fields: &mut Fields, depth: usize) {
+        if depth > MAX_REFINES_DEPTH {
+            fields.open = true;
+            return;
+        }
+        let concept = self.graph.concept(target);
+        if let Some(parent) = &concept.parent {
+            self.inherit(parent, fields, depth + 1);
+        }
+        let Some(own) = concept.fields else {
+            fields.open = true;
+            return;
+        };
+
+        for field in own {
+            let mut schema = self.field_type(&field.ty);
+            if let Value::Object(schema) = &mut schema {
+                if let Some(description) = field.description {
+                    schema.insert("description".into(), description.into());
+                }
+                if !field.choices.is_empty() {
+                    schema.insert("enum".into(), field.choices.into());
+                }
+                if let Some(default) = field.default {
+                    schema.insert("default".into(), default);
+                }
+            }
+
+            fields.properties.insert(field.name.clone(), schema);
+            fields.required.retain(|name| *name != field.name);
+            if field.required {
+                fields.required.push(field.name);
+            }
+        }
+    }
+
+    fn field_type(&mut self, ty: &FieldType) -> Value {
+        match ty {
+            FieldType::Text => json!({ "type": "string" }),
+            FieldType::Integer => json!({ "type": "integer" }),
+            FieldType::Number => json!({ "type": "number" }),
+            FieldType::Boolean => json!({ "type": "boolean" }),
+            FieldType::Date => json!({ "type": "string", "format": "date" }),
+            FieldType::DateTime => json!({ "type": "string", "format": "date-time" }),
+            FieldType::Time => json!({ "type": "string", "format": "time" }),
+            FieldType::Concept(target) => self.reference(target.clone()),
+            FieldType::List(item) => match item {
+                Some(item) => json!({ "type": "array", "items": self.field_type(item) }),
+                None => json!({ "type": "array", "items": {} }),
+            },
+            FieldType::Dict(_, value) => match value {
+                Some(value) => {
+                    json!({ "type": "object", "additionalProperties": self.field_type(value) })
+                }
+                None => json!({ "type": "object" }),
+            },
+            FieldType::Union(variants) => {
+                let variants: Vec<Value> = variants
+                    .iter()
+                    .map(|variant| self.field_type(variant))
+                    .collect();
+                json!({ "anyOf": variants })
+            }
+            FieldType::Any => json!({}),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLE: &str = r#"domain = "reviews"
+description = "Review analysis"
+
+[concept.Review]
+description = "A customer review"
+
+[concept.Review.structure]
+summary = "A one-line summary"
+rating = { type = "integer", description = "From 1 to 5", required = true }
+sentiment = { type = "text", description = "Overall tone", choices = ["positive", "negative"], default_value = "positive" }
+published = { type = "date", description = "Publication date" }
+author = { type = "concept", concept_ref = "Author", description = "Who wrote it", required = true }
+photos = { type = "list", item_concept_ref = "native.Image", description = "Attached photos" }
+tags = { type = "list", item_type = "text", description = "Tags" }
+scores = { type = "dict", key_type = "text", value_type = "number", description = "Scores by aspect" }
+
+[concept.Author]
+description = "The author of a review"
+refines = "Text"
+
+[concept.Verdict]
+description = "A free-form verdict"
+"#;
+
+    fn schema(concept: Option<&str>) -> Value {
+        concept_schema_impl(BUNDLE, concept).unwrap()
+    }
+
+    #[test]
+    fn structures_become_closed_objects() {
+        let schema = schema(None);
+        assert_eq!(schema["$schema"], JSON_SCHEMA_DIALECT);
+        assert_eq!(schema["title"], "reviews");
+        assert_eq!(
+            schema["$defs"]["Review"],
+            json!({
+                "title": "Review",
+                "description": "A customer review",
+                "type": "object",
+                "properties": {
+                    "summary": { "type": "string", "description": "A one-line summary" },
+                    "rating": { "type": "integer", "description": "From 1 to 5" },
+                    "sentiment": {
+                        "type": "string",
+                        "description": "Overall tone",
+                        "enum": ["positive", "negative"],
+                        "default": "positive"
+                    },
+                    "published": { "type": "string", "format": "date", "description": "Publication date" },
+                    "author": { "$ref": "#/$defs/Author", "description": "Who wrote it" },
+                    "photos": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/native.Image" },
+                        "description": "Attached photos"
+                    },
+                    "tags": { "type": "array", "items": { "type": "string" }, "description": "Tags" },
+                    "scores": {
+                        "type": "object",
+                        "additionalProperties": { "type": "number" },
+                        "description": "Scores by aspect"
+                    }
+                },
+                "required": ["rating", "author"],
+                "additionalProperties": false
+            })
+        );
+    }
+
+    #[test]
+    fn concepts_without_structure_have_the_fields_they_refine() {
+        let schema = schema(None);
+        let text_field = json!({ "text": { "type": "string" } });
+        assert_eq!(schema["$defs"]["Author"]["properties"], text_field);
+        assert_eq!(schema["$defs"]["Verdict"]["properties"], text_field);
+        assert_eq!(schema["$defs"]["Verdict"]["required"], json!(["text"]));
+
+        let image = &schema["$defs"]["native.Image"];
+        assert_eq!(image["required"], json!(["url"]));
+        assert_eq!(image["properties"]["caption"], json!({ "type": "string" }));
+    }
+
+    #[test]
+    fn a_single_concept_brings_what_it_references() {
+        let schema = schema(Some("Author"));
+        assert_eq!(schema["$ref"], "#/$defs/Author");
+        let defs: Vec<&String> = schema["$defs"].as_object().unwrap().keys().collect();
+        assert_eq!(defs, ["Author"]);
+
+        let schema = concept_schema_impl(BUNDLE, Some("Review")).unwrap();
+        let defs: Vec<&String> = schema["$defs"].as_object().unwrap().keys().collect();
+        assert_eq!(defs, ["Review", "Author", "native.Image"]);
+
+        assert!(concept_schema_impl(BUNDLE, Some("Missing"))
+            .unwrap_err()
+            .to_string()
+            .contains("no concept `Missing`"));
+    }
+
+    #[test]
+    fn native_types_are_translated() {
+        let schema = concept_schema_impl("", Some("native.Page")).unwrap();
+        let page = &schema["$defs"]["native.Page"];
+        assert_eq!(
+            page["properties"]["text_and_images"],
+            json!({ "$ref": "#/$defs/native.TextAndImages" })
+        );
+        assert_eq!(page["required"], json!(["text_and_images"]));
+
+        let number = concept_schema_impl("", Some("Number")).unwrap();
+        assert_eq!(
+            number["$defs"]["native.Number"]["properties"]["number"],
+            json!({ "anyOf": [{ "type": "integer" }, { "type": "number" }] })
+        );
+
+        let anything = concept_schema_impl("", Some("Anything")).unwrap();
+        assert!(anything["$defs"]["native.Anything"].get("type").is_none());
+    }
+}
@@ -0,0 +1,374 @@
+//! The concepts of a bundle, as the [`concept_schema`](super::concept_schema)
+//! and [`codegen`](super::codegen) exports walk them.
+//!
+//! A [`ConceptGraph`] resolves concept references across the documents of a
+//! bundle and the [`NativeConcepts`] registry, and reads each concept into a
+//! [`ConceptDef`]: what it inherits from and its own typed [`Field`]s, from
+//! either a `[concept.X.structure]` or a native concept's Python-like field
+//! types.
+
+use serde_json::Value;
+use taplo::dom::Node;
+
+use super::{bundle::string_of, native_concepts::NativeConcepts};
+
+/// How far `refines` chains are followed, so that a cycle cannot loop forever.
+pub(crate) const MAX_REFINES_DEPTH: usize = 8;
+
+/// What a concept reference resolves to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Target {
+    /// The concept at this index of the graph's bundle concepts.
+    Bundle(usize),
+    /// A native concept, by name.
+    Native(String),
+    /// A concept the bundle does not define, as referenced.
+    Foreign(String),
+}
+
+/// The type of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FieldType {
+    Text,
+    Integer,
+    Number,
+    Boolean,
+    Date,
+    DateTime,
+    Time,
+    Concept(Target),
+    /// A list, of items of the given type when known.
+    List(Option<Box<FieldType>>),
+    /// A mapping, with the key and value types when known.
+    Dict(Option<Box<FieldType>>, Option<Box<FieldType>>),
+    Union(Vec<FieldType>),
+    Any,
+}
+
+/// A field of a concept.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Field {
+    pub(crate) name: String,
+    pub(crate) ty: FieldType,
+    pub(crate) description: Option<String>,
+    pub(crate) choices: Vec<String>,
+    pub(crate) default: Option<Value>,
+    pub(crate) required: bool,
+}
+
+/// A concept, as read from the bundle or the native registry.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ConceptDef {
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    /// The concept it inherits the fields of.
+    pub(crate) parent: Option<Target>,
+    /// Its own fields, or `None` when they are unknown (a structure naming a
+    /// class defined elsewhere, a concept of another bundle, or a native
+    /// concept accepting any content).
+    pub(crate) fields: Option<Vec<Field>>,
+}
+
+/// A concept defined by a bundle document.
+pub(crate) struct BundleConcept {
+    pub(crate) domain: Option<String>,
+    pub(crate) name: String,
+    pub(crate) node: Node,
+}
+
+pub(crate) struct ConceptGraph<'a> {
+    /// The domain of the exported document.
+    pub(crate) domain: Option<String>,
+    /// The concepts of the exported document first, then those of its
+    /// siblings.
+    pub(crate) concepts: Vec<BundleConcept>,
+    /// How many of `concepts` the exported document defines.
+    pub(crate) exported: usize,
+    pub(crate) natives: &'a NativeConcepts,
+}
+
+impl<'a> ConceptGraph<'a> {
+    pub(crate) fn new(document: &Node, siblings: &[Node], natives: &'a NativeConcepts) -> Self {
+        let mut concepts = bundle_concepts(document);
+        let exported = concepts.len();
+        for sibling in siblings {
+            concepts.extend(bundle_concepts(sibling));
+        }
+        Self {
+            domain: string_of(&document.get("domain")),
+            concepts,
+            exported,
+            natives,
+        }
+    }
+
+    /// Resolve `reference`, as written in a document of `from_domain`.
+    pub(crate) fn resolve(&self, reference: &str, from_domain: Option<&str>) -> Target {
+        let bare = reference.split('[').next().unwrap_or(reference);
+        let (domain, name) = match bare.rsplit_once('.') {
+            Some((domain, name)) => (Some(domain), name),
+            None => (None, bare),
+        };
+        if domain == Some("native") {
+            return match self.natives.find(name) {
+                Some(_) => Target::Native(name.to_string()),
+                None => Target::Foreign(bare.to_string()),
+            };
+        }
+
+        let domain = domain.or(from_domain);
+        if let Some(index) = self
+            .concepts
+            .iter()
+            .position(|concept| concept.name == name && concept.domain.as_deref() == domain)
+        {
+            return Target::Bundle(index);
+        }
+        if bare == name && self.natives.find(name).is_some() {
+            return Target::Native(name.to_string());
+        }
+        Target::Foreign(bare.to_string())
+    }
+
+    /// The name of `target` as seen from the exported document: bare for the
+    /// concepts of its domain, qualified otherwise.
+    pub(crate) fn qualified_name(&self, target: &Target) -> String {
+        match target {
+            Target::Bundle(index) => {
+                let concept = &self.concepts[*index];
+                match &concept.domain {
+                    Some(domain) if Some(domain) != self.domain.as_ref() => {
+                        format!("{domain}.{}", concept.name)
+                    }
+                    _ => concept.name.clone(),
+                }
+            }
+            Target::Native(name) => format!("native.{name}"),
+            Target::Foreign(reference) => reference.clone(),
+        }
+    }
+
+    /// Read the concept `target` resolves to.
+    pub(crate) fn concept(&self, target: &Target) -> ConceptDef {
+        match target {
+            Target::Bundle(index) => self.bundle_concept(&self.concepts[*index]),
+            Target::Native(name) => {
+                let native = self.natives.find(name);
+                let own: Vec<Field> = native
+                    .map(|native| native.fields.as_slice())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(name, ty)| {
+                        let (ty, optional) = match ty.trim().strip_suffix('?') {
+                            Some(ty) => (ty, true),
+                            None => (ty.as_str(), false),
+                        };
+                        Field {
+                            name: name.clone(),
+                            ty: self.native_type(ty),
+                            description: None,
+                            choices: Vec::new(),
+                            default: None,
+                            required: !optional,
+                        }
+                    })
+                    .collect();
+                let parent = native
+                    .and_then(|native| native.refines.clone())
+                    .map(Target::Native);
+                ConceptDef {
+                    name: name.clone(),
+                    description: native.map(|native| native.description.clone()),
+                    fields: (!own.is_empty() || parent.is_some()).then_some(own),
+                    parent,
+                }
+            }
+            Target::Foreign(reference) => ConceptDef {
+                name: reference.clone(),
+                description: None,
+                parent: None,
+                fields: None,
+            },
+        }
+    }
+
+    /// A bundle concept: it inherits from what it `refines`, or from the
+    /// native `Text` when it has neither `refines` nor `structure`.
+    fn bundle_concept(&self, concept: &BundleConcept) -> ConceptDef {
+        let domain = concept.domain.as_deref();
+        let structure = concept.node.get("structure");
+        let parent = match string_of(&concept.node.get("refines")) {
+            Some(refines) => Some(self.resolve(&refines, domain)),
+            None if structure.is_invalid() => Some(Target::Native("Text".into())),
+            None => None,
+        };
+        let fields = if structure.is_invalid() {
+            Some(Vec::new())
+        } else {
+            structure.as_table().map(|structure| {
+                let entries = structure.entries().read();
+                entries
+                    .iter()
+                    .map(|(key, field)| self.structure_field(key.value(), field, domain))
+                    .collect()
+            })
+        };
+        ConceptDef {
+            name: concept.name.clone(),
+            description: string_of(&concept.node.get("description")),
+            parent,
+            fields,
+        }
+    }
+
+    /// A `structure` entry, as written in a document of `domain`: the
+    /// `field = "description"` shorthand for an optional text field, or a
+    /// `{ type, description, … }` table.
+    fn structure_field(&self, name: &str, field: &Node, domain: Option<&str>) -> Field {
+        if let Some(description) = string_of(field) {
+            return Field {
+                name: name.to_string(),
+                ty: FieldType::Text,
+                description: Some(description),
+                choices: Vec::new(),
+                default: None,
+                required: false,
+            };
+        }
+
+        let concept_ref = string_of(&field.get("concept_ref"));
+        let item_concept_ref = string_of(&field.get("item_concept_ref"));
+        let item_type = string_of(&field.get("item_type"));
+        let ty = string_of(&field.get("type")).unwrap_or_else(|| {
+            if concept_ref.is_some() {
+                "concept".into()
+            } else if item_concept_ref.is_some() || item_type.is_some() {
+                "list".into()
+            } else {
+                "text".into()
+            }
+        });
+
+        let ty = match ty.as_str() {
+            "concept" => concept_ref.map_or(FieldType::Any, |reference| {
+                FieldType::Concept(self.resolve(&reference, domain))
+            }),
+            "list" => FieldType::List(match (item_concept_ref, item_type) {
+                (Some(reference), _) => Some(Box::new(FieldType::Concept(
+                    self.resolve(&reference, domain),
+                ))),
+                (None, Some(item_type)) => Some(Box::new(scalar_type(&item_type))),
+                (None, None) => None,
+            }),
+            "dict" => FieldType::Dict(
+                string_of(&field.get("key_type")).map(|ty| Box::new(scalar_type(&ty))),
+                string_of(&field.get("value_type")).map(|ty| Box::new(scalar_type(&ty))),
+            ),
+            other => scalar_type(other),
+        };
+
+        let choices = field
+            .get("choices")
+            .as_array()
+            .map(|choices| {
+                choices
+                    .items()
+                    .read()
+                    .iter()
+                    .filter_map(string_of)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let default = field.get("default_value");
+        let default = if default.is_invalid() {
+            None
+        } else {
+            serde_json::to_value(&default).ok()
+        };
+
+        Field {
+            name: name.to_string(),
+            ty,
+            description: string_of(&field.get("description")),
+            choices,
+            default,
+            required: field
+                .get("required")
+                .as_bool()
+                .is_some_and(|required| required.value()),
+        }
+    }
+
+    /// A native field's Python-like type: `str`, `int | float`,
+    /// `list[ImageContent]`, `dict`, …
+    fn native_type(&self, ty: &str) -> FieldType {
+        let ty = ty.trim();
+        if ty.contains('|') {
+            return FieldType::Union(ty.split('|').map(|ty| self.native_type(ty)).collect());
+        }
+        if let Some(item) = ty
+            .strip_prefix("list[")
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            return FieldType::List(Some(Box::new(self.native_type(item))));
+        }
+        if let Some((key, value)) = ty
+            .strip_prefix("dict[")
+            .and_then(|rest| rest.strip_suffix(']'))
+            .and_then(|rest| rest.split_once(','))
+        {
+            return FieldType::Dict(
+                Some(Box::new(self.native_type(key))),
+                Some(Box::new(self.native_type(value))),
+            );
+        }
+        match ty {
+            "str" => FieldType::Text,
+            "int" => FieldType::Integer,
+            "float" => FieldType::Number,
+            "bool" => FieldType::Boolean,
+            "date" => FieldType::Date,
+            "datetime" => FieldType::DateTime,
+            "time" => FieldType::Time,
+            "dict" => FieldType::Dict(None, None),
+            "list" => FieldType::List(None),
+            _ => match ty.strip_suffix("Content") {
+                Some(native) if self.natives.find(native).is_some() => {
+                    FieldType::Concept(Target::Native(native.to_string()))
+                }
+                _ => FieldType::Any,
+            },
+        }
+    }
+}
+
+fn bundle_concepts(document: &Node) -> Vec<BundleConcept> {
+    let domain = string_of(&document.get("domain"));
+    let Some(table) = document.get("concept").as_table().cloned() else {
+        return Vec::new();
+    };
+    let entries = table.entries().read();
+    entries
+        .iter()
+        .filter(|(_, node)| node.as_table().is_some())
+        .map(|(key, node)| BundleConcept {
+            domain: domain.clone(),
+            name: key.value().to_string(),
+            node: node.clone(),
+        })
+        .collect()
+}
+
+/// A structure field `type` other than `concept`, `list` and `dict`.
+fn scalar_type(ty: &str) -> FieldType {
+    match ty {
+        "text" => FieldType::Text,
+        "integer" => FieldType::Integer,
+        "number" => FieldType::Number,
+        "boolean" => FieldType::Boolean,
+        "date" => FieldType::Date,
+        "datetime" => FieldType::DateTime,
+        "time" => FieldType::Time,
+        _ => FieldType::Any,
+    }
+}
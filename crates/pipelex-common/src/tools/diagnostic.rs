@@ -8,6 +8,10 @@
 
 use serde::Serialize;
 use taplo::rowan::TextRange;
+use taplo_common::schema::SchemaDeprecation;
+
+use crate::tools::codes::code_description;
+use crate::tools::fixes::Fix;
 
 // ⚠️ PUBLIC BINDING SURFACE — these variants are serialized lowercase into each
 // diagnostic's `kind`; mirror any change in `pipelex_tools.pyi`
@@ -22,6 +26,32 @@ pub enum DiagnosticKind {
     Semantic,
     /// JSON-schema validation error against the embedded MTHDS schema.
     Schema,
+    /// A check from the MTHDS lint rule registry (see [`rules`](super::rules)).
+    Rule,
+}
+
+// ⚠️ PUBLIC BINDING SURFACE — these variants are serialized lowercase into each
+// diagnostic's `severity`; mirror any change in `pipelex_tools.pyi`
+// (`Diagnostic.severity`) and in `js/tools-wasm`'s `Diagnostic` TS doc comment.
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    /// The lowercase name, as serialized and as printed by the CLI.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
 }
 
 // ⚠️ PUBLIC BINDING SURFACE — serialized into each diagnostic's `range`; mirror
@@ -60,6 +90,46 @@ impl Range {
     }
 }
 
+// ⚠️ PUBLIC BINDING SURFACE — serialized into each diagnostic's `fixes`; mirror
+// any field change in `pipelex_tools.pyi` (`Fix`, `TextEdit`) and in
+// `js/tools-wasm`'s `Fix` / `TextEdit` TS types.
+/// A machine-applicable fix for a diagnostic: a titled set of edits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticFix {
+    pub title: String,
+    /// Whether the fix only repairs what the diagnostic reports, so it can be
+    /// applied without review (`plxt lint --fix` applies only these).
+    pub safe: bool,
+    /// Non-overlapping edits, in document order.
+    pub edits: Vec<TextEdit>,
+}
+
+/// Replace the text in `range` with `new_text`; an empty range inserts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+impl DiagnosticFix {
+    /// Build a [`DiagnosticFix`] from an engine [`Fix`] over `source`.
+    #[must_use]
+    pub fn from_fix(source: &str, fix: &Fix) -> Self {
+        Self {
+            title: fix.title.clone(),
+            safe: fix.safe,
+            edits: fix
+                .edits
+                .iter()
+                .map(|edit| TextEdit {
+                    range: Range::from_text_range(source, edit.range),
+                    new_text: edit.new_text.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
 // ⚠️ PUBLIC BINDING SURFACE — serialized into the Python diagnostic dicts via
 // `pythonize` and into JS objects via `serde-wasm-bindgen`; mirror any field
 // change in `pipelex_tools.pyi` (`Diagnostic`) and in `js/tools-wasm`'s
@@ -67,56 +137,131 @@ impl Range {
 /// A single lint/format diagnostic.
 ///
 /// Field names are kept neutral — the `pipelex-api` repo owns the wire contract
-/// and will `model_validate` these. `location` and `range` are always present
-/// (as `null` when absent) so the serialized shape is stable.
+/// and will `model_validate` these. `code_description`, `location` and `range`
+/// are always present (as `null` when absent), and `fixes` as a possibly empty
+/// list, so the serialized shape is stable.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Diagnostic {
     pub kind: DiagnosticKind,
-    /// Always `"error"` today; lint has no warnings yet. Kept for room to grow.
-    pub severity: &'static str,
+    /// Always `error` for syntax, semantic and schema diagnostics, except
+    /// `warning` for schema deprecations; rule diagnostics use the rule's
+    /// configured severity.
+    pub severity: Severity,
+    /// Stable machine-readable code (e.g. `"schema/required"`,
+    /// `"mthds/undefined-pipe"`); see [`codes`](super::codes).
+    pub code: &'static str,
+    /// Documentation URL for `code`, `None` if the code is undocumented.
+    pub code_description: Option<String>,
     pub message: String,
     /// Dotted instance path for schema errors (e.g. `"pipe.foo.model"`),
     /// `None` otherwise.
     pub location: Option<String>,
     /// Source range; `None` for semantic/schema errors that carry no position.
     pub range: Option<Range>,
+    /// Fixes that resolve the diagnostic, most often none.
+    pub fixes: Vec<DiagnosticFix>,
 }
 
 impl Diagnostic {
     /// A `kind: "syntax"` diagnostic (always positioned).
     #[must_use]
     pub fn syntax(message: String, range: Range) -> Self {
-        Self {
-            kind: DiagnosticKind::Syntax,
-            severity: "error",
+        Self::new(
+            DiagnosticKind::Syntax,
+            Severity::Error,
+            taplo::parser::Error::CODE,
             message,
-            location: None,
-            range: Some(range),
-        }
+            None,
+            Some(range),
+        )
     }
 
     /// A `kind: "semantic"` diagnostic (position may be absent for some
     /// `dom::Error` variants).
     #[must_use]
-    pub fn semantic(message: String, range: Option<Range>) -> Self {
-        Self {
-            kind: DiagnosticKind::Semantic,
-            severity: "error",
+    pub fn semantic(code: &'static str, message: String, range: Option<Range>) -> Self {
+        Self::new(
+            DiagnosticKind::Semantic,
+            Severity::Error,
+            code,
             message,
-            location: None,
+            None,
             range,
-        }
+        )
     }
 
     /// A `kind: "schema"` diagnostic, carrying the dotted instance `location`.
     #[must_use]
-    pub fn schema(message: String, location: Option<String>, range: Option<Range>) -> Self {
+    pub fn schema(
+        code: &'static str,
+        message: String,
+        location: Option<String>,
+        range: Option<Range>,
+    ) -> Self {
+        Self::new(
+            DiagnosticKind::Schema,
+            Severity::Error,
+            code,
+            message,
+            location,
+            range,
+        )
+    }
+
+    /// A `kind: "schema"` warning for an entry or value the schema marks
+    /// deprecated.
+    #[must_use]
+    pub fn deprecation(message: String, location: Option<String>, range: Option<Range>) -> Self {
+        Self::new(
+            DiagnosticKind::Schema,
+            Severity::Warning,
+            SchemaDeprecation::CODE,
+            message,
+            location,
+            range,
+        )
+    }
+
+    /// A `kind: "rule"` diagnostic for a violation of the rule `code`.
+    #[must_use]
+    pub fn rule(code: &'static str, severity: Severity, message: String, range: Range) -> Self {
+        Self::new(
+            DiagnosticKind::Rule,
+            severity,
+            code,
+            message,
+            None,
+            Some(range),
+        )
+    }
+
+    /// Attach `fixes`, computed over `source`.
+    #[must_use]
+    pub fn with_fixes(mut self, source: &str, fixes: &[Fix]) -> Self {
+        self.fixes = fixes
+            .iter()
+            .map(|fix| DiagnosticFix::from_fix(source, fix))
+            .collect();
+        self
+    }
+
+    fn new(
+        kind: DiagnosticKind,
+        severity: Severity,
+        code: &'static str,
+        message: String,
+        location: Option<String>,
+        range: Option<Range>,
+    ) -> Self {
         Self {
-            kind: DiagnosticKind::Schema,
-            severity: "error",
+            kind,
+            severity,
+            code,
+            code_description: code_description(code),
             message,
             location,
             range,
+            fixes: Vec::new(),
         }
     }
 }
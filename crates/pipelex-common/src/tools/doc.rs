@@ -0,0 +1,868 @@
+//! Documentation pages for MTHDS bundles.
+//!
+//! [`bundle_docs`] writes one page per domain — its description, `main_pipe`
+//! and sources, a Mermaid diagram of its pipe graph, a table of its concepts
+//! and one of its pipes with their signatures and who calls whom — plus an
+//! index of the domains. Each concept and pipe then gets a section holding
+//! the [`hover`](super::hover) content the editor shows for it, and a link
+//! to the line defining it.
+//!
+//! Pages are Markdown or standalone HTML, both written from the same blocks
+//! of inline Markdown, the language the hover content is written in.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use taplo::dom::{node::Key, Node};
+
+use super::{
+    bundle::{string_of, BundleIndex},
+    hover::{concept_hover, pipe_hover},
+};
+
+/// The format of the documentation pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+impl DocFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// An MTHDS file to document.
+#[derive(Debug, Clone)]
+pub struct SourceFile<'s> {
+    /// The path of the file as shown in the pages.
+    pub name: String,
+    /// The URL of the file from the directory of the pages.
+    pub link: String,
+    pub source: &'s str,
+    pub document: Node,
+}
+
+/// A documentation page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    /// The file name of the page, e.g. `reviews.md`.
+    pub file_name: String,
+    pub content: String,
+}
+
+/// The pages documenting `files`: an `index` page, and a page per domain.
+/// A file declaring no `domain` is documented under its name.
+#[must_use]
+pub fn bundle_docs(files: &[SourceFile<'_>], format: DocFormat) -> Vec<Page> {
+    let catalog = Catalog::new(files);
+    let mut pages = vec![Page {
+        file_name: format!("{INDEX_PAGE}.{}", format.extension()),
+        content: render("Bundles", &catalog.index(format), format),
+    }];
+    for domain in catalog.domains.keys() {
+        pages.push(Page {
+            file_name: format!("{}.{}", page_name(domain), format.extension()),
+            content: render(domain, &catalog.domain_page(domain, format), format),
+        });
+    }
+    pages
+}
+
+/// A pipe or concept definition.
+struct Definition<'s> {
+    file: &'s SourceFile<'s>,
+    name: String,
+    node: Node,
+    /// The 1-based line of its key.
+    line: usize,
+}
+
+/// What a domain holds.
+#[derive(Default)]
+struct Domain<'s> {
+    files: Vec<&'s SourceFile<'s>>,
+    concepts: Vec<Definition<'s>>,
+    pipes: Vec<Definition<'s>>,
+}
+
+/// A pipe, as `(domain, name)`.
+type PipeId = (String, String);
+
+struct Catalog<'s> {
+    domains: BTreeMap<String, Domain<'s>>,
+    /// The pipes each pipe calls, in call order.
+    callees: BTreeMap<PipeId, Vec<PipeId>>,
+    /// The pipes calling each pipe.
+    callers: BTreeMap<PipeId, Vec<PipeId>>,
+}
+
+impl<'s> Catalog<'s> {
+    fn new(files: &'s [SourceFile<'s>]) -> Self {
+        let mut domains: BTreeMap<String, Domain<'s>> = BTreeMap::new();
+        for file in files {
+            let domain = domains.entry(domain_of(file)).or_default();
+            domain.files.push(file);
+            domain.concepts.extend(definitions(file, "concept"));
+            domain.pipes.extend(definitions(file, "pipe"));
+        }
+
+        let mut callees: BTreeMap<PipeId, Vec<PipeId>> = BTreeMap::new();
+        let mut callers: BTreeMap<PipeId, Vec<PipeId>> = BTreeMap::new();
+        for (domain_name, domain) in &domains {
+            for pipe in &domain.pipes {
+                let caller = (domain_name.clone(), pipe.name.clone());
+                for reference in BundleIndex::pipe_calls(&pipe.node) {
+                    let callee = (
+                        reference.domain.unwrap_or_else(|| domain_name.clone()),
+                        reference.name,
+                    );
+                    let calls = callees.entry(caller.clone()).or_default();
+                    if calls.contains(&callee) {
+                        continue;
+                    }
+                    calls.push(callee.clone());
+                    callers.entry(callee).or_default().push(caller.clone());
+                }
+            }
+        }
+
+        Self {
+            domains,
+            callees,
+            callers,
+        }
+    }
+
+    fn defines_pipe(&self, (domain, name): &PipeId) -> bool {
+        self.domains
+            .get(domain)
+            .is_some_and(|domain| domain.pipes.iter().any(|pipe| pipe.name == *name))
+    }
+
+    /// A link to the pipe `id` from the page of `from`, or its code when no
+    /// page documents it.
+    fn pipe_link(&self, id: &PipeId, from: &str, format: DocFormat) -> String {
+        let (domain, name) = id;
+        let label = if domain == from {
+            format!("`{name}`")
+        } else {
+            format!("`{domain}.{name}`")
+        };
+        if !self.defines_pipe(id) {
+            return label;
+        }
+        let page = if domain == from {
+            String::new()
+        } else {
+            format!("{}.{}", page_name(domain), format.extension())
+        };
+        format!("[{label}]({page}#pipe-{name})")
+    }
+
+    fn index(&self, format: DocFormat) -> Vec<Block> {
+        let rows = self
+            .domains
+            .iter()
+            .map(|(name, domain)| {
+                let main_pipes: Vec<String> = main_pipes(domain)
+                    .iter()
+                    .map(|pipe| self.pipe_link(&(name.clone(), pipe.clone()), "", format))
+                    .collect();
+                vec![
+                    format!("[`{name}`]({}.{})", page_name(name), format.extension()),
+                    descriptions(domain).join(" "),
+                    main_pipes.join(", "),
+                    domain.concepts.len().to_string(),
+                    domain.pipes.len().to_string(),
+                ]
+            })
+            .collect();
+        vec![
+            Block::Heading(1, "Bundles".into(), None),
+            Block::Table(
+                ["Domain", "Description", "Main pipe", "Concepts", "Pipes"]
+                    .map(String::from)
+                    .to_vec(),
+                rows,
+            ),
+        ]
+    }
+
+    fn domain_page(&self, name: &str, format: DocFormat) -> Vec<Block> {
+        let domain = &self.domains[name];
+        let mut blocks = vec![Block::Heading(1, format!("`{name}`"), None)];
+        blocks.extend(descriptions(domain).into_iter().map(Block::Paragraph));
+
+        let main_pipes: Vec<String> = main_pipes(domain)
+            .into_iter()
+            .map(|pipe| self.pipe_link(&(name.to_string(), pipe), name, format))
+            .collect();
+        if !main_pipes.is_empty() {
+            blocks.push(Block::Paragraph(format!(
+                "**Main pipe:** {}",
+                main_pipes.join(", ")
+            )));
+        }
+        let sources: Vec<String> = domain
+            .files
+            .iter()
+            .map(|file| format!("[`{}`]({})", file.name, file.link))
+            .collect();
+        blocks.push(Block::Paragraph(format!(
+            "**Sources:** {}",
+            sources.join(", ")
+        )));
+
+        if !domain.pipes.is_empty() {
+            blocks.push(Block::Heading(2, "Pipe graph".into(), None));
+            blocks.push(Block::Mermaid(self.pipe_graph(name)));
+        }
+
+        if !domain.concepts.is_empty() {
+            blocks.push(Block::Heading(2, "Concepts".into(), None));
+            let rows = domain
+                .concepts
+                .iter()
+                .map(|concept| {
+                    vec![
+                        format!("[`{0}`](#concept-{0})", concept.name),
+                        description(&concept.node).unwrap_or_default(),
+                        string_of(&concept.node.get("refines"))
+                            .map(|refines| self.concept_link(&refines, name))
+                            .unwrap_or_default(),
+                        concept_fields(&concept.node).join(", "),
+                    ]
+                })
+                .collect();
+            blocks.push(Block::Table(
+                ["Concept", "Description", "Refines", "Fields"]
+                    .map(String::from)
+                    .to_vec(),
+                rows,
+            ));
+        }
+
+        if !domain.pipes.is_empty() {
+            blocks.push(Block::Heading(2, "Pipes".into(), None));
+            let rows = domain
+                .pipes
+                .iter()
+                .map(|pipe| {
+                    let id = (name.to_string(), pipe.name.clone());
+                    let links = |pipes: Option<&Vec<PipeId>>| {
+                        pipes
+                            .into_iter()
+                            .flatten()
+                            .map(|other| self.pipe_link(other, name, format))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    };
+                    vec![
+                        format!("[`{0}`](#pipe-{0})", pipe.name),
+                        string_of(&pipe.node.get("type"))
+                            .map(|pipe_type| format!("`{pipe_type}`"))
+                            .unwrap_or_default(),
+                        format!("`{}`", signature(&pipe.name, &pipe.node)),
+                        description(&pipe.node).unwrap_or_default(),
+                        links(self.callers.get(&id)),
+                        links(self.callees.get(&id)),
+                    ]
+                })
+                .collect();
+            blocks.push(Block::Table(
+                [
+                    "Pipe",
+                    "Type",
+                    "Signature",
+                    "Description",
+                    "Callers",
+                    "Callees",
+                ]
+                .map(String::from)
+                .to_vec(),
+                rows,
+            ));
+        }
+
+        for (title, kind, definitions) in [
+            ("Concept details", "concept", &domain.concepts),
+            ("Pipe details", "pipe", &domain.pipes),
+        ] {
+            if definitions.is_empty() {
+                continue;
+            }
+            blocks.push(Block::Heading(2, title.into(), None));
+            for definition in definitions {
+                blocks.push(Block::Heading(
+                    3,
+                    format!("`{}`", definition.name),
+                    Some(format!("{kind}-{}", definition.name)),
+                ));
+                let hover = if kind == "pipe" {
+                    pipe_hover(&definition.name, &definition.node)
+                } else {
+                    concept_hover(&definition.name, &definition.node)
+                };
+                let hover = if hover.is_empty() {
+                    description(&definition.node).unwrap_or_default()
+                } else {
+                    hover
+                };
+                blocks.extend(
+                    hover
+                        .split("\n\n")
+                        .filter(|part| !part.is_empty())
+                        .map(|part| Block::Paragraph(part.to_string())),
+                );
+                blocks.push(Block::Paragraph(format!(
+                    "Defined in [{0}:{1}]({2}#L{1})",
+                    definition.file.name, definition.line, definition.file.link
+                )));
+            }
+        }
+
+        blocks
+    }
+
+    /// A link to the concept `reference` from the page of `from`, or its
+    /// code when it is not a concept of `from`.
+    fn concept_link(&self, reference: &str, from: &str) -> String {
+        let name = reference
+            .strip_prefix(&format!("{from}."))
+            .unwrap_or(reference);
+        let defined = self.domains[from]
+            .concepts
+            .iter()
+            .any(|concept| concept.name == name);
+        if defined {
+            format!("[`{reference}`](#concept-{name})")
+        } else {
+            format!("`{reference}`")
+        }
+    }
+
+    /// The Mermaid flowchart of the pipes of `domain` and the calls between
+    /// them, with the pipes they call in other domains.
+    fn pipe_graph(&self, name: &str) -> String {
+        let domain = &self.domains[name];
+        let main_pipes = main_pipes(domain);
+        let mut graph = String::from("flowchart LR\n");
+        let mut external = Vec::new();
+        for pipe in &domain.pipes {
+            let label = match string_of(&pipe.node.get("type")) {
+                Some(pipe_type) => format!("{}<br/><small>{pipe_type}</small>", pipe.name),
+                None => pipe.name.clone(),
+            };
+            let _ = writeln!(graph, "    {}[\"{label}\"]", node_id(name, &pipe.name));
+        }
+        let calls: Vec<(&str, &PipeId)> = domain
+            .pipes
+            .iter()
+            .flat_map(|pipe| {
+                let caller = (name.to_string(), pipe.name.clone());
+                self.callees
+                    .get(&caller)
+                    .into_iter()
+                    .flatten()
+                    .map(|callee| (pipe.name.as_str(), callee))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for (_, callee) in &calls {
+            if callee.0 != name && !external.contains(*callee) {
+                external.push((*callee).clone());
+                let _ = writeln!(
+                    graph,
+                    "    {}[\"{}.{}\"]:::external",
+                    node_id(&callee.0, &callee.1),
+                    callee.0,
+                    callee.1
+                );
+            }
+        }
+        for (caller, callee) in calls {
+            let _ = writeln!(
+                graph,
+                "    {} --> {}",
+                node_id(name, caller),
+                node_id(&callee.0, &callee.1)
+            );
+        }
+        for pipe in main_pipes {
+            if domain.pipes.iter().any(|defined| defined.name == pipe) {
+                let _ = writeln!(graph, "    class {} main", node_id(name, &pipe));
+            }
+        }
+        graph.push_str("    classDef main stroke-width:3px\n");
+        graph.push_str("    classDef external stroke-dasharray:4 4\n");
+        graph
+    }
+}
+
+fn domain_of(file: &SourceFile<'_>) -> String {
+    string_of(&file.document.get("domain"))
+        .filter(|domain| !domain.is_empty())
+        .unwrap_or_else(|| {
+            let name = file.name.rsplit(['/', '\\']).next().unwrap_or(&file.name);
+            name.strip_suffix(".mthds").unwrap_or(name).to_string()
+        })
+}
+
+/// The entries of the `root` table of `file`, in document order.
+fn definitions<'s>(file: &'s SourceFile<'s>, root: &str) -> Vec<Definition<'s>> {
+    let Some(table) = file.document.get(root).as_table().cloned() else {
+        return Vec::new();
+    };
+    let entries = table.entries().read();
+    entries
+        .iter()
+        .map(|(key, node)| Definition {
+            file,
+            name: key.value().to_string(),
+            node: node.clone(),
+            line: line_of(file.source, key),
+        })
+        .collect()
+}
+
+fn line_of(source: &str, key: &Key) -> usize {
+    let offset = key
+        .text_ranges()
+        .next()
+        .map_or(0, |range| usize::from(range.start()));
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// The top-level `description`s of the files of `domain`.
+fn descriptions(domain: &Domain<'_>) -> Vec<String> {
+    let mut descriptions: Vec<String> = Vec::new();
+    for file in &domain.files {
+        if let Some(description) = description(&file.document) {
+            if !descriptions.contains(&description) {
+                descriptions.push(description);
+            }
+        }
+    }
+    descriptions
+}
+
+/// The `main_pipe`s the files of `domain` declare.
+fn main_pipes(domain: &Domain<'_>) -> Vec<String> {
+    let mut pipes: Vec<String> = Vec::new();
+    for file in &domain.files {
+        if let Some(pipe) = string_of(&file.document.get("main_pipe")) {
+            if !pipes.contains(&pipe) {
+                pipes.push(pipe);
+            }
+        }
+    }
+    pipes
+}
+
+/// The `description` of a table, or the value of a `Name = "description"`
+/// concept shorthand.
+fn description(node: &Node) -> Option<String> {
+    string_of(node)
+        .or_else(|| string_of(&node.get("description")))
+        .filter(|description| !description.is_empty())
+}
+
+/// `name(input: Concept, …) -> Output`, as signature help shows it.
+fn signature(name: &str, pipe: &Node) -> String {
+    let mut signature = format!("{name}(");
+    if let Some(inputs) = pipe.get("inputs").as_table() {
+        let inputs: Vec<String> = inputs
+            .entries()
+            .read()
+            .iter()
+            .map(|(key, concept)| {
+                format!(
+                    "{}: {}",
+                    key.value(),
+                    string_of(concept).unwrap_or_else(|| "?".into())
+                )
+            })
+            .collect();
+        signature.push_str(&inputs.join(", "));
+    }
+    signature.push(')');
+    if let Some(output) = string_of(&pipe.get("output")) {
+        let _ = write!(signature, " -> {output}");
+    }
+    signature
+}
+
+/// The structure fields of a concept, as `` `name`: type ``.
+fn concept_fields(concept: &Node) -> Vec<String> {
+    let Some(structure) = concept.get("structure").as_table().cloned() else {
+        return Vec::new();
+    };
+    let entries = structure.entries().read();
+    entries
+        .iter()
+        .map(|(key, field)| {
+            let ty = match string_of(&field.get("type")).as_deref() {
+                None if field.as_str().is_some() => "text".to_string(),
+                None => string_of(&field.get("concept_ref")).unwrap_or_default(),
+                Some("concept") => string_of(&field.get("concept_ref")).unwrap_or_default(),
+                Some("list") => {
+                    let item = string_of(&field.get("item_concept_ref"))
+                        .or_else(|| string_of(&field.get("item_type")));
+                    item.map_or_else(|| "list".into(), |item| format!("list[{item}]"))
+                }
+                Some(ty) => ty.to_string(),
+            };
+            if ty.is_empty() {
+                format!("`{}`", key.value())
+            } else {
+                format!("`{}`: {ty}", key.value())
+            }
+        })
+        .collect()
+}
+
+/// The name of the index page.
+const INDEX_PAGE: &str = "index";
+
+/// The name of the page of `domain`. An `index` domain gets `index.domain`,
+/// which no other domain's page name can be, to keep the index page.
+fn page_name(domain: &str) -> String {
+    let name: String = domain
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name == INDEX_PAGE {
+        format!("{INDEX_PAGE}.domain")
+    } else {
+        name
+    }
+}
+
+/// A Mermaid node id for a pipe.
+fn node_id(domain: &str, pipe: &str) -> String {
+    page_name(&format!("{domain}__{pipe}"))
+}
+
+/// A piece of a page. Text is inline Markdown.
+enum Block {
+    /// A heading of the given level, with an anchor.
+    Heading(usize, String, Option<String>),
+    Paragraph(String),
+    /// Column headers and rows.
+    Table(Vec<String>, Vec<Vec<String>>),
+    Mermaid(String),
+}
+
+fn render(title: &str, blocks: &[Block], format: DocFormat) -> String {
+    match format {
+        DocFormat::Markdown => render_markdown(blocks),
+        DocFormat::Html => render_html(title, blocks),
+    }
+}
+
+fn render_markdown(blocks: &[Block]) -> String {
+    let blocks: Vec<String> = blocks
+        .iter()
+        .map(|block| match block {
+            Block::Heading(level, text, anchor) => {
+                let anchor = anchor
+                    .as_ref()
+                    .map(|anchor| format!("<a id=\"{anchor}\"></a>\n\n"))
+                    .unwrap_or_default();
+                format!("{anchor}{} {text}", "#".repeat(*level))
+            }
+            Block::Paragraph(text) => text.clone(),
+            Block::Table(headers, rows) => {
+                let mut table = format!("| {} |\n", headers.join(" | "));
+                let _ = writeln!(table, "|{}", " --- |".repeat(headers.len()));
+                for row in rows {
+                    let cells: Vec<String> = row
+                        .iter()
+                        .map(|cell| cell.replace('|', "\\|").replace('\n', "<br>"))
+                        .collect();
+                    let _ = writeln!(table, "| {} |", cells.join(" | "));
+                }
+                table.trim_end().to_string()
+            }
+            Block::Mermaid(graph) => format!("```mermaid\n{graph}```"),
+        })
+        .collect();
+    format!("{}\n", blocks.join("\n\n"))
+}
+
+fn render_html(title: &str, blocks: &[Block]) -> String {
+    let mut body = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text, anchor) => {
+                let id = anchor
+                    .as_ref()
+                    .map(|anchor| format!(" id=\"{anchor}\""))
+                    .unwrap_or_default();
+                let _ = writeln!(body, "<h{level}{id}>{}</h{level}>", inline_html(text));
+            }
+            Block::Paragraph(text) => {
+                let _ = writeln!(body, "<p>{}</p>", inline_html(text));
+            }
+            Block::Table(headers, rows) => {
+                body.push_str("<table>\n<thead><tr>");
+                for header in headers {
+                    let _ = write!(body, "<th>{}</th>", inline_html(header));
+                }
+                body.push_str("</tr></thead>\n<tbody>\n");
+                for row in rows {
+                    body.push_str("<tr>");
+                    for cell in row {
+                        let _ = write!(body, "<td>{}</td>", inline_html(cell));
+                    }
+                    body.push_str("</tr>\n");
+                }
+                body.push_str("</tbody>\n</table>\n");
+            }
+            Block::Mermaid(graph) => {
+                let _ = writeln!(
+                    body,
+                    "<pre class=\"mermaid\">\n{}</pre>",
+                    escape_html(graph)
+                );
+            }
+        }
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 72rem; margin: 2rem auto; padding: 0 1rem; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #ccc; padding: 0.3rem 0.6rem; text-align: left; vertical-align: top; }}
+</style>
+<script type="module">
+import mermaid from "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs";
+mermaid.initialize({{ startOnLoad: true }});
+</script>
+</head>
+<body>
+{body}</body>
+</html>
+"#,
+        escape_html(title)
+    )
+}
+
+/// The HTML of inline Markdown: `code`, **strong**, *emphasis* and
+/// [links](url). Everything else is escaped.
+fn inline_html(text: &str) -> String {
+    let mut html = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let (open, close, tag) = match c {
+            '`' => ("`", "`", "code"),
+            '*' if rest.starts_with("**") => ("**", "**", "strong"),
+            '*' => ("*", "*", "em"),
+            '[' => {
+                if let Some((label, url, after)) = link(rest) {
+                    let _ = write!(
+                        html,
+                        "<a href=\"{}\">{}</a>",
+                        escape_html(url),
+                        inline_html(label)
+                    );
+                    rest = after;
+                    continue;
+                }
+                ("", "", "")
+            }
+            '<' if rest.starts_with("<br>") => {
+                html.push_str("<br>");
+                rest = &rest[4..];
+                continue;
+            }
+            _ => ("", "", ""),
+        };
+        if !open.is_empty() {
+            if let Some(end) = rest[open.len()..].find(close) {
+                let inner = &rest[open.len()..open.len() + end];
+                let inner = if tag == "code" {
+                    escape_html(inner)
+                } else {
+                    inline_html(inner)
+                };
+                let _ = write!(html, "<{tag}>{inner}</{tag}>");
+                rest = &rest[open.len() + end + close.len()..];
+                continue;
+            }
+        }
+        html.push_str(&escape_html(&c.to_string()));
+        rest = &rest[c.len_utf8()..];
+    }
+    html
+}
+
+/// The label, URL and rest of `[label](url)…`.
+fn link(text: &str) -> Option<(&str, &str, &str)> {
+    let label_end = text.find("](")?;
+    let url_end = label_end + text[label_end..].find(')')?;
+    Some((
+        &text[1..label_end],
+        &text[label_end + 2..url_end],
+        &text[url_end + 1..],
+    ))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use taplo::parser;
+
+    use super::*;
+
+    const REVIEWS: &str = r#"domain = "reviews"
+description = "Review analysis"
+main_pipe = "analyze"
+
+[concept.Review]
+description = "A customer review"
+
+[concept.Review.structure]
+rating = { type = "integer", description = "From 1 to 5", required = true }
+body = "The review text"
+
+[concept.Summary]
+description = "A summary"
+refines = "Text"
+
+[pipe.analyze]
+type = "PipeSequence"
+description = "Analyze a review"
+inputs = { review = "Review" }
+output = "Summary"
+steps = [
+    { pipe = "summarize", result = "summary" },
+    { pipe = "notify.send", result = "sent" },
+]
+
+[pipe.summarize]
+type = "PipeLLM"
+description = "Summarize a review"
+inputs = { review = "Review" }
+output = "Summary"
+prompt = "Summarize @review"
+"#;
+
+    const NOTIFY: &str = r#"domain = "notify"
+
+[pipe.send]
+type = "PipeFunc"
+description = "Send | a notification"
+inputs = { summary = "reviews.Summary" }
+output = "Text"
+function_name = "send"
+"#;
+
+    fn files() -> Vec<SourceFile<'static>> {
+        [("reviews.mthds", REVIEWS), ("notify.mthds", NOTIFY)]
+            .into_iter()
+            .map(|(name, source)| SourceFile {
+                name: name.into(),
+                link: format!("../{name}"),
+                source,
+                document: parser::parse(source).into_dom(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn an_index_domain_keeps_the_index_page() {
+        let source = NOTIFY.replace("domain = \"notify\"", "domain = \"index\"");
+        let files = [SourceFile {
+            name: "index.mthds".into(),
+            link: "../index.mthds".into(),
+            source: &source,
+            document: parser::parse(&source).into_dom(),
+        }];
+        let pages = bundle_docs(&files, DocFormat::Markdown);
+        let names: Vec<&str> = pages.iter().map(|page| page.file_name.as_str()).collect();
+        assert_eq!(names, ["index.md", "index.domain.md"]);
+        assert!(pages[0].content.starts_with("# Bundles\n"));
+        assert!(pages[0].content.contains("[`index`](index.domain.md)"));
+    }
+
+    #[test]
+    fn writes_a_page_per_domain() {
+        let files = files();
+        let pages = bundle_docs(&files, DocFormat::Markdown);
+        let names: Vec<&str> = pages.iter().map(|page| page.file_name.as_str()).collect();
+        assert_eq!(names, ["index.md", "notify.md", "reviews.md"]);
+
+        assert_eq!(
+            pages[0].content,
+            "# Bundles\n\n\
+             | Domain | Description | Main pipe | Concepts | Pipes |\n\
+             | --- | --- | --- | --- | --- |\n\
+             | [`notify`](notify.md) |  |  | 0 | 1 |\n\
+             | [`reviews`](reviews.md) | Review analysis | [`reviews.analyze`](reviews.md#pipe-analyze) | 2 | 2 |\n"
+        );
+
+        let reviews = &pages[2].content;
+        for expected in [
+            "# `reviews`\n\nReview analysis\n\n**Main pipe:** [`analyze`](#pipe-analyze)\n\n**Sources:** [`reviews.mthds`](../reviews.mthds)",
+            "    reviews__analyze[\"analyze<br/><small>PipeSequence</small>\"]\n",
+            "    notify__send[\"notify.send\"]:::external\n",
+            "    reviews__analyze --> reviews__summarize\n    reviews__analyze --> notify__send\n",
+            "    class reviews__analyze main\n",
+            "| [`Review`](#concept-Review) | A customer review |  | `rating`: integer, `body`: text |\n",
+            "| [`Summary`](#concept-Summary) | A summary | `Text` |  |\n",
+            "| [`summarize`](#pipe-summarize) | `PipeLLM` | `summarize(review: Review) -> Summary` | Summarize a review | [`analyze`](#pipe-analyze) |  |\n",
+            "| [`analyze`](#pipe-analyze) | `PipeSequence` | `analyze(review: Review) -> Summary` | Analyze a review |  | [`summarize`](#pipe-summarize), [`notify.send`](notify.md#pipe-send) |\n",
+            "<a id=\"concept-Review\"></a>\n\n### `Review`\n\n**Review**\n\nA customer review\n\n**Fields:** `rating`, `body`\n\nDefined in [reviews.mthds:5](../reviews.mthds#L5)\n",
+            "<a id=\"pipe-summarize\"></a>\n\n### `summarize`\n\n**summarize** `PipeLLM`\n\nSummarize a review\n\n**Inputs:** `review`: Review\n\n**Output:** `Summary`\n\nDefined in [reviews.mthds:26](../reviews.mthds#L26)\n",
+        ] {
+            assert!(reviews.contains(expected), "missing {expected:?} in:\n{reviews}");
+        }
+
+        let notify = &pages[1].content;
+        assert!(
+            notify.contains("| `send(summary: reviews.Summary) -> Text` | Send \\| a notification | [`reviews.analyze`](reviews.md#pipe-analyze) |  |"),
+            "{notify}"
+        );
+    }
+
+    #[test]
+    fn writes_html_pages() {
+        let files = files();
+        let pages = bundle_docs(&files, DocFormat::Html);
+        assert_eq!(pages[2].file_name, "reviews.html");
+        let reviews = &pages[2].content;
+        for expected in [
+            "<title>reviews</title>",
+            "<h1><code>reviews</code></h1>",
+            "<pre class=\"mermaid\">\nflowchart LR\n",
+            "reviews__analyze[&quot;analyze&lt;br/&gt;&lt;small&gt;PipeSequence&lt;/small&gt;&quot;]",
+            "<a href=\"notify.html#pipe-send\"><code>notify.send</code></a></td>",
+            "<h3 id=\"pipe-analyze\"><code>analyze</code></h3>\n<p><strong>analyze</strong> <code>PipeSequence</code></p>",
+            "<p>Defined in <a href=\"../reviews.mthds#L16\">reviews.mthds:16</a></p>",
+        ] {
+            assert!(reviews.contains(expected), "missing {expected:?} in:\n{reviews}");
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! Machine-applicable fixes attached to lint diagnostics.
+//!
+//! A [`Fix`] is a titled set of text edits over the linted document. Fixes
+//! marked `safe` only repair what the diagnostic reports and are applied by
+//! `plxt lint --fix`; the others (such as placeholders that still need writing)
+//! are only offered as editor quick fixes.
+//!
+//! Fixes are applied through [`Rewrite`], which refuses overlapping patches:
+//! [`apply_fixes`] skips any fix that would touch text an earlier one already
+//! changed, so the result is always well-defined.
+
+use taplo::{
+    dom::{
+        node::{DomNode, Key},
+        rewrite::Rewrite,
+        Node,
+    },
+    rowan::{TextRange, TextSize},
+    syntax::{SyntaxElement, SyntaxKind},
+    util::escape,
+};
+use taplo_common::schema::{NodeValidationError, SchemaDeprecation};
+
+/// A titled set of edits that resolves one diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// Short imperative description, e.g. ``Replace with `summarize` ``.
+    pub title: String,
+    /// Whether `plxt lint --fix` may apply it without review.
+    pub safe: bool,
+    /// Non-overlapping edits, in document order.
+    pub edits: Vec<Edit>,
+}
+
+/// Replace the text in `range` with `new_text`; an empty range inserts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub range: TextRange,
+    pub new_text: String,
+}
+
+impl Fix {
+    /// A fix made of a single edit.
+    #[must_use]
+    pub fn replace(title: String, safe: bool, range: TextRange, new_text: String) -> Self {
+        Self {
+            title,
+            safe,
+            edits: vec![Edit { range, new_text }],
+        }
+    }
+}
+
+/// The fixes of a schema error: a missing `description` gets a placeholder
+/// after the table's `type` (or its header).
+#[must_use]
+pub fn schema_fixes(error: &NodeValidationError) -> Vec<Fix> {
+    if error.missing_property().as_deref() != Some("description") {
+        return Vec::new();
+    }
+    // Only tables opened by a `[header]`: an inline table would need the entry
+    // spliced in between its braces.
+    let Some(header) = error
+        .node
+        .syntax()
+        .and_then(SyntaxElement::as_node)
+        .filter(|syntax| syntax.kind() == SyntaxKind::TABLE_HEADER)
+    else {
+        return Vec::new();
+    };
+
+    let type_entry = error.node.get("type").syntax().and_then(|syntax| {
+        syntax
+            .ancestors()
+            .find(|node| node.kind() == SyntaxKind::ENTRY)
+    });
+    let at = type_entry.map_or(header.text_range().end(), |entry| entry.text_range().end());
+
+    vec![Fix::replace(
+        "Add a placeholder `description`".into(),
+        false,
+        TextRange::empty(at),
+        "\ndescription = \"TODO\"".into(),
+    )]
+}
+
+/// The fix of a deprecation whose schema names a replacement, in the
+/// document of `root`: a rename of the key, unless its table already has the
+/// new one, or a replacement of the string value.
+#[must_use]
+pub fn deprecation_fixes(root: &Node, deprecation: &SchemaDeprecation) -> Vec<Fix> {
+    let Some(replacement) = deprecation.replacement() else {
+        return Vec::new();
+    };
+
+    let edits: Vec<Edit> = match (&deprecation.key, deprecation.targets_value) {
+        (Some(key), false) => {
+            let renamed = deprecation.keys.skip_right(1).join(Key::new(replacement));
+            if root.path(&renamed).is_some() {
+                return Vec::new();
+            }
+            let new_text = Key::new(replacement).to_string();
+            key.text_ranges()
+                .map(|range| Edit {
+                    range,
+                    new_text: new_text.clone(),
+                })
+                .collect()
+        }
+        _ => match &deprecation.node {
+            Node::Str(string) => string
+                .syntax()
+                .map(|syntax| Edit {
+                    range: syntax.text_range(),
+                    new_text: format!("\"{}\"", escape(replacement)),
+                })
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        },
+    };
+    if edits.is_empty() {
+        return Vec::new();
+    }
+
+    vec![Fix {
+        title: format!("Replace with `{replacement}`"),
+        safe: true,
+        edits,
+    }]
+}
+
+/// Stage `fixes` as patches over the document of `dom`, skipping any fix
+/// whose edits overlap each other, reach past the end of the document, or
+/// overlap a fix staged before it. Returns the patches and how many fixes
+/// were staged.
+///
+/// The edits of each fix must be in document order; debug builds check it.
+///
+/// # Errors
+///
+/// If `dom` is not a document root.
+pub fn rewrite_fixes<'f>(
+    dom: &Node,
+    fixes: impl IntoIterator<Item = &'f Fix>,
+) -> Result<(Rewrite, usize), taplo::dom::rewrite::Error> {
+    let mut rewrite = Rewrite::new(dom.clone())?;
+    let mut staged = 0;
+    let len = dom
+        .syntax()
+        .map_or_else(TextSize::default, |syntax| syntax.text_range().end());
+
+    for fix in fixes {
+        debug_assert!(
+            fix.edits
+                .windows(2)
+                .all(|pair| pair[0].range.start() <= pair[1].range.start()),
+            "the edits of `{}` are not in document order",
+            fix.title
+        );
+        let valid = fix.edits.iter().enumerate().all(|(i, edit)| {
+            edit.range.end() <= len
+                && !fix.edits[..i]
+                    .iter()
+                    .any(|other| overlap(other.range, edit.range))
+        });
+        if !valid || fix.edits.iter().any(|edit| rewrite.overlaps(edit.range)) {
+            continue;
+        }
+        for edit in &fix.edits {
+            rewrite.replace_range(edit.range, &edit.new_text)?;
+        }
+        staged += 1;
+    }
+
+    Ok((rewrite, staged))
+}
+
+/// Whether a patch of `b` would overlap one of `a`, as
+/// [`Rewrite::overlaps`] decides it.
+fn overlap(a: TextRange, b: TextRange) -> bool {
+    a.contains_range(b) || b.contains_range(a) || a.contains(b.start()) || a.contains(b.end())
+}
+
+/// Apply `fixes` to the document of `dom` (see [`rewrite_fixes`]). Returns
+/// the new source and how many fixes were applied.
+///
+/// # Errors
+///
+/// If `dom` is not a document root.
+pub fn apply_fixes<'f>(
+    dom: &Node,
+    fixes: impl IntoIterator<Item = &'f Fix>,
+) -> Result<(String, usize), taplo::dom::rewrite::Error> {
+    rewrite_fixes(dom, fixes).map(|(rewrite, applied)| (rewrite.to_string(), applied))
+}
+
+/// The single candidate close enough to `name` to be what was meant, if there
+/// is exactly one.
+pub fn closest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).clamp(1, 3);
+    let mut closest = None;
+    for candidate in candidates {
+        if candidate == name || closest == Some(candidate) {
+            continue;
+        }
+        if edit_distance(name, candidate) <= max_distance {
+            if closest.is_some() {
+                return None;
+            }
+            closest = Some(candidate);
+        }
+    }
+    closest
+}
+
+/// Levenshtein distance, over chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taplo::parser;
+
+    #[test]
+    fn closest_name_needs_a_single_close_candidate() {
+        let pipes = ["summarize", "summarize_all", "review"];
+        assert_eq!(closest_name("sumarize", pipes), Some("summarize"));
+        assert_eq!(closest_name("reviw", pipes), Some("review"));
+        assert_eq!(closest_name("translate", pipes), None);
+        // Too short to guess from.
+        assert_eq!(closest_name("ab", ["cd", "xy"]), None);
+        assert_eq!(
+            closest_name("summarize_al", ["summarize_all", "summarize_alt"]),
+            None
+        );
+    }
+
+    #[test]
+    fn overlapping_fixes_are_skipped() {
+        let source = "a = 1\nb = 2\n";
+        let dom = parser::parse(source).into_dom();
+        let range = |start: u32, end: u32| TextRange::new(start.into(), end.into());
+        let fixes = [
+            Fix::replace("one".into(), true, range(4, 5), "10".into()),
+            Fix::replace("overlaps".into(), true, range(4, 5), "11".into()),
+            Fix::replace("two".into(), true, range(10, 11), "20".into()),
+        ];
+        assert_eq!(
+            apply_fixes(&dom, &fixes).unwrap(),
+            ("a = 10\nb = 20\n".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn fixes_with_invalid_edits_are_skipped() {
+        let source = "a = 1\nb = 2\n";
+        let dom = parser::parse(source).into_dom();
+        let range = |start: u32, end: u32| TextRange::new(start.into(), end.into());
+        let edit = |start, end, new_text: &str| Edit {
+            range: range(start, end),
+            new_text: new_text.into(),
+        };
+        let fixes = [
+            Fix {
+                title: "overlaps itself".into(),
+                safe: true,
+                edits: vec![edit(0, 5, "x = 0"), edit(4, 5, "10")],
+            },
+            Fix {
+                title: "past the end".into(),
+                safe: true,
+                edits: vec![edit(0, 1, "z"), edit(10, 40, "20")],
+            },
+            Fix {
+                title: "two edits".into(),
+                safe: true,
+                edits: vec![edit(4, 5, "10"), edit(10, 11, "20")],
+            },
+        ];
+        assert_eq!(
+            apply_fixes(&dom, &fixes).unwrap(),
+            ("a = 10\nb = 20\n".to_string(), 1)
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not in document order")]
+    fn edits_out_of_document_order_are_rejected() {
+        let dom = parser::parse("a = 1\nb = 2\n").into_dom();
+        let edit = |start: u32, end: u32| Edit {
+            range: TextRange::new(start.into(), end.into()),
+            new_text: "0".into(),
+        };
+        let fix = Fix {
+            title: "backwards".into(),
+            safe: true,
+            edits: vec![edit(10, 11), edit(4, 5)],
+        };
+        let _ = apply_fixes(&dom, [&fix]);
+    }
+
+    #[test]
+    fn deprecated_keys_are_renamed_to_their_replacement() {
+        use crate::tools::environment::NullEnvironment;
+        use futures::FutureExt;
+        use taplo_common::schema::{builtins::MTHDS_SCHEMA_URL, Schemas};
+
+        let fixed = |source: &str| {
+            let dom = parser::parse(source).into_dom();
+            let schemas = Schemas::new(NullEnvironment, None);
+            let deprecations = schemas
+                .deprecations(&MTHDS_SCHEMA_URL.parse().unwrap(), &dom)
+                .now_or_never()
+                .unwrap()
+                .unwrap();
+            let fixes: Vec<Fix> = deprecations
+                .iter()
+                .flat_map(|deprecation| deprecation_fixes(&dom, deprecation))
+                .collect();
+            apply_fixes(&dom, &fixes).unwrap()
+        };
+
+        let source = "domain = \"d\"\n\n[pipe.p]\ntype = \"PipeLLM\"\ndescription = \"P\"\n\
+                      output = \"Text\"\nprompt_template = \"Hi\"\n";
+        assert_eq!(
+            fixed(source),
+            (source.replace("prompt_template", "prompt"), 1)
+        );
+        // Renaming would clash with the new key.
+        let both = format!("{source}prompt = \"Hello\"\n");
+        assert_eq!(fixed(&both), (both.clone(), 0));
+    }
+}
@@ -0,0 +1,580 @@
+//! Sample input fixtures for MTHDS pipes.
+//!
+//! Running a pipe needs a working memory holding its `inputs`, written as
+//! the `inputs.json` Pipelex reads: one `{ "concept": …, "content": … }`
+//! entry per input. [`fixture`] synthesizes one with placeholder content
+//! shaped like each input's concept, and [`check_fixture`] validates a
+//! hand-written one against the same shapes.
+//!
+//! A concept's shape is its fields and those of what it `refines`, as
+//! [`concept_schema`](super::concept_schema) reads them: a structure field
+//! gets its `default_value`, else its first `choice`, else an example of its
+//! `type`; a `concept` field nests the referenced concept's shape. Native
+//! concepts take the fields of the [`NativeConcepts`] registry, so an
+//! `Image` input holds a `url`, a `filename`, and so on. An input declared
+//! as `Concept[]` holds a list of one item, and `Concept[N]` a list of `N`.
+
+use std::fmt;
+
+use anyhow::bail;
+use serde_json::{json, Map, Value};
+use taplo::dom::Node;
+
+use super::{
+    bundle::string_of,
+    concepts::{ConceptGraph, Field, FieldType, Target, MAX_REFINES_DEPTH},
+    native_concepts::NativeConcepts,
+};
+
+/// A way a fixture does not match the inputs of its pipe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureError {
+    /// The JSON Pointer of the offending value.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for FixtureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// A placeholder working memory for the `inputs` of `pipe`, a pipe of
+/// `document`. `siblings` are the other documents of the bundle, searched
+/// for the concepts of the inputs.
+///
+/// # Errors
+///
+/// If `document` defines no pipe `pipe`.
+pub fn fixture(
+    document: &Node,
+    siblings: &[Node],
+    pipe: &str,
+    natives: &NativeConcepts,
+) -> anyhow::Result<Value> {
+    let shapes = Shapes {
+        graph: ConceptGraph::new(document, siblings, natives),
+    };
+    let mut memory = Map::new();
+    for input in pipe_inputs(document, pipe)? {
+        let target = shapes.resolve(&input.concept);
+        let content = match input.multiplicity {
+            Multiplicity::One => shapes.example(&target, &mut Vec::new()),
+            Multiplicity::List(count) => (0..count.unwrap_or(1))
+                .map(|_| shapes.example(&target, &mut Vec::new()))
+                .collect(),
+        };
+        memory.insert(
+            input.name,
+            json!({ "concept": shapes.concept_code(&target), "content": content }),
+        );
+    }
+    Ok(Value::Object(memory))
+}
+
+/// How `fixture` does not match the `inputs` of `pipe`, none when it does.
+///
+/// # Errors
+///
+/// If `document` defines no pipe `pipe`.
+pub fn check_fixture(
+    document: &Node,
+    siblings: &[Node],
+    pipe: &str,
+    natives: &NativeConcepts,
+    fixture: &Value,
+) -> anyhow::Result<Vec<FixtureError>> {
+    let shapes = Shapes {
+        graph: ConceptGraph::new(document, siblings, natives),
+    };
+    let inputs = pipe_inputs(document, pipe)?;
+    let mut errors = Errors::default();
+
+    let Some(memory) = fixture.as_object() else {
+        errors.push("", "expected an object keyed by input name");
+        return Ok(errors.0);
+    };
+    for input in &inputs {
+        let path = pointer("", &input.name);
+        match memory.get(&input.name) {
+            Some(value) => shapes.check_input(input, value, &path, &mut errors),
+            None => errors.push(&path, format!("missing input `{}`", input.name)),
+        }
+    }
+    for name in memory.keys() {
+        if !inputs.iter().any(|input| input.name == *name) {
+            errors.push(
+                &pointer("", name),
+                format!("`{name}` is not an input of pipe `{pipe}`"),
+            );
+        }
+    }
+    Ok(errors.0)
+}
+
+/// An input of a pipe, as declared in its `inputs` table.
+struct Input {
+    name: String,
+    /// The concept reference, without its multiplicity.
+    concept: String,
+    multiplicity: Multiplicity,
+}
+
+enum Multiplicity {
+    One,
+    /// `Concept[]`, or `Concept[N]` with a fixed number of items.
+    List(Option<usize>),
+}
+
+fn pipe_inputs(document: &Node, pipe: &str) -> anyhow::Result<Vec<Input>> {
+    let node = document.get("pipe").get(pipe);
+    if node.as_table().is_none() {
+        bail!("no pipe `{pipe}` in the bundle");
+    }
+    let Some(inputs) = node.get("inputs").as_table().cloned() else {
+        return Ok(Vec::new());
+    };
+    let entries = inputs.entries().read();
+    Ok(entries
+        .iter()
+        .filter_map(|(key, value)| {
+            let reference = string_of(value)?;
+            let (concept, multiplicity) = match reference.split_once('[') {
+                Some((concept, rest)) => {
+                    let count = rest.trim_end_matches(']').trim();
+                    (concept.to_string(), Multiplicity::List(count.parse().ok()))
+                }
+                None => (reference.clone(), Multiplicity::One),
+            };
+            Some(Input {
+                name: key.value().to_string(),
+                concept: concept.trim().to_string(),
+                multiplicity,
+            })
+        })
+        .collect())
+}
+
+/// JSON Pointer `parent` extended with `token`.
+fn pointer(parent: &str, token: &str) -> String {
+    format!("{parent}/{}", token.replace('~', "~0").replace('/', "~1"))
+}
+
+#[derive(Default)]
+struct Errors(Vec<FixtureError>);
+
+impl Errors {
+    fn push(&mut self, path: &str, message: impl Into<String>) {
+        self.0.push(FixtureError {
+            path: if path.is_empty() {
+                "/".into()
+            } else {
+                path.into()
+            },
+            message: message.into(),
+        });
+    }
+}
+
+struct Shapes<'a> {
+    graph: ConceptGraph<'a>,
+}
+
+impl Shapes<'_> {
+    fn resolve(&self, reference: &str) -> Target {
+        self.graph.resolve(reference, self.graph.domain.as_deref())
+    }
+
+    /// The concept code of `target` in a working memory: qualified with its
+    /// domain for the bundle's concepts, bare for native ones.
+    fn concept_code(&self, target: &Target) -> String {
+        match target {
+            Target::Bundle(index) => {
+                let concept = &self.graph.concepts[*index];
+                match &concept.domain {
+                    Some(domain) => format!("{domain}.{}", concept.name),
+                    None => concept.name.clone(),
+                }
+            }
+            Target::Native(name) | Target::Foreign(name) => name.clone(),
+        }
+    }
+
+    /// The fields of `target`, those of what it refines first, and whether
+    /// it may hold others.
+    fn fields(&self, target: &Target) -> (Vec<Field>, bool) {
+        let mut fields: Vec<Field> = Vec::new();
+        let mut open = false;
+        let mut chain = vec![self.graph.concept(target)];
+        while let Some(parent) = chain.last().and_then(|concept| concept.parent.clone()) {
+            if chain.len() > MAX_REFINES_DEPTH {
+                open = true;
+                break;
+            }
+            chain.push(self.graph.concept(&parent));
+        }
+        for concept in chain.into_iter().rev() {
+            let Some(own) = concept.fields else {
+                open = true;
+                continue;
+            };
+            for field in own {
+                fields.retain(|known| known.name != field.name);
+                fields.push(field);
+            }
+        }
+        (fields, open)
+    }
+
+    /// Placeholder content for `target`; `stack` holds the concepts being
+    /// exemplified, so that recursive structures stop.
+    fn example(&self, target: &Target, stack: &mut Vec<Target>) -> Value {
+        if stack.contains(target) || stack.len() > MAX_REFINES_DEPTH {
+            return Value::Object(Map::new());
+        }
+        stack.push(target.clone());
+        let content = self
+            .fields(target)
+            .0
+            .iter()
+            .map(|field| (field.name.clone(), self.example_field(field, stack)))
+            .collect();
+        stack.pop();
+        Value::Object(content)
+    }
+
+    fn example_field(&self, field: &Field, stack: &mut Vec<Target>) -> Value {
+        if let Some(default) = &field.default {
+            return default.clone();
+        }
+        if let Some(choice) = field.choices.first() {
+            return match field.ty {
+                FieldType::List(_) => json!([choice]),
+                _ => choice.clone().into(),
+            };
+        }
+        self.example_type(&field.ty, &field.name, stack)
+    }
+
+    fn example_type(&self, ty: &FieldType, name: &str, stack: &mut Vec<Target>) -> Value {
+        match ty {
+            FieldType::Text if name == "url" || name.ends_with("_url") => {
+                format!("https://example.com/{name}").into()
+            }
+            FieldType::Text => format!("example {}", name.replace('_', " ")).into(),
+            FieldType::Integer => 1.into(),
+            FieldType::Number => 1.5.into(),
+            FieldType::Boolean => false.into(),
+            FieldType::Date => "2025-01-01".into(),
+            FieldType::DateTime => "2025-01-01T12:00:00Z".into(),
+            FieldType::Time => "12:00:00".into(),
+            FieldType::Concept(target) => self.example(target, stack),
+            FieldType::List(Some(item)) => json!([self.example_type(item, name, stack)]),
+            FieldType::List(None) => json!([]),
+            FieldType::Dict(_, Some(value)) => {
+                json!({ "key": self.example_type(value, name, stack) })
+            }
+            FieldType::Dict(_, None) => json!({}),
+            FieldType::Union(variants) => variants.first().map_or(Value::Null, |variant| {
+                self.example_type(variant, name, stack)
+            }),
+            FieldType::Any => Value::Null,
+        }
+    }
+
+    fn check_input(&self, input: &Input, value: &Value, path: &str, errors: &mut Errors) {
+        let target = self.resolve(&input.concept);
+        let Some(entry) = value.as_object() else {
+            errors.push(path, "expected an object with `concept` and `content`");
+            return;
+        };
+
+        if let Some(concept) = entry.get("concept") {
+            let matches = concept
+                .as_str()
+                .is_some_and(|concept| self.resolve(concept) == target);
+            if !matches {
+                errors.push(
+                    &pointer(path, "concept"),
+                    format!("expected concept `{}`", self.concept_code(&target)),
+                );
+            }
+        }
+
+        let path = pointer(path, "content");
+        let Some(content) = entry.get("content") else {
+            errors.push(&path, "missing `content`");
+            return;
+        };
+        match input.multiplicity {
+            Multiplicity::One => {
+                self.check_concept(&target, content, &path, errors, &mut Vec::new())
+            }
+            Multiplicity::List(count) => {
+                let Some(items) = content.as_array() else {
+                    errors.push(&path, "expected a list");
+                    return;
+                };
+                if let Some(count) = count.filter(|count| *count != items.len()) {
+                    errors.push(
+                        &path,
+                        format!("expected {count} items, got {}", items.len()),
+                    );
+                }
+                for (index, item) in items.iter().enumerate() {
+                    let path = pointer(&path, &index.to_string());
+                    self.check_concept(&target, item, &path, errors, &mut Vec::new());
+                }
+            }
+        }
+    }
+
+    fn check_concept(
+        &self,
+        target: &Target,
+        value: &Value,
+        path: &str,
+        errors: &mut Errors,
+        stack: &mut Vec<Target>,
+    ) {
+        if stack.contains(target) || stack.len() > MAX_REFINES_DEPTH {
+            return;
+        }
+        let (fields, open) = self.fields(target);
+        if fields.is_empty() && open {
+            return;
+        }
+        let Some(object) = value.as_object() else {
+            errors.push(path, "expected an object");
+            return;
+        };
+
+        stack.push(target.clone());
+        for field in &fields {
+            let path = pointer(path, &field.name);
+            match object.get(&field.name) {
+                Some(Value::Null) if !field.required => {}
+                Some(value) => self.check_field(field, value, &path, errors, stack),
+                None if field.required && field.default.is_none() => {
+                    errors.push(&path, format!("missing required field `{}`", field.name));
+                }
+                None => {}
+            }
+        }
+        stack.pop();
+
+        if !open {
+            for name in object.keys() {
+                if !fields.iter().any(|field| field.name == *name) {
+                    errors.push(&pointer(path, name), format!("unknown field `{name}`"));
+                }
+            }
+        }
+    }
+
+    fn check_field(
+        &self,
+        field: &Field,
+        value: &Value,
+        path: &str,
+        errors: &mut Errors,
+        stack: &mut Vec<Target>,
+    ) {
+        if !field.choices.is_empty() {
+            let values = match value {
+                Value::Array(items) => items.iter().collect(),
+                value => vec![value],
+            };
+            for value in values {
+                if !value
+                    .as_str()
+                    .is_some_and(|value| field.choices.iter().any(|choice| choice == value))
+                {
+                    errors.push(
+                        path,
+                        format!("expected one of `{}`", field.choices.join("`, `")),
+                    );
+                    return;
+                }
+            }
+        }
+        self.check_type(&field.ty, value, path, errors, stack);
+    }
+
+    fn check_type(
+        &self,
+        ty: &FieldType,
+        value: &Value,
+        path: &str,
+        errors: &mut Errors,
+        stack: &mut Vec<Target>,
+    ) {
+        let expected = match ty {
+            FieldType::Text | FieldType::Date | FieldType::DateTime | FieldType::Time => {
+                (!value.is_string()).then_some("a string")
+            }
+            FieldType::Integer => (!value.is_i64() && !value.is_u64()).then_some("an integer"),
+            FieldType::Number => (!value.is_number()).then_some("a number"),
+            FieldType::Boolean => (!value.is_boolean()).then_some("a boolean"),
+            FieldType::Concept(target) => {
+                self.check_concept(target, value, path, errors, stack);
+                None
+            }
+            FieldType::List(item) => match value.as_array() {
+                Some(items) => {
+                    if let Some(item) = item {
+                        for (index, value) in items.iter().enumerate() {
+                            let path = pointer(path, &index.to_string());
+                            self.check_type(item, value, &path, errors, stack);
+                        }
+                    }
+                    None
+                }
+                None => Some("a list"),
+            },
+            FieldType::Dict(_, item) => match value.as_object() {
+                Some(entries) => {
+                    if let Some(item) = item {
+                        for (key, value) in entries {
+                            self.check_type(item, value, &pointer(path, key), errors, stack);
+                        }
+                    }
+                    None
+                }
+                None => Some("an object"),
+            },
+            FieldType::Union(variants) => {
+                let matches = variants.iter().any(|variant| {
+                    let mut variant_errors = Errors::default();
+                    self.check_type(variant, value, path, &mut variant_errors, stack);
+                    variant_errors.0.is_empty()
+                });
+                (!matches).then_some("a value of one of the accepted types")
+            }
+            FieldType::Any => None,
+        };
+        if let Some(expected) = expected {
+            errors.push(path, format!("expected {expected}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taplo::parser;
+
+    const BUNDLE: &str = r#"domain = "reviews"
+
+[concept.Review]
+description = "A customer review"
+
+[concept.Review.structure]
+rating = { type = "integer", description = "From 1 to 5", required = true }
+sentiment = { type = "text", choices = ["positive", "negative"], required = true }
+language = { type = "text", default_value = "en" }
+author = { type = "concept", concept_ref = "Author", required = true }
+
+[concept.Author]
+refines = "Text"
+
+[pipe.analyze]
+type = "PipeLLM"
+description = "Analyze reviews"
+inputs = { review = "Review", photos = "Image[]", pair = "native.Text[2]" }
+output = "Text"
+"#;
+
+    fn document() -> Node {
+        parser::parse(BUNDLE).into_dom()
+    }
+
+    fn check(fixture: &Value) -> Vec<String> {
+        check_fixture(
+            &document(),
+            &[],
+            "analyze",
+            NativeConcepts::embedded(),
+            fixture,
+        )
+        .unwrap()
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+    }
+
+    #[test]
+    fn fixtures_follow_the_concepts_of_the_inputs() {
+        let fixture = fixture(&document(), &[], "analyze", NativeConcepts::embedded()).unwrap();
+        assert_eq!(
+            fixture,
+            json!({
+                "review": {
+                    "concept": "reviews.Review",
+                    "content": {
+                        "rating": 1,
+                        "sentiment": "positive",
+                        "language": "en",
+                        "author": { "text": "example text" }
+                    }
+                },
+                "photos": {
+                    "concept": "Image",
+                    "content": [{
+                        "url": "https://example.com/url",
+                        "filename": "example filename",
+                        "caption": "example caption",
+                        "mime_type": "example mime type"
+                    }]
+                },
+                "pair": {
+                    "concept": "Text",
+                    "content": [{ "text": "example text" }, { "text": "example text" }]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn generated_fixtures_pass_the_check() {
+        let fixture = fixture(&document(), &[], "analyze", NativeConcepts::embedded()).unwrap();
+        assert_eq!(check(&fixture), Vec::<String>::new());
+    }
+
+    #[test]
+    fn mismatches_are_reported_with_their_path() {
+        let errors = check(&json!({
+            "review": {
+                "concept": "Author",
+                "content": { "rating": "five", "sentiment": "mixed", "extra": true }
+            },
+            "photos": { "content": { "url": "https://example.com/a.png" } },
+            "pair": { "concept": "Text", "content": [{ "text": "only one" }] },
+            "unexpected": {}
+        }));
+        assert_eq!(
+            errors,
+            [
+                "/review/concept: expected concept `reviews.Review`",
+                "/review/content/rating: expected an integer",
+                "/review/content/sentiment: expected one of `positive`, `negative`",
+                "/review/content/author: missing required field `author`",
+                "/review/content/extra: unknown field `extra`",
+                "/photos/content: expected a list",
+                "/pair/content: expected 2 items, got 1",
+                "/unexpected: `unexpected` is not an input of pipe `analyze`",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_pipes_are_an_error() {
+        assert!(
+            fixture(&document(), &[], "missing", NativeConcepts::embedded())
+                .unwrap_err()
+                .to_string()
+                .contains("no pipe `missing`")
+        );
+    }
+}
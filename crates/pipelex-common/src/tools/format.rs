@@ -39,7 +39,8 @@ pub struct FormatOutcome {
 /// full struct literal so the baked defaults are self-documenting — and so a new
 /// upstream `formatter::Options` field forces a conscious choice here rather than
 /// silently inheriting taplo's default.
-fn mthds_format_options() -> formatter::Options {
+#[must_use]
+pub fn mthds_format_options() -> formatter::Options {
     formatter::Options {
         align_entries: true,
         align_comments: true,
@@ -133,7 +134,7 @@ pub fn format_mthds_impl(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tools::diagnostic::DiagnosticKind;
+    use crate::tools::diagnostic::{DiagnosticKind, Severity};
 
     const VALID: &str = include_str!("../../../../test-data/mthds/lint/valid.mthds");
     const PIPE_DEFINITIONS: &str =
@@ -206,7 +207,7 @@ mod tests {
         );
         let diag = &outcome.diagnostics[0];
         assert_eq!(diag.kind, DiagnosticKind::Syntax);
-        assert_eq!(diag.severity, "error");
+        assert_eq!(diag.severity, Severity::Error);
         assert!(diag.range.is_some(), "syntax diagnostics are positioned");
     }
 
@@ -0,0 +1,120 @@
+//! Markdown descriptions of MTHDS pipes and concepts.
+//!
+//! The language server shows them when hovering a reference and as the
+//! documentation of completion items, and `plxt doc` writes them into the
+//! bundle documentation, so the editor and the published docs describe a
+//! definition the same way.
+
+use taplo::dom::Node;
+
+use super::native_concepts::{NativeConcept, NativeConcepts};
+
+/// The description of the pipe `name`: its type, description, inputs and
+/// output. Empty when `pipe` is not a table.
+#[must_use]
+pub fn pipe_hover(name: &str, pipe: &Node) -> String {
+    if pipe.as_table().is_none() {
+        return String::new();
+    }
+
+    let mut parts: Vec<String> = Vec::new();
+
+    // Header: **pipe_name** `PipeType`
+    let header = match pipe.get("type").as_str() {
+        Some(pipe_type) => format!("**{name}** `{}`", pipe_type.value()),
+        None => format!("**{name}**"),
+    };
+    parts.push(header);
+
+    if let Some(description) = pipe.get("description").as_str() {
+        if !description.value().is_empty() {
+            parts.push(description.value().to_string());
+        }
+    }
+
+    if let Some(inputs) = pipe.get("inputs").as_table() {
+        let entries = inputs.entries().read();
+        let inputs: Vec<String> = entries
+            .iter()
+            .map(|(key, value)| {
+                let concept = value
+                    .as_str()
+                    .map_or_else(|| "?".to_string(), |s| s.value().to_string());
+                format!("`{}`: {}", key.value(), concept)
+            })
+            .collect();
+        if !inputs.is_empty() {
+            parts.push(format!("**Inputs:** {}", inputs.join(", ")));
+        }
+    }
+
+    if let Some(output) = pipe.get("output").as_str() {
+        if !output.value().is_empty() {
+            parts.push(format!("**Output:** `{}`", output.value()));
+        }
+    }
+
+    parts.join("\n\n")
+}
+
+/// The description of the concept `name`: its description, what it refines
+/// and the names of its structure fields. Empty when `concept` is not a
+/// table.
+#[must_use]
+pub fn concept_hover(name: &str, concept: &Node) -> String {
+    if concept.as_table().is_none() {
+        return String::new();
+    }
+
+    let mut parts: Vec<String> = vec![format!("**{name}**")];
+
+    if let Some(description) = concept.get("description").as_str() {
+        if !description.value().is_empty() {
+            parts.push(description.value().to_string());
+        }
+    }
+
+    if let Some(refines) = concept.get("refines").as_str() {
+        if !refines.value().is_empty() {
+            parts.push(format!("**Refines:** `{}`", refines.value()));
+        }
+    }
+
+    if let Some(structure) = concept.get("structure").as_table() {
+        let entries = structure.entries().read();
+        let fields: Vec<String> = entries
+            .iter()
+            .map(|(key, _)| format!("`{}`", key.value()))
+            .collect();
+        if !fields.is_empty() {
+            parts.push(format!("**Fields:** {}", fields.join(", ")));
+        }
+    }
+
+    parts.join("\n\n")
+}
+
+/// The description of a native (built-in) concept, with the fields it
+/// inherits from the registry's concepts it refines.
+#[must_use]
+pub fn native_concept_hover(concept: &NativeConcept, natives: &NativeConcepts) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    parts.push(format!("**{}** *(native)*", concept.name));
+    parts.push(concept.description.clone());
+
+    if let Some(parent) = &concept.refines {
+        parts.push(format!("**Refines:** `{parent}`"));
+    }
+
+    let fields = natives.fields(&concept.name);
+    if !fields.is_empty() {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(name, ty)| format!("`{name}`: {ty}"))
+            .collect();
+        parts.push(format!("**Fields:** {}", fields.join(", ")));
+    }
+
+    parts.join("\n\n")
+}
@@ -0,0 +1,255 @@
+//! The syntax tree of a template. Ranges are offsets into the template text.
+
+use taplo::rowan::TextRange;
+
+/// A parsed template.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Template {
+    pub nodes: Vec<Node>,
+}
+
+/// A piece of a template.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    /// Literal text, including the body of a `{% raw %}` block.
+    Text(TextRange),
+    /// A Pipelex `@name` / `$name` shorthand.
+    Shorthand(Shorthand),
+    /// `{{ expression }}`.
+    Output(Expr),
+    If(If),
+    For(For),
+    Set(Set),
+    /// `{% with … %}`, `{% filter … %}`, `{% macro … %}`, `{% call … %}`,
+    /// `{% block … %}`, `{% autoescape … %}` and `{% raw %}`.
+    Block(Block),
+}
+
+/// A Pipelex `@name.field` / `$name.field` shorthand: `@` renders the
+/// variable as a tagged block, `$` inline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shorthand {
+    pub sigil: char,
+    /// The variable and its `.field` segments.
+    pub path: Vec<Name>,
+    /// The whole shorthand, sigil included.
+    pub range: TextRange,
+}
+
+/// `{% if %}` … `{% elif %}` … `{% else %}` … `{% endif %}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct If {
+    /// Each condition with its body: the `if`, then every `elif`.
+    pub branches: Vec<(Expr, Vec<Node>)>,
+    pub otherwise: Option<Vec<Node>>,
+    /// From the opening tag to the closing one.
+    pub range: TextRange,
+}
+
+/// `{% for target in iter if condition %}` … `{% else %}` … `{% endfor %}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct For {
+    pub targets: Vec<Name>,
+    pub iter: Expr,
+    pub condition: Option<Expr>,
+    pub body: Vec<Node>,
+    /// Rendered when `iter` is empty.
+    pub otherwise: Option<Vec<Node>>,
+    pub range: TextRange,
+}
+
+/// `{% set target = value %}`, or the block form `{% set target %}` …
+/// `{% endset %}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Set {
+    /// A name, or a `namespace.attribute`.
+    pub targets: Vec<Expr>,
+    pub value: SetValue,
+    pub range: TextRange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetValue {
+    Expr(Expr),
+    /// The rendered body, through the filters written after the target.
+    Block {
+        filters: Vec<Filter>,
+        body: Vec<Node>,
+    },
+}
+
+/// A block tag this parser only checks for balance and, for `with` and
+/// `filter`, for the syntax of its header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub tag: Name,
+    /// The `name = value` assignments of a `with`.
+    pub assignments: Vec<(Name, Expr)>,
+    /// The filters of a `filter` block.
+    pub filters: Vec<Filter>,
+    pub body: Vec<Node>,
+    pub range: TextRange,
+}
+
+/// A name in an expression or a tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name {
+    pub name: String,
+    pub range: TextRange,
+}
+
+/// A filter application, `| name(args)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub name: Name,
+    pub args: Vec<Argument>,
+}
+
+/// A positional or `name=value` argument of a call, filter or test.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub name: Option<Name>,
+    pub value: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+    Pos,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    NotIn,
+    /// `~`, string concatenation.
+    Concat,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    FloorDiv,
+    Rem,
+    Pow,
+}
+
+/// An expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Name(Name),
+    Literal(Literal, TextRange),
+    List(Vec<Expr>, TextRange),
+    Tuple(Vec<Expr>, TextRange),
+    Dict(Vec<(Expr, Expr)>, TextRange),
+    /// `target.attribute`.
+    Attribute {
+        target: Box<Expr>,
+        attribute: Name,
+    },
+    /// `target[index]`.
+    Item {
+        target: Box<Expr>,
+        index: Box<Expr>,
+        range: TextRange,
+    },
+    Call {
+        target: Box<Expr>,
+        args: Vec<Argument>,
+        range: TextRange,
+    },
+    /// `target | filter`.
+    Filter {
+        target: Box<Expr>,
+        filter: Filter,
+        range: TextRange,
+    },
+    /// `target is [not] test`.
+    Test {
+        target: Box<Expr>,
+        test: Name,
+        args: Vec<Argument>,
+        negated: bool,
+        range: TextRange,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+        range: TextRange,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// `then if condition else otherwise`.
+    Conditional {
+        then: Box<Expr>,
+        condition: Box<Expr>,
+        otherwise: Option<Box<Expr>>,
+    },
+}
+
+impl Node {
+    /// The range the node covers.
+    #[must_use]
+    pub fn range(&self) -> TextRange {
+        match self {
+            Node::Text(range) => *range,
+            Node::Shorthand(Shorthand { range, .. })
+            | Node::If(If { range, .. })
+            | Node::For(For { range, .. })
+            | Node::Set(Set { range, .. })
+            | Node::Block(Block { range, .. }) => *range,
+            Node::Output(expr) => expr.range(),
+        }
+    }
+}
+
+impl Expr {
+    /// The range the expression covers.
+    #[must_use]
+    pub fn range(&self) -> TextRange {
+        match self {
+            Expr::Name(name) => name.range,
+            Expr::Literal(_, range)
+            | Expr::List(_, range)
+            | Expr::Tuple(_, range)
+            | Expr::Dict(_, range)
+            | Expr::Item { range, .. }
+            | Expr::Call { range, .. }
+            | Expr::Filter { range, .. }
+            | Expr::Test { range, .. }
+            | Expr::Unary { range, .. } => *range,
+            Expr::Attribute { target, attribute } => target.range().cover(attribute.range),
+            Expr::Binary { left, right, .. } => left.range().cover(right.range()),
+            Expr::Conditional {
+                then,
+                condition,
+                otherwise,
+            } => {
+                let range = then.range().cover(condition.range());
+                otherwise
+                    .as_ref()
+                    .map_or(range, |otherwise| range.cover(otherwise.range()))
+            }
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Splits a template into text, Pipelex shorthands, tag delimiters and the
+//! tokens of the expressions and statements between them.
+
+use taplo::rowan::{TextRange, TextSize};
+
+use super::TemplateError;
+
+/// What a [`Token`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Literal text outside of any tag, or inside a `{% raw %}` block.
+    Text,
+    /// A Pipelex `@name` / `$name` shorthand with its `.field` segments,
+    /// sigil included.
+    Shorthand,
+    /// `{{`, with its `-` / `+` whitespace control.
+    ExpressionOpen,
+    /// `}}`, with its `-` / `+` whitespace control.
+    ExpressionClose,
+    /// `{%`, with its `-` / `+` whitespace control.
+    StatementOpen,
+    /// `%}`, with its `-` / `+` whitespace control.
+    StatementClose,
+    /// A whole `{# … #}` comment.
+    Comment,
+    Name,
+    String,
+    Integer,
+    Float,
+    /// An operator or a punctuation sign: `|`, `.`, `(`, `==`, `~`, …
+    Operator,
+}
+
+/// A token of a template, located in the template text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: TextRange,
+}
+
+impl Token {
+    /// The text of the token in `source`.
+    #[must_use]
+    pub fn text<'s>(&self, source: &'s str) -> &'s str {
+        &source[self.range]
+    }
+}
+
+/// Longest first, so `**` wins over `*`.
+const OPERATORS: &[&str] = &[
+    "**", "//", "==", "!=", "<=", ">=", "+", "-", "*", "/", "%", "~", "<", ">", "=", "(", ")", "[",
+    "]", "{", "}", ",", ".", ":", "|",
+];
+
+/// Tokenize `source`. Lexical errors — unclosed tags and comments,
+/// unterminated strings, stray characters — are reported and skipped, so the
+/// tokens always cover what could be read.
+#[must_use]
+pub fn tokenize(source: &str) -> (Vec<Token>, Vec<TemplateError>) {
+    let mut lexer = Lexer {
+        source,
+        at: 0,
+        tokens: Vec::new(),
+        errors: Vec::new(),
+    };
+    lexer.run();
+    (lexer.tokens, lexer.errors)
+}
+
+/// Whether `c` may continue a name.
+#[must_use]
+pub fn is_name(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn range(start: usize, end: usize) -> TextRange {
+    TextRange::new(offset(start), offset(end))
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn offset(at: usize) -> TextSize {
+    TextSize::from(at as u32)
+}
+
+struct Lexer<'s> {
+    source: &'s str,
+    at: usize,
+    tokens: Vec<Token>,
+    errors: Vec<TemplateError>,
+}
+
+impl Lexer<'_> {
+    fn run(&mut self) {
+        while self.at < self.source.len() {
+            let rest = &self.source[self.at..];
+            let Some(open) = find_tag(rest) else {
+                self.text(self.source.len());
+                break;
+            };
+            self.text(self.at + open);
+            match &rest[open..open + 2] {
+                "{#" => self.comment(),
+                "{{" => self.tag(TokenKind::ExpressionOpen, TokenKind::ExpressionClose, "}}"),
+                _ => {
+                    let first = self.tokens.len();
+                    self.tag(TokenKind::StatementOpen, TokenKind::StatementClose, "%}");
+                    if self.is_raw(first) {
+                        self.raw();
+                    }
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize, end: usize) {
+        self.tokens.push(Token {
+            kind,
+            range: range(start, end),
+        });
+    }
+
+    fn error(&mut self, message: impl Into<String>, start: usize, end: usize) {
+        self.errors.push(TemplateError {
+            message: message.into(),
+            range: range(start, end),
+        });
+    }
+
+    /// The text up to `end`, split around its shorthands.
+    fn text(&mut self, end: usize) {
+        let text = &self.source[self.at..end];
+        let mut start = self.at;
+        let mut previous = None;
+        let mut chars = text.char_indices().peekable();
+        while let Some((at, c)) = chars.next() {
+            let after_word = previous.is_some_and(is_name);
+            previous = Some(c);
+            if !matches!(c, '@' | '$') || after_word {
+                continue;
+            }
+            let len = shorthand_len(&text[at + 1..]);
+            if len == 0 {
+                continue;
+            }
+            let at = self.at + at;
+            if start < at {
+                self.push(TokenKind::Text, start, at);
+            }
+            start = at + 1 + len;
+            self.push(TokenKind::Shorthand, at, start);
+            while chars.next_if(|(next, _)| self.at + next < start).is_some() {}
+            previous = self.source[..start].chars().next_back();
+        }
+        if start < end {
+            self.push(TokenKind::Text, start, end);
+        }
+        self.at = end;
+    }
+
+    fn comment(&mut self) {
+        let start = self.at;
+        match self.source[start + 2..].find("#}") {
+            Some(len) => {
+                self.at = start + 2 + len + 2;
+                self.push(TokenKind::Comment, start, self.at);
+            }
+            None => {
+                self.error("unclosed comment: expected `#}`", start, start + 2);
+                self.at = self.source.len();
+                self.push(TokenKind::Comment, start, self.at);
+            }
+        }
+    }
+
+    /// A `{{ … }}` or `{% … %}` tag, from its opening delimiter on.
+    fn tag(&mut self, open: TokenKind, close: TokenKind, closer: &str) {
+        let start = self.at;
+        let mut end = start + 2;
+        if self.source[end..].starts_with(['-', '+']) {
+            end += 1;
+        }
+        self.push(open, start, end);
+        self.at = end;
+
+        loop {
+            let rest = &self.source[self.at..];
+            let trimmed = rest.trim_start();
+            self.at += rest.len() - trimmed.len();
+            if trimmed.is_empty() {
+                let delimiter = &self.source[start..start + 2];
+                self.error(
+                    format!("unclosed `{delimiter}`: expected `{closer}`"),
+                    start,
+                    start + 2,
+                );
+                return;
+            }
+            for control in ["-", "+", ""] {
+                if trimmed.starts_with(control) && trimmed[control.len()..].starts_with(closer) {
+                    let end = self.at + control.len() + 2;
+                    self.push(close, self.at, end);
+                    self.at = end;
+                    return;
+                }
+            }
+            self.expression_token(trimmed);
+        }
+    }
+
+    /// One token inside a tag, at the start of `rest`.
+    fn expression_token(&mut self, rest: &str) {
+        let start = self.at;
+        let c = rest.chars().next().unwrap_or_default();
+        if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest.find(|c: char| !is_name(c)).unwrap_or(rest.len());
+            self.at += len;
+            self.push(TokenKind::Name, start, self.at);
+        } else if c.is_ascii_digit() {
+            let digits = |s: &str| {
+                s.find(|c: char| !c.is_ascii_digit() && c != '_')
+                    .unwrap_or(s.len())
+            };
+            let mut len = digits(rest);
+            let mut kind = TokenKind::Integer;
+            if rest[len..].starts_with('.')
+                && rest[len + 1..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                len += 1 + digits(&rest[len + 1..]);
+                kind = TokenKind::Float;
+            }
+            self.at += len;
+            self.push(kind, start, self.at);
+        } else if c == '"' || c == '\'' {
+            let mut escaped = false;
+            let end = rest[1..].char_indices().find_map(|(at, next)| {
+                let closes = next == c && !escaped;
+                escaped = next == '\\' && !escaped;
+                closes.then_some(at + 2)
+            });
+            match end {
+                Some(len) => {
+                    self.at += len;
+                    self.push(TokenKind::String, start, self.at);
+                }
+                None => {
+                    // The rest of the tag is the string; the tag is unclosed.
+                    self.error("unterminated string", start, start + 1);
+                    self.at = self.source.len();
+                }
+            }
+        } else if let Some(operator) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            self.at += operator.len();
+            self.push(TokenKind::Operator, start, self.at);
+        } else {
+            self.at += c.len_utf8();
+            self.error(format!("unexpected character `{c}`"), start, self.at);
+        }
+    }
+
+    /// Whether the tokens from `first` are a complete `{% raw %}` tag.
+    fn is_raw(&self, first: usize) -> bool {
+        matches!(
+            &self.tokens[first..],
+            [_, name, close]
+                if name.text(self.source) == "raw" && close.kind == TokenKind::StatementClose
+        )
+    }
+
+    /// The body of a `{% raw %}` block, up to its `{% endraw %}`, as text.
+    fn raw(&mut self) {
+        let start = self.at;
+        let mut search = start;
+        while let Some(open) = self.source[search..].find("{%") {
+            let tag = search + open;
+            let rest = self.source[tag + 2..]
+                .trim_start_matches(['-', '+'])
+                .trim_start();
+            if rest.starts_with("endraw") && !rest["endraw".len()..].starts_with(is_name) {
+                if start < tag {
+                    self.push(TokenKind::Text, start, tag);
+                }
+                self.at = tag;
+                return;
+            }
+            search = tag + 2;
+        }
+        // No `{% endraw %}`: the parser reports the unclosed block.
+        self.push(TokenKind::Text, start, self.source.len());
+        self.at = self.source.len();
+    }
+}
+
+/// The offset of the next `{{`, `{%` or `{#` in `text`.
+fn find_tag(text: &str) -> Option<usize> {
+    let mut search = 0;
+    while let Some(open) = text[search..].find('{') {
+        let at = search + open;
+        if text[at + 1..].starts_with(['{', '%', '#']) {
+            return Some(at);
+        }
+        search = at + 1;
+    }
+    None
+}
+
+/// The length of the `name.field…` path of a shorthand, after its sigil; 0
+/// when no name follows the sigil.
+fn shorthand_len(rest: &str) -> usize {
+    if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return 0;
+    }
+    let len = rest
+        .find(|c: char| !is_name(c) && c != '.')
+        .unwrap_or(rest.len());
+    rest[..len].trim_end_matches('.').len()
+}
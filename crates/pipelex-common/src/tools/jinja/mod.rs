@@ -0,0 +1,348 @@
+//! A small Jinja lexer and parser for the templates embedded in MTHDS pipes.
+//!
+//! The `prompt`, `system_prompt`, `template` and `expression_template` of a
+//! pipe are Jinja2 templates, plus the Pipelex `@name` / `$name` shorthands.
+//! [`parse`] reads one into an [`ast::Template`] and reports its syntax
+//! errors: unclosed tags and blocks, stray end tags, unknown tags, malformed
+//! expressions and filters. [`variables::variables`] lists the context
+//! variables a parsed template reads, and [`render::render`] evaluates it for
+//! previews.
+//!
+//! Ranges are offsets into the template *value*, which differs from the
+//! document text as soon as the TOML string holds escapes. A
+//! [`TemplateSource`] maps them back to the document, through the same escape
+//! handling taplo uses to read the string.
+
+pub mod ast;
+pub mod lexer;
+mod parser;
+pub mod render;
+pub mod variables;
+
+use taplo::{
+    dom::{node::DomNode, Node},
+    rowan::{TextRange, TextSize},
+    syntax::SyntaxKind,
+    util::unescape_with_offsets,
+};
+
+use self::{ast::Template, lexer::Token};
+
+/// The pipe fields whose string value is a template.
+pub const TEMPLATE_FIELDS: &[&str] = &[
+    "prompt",
+    "system_prompt",
+    "negative_prompt",
+    "template",
+    "expression_template",
+];
+
+/// A syntax error in a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    pub message: String,
+    pub range: TextRange,
+}
+
+/// A tokenized and parsed template.
+#[derive(Debug, Clone)]
+pub struct Parse {
+    pub tokens: Vec<Token>,
+    pub template: Template,
+    /// Lexical and syntax errors, in template order.
+    pub errors: Vec<TemplateError>,
+}
+
+/// Tokenize and parse the template `source`.
+#[must_use]
+pub fn parse(source: &str) -> Parse {
+    let (tokens, mut errors) = lexer::tokenize(source);
+    let (template, syntax_errors) = parser::parse(source, &tokens);
+    errors.extend(syntax_errors);
+    errors.sort_by_key(|error| error.range.start());
+    Parse {
+        tokens,
+        template,
+        errors,
+    }
+}
+
+/// The template strings of `pipe`: its [`TEMPLATE_FIELDS`] holding a string,
+/// and the `template` of a `template = { template = "…", … }` table.
+#[must_use]
+pub fn pipe_templates(pipe: &Node) -> Vec<(&'static str, Node)> {
+    TEMPLATE_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let value = pipe.get(*field);
+            if value.as_table().is_some() {
+                let template = value.get("template");
+                return template.as_str().is_some().then_some((*field, template));
+            }
+            value.as_str().is_some().then_some((*field, value))
+        })
+        .collect()
+}
+
+/// The value of a TOML string holding a template, with the map from offsets
+/// in the value back to offsets in the document.
+#[derive(Debug, Clone)]
+pub struct TemplateSource {
+    pub text: String,
+    /// The document offset of every byte of `text`, and of its end.
+    offsets: Vec<TextSize>,
+}
+
+impl TemplateSource {
+    /// The template held by the string `node`; `None` for other values and
+    /// for strings with invalid escapes.
+    #[must_use]
+    pub fn from_node(node: &Node) -> Option<Self> {
+        node.as_str()?;
+        let token = node.syntax()?.as_token()?.clone();
+        let raw = token.text();
+        let (delimiter, escaped) = match token.kind() {
+            SyntaxKind::STRING => ("\"", true),
+            SyntaxKind::STRING_LITERAL => ("'", false),
+            SyntaxKind::MULTI_LINE_STRING => ("\"\"\"", true),
+            SyntaxKind::MULTI_LINE_STRING_LITERAL => ("'''", false),
+            _ => return None,
+        };
+        let inner = raw.strip_prefix(delimiter)?;
+        let mut start = delimiter.len();
+        // Like taplo, drop the newline right after an opening `"""` / `'''`.
+        if delimiter.len() == 3 {
+            let trimmed = inner
+                .strip_prefix("\r\n")
+                .or_else(|| inner.strip_prefix('\n'))
+                .unwrap_or(inner);
+            start += inner.len() - trimmed.len();
+        }
+        let inner = raw[start..].strip_suffix(delimiter)?;
+
+        let (text, offsets) = if escaped {
+            unescape_with_offsets(inner).ok()?
+        } else {
+            (inner.to_string(), (0..=inner.len()).collect())
+        };
+        let base = token.text_range().start() + TextSize::from(start as u32);
+        Some(Self {
+            text,
+            offsets: offsets
+                .into_iter()
+                .map(|offset| base + TextSize::from(offset as u32))
+                .collect(),
+        })
+    }
+
+    /// The document offset of `offset` in the template.
+    #[must_use]
+    pub fn document_offset(&self, offset: TextSize) -> TextSize {
+        let index = usize::from(offset).min(self.offsets.len() - 1);
+        self.offsets[index]
+    }
+
+    /// The offset in the template of the document offset `offset`; `None`
+    /// outside of the template and inside an escape sequence.
+    #[must_use]
+    pub fn template_offset(&self, offset: TextSize) -> Option<TextSize> {
+        let index = self.offsets.partition_point(|known| *known < offset);
+        (self.offsets.get(index) == Some(&offset)).then(|| TextSize::from(index as u32))
+    }
+
+    /// The document range of `range` in the template.
+    #[must_use]
+    pub fn document_range(&self, range: TextRange) -> TextRange {
+        TextRange::new(
+            self.document_offset(range.start()),
+            self.document_offset(range.end()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ast::{BinaryOp, Expr, Literal, Node as TemplateNode};
+    use super::*;
+
+    fn messages(source: &str) -> Vec<String> {
+        parse(source)
+            .errors
+            .into_iter()
+            .map(|error| format!("{}: {}", &source[error.range], error.message))
+            .collect()
+    }
+
+    #[test]
+    fn parses_text_shorthands_and_expressions() {
+        let source = "Summarize @doc.title in $style: {{ items | join(', ') }}";
+        let parsed = parse(source);
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        let nodes = &parsed.template.nodes;
+        assert_eq!(nodes.len(), 6);
+        let TemplateNode::Shorthand(shorthand) = &nodes[1] else {
+            panic!("expected a shorthand, got {:?}", nodes[1]);
+        };
+        assert_eq!(shorthand.sigil, '@');
+        let path: Vec<_> = shorthand
+            .path
+            .iter()
+            .map(|name| &source[name.range])
+            .collect();
+        assert_eq!(path, ["doc", "title"]);
+        let TemplateNode::Output(Expr::Filter { filter, .. }) = &nodes[5] else {
+            panic!("expected a filter, got {:?}", nodes[5]);
+        };
+        assert_eq!(filter.name.name, "join");
+        assert_eq!(filter.args.len(), 1);
+    }
+
+    #[test]
+    fn parses_blocks() {
+        let source = "{% if a and not b %}x{% elif c %}y{% else %}z{% endif %}\
+{% for k, v in items if v %}{{ loop.index }}{% else %}none{% endfor %}\
+{% set total = a + b * 2 %}{% set block | upper %}t{% endset %}\
+{% raw %}{{ not parsed {% endraw %}{# a comment #}";
+        let parsed = parse(source);
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        let nodes = &parsed.template.nodes;
+        let TemplateNode::If(branches) = &nodes[0] else {
+            panic!("expected an if, got {:?}", nodes[0]);
+        };
+        assert_eq!(branches.branches.len(), 2);
+        assert!(branches.otherwise.is_some());
+        let TemplateNode::For(for_block) = &nodes[1] else {
+            panic!("expected a for, got {:?}", nodes[1]);
+        };
+        assert_eq!(for_block.targets.len(), 2);
+        assert!(for_block.condition.is_some());
+        let TemplateNode::Set(set) = &nodes[2] else {
+            panic!("expected a set, got {:?}", nodes[2]);
+        };
+        let ast::SetValue::Expr(Expr::Binary { op, right, .. }) = &set.value else {
+            panic!("expected a binary expression, got {:?}", set.value);
+        };
+        assert_eq!(*op, BinaryOp::Add);
+        assert!(matches!(
+            **right,
+            Expr::Binary {
+                op: BinaryOp::Mul,
+                ..
+            }
+        ));
+        let TemplateNode::Block(raw) = &nodes[4] else {
+            panic!("expected a raw block, got {:?}", nodes[4]);
+        };
+        assert!(
+            matches!(raw.body[..], [TemplateNode::Text(range)] if &source[range] == "{{ not parsed ")
+        );
+    }
+
+    #[test]
+    fn parses_literals_and_tests() {
+        let parsed = parse("{{ x if n is divisibleby 3 else [1, 2.5, 'a' \"b\", none] }}");
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        let TemplateNode::Output(Expr::Conditional {
+            condition,
+            otherwise,
+            ..
+        }) = &parsed.template.nodes[0]
+        else {
+            panic!("expected a conditional");
+        };
+        assert!(matches!(**condition, Expr::Test { ref args, .. } if args.len() == 1));
+        let Some(otherwise) = otherwise else {
+            panic!("expected an else branch");
+        };
+        let Expr::List(items, _) = &**otherwise else {
+            panic!("expected a list");
+        };
+        assert_eq!(
+            items[2],
+            Expr::Literal(Literal::String("ab".into()), items[2].range())
+        );
+        assert!(matches!(items[3], Expr::Literal(Literal::None, _)));
+    }
+
+    #[test]
+    fn reports_unclosed_tags_and_blocks() {
+        assert_eq!(
+            messages("Hello {{ name"),
+            ["{{: unclosed `{{`: expected `}}`"]
+        );
+        assert_eq!(
+            messages("{% if a %}yes"),
+            ["{% if: unclosed `{% if %}` block: expected `{% endif %}`"]
+        );
+        assert_eq!(
+            messages("{% for x in xs %}{% if x %}{% endfor %}"),
+            [
+                "{% for: unclosed `{% for %}` block: expected `{% endfor %}`",
+                "{% if: unclosed `{% if %}` block: expected `{% endif %}`",
+                "endfor: unexpected `{% endfor %}`: no open block takes it",
+            ]
+        );
+        assert_eq!(messages("{# note"), ["{#: unclosed comment: expected `#}`"]);
+    }
+
+    #[test]
+    fn reports_bad_filters_and_expressions() {
+        assert_eq!(
+            messages("{{ text | }}"),
+            ["}}: expected a filter name after `|`"]
+        );
+        assert_eq!(
+            messages("{{ text | truncate(10 }}"),
+            ["}}: expected `,` or `)`"]
+        );
+        assert_eq!(messages("{{ a b }}"), ["b: unexpected `b`"]);
+        assert_eq!(
+            messages("{{ }}"),
+            ["}}: expected an expression before `}}`"]
+        );
+        assert_eq!(
+            messages("{% frobnicate %}"),
+            ["frobnicate: unknown tag `frobnicate`"]
+        );
+    }
+
+    #[test]
+    fn keeps_parsing_after_an_error() {
+        let parsed = parse("{{ a | }} then {{ b }}");
+        assert_eq!(parsed.errors.len(), 1);
+        assert!(matches!(
+            parsed.template.nodes.last(),
+            Some(TemplateNode::Output(Expr::Name(name))) if name.name == "b"
+        ));
+    }
+
+    #[test]
+    fn maps_ranges_through_escapes() {
+        let document = "prompt = \"Say \\\"hi\\\" to {{ name | }}\"\n";
+        let dom = taplo::parser::parse(document).into_dom();
+        let source = TemplateSource::from_node(&dom.get("prompt")).unwrap();
+        assert_eq!(source.text, "Say \"hi\" to {{ name | }}");
+        let error = &parse(&source.text).errors[0];
+        assert_eq!(&document[source.document_range(error.range)], "}}");
+
+        let to = |text: &str, needle: &str| TextSize::from(text.find(needle).unwrap() as u32);
+        assert_eq!(
+            source.template_offset(to(document, "to")),
+            Some(to(&source.text, "to"))
+        );
+        assert_eq!(
+            source.template_offset(to(document, "\\\"") + TextSize::from(1)),
+            None
+        );
+        assert_eq!(source.template_offset(TextSize::from(0)), None);
+    }
+
+    #[test]
+    fn maps_ranges_of_multi_line_strings() {
+        let document = "prompt = '''\n{% if x %}\n'''\n";
+        let dom = taplo::parser::parse(document).into_dom();
+        let source = TemplateSource::from_node(&dom.get("prompt")).unwrap();
+        let error = &parse(&source.text).errors[0];
+        assert_eq!(&document[source.document_range(error.range)], "{% if");
+    }
+}
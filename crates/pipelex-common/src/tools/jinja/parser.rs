@@ -0,0 +1,956 @@
+//! A recursive-descent parser from the [`lexer`](super::lexer)'s tokens to
+//! the [`ast`](super::ast), following Jinja's grammar and operator
+//! precedence.
+//!
+//! Errors are collected rather than returned: a tag that fails to parse is
+//! skipped up to its closing delimiter and parsing goes on, so one typo does
+//! not hide the rest of the template.
+
+use taplo::rowan::{TextRange, TextSize};
+
+use super::ast::{
+    Argument, BinaryOp, Block, Expr, Filter, For, If, Literal, Name, Node, Set, SetValue,
+    Shorthand, Template, UnaryOp,
+};
+use super::lexer::{Token, TokenKind};
+use super::TemplateError;
+
+/// Words that end an expression rather than name a variable.
+const KEYWORDS: &[&str] = &["and", "or", "not", "in", "is", "if", "else"];
+
+/// Tags without a body.
+const STANDALONE_TAGS: &[&str] = &[
+    "include", "import", "from", "extends", "do", "break", "continue",
+];
+
+/// Block tags whose header is not checked.
+const OPAQUE_BLOCKS: &[&str] = &["macro", "call", "block", "autoescape"];
+
+pub(super) fn parse(source: &str, tokens: &[Token]) -> (Template, Vec<TemplateError>) {
+    let mut parser = Parser {
+        source,
+        tokens: tokens
+            .iter()
+            .filter(|token| token.kind != TokenKind::Comment)
+            .copied()
+            .collect(),
+        pos: 0,
+        last_end: TextSize::default(),
+        errors: Vec::new(),
+    };
+    let (nodes, _) = parser.nodes(&[]);
+    (Template { nodes }, parser.errors)
+}
+
+struct Parser<'s> {
+    source: &'s str,
+    tokens: Vec<Token>,
+    pos: usize,
+    /// The end of the last token consumed.
+    last_end: TextSize,
+    errors: Vec<TemplateError>,
+}
+
+impl<'s> Parser<'s> {
+    // --- Token helpers ---
+
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn peek_text(&self) -> &'s str {
+        self.peek().map_or("", |token| token.text(self.source))
+    }
+
+    fn bump(&mut self) -> Option<Token> {
+        let token = self.peek()?;
+        self.pos += 1;
+        self.last_end = token.range.end();
+        Some(token)
+    }
+
+    fn at_op(&self, op: &str) -> bool {
+        self.peek()
+            .is_some_and(|token| token.kind == TokenKind::Operator && token.text(self.source) == op)
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        self.peek().is_some_and(|token| {
+            token.kind == TokenKind::Name && token.text(self.source) == keyword
+        })
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        self.at_op(op) && self.bump().is_some()
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.at_keyword(keyword) && self.bump().is_some()
+    }
+
+    fn at_tag_end(&self) -> bool {
+        self.peek().map_or(true, |token| {
+            matches!(
+                token.kind,
+                TokenKind::ExpressionClose | TokenKind::StatementClose
+            )
+        })
+    }
+
+    /// Report `message` at the next token. At the end of the template the
+    /// lexer has already reported the unclosed tag, so nothing is added.
+    fn error(&mut self, message: impl Into<String>) {
+        if let Some(token) = self.peek() {
+            self.errors.push(TemplateError {
+                message: message.into(),
+                range: token.range,
+            });
+        }
+    }
+
+    fn expect_op(&mut self, op: &str) -> Option<()> {
+        if self.eat_op(op) {
+            Some(())
+        } else {
+            self.error(format!("expected `{op}`"));
+            None
+        }
+    }
+
+    fn name(&mut self) -> Option<Name> {
+        let token = self.peek()?;
+        if token.kind != TokenKind::Name {
+            return None;
+        }
+        self.bump();
+        Some(Name {
+            name: token.text(self.source).to_string(),
+            range: token.range,
+        })
+    }
+
+    /// Skip the rest of the current tag, its closing delimiter included.
+    fn recover(&mut self) {
+        while !self.at_tag_end() {
+            self.bump();
+        }
+        self.bump();
+    }
+
+    /// Consume the closing delimiter of the current tag, reporting and
+    /// skipping anything left before it.
+    fn close_tag(&mut self) {
+        match self.peek() {
+            None => {}
+            Some(token)
+                if matches!(
+                    token.kind,
+                    TokenKind::ExpressionClose | TokenKind::StatementClose
+                ) =>
+            {
+                self.bump();
+            }
+            Some(_) => {
+                self.error(format!("unexpected `{}`", self.peek_text()));
+                self.recover();
+            }
+        }
+    }
+
+    /// Parse the rest of a tag with `f`, then its closing delimiter.
+    fn header<T>(&mut self, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let parsed = f(self);
+        if parsed.is_some() {
+            self.close_tag();
+        } else {
+            self.recover();
+        }
+        parsed
+    }
+
+    // --- Template structure ---
+
+    /// The nodes up to one of the `ends` tags, which is returned with the
+    /// start of its `{%` — the rest of that tag is left to the caller.
+    fn nodes(&mut self, ends: &[&str]) -> (Vec<Node>, Option<(Name, TextSize)>) {
+        let mut nodes = Vec::new();
+        while let Some(token) = self.bump() {
+            match token.kind {
+                TokenKind::Text => nodes.push(Node::Text(token.range)),
+                TokenKind::Shorthand => nodes.push(Node::Shorthand(self.shorthand(token))),
+                TokenKind::ExpressionOpen => {
+                    if let Some(expr) = self.header(Self::expr) {
+                        nodes.push(Node::Output(expr));
+                    }
+                }
+                TokenKind::StatementOpen => {
+                    let Some(tag) = self.name() else {
+                        self.error("expected a tag name");
+                        self.recover();
+                        continue;
+                    };
+                    if ends.contains(&tag.name.as_str()) {
+                        return (nodes, Some((tag, token.range.start())));
+                    }
+                    if let Some(node) = self.statement(token.range.start(), tag) {
+                        nodes.push(node);
+                    }
+                }
+                _ => {}
+            }
+        }
+        (nodes, None)
+    }
+
+    fn shorthand(&self, token: Token) -> Shorthand {
+        let text = token.text(self.source);
+        let mut start = token.range.start() + TextSize::from(1);
+        let path = text[1..]
+            .split('.')
+            .map(|segment| {
+                let range = TextRange::at(start, TextSize::of(segment));
+                start = range.end() + TextSize::from(1);
+                Name {
+                    name: segment.to_string(),
+                    range,
+                }
+            })
+            .collect();
+        Shorthand {
+            sigil: text.chars().next().unwrap_or('@'),
+            path,
+            range: token.range,
+        }
+    }
+
+    /// A statement tag, from the `{%` at `open` and its tag name on.
+    fn statement(&mut self, open: TextSize, tag: Name) -> Option<Node> {
+        match tag.name.as_str() {
+            "if" => Some(self.if_block(open, &tag)),
+            "for" => Some(self.for_block(open, &tag)),
+            "set" => self.set(open, &tag),
+            "with" => {
+                let assignments = self.header(Self::assignments).unwrap_or_default();
+                self.block(open, tag, assignments, Vec::new())
+            }
+            "filter" => {
+                let filters = self.header(Self::filter_chain).unwrap_or_default();
+                self.block(open, tag, Vec::new(), filters)
+            }
+            "raw" => {
+                self.close_tag();
+                self.block(open, tag, Vec::new(), Vec::new())
+            }
+            name if OPAQUE_BLOCKS.contains(&name) => {
+                self.recover();
+                self.block(open, tag, Vec::new(), Vec::new())
+            }
+            name if STANDALONE_TAGS.contains(&name) => {
+                self.recover();
+                None
+            }
+            name if name.starts_with("end") || matches!(name, "elif" | "else") => {
+                self.errors.push(TemplateError {
+                    message: format!("unexpected `{{% {name} %}}`: no open block takes it"),
+                    range: tag.range,
+                });
+                self.recover();
+                None
+            }
+            name => {
+                self.errors.push(TemplateError {
+                    message: format!("unknown tag `{name}`"),
+                    range: tag.range,
+                });
+                self.recover();
+                None
+            }
+        }
+    }
+
+    /// Report a block that reached the end of the template unclosed.
+    fn unclosed(&mut self, open: TextSize, tag: &Name) {
+        self.errors.push(TemplateError {
+            message: format!(
+                "unclosed `{{% {0} %}}` block: expected `{{% end{0} %}}`",
+                tag.name
+            ),
+            range: TextRange::new(open, tag.range.end()),
+        });
+    }
+
+    fn if_block(&mut self, open: TextSize, tag: &Name) -> Node {
+        let mut condition = self.condition();
+        let mut branches = Vec::new();
+        let mut otherwise = None;
+        loop {
+            let (body, end) = self.nodes(&["elif", "else", "endif"]);
+            branches.push((condition, body));
+            match end.as_ref().map(|(name, _)| name.name.as_str()) {
+                Some("elif") => condition = self.condition(),
+                Some("else") => {
+                    self.close_tag();
+                    let (body, end) = self.nodes(&["endif"]);
+                    otherwise = Some(body);
+                    self.end_block(open, tag, end.is_some());
+                    break;
+                }
+                Some(_) => {
+                    self.close_tag();
+                    break;
+                }
+                None => {
+                    self.unclosed(open, tag);
+                    break;
+                }
+            }
+        }
+        Node::If(If {
+            branches,
+            otherwise,
+            range: TextRange::new(open, self.last_end),
+        })
+    }
+
+    /// The condition of an `if` or `elif`; a placeholder when it is invalid.
+    fn condition(&mut self) -> Expr {
+        let at = self.last_end;
+        self.header(Self::or_expr)
+            .unwrap_or(Expr::Literal(Literal::None, TextRange::empty(at)))
+    }
+
+    /// Close the current block at its end tag, or report it unclosed.
+    fn end_block(&mut self, open: TextSize, tag: &Name, closed: bool) {
+        if closed {
+            self.close_tag();
+        } else {
+            self.unclosed(open, tag);
+        }
+    }
+
+    fn for_block(&mut self, open: TextSize, tag: &Name) -> Node {
+        let at = self.last_end;
+        let header = self.header(|parser| {
+            let parenthesized = parser.eat_op("(");
+            let mut targets = Vec::new();
+            loop {
+                let Some(target) = parser.name() else {
+                    parser.error("expected a loop variable");
+                    return None;
+                };
+                targets.push(target);
+                if !parser.eat_op(",") {
+                    break;
+                }
+            }
+            if parenthesized {
+                parser.expect_op(")")?;
+            }
+            if !parser.eat_keyword("in") {
+                parser.error("expected `in`");
+                return None;
+            }
+            let iter = parser.or_expr()?;
+            let condition = if parser.eat_keyword("if") {
+                Some(parser.or_expr()?)
+            } else {
+                None
+            };
+            parser.eat_keyword("recursive");
+            Some((targets, iter, condition))
+        });
+        let (targets, iter, condition) = header.unwrap_or_else(|| {
+            (
+                Vec::new(),
+                Expr::Literal(Literal::None, TextRange::empty(at)),
+                None,
+            )
+        });
+
+        let (body, end) = self.nodes(&["else", "endfor"]);
+        let mut otherwise = None;
+        match end.as_ref().map(|(name, _)| name.name.as_str()) {
+            Some("else") => {
+                self.close_tag();
+                let (body, end) = self.nodes(&["endfor"]);
+                otherwise = Some(body);
+                self.end_block(open, tag, end.is_some());
+            }
+            closed => self.end_block(open, tag, closed.is_some()),
+        }
+        Node::For(For {
+            targets,
+            iter,
+            condition,
+            body,
+            otherwise,
+            range: TextRange::new(open, self.last_end),
+        })
+    }
+
+    fn set(&mut self, open: TextSize, tag: &Name) -> Option<Node> {
+        let header = self.header(|parser| {
+            let mut targets = Vec::new();
+            loop {
+                let Some(name) = parser.name() else {
+                    parser.error("expected a variable name");
+                    return None;
+                };
+                let mut target = Expr::Name(name);
+                if parser.eat_op(".") {
+                    let Some(attribute) = parser.name() else {
+                        parser.error("expected an attribute name after `.`");
+                        return None;
+                    };
+                    target = Expr::Attribute {
+                        target: Box::new(target),
+                        attribute,
+                    };
+                }
+                targets.push(target);
+                if !parser.eat_op(",") {
+                    break;
+                }
+            }
+            if parser.eat_op("=") {
+                return Some((targets, Some(parser.tuple_expr()?), Vec::new()));
+            }
+            let filters = parser.filter_chain_after_pipe()?;
+            Some((targets, None, filters))
+        })?;
+        let (targets, value, filters) = header;
+        let value = match value {
+            Some(value) => SetValue::Expr(value),
+            None => {
+                let (body, end) = self.nodes(&["endset"]);
+                self.end_block(open, tag, end.is_some());
+                SetValue::Block { filters, body }
+            }
+        };
+        Some(Node::Set(Set {
+            targets,
+            value,
+            range: TextRange::new(open, self.last_end),
+        }))
+    }
+
+    fn block(
+        &mut self,
+        open: TextSize,
+        tag: Name,
+        assignments: Vec<(Name, Expr)>,
+        filters: Vec<Filter>,
+    ) -> Option<Node> {
+        let end = format!("end{}", tag.name);
+        let (body, closed) = self.nodes(&[end.as_str()]);
+        self.end_block(open, &tag, closed.is_some());
+        Some(Node::Block(Block {
+            tag,
+            assignments,
+            filters,
+            body,
+            range: TextRange::new(open, self.last_end),
+        }))
+    }
+
+    /// The `name = value, …` header of a `with`.
+    fn assignments(&mut self) -> Option<Vec<(Name, Expr)>> {
+        let mut assignments = Vec::new();
+        while !self.at_tag_end() {
+            let Some(name) = self.name() else {
+                self.error("expected a variable name");
+                return None;
+            };
+            self.expect_op("=")?;
+            assignments.push((name, self.expr()?));
+            if !self.eat_op(",") {
+                break;
+            }
+        }
+        Some(assignments)
+    }
+
+    /// `name(args) | name | …`, the header of a `filter` block.
+    fn filter_chain(&mut self) -> Option<Vec<Filter>> {
+        let mut filters = vec![self.filter()?];
+        filters.extend(self.filter_chain_after_pipe()?);
+        Some(filters)
+    }
+
+    /// `| name(args) | …`, possibly empty.
+    fn filter_chain_after_pipe(&mut self) -> Option<Vec<Filter>> {
+        let mut filters = Vec::new();
+        while self.eat_op("|") {
+            filters.push(self.filter()?);
+        }
+        Some(filters)
+    }
+
+    /// A filter name and its optional arguments, after the `|`.
+    fn filter(&mut self) -> Option<Filter> {
+        let Some(name) = self.name() else {
+            self.error("expected a filter name after `|`");
+            return None;
+        };
+        let args = if self.eat_op("(") {
+            self.arguments()?
+        } else {
+            Vec::new()
+        };
+        Some(Filter { name, args })
+    }
+
+    // --- Expressions, loosest binding first ---
+
+    /// `a, b` as a tuple, or a single expression.
+    fn tuple_expr(&mut self) -> Option<Expr> {
+        let first = self.expr()?;
+        if !self.at_op(",") {
+            return Some(first);
+        }
+        let mut items = vec![first];
+        while self.eat_op(",") && !self.at_tag_end() {
+            items.push(self.expr()?);
+        }
+        let range = items[0].range().cover(self.last_range());
+        Some(Expr::Tuple(items, range))
+    }
+
+    fn last_range(&self) -> TextRange {
+        TextRange::empty(self.last_end)
+    }
+
+    pub(super) fn expr(&mut self) -> Option<Expr> {
+        let then = self.or_expr()?;
+        if !self.eat_keyword("if") {
+            return Some(then);
+        }
+        let condition = self.or_expr()?;
+        let otherwise = if self.eat_keyword("else") {
+            Some(Box::new(self.expr()?))
+        } else {
+            None
+        };
+        Some(Expr::Conditional {
+            then: Box::new(then),
+            condition: Box::new(condition),
+            otherwise,
+        })
+    }
+
+    fn binary(
+        &mut self,
+        operand: fn(&mut Self) -> Option<Expr>,
+        operator: fn(&Self) -> Option<BinaryOp>,
+    ) -> Option<Expr> {
+        let mut left = operand(self)?;
+        while let Some(op) = operator(self) {
+            self.bump();
+            if op == BinaryOp::NotIn {
+                self.bump();
+            }
+            let right = operand(self)?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Some(left)
+    }
+
+    fn or_expr(&mut self) -> Option<Expr> {
+        self.binary(Self::and_expr, |parser| {
+            parser.at_keyword("or").then_some(BinaryOp::Or)
+        })
+    }
+
+    fn and_expr(&mut self) -> Option<Expr> {
+        self.binary(Self::not_expr, |parser| {
+            parser.at_keyword("and").then_some(BinaryOp::And)
+        })
+    }
+
+    fn not_expr(&mut self) -> Option<Expr> {
+        if self.at_keyword("not") {
+            let start = self.bump()?.range.start();
+            let operand = self.not_expr()?;
+            let range = TextRange::new(start, operand.range().end());
+            return Some(Expr::Unary {
+                op: UnaryOp::Not,
+                operand: Box::new(operand),
+                range,
+            });
+        }
+        self.compare()
+    }
+
+    fn compare(&mut self) -> Option<Expr> {
+        self.binary(Self::concat, |parser| {
+            let token = parser.peek()?;
+            let text = token.text(parser.source);
+            match (token.kind, text) {
+                (TokenKind::Operator, "==") => Some(BinaryOp::Eq),
+                (TokenKind::Operator, "!=") => Some(BinaryOp::Ne),
+                (TokenKind::Operator, "<") => Some(BinaryOp::Lt),
+                (TokenKind::Operator, "<=") => Some(BinaryOp::Le),
+                (TokenKind::Operator, ">") => Some(BinaryOp::Gt),
+                (TokenKind::Operator, ">=") => Some(BinaryOp::Ge),
+                (TokenKind::Name, "in") => Some(BinaryOp::In),
+                (TokenKind::Name, "not") => parser
+                    .tokens
+                    .get(parser.pos + 1)
+                    .is_some_and(|next| {
+                        next.kind == TokenKind::Name && next.text(parser.source) == "in"
+                    })
+                    .then_some(BinaryOp::NotIn),
+                _ => None,
+            }
+        })
+    }
+
+    fn concat(&mut self) -> Option<Expr> {
+        self.binary(Self::additive, |parser| {
+            parser.at_op("~").then_some(BinaryOp::Concat)
+        })
+    }
+
+    fn additive(&mut self) -> Option<Expr> {
+        self.binary(Self::multiplicative, |parser| match parser.peek_text() {
+            "+" => Some(BinaryOp::Add),
+            "-" => Some(BinaryOp::Sub),
+            _ => None,
+        })
+    }
+
+    fn multiplicative(&mut self) -> Option<Expr> {
+        self.binary(Self::power, |parser| match parser.peek_text() {
+            "*" => Some(BinaryOp::Mul),
+            "/" => Some(BinaryOp::Div),
+            "//" => Some(BinaryOp::FloorDiv),
+            "%" => Some(BinaryOp::Rem),
+            _ => None,
+        })
+    }
+
+    fn power(&mut self) -> Option<Expr> {
+        self.binary(Self::unary, |parser| {
+            parser.at_op("**").then_some(BinaryOp::Pow)
+        })
+    }
+
+    fn unary(&mut self) -> Option<Expr> {
+        let op = match self.peek_text() {
+            "-" if self.at_op("-") => Some(UnaryOp::Neg),
+            "+" if self.at_op("+") => Some(UnaryOp::Pos),
+            _ => None,
+        };
+        if let Some(op) = op {
+            let start = self.bump()?.range.start();
+            let operand = self.unary()?;
+            let range = TextRange::new(start, operand.range().end());
+            return Some(Expr::Unary {
+                op,
+                operand: Box::new(operand),
+                range,
+            });
+        }
+        let primary = self.primary()?;
+        let postfix = self.postfix(primary)?;
+        self.filters(postfix)
+    }
+
+    /// `.attribute`, `[index]`, `[start:stop]` and `(args)` suffixes.
+    fn postfix(&mut self, mut expr: Expr) -> Option<Expr> {
+        loop {
+            if self.eat_op(".") {
+                let token = self.peek();
+                let attribute = match token {
+                    Some(token) if matches!(token.kind, TokenKind::Name | TokenKind::Integer) => {
+                        self.bump();
+                        Name {
+                            name: token.text(self.source).to_string(),
+                            range: token.range,
+                        }
+                    }
+                    _ => {
+                        self.error("expected an attribute name after `.`");
+                        return None;
+                    }
+                };
+                expr = Expr::Attribute {
+                    target: Box::new(expr),
+                    attribute,
+                };
+            } else if self.eat_op("[") {
+                let index = self.subscript()?;
+                self.expect_op("]")?;
+                let range = expr.range().cover(self.last_range());
+                expr = Expr::Item {
+                    target: Box::new(expr),
+                    index: Box::new(index),
+                    range,
+                };
+            } else if self.eat_op("(") {
+                let args = self.arguments()?;
+                let range = expr.range().cover(self.last_range());
+                expr = Expr::Call {
+                    target: Box::new(expr),
+                    args,
+                    range,
+                };
+            } else {
+                return Some(expr);
+            }
+        }
+    }
+
+    /// The inside of `[…]`: an index, or a `start:stop:step` slice, kept as
+    /// a call to `slice` with its three bounds.
+    fn subscript(&mut self) -> Option<Expr> {
+        let start = self.last_end;
+        let bound = |parser: &mut Self| -> Option<Expr> {
+            if parser.at_op(":") || parser.at_op("]") {
+                Some(Expr::Literal(Literal::None, parser.last_range()))
+            } else {
+                parser.expr()
+            }
+        };
+        let first = bound(self)?;
+        if !self.at_op(":") {
+            return Some(first);
+        }
+        let mut bounds = vec![first];
+        while bounds.len() < 3 && self.eat_op(":") {
+            bounds.push(bound(self)?);
+        }
+        bounds.resize_with(3, || Expr::Literal(Literal::None, self.last_range()));
+        let range = TextRange::new(start, self.last_end);
+        Some(Expr::Call {
+            target: Box::new(Expr::Name(Name {
+                name: "slice".to_string(),
+                range: TextRange::empty(start),
+            })),
+            args: bounds
+                .into_iter()
+                .map(|value| Argument { name: None, value })
+                .collect(),
+            range,
+        })
+    }
+
+    /// `| filter` and `is [not] test` suffixes.
+    fn filters(&mut self, mut expr: Expr) -> Option<Expr> {
+        loop {
+            if self.eat_op("|") {
+                let filter = self.filter()?;
+                let range = expr.range().cover(self.last_range());
+                expr = Expr::Filter {
+                    target: Box::new(expr),
+                    filter,
+                    range,
+                };
+            } else if self.eat_keyword("is") {
+                let negated = self.eat_keyword("not");
+                let Some(test) = self.name() else {
+                    self.error("expected a test name after `is`");
+                    return None;
+                };
+                let args = if self.eat_op("(") {
+                    self.arguments()?
+                } else if self.starts_test_argument() {
+                    let value = self.primary()?;
+                    vec![Argument { name: None, value }]
+                } else {
+                    Vec::new()
+                };
+                let range = expr.range().cover(self.last_range());
+                expr = Expr::Test {
+                    target: Box::new(expr),
+                    test,
+                    args,
+                    negated,
+                    range,
+                };
+            } else {
+                return Some(expr);
+            }
+        }
+    }
+
+    /// Whether the next token is the single, unparenthesized argument of a
+    /// test, as in `is divisibleby 3`.
+    fn starts_test_argument(&self) -> bool {
+        self.peek().is_some_and(|token| match token.kind {
+            TokenKind::String | TokenKind::Integer | TokenKind::Float => true,
+            TokenKind::Name => !KEYWORDS.contains(&token.text(self.source)),
+            _ => false,
+        })
+    }
+
+    /// The arguments of a call, after its `(`, up to and including the `)`.
+    fn arguments(&mut self) -> Option<Vec<Argument>> {
+        let mut args = Vec::new();
+        while !self.eat_op(")") {
+            // `*args` and `**kwargs` are kept as plain arguments.
+            let _ = self.eat_op("*") || self.eat_op("**");
+            let named = self
+                .peek()
+                .is_some_and(|token| token.kind == TokenKind::Name)
+                && self.tokens.get(self.pos + 1).is_some_and(|next| {
+                    next.kind == TokenKind::Operator && next.text(self.source) == "="
+                });
+            let name = if named {
+                let name = self.name();
+                self.bump();
+                name
+            } else {
+                None
+            };
+            args.push(Argument {
+                name,
+                value: self.expr()?,
+            });
+            if !self.at_op(")") && !self.eat_op(",") {
+                self.error("expected `,` or `)`");
+                return None;
+            }
+        }
+        Some(args)
+    }
+
+    fn primary(&mut self) -> Option<Expr> {
+        let Some(token) = self.peek() else {
+            self.error("expected an expression");
+            return None;
+        };
+        let text = token.text(self.source);
+        match token.kind {
+            TokenKind::Name if !KEYWORDS.contains(&text) => {
+                self.bump();
+                let literal = match text {
+                    "true" | "True" => Some(Literal::Bool(true)),
+                    "false" | "False" => Some(Literal::Bool(false)),
+                    "none" | "None" => Some(Literal::None),
+                    _ => None,
+                };
+                Some(match literal {
+                    Some(literal) => Expr::Literal(literal, token.range),
+                    None => Expr::Name(Name {
+                        name: text.to_string(),
+                        range: token.range,
+                    }),
+                })
+            }
+            TokenKind::String => {
+                // Adjacent strings are concatenated, as in Python.
+                let mut value = String::new();
+                let mut range = token.range;
+                while let Some(token) = self.peek().filter(|t| t.kind == TokenKind::String) {
+                    self.bump();
+                    value.push_str(&unquote(token.text(self.source)));
+                    range = range.cover(token.range);
+                }
+                Some(Expr::Literal(Literal::String(value), range))
+            }
+            TokenKind::Integer => {
+                self.bump();
+                if let Ok(value) = text.replace('_', "").parse() {
+                    Some(Expr::Literal(Literal::Integer(value), token.range))
+                } else {
+                    self.errors.push(TemplateError {
+                        message: "integer literal is too large".to_string(),
+                        range: token.range,
+                    });
+                    None
+                }
+            }
+            TokenKind::Float => {
+                self.bump();
+                let value = text.replace('_', "").parse().unwrap_or_default();
+                Some(Expr::Literal(Literal::Float(value), token.range))
+            }
+            TokenKind::Operator if text == "(" => {
+                self.bump();
+                if self.eat_op(")") {
+                    return Some(Expr::Tuple(
+                        Vec::new(),
+                        token.range.cover(self.last_range()),
+                    ));
+                }
+                let first = self.expr()?;
+                if self.eat_op(")") {
+                    return Some(first);
+                }
+                let mut items = vec![first];
+                while self.eat_op(",") {
+                    if self.at_op(")") {
+                        break;
+                    }
+                    items.push(self.expr()?);
+                }
+                self.expect_op(")")?;
+                Some(Expr::Tuple(items, token.range.cover(self.last_range())))
+            }
+            TokenKind::Operator if text == "[" => {
+                self.bump();
+                let mut items = Vec::new();
+                while !self.eat_op("]") {
+                    items.push(self.expr()?);
+                    if !self.at_op("]") && !self.eat_op(",") {
+                        self.error("expected `,` or `]`");
+                        return None;
+                    }
+                }
+                Some(Expr::List(items, token.range.cover(self.last_range())))
+            }
+            TokenKind::Operator if text == "{" => {
+                self.bump();
+                let mut entries = Vec::new();
+                while !self.eat_op("}") {
+                    let key = self.expr()?;
+                    self.expect_op(":")?;
+                    entries.push((key, self.expr()?));
+                    if !self.at_op("}") && !self.eat_op(",") {
+                        self.error("expected `,` or `}`");
+                        return None;
+                    }
+                }
+                Some(Expr::Dict(entries, token.range.cover(self.last_range())))
+            }
+            TokenKind::ExpressionClose | TokenKind::StatementClose => {
+                self.error(format!(
+                    "expected an expression before `{}`",
+                    text.trim_start_matches(['-', '+'])
+                ));
+                None
+            }
+            _ => {
+                self.error(format!("expected an expression, found `{text}`"));
+                None
+            }
+        }
+    }
+}
+
+/// The value of a string literal: its text without the quotes, with the
+/// backslash escapes resolved.
+fn unquote(text: &str) -> String {
+    let inner = &text[1..text.len().saturating_sub(1).max(1)];
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some(other) => value.push(other),
+            None => value.push('\\'),
+        }
+    }
+    value
+}
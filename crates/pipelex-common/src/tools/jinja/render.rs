@@ -0,0 +1,1970 @@
+//! Renders a parsed [`Template`] against JSON values, for previews.
+//!
+//! [`render`] evaluates a template the way Pipelex renders a pipe's prompts:
+//! `@name` writes the value of `name` in a tag of the [`TagStyle`], `$name`
+//! writes it inline, both through the [`TextFormat`], and the `tag` and
+//! `format` filters do the same from a `{{ … }}` expression. The common
+//! Jinja filters, tests and statements are supported; `{% macro %}` and
+//! `{% call %}` are not.
+//!
+//! Rendering never stops at an error: an undefined variable, a missing
+//! field or a failing filter is reported with its range and renders as
+//! nothing, so that a preview is always produced. Templates are user text,
+//! so rendering is bounded too: a value larger than [`MAX_SIZE`] bytes, or
+//! nested deeper than [`MAX_DEPTH`], is reported and dropped, and a preview
+//! that grows past [`MAX_SIZE`] bytes or runs more than [`MAX_ITERATIONS`]
+//! loop iterations is reported and cut off there.
+
+use std::fmt::Write;
+
+use serde_json::{Map, Number, Value};
+use taplo::rowan::TextRange;
+
+use super::{
+    ast::{
+        Argument, BinaryOp, Block, Expr, Filter, For, If, Literal, Name, Node, Set, SetValue,
+        Shorthand, Template, UnaryOp,
+    },
+    TemplateError,
+};
+
+/// The most bytes a preview, or any text or structure it computes, holds.
+pub const MAX_SIZE: usize = 1 << 20;
+
+/// The most lists and mappings a computed value nests.
+pub const MAX_DEPTH: usize = 64;
+
+/// The most loop iterations a preview runs, and items a `range` produces.
+pub const MAX_ITERATIONS: usize = 100_000;
+
+/// How `@name` shorthands and the `tag` filter delimit a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TagStyle {
+    NoTag,
+    /// ``name: ```…``` ``.
+    #[default]
+    Ticks,
+    /// `<name>…</name>`.
+    Xml,
+    /// `[name]…[/name]`.
+    SquareBrackets,
+}
+
+impl TagStyle {
+    /// The style named `name` in MTHDS, e.g. `square_brackets`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "no_tag" => Some(Self::NoTag),
+            "ticks" => Some(Self::Ticks),
+            "xml" => Some(Self::Xml),
+            "square_brackets" => Some(Self::SquareBrackets),
+            _ => None,
+        }
+    }
+
+    fn wrap(self, name: &str, text: &str) -> String {
+        match self {
+            Self::NoTag => text.to_string(),
+            Self::Ticks => format!("{name}: ```\n{text}\n```"),
+            Self::Xml => format!("<{name}>\n{text}\n</{name}>"),
+            Self::SquareBrackets => format!("[{name}]\n{text}\n[/{name}]"),
+        }
+    }
+}
+
+/// How values are written as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextFormat {
+    #[default]
+    Plain,
+    Markdown,
+    Html,
+    Json,
+}
+
+impl TextFormat {
+    /// The format named `name` in MTHDS, e.g. `markdown`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Self::Plain),
+            "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// `value` as text: text content as is, structures as `field: value`
+    /// lines, Markdown or HTML lists, or pretty-printed JSON.
+    #[must_use]
+    pub fn write(self, value: &Value) -> String {
+        if let Some(text) = text_content(value) {
+            return match self {
+                Self::Html => escape_html(text),
+                _ => text.to_string(),
+            };
+        }
+        match (self, value) {
+            (Self::Json, value) => serde_json::to_string_pretty(value).unwrap_or_default(),
+            (_, Value::Null) => String::new(),
+            (Self::Html, Value::Array(items)) => {
+                let items: String = items
+                    .iter()
+                    .map(|item| format!("<li>{}</li>", self.write(item)))
+                    .collect();
+                format!("<ul>{items}</ul>")
+            }
+            (_, Value::Array(items)) => items
+                .iter()
+                .map(|item| {
+                    let item = self.write(item);
+                    match self {
+                        Self::Markdown => format!("- {}", indent(&item, 2, false)),
+                        _ => item,
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            (Self::Html, Value::Object(fields)) => {
+                let fields: String = fields
+                    .iter()
+                    .filter(|(_, value)| !value.is_null())
+                    .map(|(name, value)| {
+                        format!(
+                            "<li><strong>{}</strong>: {}</li>",
+                            escape_html(name),
+                            self.write(value)
+                        )
+                    })
+                    .collect();
+                format!("<ul>{fields}</ul>")
+            }
+            (_, Value::Object(fields)) => fields
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, value)| {
+                    let name = match self {
+                        Self::Markdown => format!("**{name}**"),
+                        _ => name.clone(),
+                    };
+                    let value = self.write(value);
+                    if value.contains('\n') {
+                        format!("{name}:\n{}", indent(&value, 2, true))
+                    } else {
+                        format!("{name}: {value}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            (_, scalar) => display(scalar),
+        }
+    }
+}
+
+/// The tag style and text format prompts are rendered with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TemplatingStyle {
+    pub tag_style: TagStyle,
+    pub text_format: TextFormat,
+}
+
+/// A rendered template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendered {
+    pub text: String,
+    /// Undefined variables and render errors, in template order.
+    pub errors: Vec<TemplateError>,
+}
+
+/// Render `template`, parsed from `source`, with the variables of `context`.
+#[must_use]
+pub fn render(
+    source: &str,
+    template: &Template,
+    context: &Map<String, Value>,
+    style: TemplatingStyle,
+) -> Rendered {
+    let mut renderer = Renderer {
+        source,
+        style,
+        scopes: vec![context.clone()],
+        errors: Vec::new(),
+        iterations: 0,
+        stopped: false,
+    };
+    let mut text = String::new();
+    renderer.nodes(&template.nodes, &mut text);
+    renderer.errors.sort_by_key(|error| error.range.start());
+    Rendered {
+        text,
+        errors: renderer.errors,
+    }
+}
+
+struct Renderer<'s> {
+    source: &'s str,
+    style: TemplatingStyle,
+    /// The variables in scope, innermost last.
+    scopes: Vec<Map<String, Value>>,
+    errors: Vec<TemplateError>,
+    /// The loop iterations run so far.
+    iterations: usize,
+    /// Set once the preview hits a limit; nothing more is rendered.
+    stopped: bool,
+}
+
+impl Renderer<'_> {
+    fn error(&mut self, range: TextRange, message: impl Into<String>) {
+        self.errors.push(TemplateError {
+            message: message.into(),
+            range,
+        });
+    }
+
+    /// Evaluate `expr` without reporting its errors, for `default` and
+    /// `is defined`.
+    /// Report at `range`, when `size` exceeds [`MAX_SIZE`] or overflowed,
+    /// that the result is too large.
+    fn oversized(&mut self, range: TextRange, size: Option<usize>) -> bool {
+        let oversized = size.map_or(true, |size| size > MAX_SIZE);
+        if oversized {
+            self.error(
+                range,
+                format!("the result would exceed the {MAX_SIZE} bytes a preview allows"),
+            );
+        }
+        oversized
+    }
+
+    /// `value`, unless it is larger than [`MAX_SIZE`] bytes or nested deeper
+    /// than [`MAX_DEPTH`], which is reported at `range`.
+    fn bounded(&mut self, range: TextRange, value: Option<Value>) -> Option<Value> {
+        let value = value?;
+        let mut budget = MAX_SIZE;
+        if fits(&value, &mut budget, MAX_DEPTH) {
+            Some(value)
+        } else {
+            self.error(
+                range,
+                format!(
+                    "the result exceeds the {MAX_SIZE} bytes or {MAX_DEPTH} levels a preview allows"
+                ),
+            );
+            None
+        }
+    }
+
+    fn eval_quietly(&mut self, expr: &Expr) -> Option<Value> {
+        let errors = self.errors.len();
+        let value = self.eval(expr);
+        self.errors.truncate(errors);
+        value
+    }
+
+    fn lookup(&self, name: &str) -> Option<Value> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+    }
+
+    fn assign(&mut self, name: &str, value: Value) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), value);
+        }
+    }
+
+    fn with_scope(&mut self, scope: Map<String, Value>, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(scope);
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn nodes(&mut self, nodes: &[Node], out: &mut String) {
+        for node in nodes {
+            if self.stopped {
+                return;
+            }
+            self.node(node, out);
+            if out.len() > MAX_SIZE && !self.stopped {
+                let mut end = MAX_SIZE;
+                while !out.is_char_boundary(end) {
+                    end -= 1;
+                }
+                out.truncate(end);
+                self.error(
+                    node.range(),
+                    format!("the preview exceeds {MAX_SIZE} bytes; rendering stopped here"),
+                );
+                self.stopped = true;
+            }
+        }
+    }
+
+    fn node(&mut self, node: &Node, out: &mut String) {
+        match node {
+            Node::Text(range) => out.push_str(self.text(*range)),
+            Node::Shorthand(shorthand) => self.shorthand(shorthand, out),
+            Node::Output(expr) => {
+                if let Some(value) = self.eval(expr) {
+                    out.push_str(&self.display(&value));
+                }
+            }
+            Node::If(node) => self.if_block(node, out),
+            Node::For(node) => self.for_block(node, out),
+            Node::Set(node) => self.set(node),
+            Node::Block(node) => self.block(node, out),
+        }
+    }
+
+    /// The text at `range`, without the whitespace the `-` of the tags
+    /// around it strips.
+    fn text(&self, range: TextRange) -> &str {
+        let mut text = &self.source[range];
+        let before = &self.source[..usize::from(range.start())];
+        if ["-}}", "-%}", "-#}"]
+            .iter()
+            .any(|close| before.ends_with(close))
+        {
+            text = text.trim_start();
+        }
+        let after = &self.source[usize::from(range.end())..];
+        if ["{{-", "{%-", "{#-"]
+            .iter()
+            .any(|open| after.starts_with(open))
+        {
+            text = text.trim_end();
+        }
+        text
+    }
+
+    fn shorthand(&mut self, shorthand: &Shorthand, out: &mut String) {
+        let Some((variable, fields)) = shorthand.path.split_first() else {
+            return;
+        };
+        let mut value = self.variable(variable);
+        for field in fields {
+            value = value.and_then(|value| self.attribute(&value, field));
+        }
+        let Some(value) = value else {
+            return;
+        };
+
+        let text = self.style.text_format.write(&value);
+        if shorthand.sigil == '@' {
+            let name: Vec<&str> = shorthand
+                .path
+                .iter()
+                .map(|name| name.name.as_str())
+                .collect();
+            out.push_str(&self.style.tag_style.wrap(&name.join("."), &text));
+        } else {
+            out.push_str(&text);
+        }
+    }
+
+    fn if_block(&mut self, node: &If, out: &mut String) {
+        for (condition, body) in &node.branches {
+            if self.eval(condition).as_ref().is_some_and(truthy) {
+                self.nodes(body, out);
+                return;
+            }
+        }
+        if let Some(otherwise) = &node.otherwise {
+            self.nodes(otherwise, out);
+        }
+    }
+
+    fn for_block(&mut self, node: &For, out: &mut String) {
+        let items = match self.eval(&node.iter) {
+            Some(Value::Array(items)) => items,
+            Some(Value::Object(fields)) => fields.keys().cloned().map(Value::from).collect(),
+            Some(Value::String(text)) => text.chars().map(|c| Value::from(c.to_string())).collect(),
+            Some(Value::Null) | None => Vec::new(),
+            Some(other) => {
+                let message = format!("`{}` is not iterable", display(&other));
+                self.error(node.iter.range(), message);
+                Vec::new()
+            }
+        };
+
+        let mut iterations = Vec::new();
+        for item in items {
+            let scope = self.unpack(&node.targets, item);
+            let keep = match &node.condition {
+                Some(condition) => {
+                    self.scopes.push(scope.clone());
+                    let keep = self.eval(condition).as_ref().is_some_and(truthy);
+                    self.scopes.pop();
+                    keep
+                }
+                None => true,
+            };
+            if keep {
+                iterations.push(scope);
+            }
+        }
+
+        if iterations.is_empty() {
+            if let Some(otherwise) = &node.otherwise {
+                self.nodes(otherwise, out);
+            }
+            return;
+        }
+        let length = iterations.len();
+        for (index, mut scope) in iterations.into_iter().enumerate() {
+            if self.stopped {
+                return;
+            }
+            self.iterations += 1;
+            if self.iterations > MAX_ITERATIONS {
+                self.error(
+                    node.range,
+                    format!("the preview runs more than {MAX_ITERATIONS} loop iterations; rendering stopped here"),
+                );
+                self.stopped = true;
+                return;
+            }
+            scope.insert(
+                "loop".into(),
+                serde_json::json!({
+                    "index": index + 1,
+                    "index0": index,
+                    "revindex": length - index,
+                    "revindex0": length - index - 1,
+                    "first": index == 0,
+                    "last": index + 1 == length,
+                    "length": length,
+                }),
+            );
+            self.with_scope(scope, |renderer| renderer.nodes(&node.body, out));
+        }
+    }
+
+    /// The variables `targets` bind to `item`.
+    fn unpack(&mut self, targets: &[Name], item: Value) -> Map<String, Value> {
+        let mut scope = Map::new();
+        match targets {
+            [target] => {
+                scope.insert(target.name.clone(), item);
+            }
+            targets => {
+                let values = match item {
+                    Value::Array(values) => values,
+                    other => vec![other],
+                };
+                if values.len() != targets.len() {
+                    let range = targets[0].range.cover(targets[targets.len() - 1].range);
+                    self.error(
+                        range,
+                        format!(
+                            "cannot unpack {} values into {} names",
+                            values.len(),
+                            targets.len()
+                        ),
+                    );
+                }
+                for (target, value) in targets.iter().zip(values) {
+                    scope.insert(target.name.clone(), value);
+                }
+            }
+        }
+        scope
+    }
+
+    fn set(&mut self, node: &Set) {
+        let value = match &node.value {
+            SetValue::Expr(expr) => self.eval(expr).unwrap_or(Value::Null),
+            SetValue::Block { filters, body } => {
+                let mut text = String::new();
+                self.nodes(body, &mut text);
+                self.apply_filters(filters, Value::from(text))
+                    .unwrap_or(Value::Null)
+            }
+        };
+
+        let values = match (&node.targets[..], value) {
+            ([_], value) => vec![value],
+            (_, Value::Array(values)) => values,
+            (_, value) => vec![value],
+        };
+        for (target, value) in node.targets.iter().zip(values) {
+            match target {
+                Expr::Name(name) => self.assign(&name.name, value),
+                Expr::Attribute { target, attribute } => {
+                    let Expr::Name(namespace) = &**target else {
+                        continue;
+                    };
+                    let scope = self
+                        .scopes
+                        .iter_mut()
+                        .rev()
+                        .find_map(|scope| scope.get_mut(&namespace.name));
+                    match scope {
+                        Some(Value::Object(fields)) => {
+                            fields.insert(attribute.name.clone(), value);
+                        }
+                        _ => self.error(
+                            namespace.range,
+                            format!("`{}` is not a namespace", namespace.name),
+                        ),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn block(&mut self, node: &Block, out: &mut String) {
+        match node.tag.name.as_str() {
+            "with" => {
+                let mut scope = Map::new();
+                for (name, value) in &node.assignments {
+                    let value = self.eval(value).unwrap_or(Value::Null);
+                    scope.insert(name.name.clone(), value);
+                }
+                self.with_scope(scope, |renderer| renderer.nodes(&node.body, out));
+            }
+            "filter" => {
+                let mut text = String::new();
+                self.nodes(&node.body, &mut text);
+                if let Some(value) = self.apply_filters(&node.filters, Value::from(text)) {
+                    out.push_str(&self.display(&value));
+                }
+            }
+            "macro" => {}
+            "call" => self.error(node.tag.range, "`{% call %}` is not supported in previews"),
+            _ => self.nodes(&node.body, out),
+        }
+    }
+
+    fn apply_filters(&mut self, filters: &[Filter], value: Value) -> Option<Value> {
+        let mut value = Some(value);
+        for filter in filters {
+            let args = self.arguments(&filter.args);
+            value = self.filter(filter, value, &args);
+        }
+        value
+    }
+
+    /// The value of variable `name`, reported when undefined.
+    fn variable(&mut self, name: &Name) -> Option<Value> {
+        let value = self.lookup(&name.name);
+        if value.is_none() {
+            self.error(name.range, format!("undefined variable `{}`", name.name));
+        }
+        value
+    }
+
+    /// The field `name` of `value`, reported when missing.
+    fn attribute(&mut self, value: &Value, name: &Name) -> Option<Value> {
+        let field = match value {
+            Value::Object(fields) => fields.get(&name.name).cloned(),
+            _ => None,
+        };
+        if field.is_none() {
+            self.error(name.range, format!("no field `{}`", name.name));
+        }
+        field
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Option<Value> {
+        match expr {
+            Expr::Name(name) => match name.name.as_str() {
+                "True" => Some(true.into()),
+                "False" => Some(false.into()),
+                "None" => Some(Value::Null),
+                _ => self.variable(name),
+            },
+            Expr::Literal(literal, _) => Some(match literal {
+                Literal::String(text) => text.clone().into(),
+                Literal::Integer(number) => (*number).into(),
+                Literal::Float(number) => {
+                    Number::from_f64(*number).map_or(Value::Null, Value::Number)
+                }
+                Literal::Bool(value) => (*value).into(),
+                Literal::None => Value::Null,
+            }),
+            Expr::List(items, range) | Expr::Tuple(items, range) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.eval(item).unwrap_or(Value::Null))
+                    .collect();
+                self.bounded(*range, Some(Value::Array(items)))
+            }
+            Expr::Dict(entries, range) => {
+                let mut fields = Map::new();
+                for (key, value) in entries {
+                    let key = self.eval(key).map(|key| display(&key));
+                    let value = self.eval(value).unwrap_or(Value::Null);
+                    if let Some(key) = key {
+                        fields.insert(key, value);
+                    }
+                }
+                self.bounded(*range, Some(Value::Object(fields)))
+            }
+            Expr::Attribute { target, attribute } => {
+                let target = self.eval(target)?;
+                self.attribute(&target, attribute)
+            }
+            Expr::Item {
+                target,
+                index,
+                range,
+            } => {
+                let target = self.eval(target)?;
+                let index = self.eval(index)?;
+                let item = match (&target, &index) {
+                    (Value::Object(fields), key) => fields.get(&display(key)).cloned(),
+                    (Value::Array(items), Value::Number(position)) => {
+                        position.as_i64().and_then(|position| {
+                            let len = i64::try_from(items.len()).ok()?;
+                            let position = if position < 0 {
+                                len + position
+                            } else {
+                                position
+                            };
+                            usize::try_from(position)
+                                .ok()
+                                .and_then(|position| items.get(position).cloned())
+                        })
+                    }
+                    _ => None,
+                };
+                if item.is_none() {
+                    self.error(*range, format!("no item `{}`", display(&index)));
+                }
+                item
+            }
+            Expr::Call {
+                target,
+                args,
+                range,
+            } => self.call(target, args, *range),
+            Expr::Filter { target, filter, .. } => {
+                let value = if matches!(filter.name.name.as_str(), "default" | "d") {
+                    self.eval_quietly(target)
+                } else {
+                    Some(self.eval(target)?)
+                };
+                let args = self.arguments(&filter.args);
+                self.filter(filter, value, &args)
+            }
+            Expr::Test {
+                target,
+                test,
+                args,
+                negated,
+                ..
+            } => {
+                let value = if matches!(test.name.as_str(), "defined" | "undefined") {
+                    self.eval_quietly(target)
+                } else {
+                    Some(self.eval(target)?)
+                };
+                let args = self.arguments(args);
+                let passed = self.test(test, value.as_ref(), &args)?;
+                Some((passed != *negated).into())
+            }
+            Expr::Unary { op, operand, range } => {
+                let value = self.eval(operand)?;
+                match op {
+                    UnaryOp::Not => Some((!truthy(&value)).into()),
+                    UnaryOp::Pos if value.is_number() => Some(value),
+                    UnaryOp::Neg => match &value {
+                        Value::Number(number) => {
+                            Some(match number.as_i64().and_then(i64::checked_neg) {
+                                Some(number) => number.into(),
+                                None => float(-number.as_f64().unwrap_or_default()),
+                            })
+                        }
+                        _ => {
+                            self.error(*range, "expected a number");
+                            None
+                        }
+                    },
+                    UnaryOp::Pos => {
+                        self.error(*range, "expected a number");
+                        None
+                    }
+                }
+            }
+            Expr::Binary { op, left, right } => self.binary(*op, left, right),
+            Expr::Conditional {
+                then,
+                condition,
+                otherwise,
+            } => {
+                if self.eval(condition).as_ref().is_some_and(truthy) {
+                    self.eval(then)
+                } else {
+                    otherwise
+                        .as_ref()
+                        .map_or(Some(Value::from("")), |otherwise| self.eval(otherwise))
+                }
+            }
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, left: &Expr, right: &Expr) -> Option<Value> {
+        let range = left.range().cover(right.range());
+        match op {
+            BinaryOp::Or => {
+                let left = self.eval(left)?;
+                return if truthy(&left) {
+                    Some(left)
+                } else {
+                    self.eval(right)
+                };
+            }
+            BinaryOp::And => {
+                let left = self.eval(left)?;
+                return if truthy(&left) {
+                    self.eval(right)
+                } else {
+                    Some(left)
+                };
+            }
+            _ => {}
+        }
+
+        let left = self.eval(left)?;
+        let right = self.eval(right)?;
+        let value = match op {
+            BinaryOp::Eq => Some(equal(&left, &right).into()),
+            BinaryOp::Ne => Some((!equal(&left, &right)).into()),
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => compare(&left, &right)
+                .map(|ordering| {
+                    Value::from(match op {
+                        BinaryOp::Lt => ordering.is_lt(),
+                        BinaryOp::Le => ordering.is_le(),
+                        BinaryOp::Gt => ordering.is_gt(),
+                        _ => ordering.is_ge(),
+                    })
+                }),
+            BinaryOp::In => contains(&right, &left).map(Value::from),
+            BinaryOp::NotIn => contains(&right, &left).map(|found| (!found).into()),
+            BinaryOp::Concat => {
+                Some(format!("{}{}", self.display(&left), self.display(&right)).into())
+            }
+            BinaryOp::Add => match (&left, &right) {
+                (Value::String(left), Value::String(right)) => {
+                    Some(format!("{left}{right}").into())
+                }
+                (Value::Array(left), Value::Array(right)) => {
+                    Some(Value::Array(left.iter().chain(right).cloned().collect()))
+                }
+                _ => arithmetic(op, &left, &right),
+            },
+            BinaryOp::Mul => match (&left, &right) {
+                (Value::String(text), Value::Number(count))
+                | (Value::Number(count), Value::String(text)) => {
+                    let count = count.as_u64().and_then(|count| usize::try_from(count).ok());
+                    match count {
+                        Some(count) if self.oversized(range, text.len().checked_mul(count)) => {
+                            return None;
+                        }
+                        count => count.map(|count| text.repeat(count).into()),
+                    }
+                }
+                _ => arithmetic(op, &left, &right),
+            },
+            _ => arithmetic(op, &left, &right),
+        };
+        if value.is_none() {
+            self.error(
+                range,
+                format!(
+                    "cannot apply `{}` to `{}` and `{}`",
+                    operator(op),
+                    display(&left),
+                    display(&right)
+                ),
+            );
+        }
+        self.bounded(range, value)
+    }
+
+    fn arguments(&mut self, args: &[Argument]) -> Arguments {
+        let mut arguments = Arguments::default();
+        for arg in args {
+            let value = self.eval(&arg.value).unwrap_or(Value::Null);
+            match &arg.name {
+                Some(name) => arguments.named.push((name.name.clone(), value)),
+                None => arguments.positional.push(value),
+            }
+        }
+        arguments
+    }
+
+    fn call(&mut self, target: &Expr, args: &[Argument], range: TextRange) -> Option<Value> {
+        let arguments = self.arguments(args);
+        match target {
+            Expr::Name(name) if name.name == "range" => {
+                let bounds: Vec<i64> = arguments
+                    .positional
+                    .iter()
+                    .filter_map(Value::as_i64)
+                    .collect();
+                let (start, stop, step) = match bounds[..] {
+                    [stop] => (0, stop, 1),
+                    [start, stop] => (start, stop, 1),
+                    [start, stop, step] if step != 0 => (start, stop, step),
+                    _ => {
+                        self.error(range, "`range` expects one to three integers");
+                        return None;
+                    }
+                };
+                let mut values = Vec::new();
+                let mut at = Some(start);
+                while let Some(value) =
+                    at.filter(|at| (step > 0 && *at < stop) || (step < 0 && *at > stop))
+                {
+                    if values.len() == MAX_ITERATIONS {
+                        self.error(
+                            range,
+                            format!("`range` produces more than the {MAX_ITERATIONS} items a preview allows"),
+                        );
+                        return None;
+                    }
+                    values.push(Value::from(value));
+                    at = value.checked_add(step);
+                }
+                Some(Value::Array(values))
+            }
+            Expr::Name(name) if name.name == "namespace" || name.name == "dict" => {
+                let fields = Value::Object(arguments.named.into_iter().collect());
+                self.bounded(range, Some(fields))
+            }
+            Expr::Attribute { target, attribute } => {
+                let value = self.eval(target)?;
+                if let (Value::String(text), "replace") = (&value, attribute.name.as_str()) {
+                    let (Some(old), Some(new)) = (arguments.get(0, "old"), arguments.get(1, "new"))
+                    else {
+                        self.error(attribute.range, "`replace` expects the old and new text");
+                        return None;
+                    };
+                    return self.replace(range, text, &display(old), &display(new));
+                }
+                let result = method(&value, &attribute.name, &arguments);
+                if result.is_none() {
+                    self.error(
+                        attribute.range,
+                        format!("cannot call `{}` in a preview", attribute.name),
+                    );
+                }
+                result
+            }
+            other => {
+                self.error(
+                    other.range(),
+                    format!("cannot call `{}` in a preview", &self.source[other.range()]),
+                );
+                None
+            }
+        }
+    }
+
+    fn filter(&mut self, filter: &Filter, value: Option<Value>, args: &Arguments) -> Option<Value> {
+        let name = filter.name.name.as_str();
+        if matches!(name, "default" | "d") {
+            let fallback = args
+                .get(0, "default_value")
+                .cloned()
+                .unwrap_or_else(|| Value::from(""));
+            let boolean = args.get(1, "boolean").is_some_and(truthy);
+            return match value {
+                Some(value) if !boolean || truthy(&value) => Some(value),
+                _ => Some(fallback),
+            };
+        }
+        let value = value?;
+        let shown = display(&value);
+        let result = match name {
+            "tag" => {
+                let tag = args.get(0, "tag_name").map(display).unwrap_or_default();
+                let text = self.style.text_format.write(&value);
+                Some(self.style.tag_style.wrap(&tag, &text).into())
+            }
+            "format" => {
+                let format = match args.get(0, "text_format") {
+                    Some(format) => {
+                        let Some(format) = format.as_str().and_then(TextFormat::from_name) else {
+                            self.error(filter.name.range, "unknown text format");
+                            return None;
+                        };
+                        format
+                    }
+                    None => self.style.text_format,
+                };
+                Some(format.write(&value).into())
+            }
+            "upper" => Some(self.display(&value).to_uppercase().into()),
+            "lower" => Some(self.display(&value).to_lowercase().into()),
+            "capitalize" => {
+                let text = self.display(&value).to_lowercase();
+                let mut chars = text.chars();
+                Some(
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                        .unwrap_or_default()
+                        .into(),
+                )
+            }
+            "title" => Some(
+                self.display(&value)
+                    .split(' ')
+                    .map(|word| {
+                        let mut chars = word.chars();
+                        chars
+                            .next()
+                            .map(|first| {
+                                first
+                                    .to_uppercase()
+                                    .chain(chars.flat_map(char::to_lowercase))
+                                    .collect::<String>()
+                            })
+                            .unwrap_or_default()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .into(),
+            ),
+            "trim" | "strip" => Some(self.display(&value).trim().into()),
+            "length" | "count" => match &value {
+                Value::String(text) => Some(text.chars().count().into()),
+                Value::Array(items) => Some(items.len().into()),
+                Value::Object(fields) => Some(fields.len().into()),
+                _ => None,
+            },
+            "join" => {
+                let separator = args.get(0, "d").map(display).unwrap_or_default();
+                let separators = value.as_array().map_or(0, Vec::len);
+                if self.oversized(filter.name.range, separator.len().checked_mul(separators)) {
+                    return None;
+                }
+                value.as_array().map(|items| {
+                    items
+                        .iter()
+                        .map(|item| self.display(item))
+                        .collect::<Vec<_>>()
+                        .join(&separator)
+                        .into()
+                })
+            }
+            "first" => match value {
+                Value::Array(items) => items.into_iter().next(),
+                Value::String(text) => text.chars().next().map(|c| c.to_string().into()),
+                _ => None,
+            },
+            "last" => match value {
+                Value::Array(items) => items.into_iter().last(),
+                Value::String(text) => text.chars().last().map(|c| c.to_string().into()),
+                _ => None,
+            },
+            "replace" => {
+                let old = args.get(0, "old").map(display).unwrap_or_default();
+                let new = args.get(1, "new").map(display).unwrap_or_default();
+                let text = self.display(&value);
+                return self.replace(filter.name.range, &text, &old, &new);
+            }
+            "string" => Some(self.display(&value).into()),
+            "int" => match &value {
+                Value::Number(number) => number
+                    .as_i64()
+                    .or_else(|| number.as_f64().map(|number| number.trunc() as i64))
+                    .map(Value::from),
+                Value::String(text) => Some(text.trim().parse::<i64>().unwrap_or_default().into()),
+                Value::Bool(value) => Some(i64::from(*value).into()),
+                _ => Some(0.into()),
+            },
+            "float" => match &value {
+                Value::Number(number) => number.as_f64().map(float),
+                Value::String(text) => Some(float(text.trim().parse().unwrap_or_default())),
+                _ => Some(float(0.0)),
+            },
+            "round" => {
+                let precision = args
+                    .get(0, "precision")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(0);
+                value.as_f64().map(|number| {
+                    let factor = 10_f64.powi(i32::try_from(precision).unwrap_or(0));
+                    float((number * factor).round() / factor)
+                })
+            }
+            "abs" => match &value {
+                Value::Number(number) => Some(match number.as_i64().and_then(i64::checked_abs) {
+                    Some(number) => number.into(),
+                    None => float(number.as_f64().unwrap_or_default().abs()),
+                }),
+                _ => None,
+            },
+            "list" => match value {
+                Value::Array(items) => Some(Value::Array(items)),
+                Value::String(text) => {
+                    Some(text.chars().map(|c| Value::from(c.to_string())).collect())
+                }
+                Value::Object(fields) => Some(fields.keys().cloned().map(Value::from).collect()),
+                _ => None,
+            },
+            "reverse" => match value {
+                Value::Array(mut items) => {
+                    items.reverse();
+                    Some(Value::Array(items))
+                }
+                Value::String(text) => Some(text.chars().rev().collect::<String>().into()),
+                _ => None,
+            },
+            "sort" => value.as_array().map(|items| {
+                let mut items = items.clone();
+                items.sort_by(|a, b| compare(a, b).unwrap_or(std::cmp::Ordering::Equal));
+                if args.get(0, "reverse").is_some_and(truthy) {
+                    items.reverse();
+                }
+                Value::Array(items)
+            }),
+            "unique" => value.as_array().map(|items| {
+                let mut unique: Vec<Value> = Vec::new();
+                for item in items {
+                    if !unique.iter().any(|known| equal(known, item)) {
+                        unique.push(item.clone());
+                    }
+                }
+                Value::Array(unique)
+            }),
+            "min" | "max" | "sum" => value.as_array().and_then(|items| match name {
+                "sum" => items.iter().try_fold(Value::from(0), |sum, item| {
+                    arithmetic(BinaryOp::Add, &sum, item)
+                }),
+                "min" => items
+                    .iter()
+                    .min_by(|a, b| compare(a, b).unwrap_or(std::cmp::Ordering::Equal))
+                    .cloned(),
+                _ => items
+                    .iter()
+                    .max_by(|a, b| compare(a, b).unwrap_or(std::cmp::Ordering::Equal))
+                    .cloned(),
+            }),
+            "items" | "dictsort" => value.as_object().map(|fields| {
+                let mut items: Vec<(&String, &Value)> = fields.iter().collect();
+                if name == "dictsort" {
+                    items.sort_by_key(|(key, _)| *key);
+                }
+                items
+                    .into_iter()
+                    .map(|(key, value)| Value::Array(vec![key.clone().into(), value.clone()]))
+                    .collect()
+            }),
+            "map" => value.as_array().and_then(|items| {
+                let attribute = args.get(usize::MAX, "attribute")?.as_str()?.to_string();
+                Some(
+                    items
+                        .iter()
+                        .map(|item| item.get(&attribute).cloned().unwrap_or(Value::Null))
+                        .collect(),
+                )
+            }),
+            "truncate" => {
+                let text = self.display(&value);
+                let length = args
+                    .get(0, "length")
+                    .and_then(Value::as_u64)
+                    .and_then(|length| usize::try_from(length).ok())
+                    .unwrap_or(255);
+                let end = args.get(2, "end").map_or_else(|| "...".into(), display);
+                if text.chars().count() <= length {
+                    Some(text.into())
+                } else {
+                    let kept: String = text
+                        .chars()
+                        .take(length.saturating_sub(end.chars().count()))
+                        .collect();
+                    Some(format!("{kept}{end}").into())
+                }
+            }
+            "wordcount" => Some(self.display(&value).split_whitespace().count().into()),
+            "indent" => {
+                let width = args
+                    .get(0, "width")
+                    .and_then(Value::as_u64)
+                    .and_then(|width| usize::try_from(width).ok())
+                    .unwrap_or(4);
+                let first = args.get(1, "first").is_some_and(truthy);
+                let text = self.display(&value);
+                let lines = text.split('\n').count();
+                let size = width
+                    .checked_mul(lines)
+                    .and_then(|padding| padding.checked_add(text.len()));
+                if self.oversized(filter.name.range, size) {
+                    return None;
+                }
+                Some(indent(&text, width, first).into())
+            }
+            "tojson" => serde_json::to_string(&value).ok().map(Value::from),
+            "escape" | "e" => Some(escape_html(&self.display(&value)).into()),
+            "safe" => Some(value),
+            _ => {
+                self.error(filter.name.range, format!("unknown filter `{name}`"));
+                return None;
+            }
+        };
+        if result.is_none() {
+            self.error(
+                filter.name.range,
+                format!("the `{name}` filter does not apply to `{shown}`"),
+            );
+        }
+        self.bounded(filter.name.range, result)
+    }
+
+    /// `text` with every `old` replaced by `new`, unless the result would be
+    /// too large, which is reported at `range`.
+    fn replace(&mut self, range: TextRange, text: &str, old: &str, new: &str) -> Option<Value> {
+        // An empty `old` matches between every character and at both ends.
+        let matches = if old.is_empty() {
+            text.chars().count() + 1
+        } else {
+            text.matches(old).count()
+        };
+        let size = new
+            .len()
+            .checked_mul(matches)
+            .and_then(|inserted| inserted.checked_add(text.len()));
+        if self.oversized(range, size) {
+            return None;
+        }
+        Some(text.replace(old, new).into())
+    }
+
+    fn test(&mut self, test: &Name, value: Option<&Value>, args: &Arguments) -> Option<bool> {
+        let name = test.name.as_str();
+        match name {
+            "defined" => return Some(value.is_some()),
+            "undefined" => return Some(value.is_none()),
+            _ => {}
+        }
+        let value = value?;
+        let argument = args.get(0, "value");
+        let passed = match name {
+            "none" => value.is_null(),
+            "boolean" => value.is_boolean(),
+            "true" => *value == Value::Bool(true),
+            "false" => *value == Value::Bool(false),
+            "string" => value.is_string(),
+            "number" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "float" => value.is_f64(),
+            "mapping" => value.is_object(),
+            "sequence" | "iterable" => value.is_array() || value.is_string() || value.is_object(),
+            "lower" => value
+                .as_str()
+                .is_some_and(|text| text.to_lowercase() == text),
+            "upper" => value
+                .as_str()
+                .is_some_and(|text| text.to_uppercase() == text),
+            "even" | "odd" | "divisibleby" => {
+                let divisor = if name == "divisibleby" {
+                    argument
+                        .and_then(Value::as_i64)
+                        .filter(|divisor| *divisor != 0)
+                } else {
+                    Some(2)
+                };
+                let remainder = value
+                    .as_i64()
+                    .zip(divisor)
+                    .and_then(|(value, divisor)| value.checked_rem(divisor));
+                match name {
+                    "odd" => remainder.is_some_and(|remainder| remainder != 0),
+                    _ => remainder == Some(0),
+                }
+            }
+            "eq" | "equalto" | "sameas" => argument.is_some_and(|other| equal(value, other)),
+            "ne" => argument.is_some_and(|other| !equal(value, other)),
+            "lt" | "gt" | "le" | "ge" => argument
+                .and_then(|other| compare(value, other))
+                .is_some_and(|ordering| match name {
+                    "lt" => ordering.is_lt(),
+                    "gt" => ordering.is_gt(),
+                    "le" => ordering.is_le(),
+                    _ => ordering.is_ge(),
+                }),
+            "in" => argument
+                .and_then(|container| contains(container, value))
+                .unwrap_or(false),
+            _ => {
+                self.error(test.range, format!("unknown test `{name}`"));
+                return None;
+            }
+        };
+        Some(passed)
+    }
+
+    /// `value` as an expression renders it: text as is, structures through
+    /// the text format.
+    fn display(&self, value: &Value) -> String {
+        match value {
+            Value::Array(_) | Value::Object(_) => self.style.text_format.write(value),
+            scalar => display(scalar),
+        }
+    }
+}
+
+/// The evaluated arguments of a filter, test or call.
+#[derive(Default)]
+struct Arguments {
+    positional: Vec<Value>,
+    named: Vec<(String, Value)>,
+}
+
+impl Arguments {
+    /// The argument at `position`, or passed as `name`.
+    fn get(&self, position: usize, name: &str) -> Option<&Value> {
+        self.positional.get(position).or_else(|| {
+            self.named
+                .iter()
+                .find(|(argument, _)| argument == name)
+                .map(|(_, value)| value)
+        })
+    }
+}
+
+/// The methods of strings and mappings a template may call.
+fn method(value: &Value, name: &str, args: &Arguments) -> Option<Value> {
+    match (value, name) {
+        (Value::Object(fields), "items") => Some(
+            fields
+                .iter()
+                .map(|(key, value)| Value::Array(vec![key.clone().into(), value.clone()]))
+                .collect(),
+        ),
+        (Value::Object(fields), "keys") => Some(fields.keys().cloned().map(Value::from).collect()),
+        (Value::Object(fields), "values") => Some(fields.values().cloned().collect()),
+        (Value::Object(fields), "get") => {
+            let key = display(args.get(0, "key")?);
+            Some(
+                fields
+                    .get(&key)
+                    .cloned()
+                    .or_else(|| args.get(1, "default").cloned())
+                    .unwrap_or_default(),
+            )
+        }
+        (Value::String(text), "upper") => Some(text.to_uppercase().into()),
+        (Value::String(text), "lower") => Some(text.to_lowercase().into()),
+        (Value::String(text), "strip") => Some(text.trim().into()),
+        (Value::String(text), "startswith") => {
+            Some(text.starts_with(&display(args.get(0, "prefix")?)).into())
+        }
+        (Value::String(text), "endswith") => {
+            Some(text.ends_with(&display(args.get(0, "suffix")?)).into())
+        }
+        (Value::String(text), "split") => Some(match args.get(0, "sep") {
+            Some(separator) if !separator.is_null() => {
+                text.split(&display(separator)).map(Value::from).collect()
+            }
+            _ => text.split_whitespace().map(Value::from).collect(),
+        }),
+        _ => None,
+    }
+}
+
+/// The text of a text content: a string, or an object whose only field is
+/// its `text`.
+fn text_content(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(text) => Some(text),
+        Value::Object(fields) => {
+            let text = fields.get("text")?.as_str()?;
+            fields
+                .iter()
+                .all(|(name, value)| name == "text" || value.is_null())
+                .then_some(text)
+        }
+        _ => None,
+    }
+}
+
+/// A scalar as Jinja prints it.
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Bool(true) => "True".into(),
+        Value::Bool(false) => "False".into(),
+        Value::Null => "None".into(),
+        other => other.to_string(),
+    }
+}
+
+/// Whether `value` holds at most `budget` bytes of text and items, nested at
+/// most `depth` deep. `budget` is what is left of it.
+fn fits(value: &Value, budget: &mut usize, depth: usize) -> bool {
+    let size = match value {
+        Value::String(text) => text.len(),
+        _ => 1,
+    };
+    let Some(left) = budget.checked_sub(size) else {
+        return false;
+    };
+    *budget = left;
+    match value {
+        Value::Array(items) => depth > 0 && items.iter().all(|item| fits(item, budget, depth - 1)),
+        Value::Object(fields) => {
+            depth > 0
+                && fields.iter().all(|(key, value)| {
+                    let Some(left) = budget.checked_sub(key.len()) else {
+                        return false;
+                    };
+                    *budget = left;
+                    fits(value, budget, depth - 1)
+                })
+        }
+        _ => true,
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64().is_some_and(|number| number != 0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+fn float(number: f64) -> Value {
+    Number::from_f64(number).map_or(Value::Null, Value::Number)
+}
+
+fn equal(left: &Value, right: &Value) -> bool {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(left), Some(right)) => (left - right).abs() < f64::EPSILON,
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<std::cmp::Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+fn contains(container: &Value, item: &Value) -> Option<bool> {
+    match container {
+        Value::String(text) => Some(text.contains(&display(item))),
+        Value::Array(items) => Some(items.iter().any(|known| equal(known, item))),
+        Value::Object(fields) => Some(fields.contains_key(&display(item))),
+        _ => None,
+    }
+}
+
+fn arithmetic(op: BinaryOp, left: &Value, right: &Value) -> Option<Value> {
+    let (Value::Number(left), Value::Number(right)) = (left, right) else {
+        return None;
+    };
+    if let (Some(a), Some(b)) = (left.as_i64(), right.as_i64()) {
+        let integer = match op {
+            BinaryOp::Add => a.checked_add(b),
+            BinaryOp::Sub => a.checked_sub(b),
+            BinaryOp::Mul => a.checked_mul(b),
+            BinaryOp::FloorDiv => a.checked_div_euclid(b),
+            BinaryOp::Rem => a.checked_rem_euclid(b),
+            BinaryOp::Pow => u32::try_from(b).ok().and_then(|b| a.checked_pow(b)),
+            _ => None,
+        };
+        if let Some(integer) = integer {
+            return Some(integer.into());
+        }
+    }
+    let (a, b) = (left.as_f64()?, right.as_f64()?);
+    let number = match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div if b != 0.0 => a / b,
+        BinaryOp::FloorDiv if b != 0.0 => (a / b).floor(),
+        BinaryOp::Rem if b != 0.0 => a.rem_euclid(b),
+        BinaryOp::Pow => a.powf(b),
+        _ => return None,
+    };
+    Some(float(number))
+}
+
+fn operator(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Or => "or",
+        BinaryOp::And => "and",
+        BinaryOp::Eq => "==",
+        BinaryOp::Ne => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Le => "<=",
+        BinaryOp::Gt => ">",
+        BinaryOp::Ge => ">=",
+        BinaryOp::In => "in",
+        BinaryOp::NotIn => "not in",
+        BinaryOp::Concat => "~",
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::FloorDiv => "//",
+        BinaryOp::Rem => "%",
+        BinaryOp::Pow => "**",
+    }
+}
+
+/// `text` with every line but the first, or every line when `first`,
+/// indented by `width` spaces.
+fn indent(text: &str, width: usize, first: bool) -> String {
+    let padding = " ".repeat(width);
+    let mut indented = String::new();
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            indented.push('\n');
+        }
+        if (index > 0 || first) && !line.is_empty() {
+            indented.push_str(&padding);
+        }
+        let _ = write!(indented, "{line}");
+    }
+    indented
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::tools::jinja::parse;
+
+    fn render_with(source: &str, context: &Value, style: TemplatingStyle) -> (String, Vec<String>) {
+        let parsed = parse(source);
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        let rendered = render(
+            source,
+            &parsed.template,
+            context.as_object().unwrap(),
+            style,
+        );
+        let errors = rendered
+            .errors
+            .iter()
+            .map(|error| format!("{}: {}", &source[error.range], error.message))
+            .collect();
+        (rendered.text, errors)
+    }
+
+    fn render_text(source: &str, context: &Value) -> String {
+        let (text, errors) = render_with(source, context, TemplatingStyle::default());
+        assert!(errors.is_empty(), "{errors:?}");
+        text
+    }
+
+    #[test]
+    fn shorthands_are_tagged_or_inline() {
+        let context = json!({ "review": { "text": "Great product" }, "tone": "formal" });
+        assert_eq!(
+            render_text("Analyze @review in a $tone tone.", &context),
+            "Analyze review: ```\nGreat product\n``` in a formal tone."
+        );
+
+        let xml = TemplatingStyle {
+            tag_style: TagStyle::Xml,
+            text_format: TextFormat::Markdown,
+        };
+        let (text, _) = render_with(
+            "{{ review | tag('input') }}\n{{ order | format }}",
+            &json!({ "review": { "text": "Great" }, "order": { "id": 3, "items": ["a", "b"] } }),
+            xml,
+        );
+        assert_eq!(
+            text,
+            "<input>\nGreat\n</input>\n**id**: 3\n**items**:\n  - a\n  - b"
+        );
+    }
+
+    #[test]
+    fn statements_and_filters() {
+        let context = json!({ "items": ["b", "a", "b"], "count": 3, "name": "ada" });
+        assert_eq!(
+            render_text(
+                "{% for item in items | unique | sort %}{{ loop.index }}.{{ item | upper }} {% endfor %}",
+                &context
+            ),
+            "1.A 2.B "
+        );
+        assert_eq!(
+            render_text(
+                "{% if count > 2 and name is defined %}{{ name | title }}{% else %}none{% endif %}",
+                &context
+            ),
+            "Ada"
+        );
+        assert_eq!(
+            render_text(
+                "{% set ns = namespace(total=0) %}{% for i in range(count) %}{% set ns.total = ns.total + i %}{% endfor %}{{ ns.total }}",
+                &context
+            ),
+            "3"
+        );
+        assert_eq!(
+            render_text(
+                "{{ missing | default('n/a') }} {{ items | join(', ') }}",
+                &context
+            ),
+            "n/a b, a, b"
+        );
+        assert_eq!(
+            render_text("a  {%- if true -%}  b  {%- endif %}", &context),
+            "ab"
+        );
+    }
+
+    #[test]
+    fn undefined_variables_and_errors_are_reported() {
+        let (text, errors) = render_with(
+            "Hi @user.nickname, {{ ghost }} {{ 1 / 0 }} {{ name | shout }}!",
+            &json!({ "user": { "name": "Ada" }, "name": "x" }),
+            TemplatingStyle::default(),
+        );
+        assert_eq!(text, "Hi ,   !");
+        assert_eq!(
+            errors,
+            [
+                "nickname: no field `nickname`",
+                "ghost: undefined variable `ghost`",
+                "1 / 0: cannot apply `/` to `1` and `0`",
+                "shout: unknown filter `shout`",
+            ]
+        );
+    }
+
+    fn errors_of(source: &str, context: &Value) -> Vec<String> {
+        render_with(source, context, TemplatingStyle::default()).1
+    }
+
+    #[test]
+    fn text_filters() {
+        let context =
+            json!({ "name": "  ada LOVELACE  ", "text": "a\nb", "html": "<a href=\"x\">&</a>" });
+        assert_eq!(
+            render_text(
+                "{{ name | trim | upper }}|{{ name | lower | trim }}|{{ name | trim | capitalize }}|{{ name | trim | title }}",
+                &context
+            ),
+            "ADA LOVELACE|ada lovelace|Ada lovelace|Ada Lovelace"
+        );
+        assert_eq!(
+            render_text(
+                "{{ 'hello world' | replace('o', '0') }}|{{ 'ab' | replace('', '-') }}|{{ 'hello world' | truncate(8) }}|{{ 'hello' | truncate(8) }}",
+                &context
+            ),
+            "hell0 w0rld|-a-b-|hello...|hello"
+        );
+        assert_eq!(
+            render_text(
+                "{{ 'one two  three' | wordcount }}|{{ 'abc' | length }}|{{ 'abc' | first }}{{ 'abc' | last }}|{{ 'abc' | reverse }}|{{ 42 | string ~ '!' }}",
+                &context
+            ),
+            "3|3|ac|cba|42!"
+        );
+        assert_eq!(
+            render_text(
+                "{{ text | indent(2) }}|{{ text | indent(2, true) }}",
+                &context
+            ),
+            "a\n  b|  a\n  b"
+        );
+        assert_eq!(
+            render_text("{{ html | escape }}|{{ html | safe }}", &context),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;|<a href=\"x\">&</a>"
+        );
+    }
+
+    #[test]
+    fn list_and_mapping_filters() {
+        let context = json!({
+            "numbers": [3, 1, 2, 3],
+            "people": [{ "name": "Ada" }, { "name": "Alan" }],
+            "scores": { "b": 2, "a": 1 },
+        });
+        assert_eq!(
+            render_text(
+                "{{ numbers | sort | join(',') }}|{{ numbers | sort(reverse=true) | join(',') }}|{{ numbers | reverse | join(',') }}|{{ numbers | unique | join(',') }}",
+                &context
+            ),
+            "1,2,3,3|3,3,2,1|3,2,1,3|3,1,2"
+        );
+        assert_eq!(
+            render_text(
+                "{{ numbers | min }}|{{ numbers | max }}|{{ numbers | sum }}|{{ numbers | length }}|{{ numbers | first }}|{{ numbers | last }}",
+                &context
+            ),
+            "1|3|9|4|3|3"
+        );
+        assert_eq!(
+            render_text(
+                "{{ people | map(attribute='name') | join(' & ') }}|{{ 'ab' | list | join('/') }}|{{ scores | list | join('/') }}",
+                &context
+            ),
+            "Ada & Alan|a/b|b/a"
+        );
+        assert_eq!(
+            render_text(
+                "{% for key, value in scores | dictsort %}{{ key }}={{ value }} {% endfor %}|{% for pair in scores | items %}{{ pair[0] }}{% endfor %}|{{ scores | length }}|{{ scores | tojson }}",
+                &context
+            ),
+            "a=1 b=2 |ba|2|{\"b\":2,\"a\":1}"
+        );
+    }
+
+    #[test]
+    fn number_filters() {
+        let context = json!({ "price": 4.5678, "count": -4, "text": " 12 " });
+        assert_eq!(
+            render_text(
+                "{{ price | round }}|{{ price | round(2) }}|{{ price | int }}|{{ text | int + 1 }}|{{ count | abs }}|{{ count | float }}|{{ true | int }}|{{ 'x' | int }}",
+                &context
+            ),
+            "5.0|4.57|4|13|4|-4.0|1|0"
+        );
+    }
+
+    #[test]
+    fn default_filter() {
+        let context = json!({ "empty": "", "name": "Ada" });
+        assert_eq!(
+            render_text(
+                "{{ missing | default('a') }}|{{ missing.field | d('b') }}|{{ empty | default('c') }}|{{ empty | default('d', true) }}|{{ name | default('e') }}|{{ missing | default }}",
+                &context
+            ),
+            "a|b||d|Ada|"
+        );
+    }
+
+    #[test]
+    fn tag_and_format_filters() {
+        let context = json!({ "order": { "id": 3, "items": ["<a>", "b"] } });
+        let style = |tag_style, text_format| TemplatingStyle {
+            tag_style,
+            text_format,
+        };
+        let (text, errors) = render_with(
+            "{{ order | tag('order') }}",
+            &context,
+            style(TagStyle::SquareBrackets, TextFormat::Plain),
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(text, "[order]\nid: 3\nitems:\n  <a>\n  b\n[/order]");
+        let (text, _) = render_with(
+            "{{ order.items | format('html') }}|{{ order.items | tag('x') }}",
+            &context,
+            style(TagStyle::NoTag, TextFormat::Html),
+        );
+        assert_eq!(
+            text,
+            "<ul><li>&lt;a&gt;</li><li>b</li></ul>|<ul><li>&lt;a&gt;</li><li>b</li></ul>"
+        );
+        let (text, _) = render_with(
+            "{{ order | format('json') }}",
+            &context,
+            TemplatingStyle::default(),
+        );
+        assert_eq!(
+            text,
+            "{\n  \"id\": 3,\n  \"items\": [\n    \"<a>\",\n    \"b\"\n  ]\n}"
+        );
+        assert_eq!(
+            errors_of("{{ order | format('yaml') }}", &context),
+            ["format: unknown text format"]
+        );
+    }
+
+    #[test]
+    fn tests_and_operators() {
+        let context = json!({ "n": 7, "name": "ada", "tags": ["a", "b"], "user": { "id": 1 } });
+        assert_eq!(
+            render_text(
+                "{{ n is odd }} {{ n is even }} {{ n is divisibleby(7) }} {{ n is divisibleby(0) }} {{ n is number }} {{ n is integer }} {{ n is string }} {{ name is lower }} {{ name is upper }}",
+                &context
+            ),
+            "True False True False True True False True False"
+        );
+        assert_eq!(
+            render_text(
+                "{{ 'a' in tags }} {{ 'c' not in tags }} {{ 'id' in user }} {{ 'd' in name }} {{ n is in([7]) }} {{ user is mapping }} {{ tags is sequence }} {{ none is none }} {{ ghost is undefined }} {{ n is not defined }}",
+                &context
+            ),
+            "True True True True True True True True True False"
+        );
+        assert_eq!(
+            render_text(
+                "{{ n + 1 }} {{ n - 10 }} {{ n * 2 }} {{ n / 2 }} {{ n // 2 }} {{ n % 4 }} {{ -n % 4 }} {{ 2 ** 10 }} {{ 'ab' * 2 }} {{ name ~ n }} {{ tags + ['c'] }}",
+                &context
+            ),
+            "8 -3 14 3.5 3 3 1 1024 abab ada7 a\nb\nc"
+        );
+        assert_eq!(
+            render_text(
+                "{{ n > 5 and n <= 7 }} {{ n == 7.0 }} {{ n != 7 }} {{ not n }} {{ 'a' < 'b' }} {{ 0 or 'x' }} {{ 'yes' if n > 5 else 'no' }}|{{ 'yes' if n < 5 }}|",
+                &context
+            ),
+            "True True False False True x yes||"
+        );
+    }
+
+    #[test]
+    fn items_calls_and_methods() {
+        let context = json!({ "tags": ["a", "b", "c"], "user": { "name": "Ada", "role": null } });
+        assert_eq!(
+            render_text(
+                "{{ tags[0] }}{{ tags[-1] }} {{ user['name'] }} {{ user.get('age', 36) }} {{ user.keys() | join(',') }} {{ user.items() | length }}",
+                &context
+            ),
+            "ac Ada 36 name,role 2"
+        );
+        assert_eq!(
+            render_text(
+                "{{ 'a-b' .split('-') | join('+') }} {{ ' a  b ' .split() | length }} {{ 'Ada'.startswith('A') }} {{ 'Ada'.endswith('x') }} {{ ' x '.strip().upper() }} {{ 'aXa'.replace('X', 'y') }}",
+                &context
+            ),
+            "a+b 2 True False X aya"
+        );
+        assert_eq!(
+            render_text(
+                "{{ range(3) | join(',') }} {{ range(1, 4) | join(',') }} {{ range(5, 0, -2) | join(',') }} {{ dict(a=1).a }}",
+                &context
+            ),
+            "0,1,2 1,2,3 5,3,1 1"
+        );
+    }
+
+    #[test]
+    fn for_loops() {
+        let context = json!({ "items": ["a", "b", "c"], "scores": { "x": 1, "y": 2 }, "pairs": [[1, 2], [3, 4]] });
+        assert_eq!(
+            render_text(
+                "{% for item in items %}{{ loop.index0 }}{{ loop.revindex }}{{ loop.revindex0 }}{{ loop.length }}{% if loop.first %}F{% endif %}{% if loop.last %}L{% endif %} {% endfor %}",
+                &context
+            ),
+            "0323F 1213 2103L "
+        );
+        assert_eq!(
+            render_text(
+                "{% for item in items if item != 'b' %}{{ loop.index }}{{ item }}{% endfor %}|{% for item in [] %}x{% else %}empty{% endfor %}|{% for item in items if false %}x{% else %}none{% endfor %}",
+                &context
+            ),
+            "1a2c|empty|none"
+        );
+        assert_eq!(
+            render_text(
+                "{% for key in scores %}{{ key }}{% endfor %}|{% for c in 'hey' %}{{ c }}.{% endfor %}|{% for a, b in pairs %}{{ a + b }} {% endfor %}|{% for key, value in scores.items() %}{{ key }}{{ value }}{% endfor %}",
+                &context
+            ),
+            "xy|h.e.y.|3 7 |x1y2"
+        );
+        assert_eq!(
+            render_text(
+                "{% for row in pairs %}{% for cell in row %}{{ loop.index }}{% endfor %}{{ loop.index }};{% endfor %}{{ loop is defined }}",
+                &context
+            ),
+            "121;122;False"
+        );
+    }
+
+    #[test]
+    fn if_statements() {
+        let template = "{% if n > 10 %}big{% elif n > 5 %}medium{% elif n > 0 %}small{% else %}none{% endif %}";
+        let rendered: Vec<String> = [20, 7, 1, 0]
+            .iter()
+            .map(|n| render_text(template, &json!({ "n": n })))
+            .collect();
+        assert_eq!(rendered, ["big", "medium", "small", "none"]);
+        assert_eq!(
+            render_text(
+                "{% if items %}some{% endif %}{% if not empty %}|none{% endif %}{% if missing is defined %}!{% endif %}",
+                &json!({ "items": [1], "empty": [] })
+            ),
+            "some|none"
+        );
+    }
+
+    #[test]
+    fn set_with_and_filter_blocks() {
+        let context = json!({ "name": "ada" });
+        assert_eq!(
+            render_text(
+                "{% set greeting = 'hi ' ~ name %}{{ greeting }}|{% set a, b = 1, 2 %}{{ a + b }}|{% set block | upper %}x {{ name }}{% endset %}{{ block }}",
+                &context
+            ),
+            "hi ada|3|X ADA"
+        );
+        assert_eq!(
+            render_text(
+                "{% with shout = name | upper, n = 2 %}{{ shout * n }}{% endwith %}{{ shout is defined }}|{% filter title %}hello {{ name }}{% endfilter %}",
+                &context
+            ),
+            "ADAADAFalse|Hello Ada"
+        );
+        assert_eq!(
+            render_text(
+                "{% for i in range(3) %}{% set last = i %}{% endfor %}{{ last is defined }}|{% set ns = namespace(items=[]) %}{% for i in range(3) %}{% set ns.items = ns.items + [i * i] %}{% endfor %}{{ ns.items | join(',') }}",
+                &context
+            ),
+            "False|0,1,4"
+        );
+        assert_eq!(
+            render_text(
+                "{% macro hello() %}hi{% endmacro %}{% raw %}{{ name }}{% endraw %}",
+                &context
+            ),
+            "{{ name }}"
+        );
+    }
+
+    #[test]
+    fn errors_render_as_nothing() {
+        let context = json!({ "n": 3, "items": [1, 2], "text": "x" });
+        assert_eq!(
+            errors_of(
+                "{% for i in n %}{% endfor %}{% for a, b in items %}{% endfor %}{{ n is shiny }}{{ n | first }}",
+                &context
+            ),
+            [
+                "n: `3` is not iterable",
+                "a, b: cannot unpack 1 values into 2 names",
+                "a, b: cannot unpack 1 values into 2 names",
+                "shiny: unknown test `shiny`",
+                "first: the `first` filter does not apply to `3`",
+            ]
+        );
+        assert_eq!(
+            errors_of(
+                "{{ items[5] }}{{ text.missing }}{{ lipsum() }}{{ text.title() }}{{ range('a') }}{{ -text }}{{ text - 1 }}{{ text | first | abs }}{% set text.x = 1 %}{% call foo() %}{% endcall %}",
+                &context
+            ),
+            [
+                "items[5]: no item `5`",
+                "missing: no field `missing`",
+                "lipsum: cannot call `lipsum` in a preview",
+                "title: cannot call `title` in a preview",
+                "range('a'): `range` expects one to three integers",
+                "-text: expected a number",
+                "text - 1: cannot apply `-` to `x` and `1`",
+                "abs: the `abs` filter does not apply to `x`",
+                "text: `text` is not a namespace",
+                "call: `{% call %}` is not supported in previews",
+            ]
+        );
+    }
+
+    #[test]
+    fn integer_overflow_is_not_a_crash() {
+        let context = json!({ "min": i64::MIN, "max": i64::MAX });
+        assert_eq!(
+            render_text(
+                "{{ -min }}|{{ min | abs }}|{{ min is divisibleby(-1) }}|{{ max + 1 }}|{{ max * 2 }}",
+                &context
+            ),
+            "9.223372036854776e18|9.223372036854776e18|False|9.223372036854776e18|1.8446744073709552e19"
+        );
+        assert_eq!(
+            render_text(
+                "{% for i in range(9223372036854775800, 9223372036854775807, 10) %}{{ i }}{% endfor %}|{{ range(-9223372036854775807, -9223372036854775800, -3) | length }}",
+                &context
+            ),
+            "9223372036854775800|0"
+        );
+    }
+
+    #[test]
+    fn oversized_values_are_reported() {
+        let context = json!({ "text": "a\nb" });
+        let (text, errors) = render_with(
+            "[{{ \"ab\" * 100000000000 }}][{{ 'ab' | replace('', 'x' * 1000000) }}][{{ 'a'.replace('a', 'x' * 1000000) * 2 }}][{{ text | indent(100000000000) }}][{{ range(10) | join('x' * 200000) }}]",
+            &context,
+            TemplatingStyle::default(),
+        );
+        assert_eq!(text, "[][][][][]");
+        assert_eq!(
+            errors,
+            [
+                "\"ab\" * 100000000000: the result would exceed the 1048576 bytes a preview allows",
+                "replace: the result would exceed the 1048576 bytes a preview allows",
+                "'a'.replace('a', 'x' * 1000000) * 2: the result would exceed the 1048576 bytes a preview allows",
+                "indent: the result would exceed the 1048576 bytes a preview allows",
+                "join: the result would exceed the 1048576 bytes a preview allows",
+            ]
+        );
+
+        let errors = errors_of(
+            "{% set big = 'x' * 1000000 %}{{ (big ~ big) | length }}{{ [big, big] | length }}",
+            &context,
+        );
+        assert_eq!(
+            errors,
+            [
+                "big ~ big: the result exceeds the 1048576 bytes or 64 levels a preview allows",
+                "[big, big]: the result exceeds the 1048576 bytes or 64 levels a preview allows",
+            ]
+        );
+
+        let errors = errors_of(
+            "{% set ns = namespace(x=[]) %}{% for i in range(70) %}{% set ns.x = [ns.x] %}{% endfor %}",
+            &context,
+        );
+        assert_eq!(
+            errors,
+            ["[ns.x]: the result exceeds the 1048576 bytes or 64 levels a preview allows"]
+        );
+    }
+
+    #[test]
+    fn long_ranges_and_loops_are_cut_off() {
+        let context = json!({});
+        assert_eq!(
+            errors_of("{% for i in range(10000000000) %}{% endfor %}", &context),
+            ["range(10000000000): `range` produces more than the 100000 items a preview allows"]
+        );
+
+        let (text, errors) = render_with(
+            "{% for i in range(1000) %}{% for j in range(1000) %}.{% endfor %}{% endfor %}after",
+            &context,
+            TemplatingStyle::default(),
+        );
+        // The outer loop's 100 iterations count too.
+        assert_eq!(text.len(), 99_900);
+        assert_eq!(
+            errors,
+            ["{% for j in range(1000) %}.{% endfor %}: the preview runs more than 100000 loop iterations; rendering stopped here"]
+        );
+
+        let (text, errors) = render_with(
+            "{% for i in range(2000) %}{{ 'é' * 999 }}{% endfor %}after",
+            &context,
+            TemplatingStyle::default(),
+        );
+        assert!(text.len() <= MAX_SIZE && text.len() > MAX_SIZE - 2);
+        assert!(text.ends_with('é'));
+        assert_eq!(
+            errors,
+            ["'é' * 999: the preview exceeds 1048576 bytes; rendering stopped here"]
+        );
+    }
+}
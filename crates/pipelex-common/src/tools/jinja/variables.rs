@@ -0,0 +1,306 @@
+//! The context variables a parsed template reads.
+//!
+//! A pipe's template reads its inputs: the `@name` / `$name` shorthands,
+//! and the names of its expressions that no enclosing `{% for %}`,
+//! `{% set %}` or `{% with %}` binds. Names called as functions (`range`,
+//! macros) and the bodies of `{% macro %}` and `{% call %}` blocks, whose
+//! parameters the parser does not keep, are left out.
+
+use taplo::rowan::TextRange;
+
+use super::ast::{Argument, Block, Expr, Filter, Name, Node, SetValue, Template};
+
+/// A variable a template reads from its context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    /// The `@` or `$` of a shorthand; `None` in an expression.
+    pub sigil: Option<char>,
+    /// The variable and the `.field` segments read from it.
+    pub path: Vec<Name>,
+    /// The whole reference, sigil included.
+    pub range: TextRange,
+}
+
+impl Variable {
+    /// The name of the variable, without its fields.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.path[0].name
+    }
+}
+
+/// The context variables `template` reads, in template order.
+#[must_use]
+pub fn variables(template: &Template) -> Vec<Variable> {
+    let mut collector = Collector {
+        scopes: vec![Vec::new()],
+        variables: Vec::new(),
+    };
+    collector.nodes(&template.nodes);
+    collector
+        .variables
+        .sort_by_key(|variable| variable.range.start());
+    collector.variables
+}
+
+/// Literals Jinja also accepts as names.
+const CONSTANTS: &[&str] = &["True", "False", "None", "true", "false", "none"];
+
+struct Collector {
+    /// The names bound by the enclosing blocks, innermost last.
+    scopes: Vec<Vec<String>>,
+    variables: Vec<Variable>,
+}
+
+impl Collector {
+    fn is_bound(&self, name: &str) -> bool {
+        CONSTANTS.contains(&name)
+            || self
+                .scopes
+                .iter()
+                .any(|scope| scope.iter().any(|bound| bound == name))
+    }
+
+    fn bind(&mut self, name: &Name) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(name.name.clone());
+        }
+    }
+
+    fn scoped(&mut self, names: Vec<String>, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(names);
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn read(&mut self, sigil: Option<char>, path: Vec<Name>, range: TextRange) {
+        if !path.is_empty() && !self.is_bound(&path[0].name) {
+            self.variables.push(Variable { sigil, path, range });
+        }
+    }
+
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Text(_) => {}
+            Node::Shorthand(shorthand) => self.read(
+                Some(shorthand.sigil),
+                shorthand.path.clone(),
+                shorthand.range,
+            ),
+            Node::Output(expr) => self.expr(expr),
+            // `{% if %}` opens no scope: what it sets is visible after it.
+            Node::If(node) => {
+                for (condition, body) in &node.branches {
+                    self.expr(condition);
+                    self.nodes(body);
+                }
+                if let Some(otherwise) = &node.otherwise {
+                    self.nodes(otherwise);
+                }
+            }
+            Node::For(node) => {
+                self.expr(&node.iter);
+                let mut names: Vec<String> = node
+                    .targets
+                    .iter()
+                    .map(|target| target.name.clone())
+                    .collect();
+                names.push("loop".into());
+                self.scoped(names, |collector| {
+                    if let Some(condition) = &node.condition {
+                        collector.expr(condition);
+                    }
+                    collector.nodes(&node.body);
+                });
+                if let Some(otherwise) = &node.otherwise {
+                    self.nodes(otherwise);
+                }
+            }
+            Node::Set(node) => {
+                match &node.value {
+                    SetValue::Expr(expr) => self.expr(expr),
+                    SetValue::Block { filters, body } => {
+                        self.nodes(body);
+                        self.filters(filters);
+                    }
+                }
+                for target in &node.targets {
+                    match target {
+                        Expr::Name(name) => self.bind(name),
+                        // `ns.attribute` reads the namespace `ns`.
+                        Expr::Attribute { target, .. } => self.expr(target),
+                        _ => {}
+                    }
+                }
+            }
+            Node::Block(node) => self.block(node),
+        }
+    }
+
+    fn block(&mut self, node: &Block) {
+        match node.tag.name.as_str() {
+            "macro" | "call" => {}
+            "with" => {
+                for (_, value) in &node.assignments {
+                    self.expr(value);
+                }
+                let names = node
+                    .assignments
+                    .iter()
+                    .map(|(name, _)| name.name.clone())
+                    .collect();
+                self.scoped(names, |collector| collector.nodes(&node.body));
+            }
+            _ => {
+                self.filters(&node.filters);
+                self.scoped(Vec::new(), |collector| collector.nodes(&node.body));
+            }
+        }
+    }
+
+    fn filters(&mut self, filters: &[Filter]) {
+        for filter in filters {
+            self.arguments(&filter.args);
+        }
+    }
+
+    fn arguments(&mut self, args: &[Argument]) {
+        for arg in args {
+            self.expr(&arg.value);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Name(name) => self.read(None, vec![name.clone()], name.range),
+            Expr::Attribute { .. } => match attribute_path(expr) {
+                Some(path) => {
+                    let range = path[0].range.cover(path[path.len() - 1].range);
+                    self.read(None, path, range);
+                }
+                None => {
+                    let mut target = expr;
+                    while let Expr::Attribute { target: inner, .. } = target {
+                        target = inner;
+                    }
+                    self.expr(target);
+                }
+            },
+            Expr::Literal(..) => {}
+            Expr::List(items, _) | Expr::Tuple(items, _) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            Expr::Dict(entries, _) => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Item { target, index, .. } => {
+                self.expr(target);
+                self.expr(index);
+            }
+            Expr::Call { target, args, .. } => {
+                match &**target {
+                    // A function or a macro, not a variable.
+                    Expr::Name(_) => {}
+                    // A method: its receiver is read, not a field of it.
+                    Expr::Attribute { target, .. } => self.expr(target),
+                    other => self.expr(other),
+                }
+                self.arguments(args);
+            }
+            Expr::Filter { target, filter, .. } => {
+                self.expr(target);
+                self.arguments(&filter.args);
+            }
+            Expr::Test { target, args, .. } => {
+                self.expr(target);
+                self.arguments(args);
+            }
+            Expr::Unary { operand, .. } => self.expr(operand),
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Conditional {
+                then,
+                condition,
+                otherwise,
+            } => {
+                self.expr(then);
+                self.expr(condition);
+                if let Some(otherwise) = otherwise {
+                    self.expr(otherwise);
+                }
+            }
+        }
+    }
+}
+
+/// The `name.field…` path of a chain of attributes on a name; `None` when
+/// the chain starts from another expression.
+fn attribute_path(expr: &Expr) -> Option<Vec<Name>> {
+    match expr {
+        Expr::Name(name) => Some(vec![name.clone()]),
+        Expr::Attribute { target, attribute } => {
+            let mut path = attribute_path(target)?;
+            path.push(attribute.clone());
+            Some(path)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::jinja::parse;
+
+    fn read(source: &str) -> Vec<String> {
+        let parsed = parse(source);
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        variables(&parsed.template)
+            .iter()
+            .map(|variable| source[variable.range].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn shorthands_and_expression_paths() {
+        assert_eq!(
+            read("@doc.title by $author: {{ doc.body | truncate(size) }} {{ user.get('x') }}"),
+            ["@doc.title", "$author", "doc.body", "size", "user"]
+        );
+        assert_eq!(
+            read("{{ range(n) | join(sep) }}{{ items[0].name }}{{ True }}"),
+            ["n", "sep", "items"]
+        );
+    }
+
+    #[test]
+    fn bound_names_are_not_read() {
+        assert_eq!(
+            read(
+                "{% for item in items if item.ok %}{{ item.name }} {{ loop.index }} @item{% else %}{{ item }}{% endfor %}"
+            ),
+            ["items", "item"]
+        );
+        assert_eq!(
+            read("{% set total = a + b %}{{ total }}{% set ns = namespace() %}{% set ns.x = c %}"),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            read("{% with x = y %}{{ x }}{% endwith %}{{ x }}{% macro m(arg) %}{{ arg }}{% endmacro %}"),
+            ["y", "x"]
+        );
+    }
+}
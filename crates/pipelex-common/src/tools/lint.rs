@@ -2,25 +2,32 @@
 //!
 //! Mirrors the CLI's `lint_source` (`taplo-cli/src/commands/lint.rs`) staging
 //! and the wasm crate's `lint` (`pipelex-wasm/src/lib.rs`), short-circuiting at
-//! the **first** failing stage: syntax → semantic → schema. Per-stage
+//! the **first** failing stage: syntax → semantic → schema → rules. Per-stage
 //! diagnostics are deduped exactly as the CLI prints them (syntax by range,
-//! schema by coords + message + location; semantic is not deduped).
+//! schema by coords + message + location; semantic is not deduped). The rules
+//! stage runs the [`rules`](crate::tools::rules) registry at default severities
+//! over the document alone — the bindings have no sibling bundle files and no
+//! `plxt.toml`. A schema-valid document also gets the schema's deprecation
+//! warnings (`schema/deprecated`), ahead of the rule diagnostics.
 //!
 //! Like `format_mthds`, this **never raises on bad content** (decision #2):
 //! malformed input — including the schema stage's "errors that couldn't be
 //! mapped to document positions" bail-out — is surfaced as diagnostics, not as
 //! an error.
 //!
-//! The schema stage validates against the embedded official MTHDS schema only
-//! (`pipelex://mthds.schema.json`), fully offline: it constructs `Schemas` with
-//! `http: None` and calls `validate_root` with the hardcoded builtin URL
-//! directly — deliberately skipping the association machinery the CLI/wasm use
-//! to *choose* a schema. There is no code path that can reach out for another
-//! schema (settled decision #3). Because validation hits only the in-memory
-//! builtin (no external `$ref`s, no env IO, no `spawn`), the future returned by
-//! [`lint_mthds_with_env`] resolves without ever yielding — which is what lets
-//! the native wrapper `block_on` a current-thread runtime and the offline
-//! wrapper poll it exactly once.
+//! The schema stage validates against the embedded official MTHDS schemas only,
+//! fully offline: it constructs `Schemas` with `http: None` and calls
+//! `validate_root` with a builtin URL directly — the latest schema
+//! (`pipelex://mthds.schema.json`), or the embedded version the document
+//! declares with `mthds_version` or a `#:schema pipelex://…` directive —
+//! deliberately skipping the association machinery the CLI/wasm use to
+//! *choose* a schema. A directive naming any other schema is ignored. There is
+//! no code path that can reach out for another schema (settled decision #3).
+//! Because validation hits only the in-memory builtin (no external `$ref`s, no
+//! env IO, no `spawn`), the future returned by [`lint_mthds_with_env`]
+//! resolves without ever yielding — which is what lets the native wrapper
+//! `block_on` a current-thread runtime and the offline wrapper poll it exactly
+//! once.
 //!
 //! Three entry points, one engine:
 //! - [`lint_mthds_with_env`] — the shared async core, generic over
@@ -37,18 +44,28 @@ use std::collections::HashSet;
 use anyhow::Context;
 use taplo::{dom, parser, rowan::TextRange};
 use taplo_common::{
+    config::LintOptions,
     environment::Environment,
-    schema::{builtins::MTHDS_SCHEMA_URL, Schemas},
+    schema::{builtins::MthdsSchemaVersion, Schemas},
 };
 use url::Url;
 
+use crate::tools::bundle::BundleIndex;
 use crate::tools::diagnostic::{Diagnostic, Range};
 use crate::tools::environment::NullEnvironment;
+use crate::tools::fixes::{deprecation_fixes, schema_fixes};
+use crate::tools::rules::rule_diagnostics;
 
-/// Lint MTHDS `content` against the embedded MTHDS schema, fully offline,
-/// using `env` for the schema machinery's bookkeeping (nothing else).
+/// The code of the schema stage's single bail-out diagnostic.
+const VALIDATION_FAILED: &str = "schema/validation-failed";
+
+/// Lint MTHDS `content` against the embedded MTHDS schema of the version it
+/// declares (the latest by default), fully offline, using `env` for the
+/// schema machinery's bookkeeping (nothing else).
 ///
-/// Returns the diagnostics from the first failing stage (empty == clean).
+/// Returns the diagnostics from the first failing stage (empty == clean). The
+/// rules stage may report warnings and infos, which do not make content
+/// invalid.
 pub async fn lint_mthds_with_env<E: Environment>(
     env: E,
     content: &str,
@@ -80,14 +97,18 @@ pub async fn lint_mthds_with_env<E: Environment>(
         return Ok(errors
             .map(|err| {
                 let range = semantic_error_range(&err).map(|r| Range::from_text_range(content, r));
-                Diagnostic::semantic(err.to_string(), range)
+                Diagnostic::semantic(err.code(), err.to_string(), range)
             })
             .collect());
     }
 
-    // Stage 3 — schema, offline against the embedded builtin. `http: None` is
+    // Stage 3 — schema, offline against an embedded builtin. `http: None` is
     // what makes this provably offline — there is no client to fetch with.
-    let url = Url::parse(MTHDS_SCHEMA_URL).context("invalid builtin MTHDS schema URL")?;
+    let version = BundleIndex::from_dom(&dom)
+        .mthds_version
+        .and_then(|declared| MthdsSchemaVersion::find(&declared.version))
+        .unwrap_or_else(MthdsSchemaVersion::latest);
+    let url = Url::parse(version.url).context("invalid builtin MTHDS schema URL")?;
     let schemas = Schemas::new(env, None);
     let validation = schemas.validate_root(&url, &dom).await;
 
@@ -99,9 +120,47 @@ pub async fn lint_mthds_with_env<E: Environment>(
     // diagnostic instead of propagating it out as an error.
     let errors = match validation {
         Ok(errors) => errors,
-        Err(err) => return Ok(vec![Diagnostic::schema(format!("{err:#}"), None, None)]),
+        Err(err) => {
+            return Ok(vec![Diagnostic::schema(
+                VALIDATION_FAILED,
+                format!("{err:#}"),
+                None,
+                None,
+            )])
+        }
     };
 
+    if errors.is_empty() {
+        // The schema's deprecation warnings, then stage 4 — MTHDS rules, only
+        // once the document is schema-valid.
+        let deprecations = match schemas.deprecations(&url, &dom).await {
+            Ok(deprecations) => deprecations,
+            Err(err) => {
+                return Ok(vec![Diagnostic::schema(
+                    VALIDATION_FAILED,
+                    format!("{err:#}"),
+                    None,
+                    None,
+                )])
+            }
+        };
+        let mut diagnostics: Vec<Diagnostic> = deprecations
+            .iter()
+            .map(|deprecation| {
+                Diagnostic::deprecation(
+                    deprecation.message(),
+                    deprecation.instance_location(),
+                    deprecation
+                        .text_range()
+                        .map(|r| Range::from_text_range(content, r)),
+                )
+                .with_fixes(content, &deprecation_fixes(&dom, deprecation))
+            })
+            .collect();
+        diagnostics.extend(rule_diagnostics(content, &dom, &LintOptions::default()));
+        return Ok(diagnostics);
+    }
+
     // Dedup identical schema errors at the same location, mirroring the CLI's
     // `print_schema_errors_compact` (`seen_messages` keyed on coords + message
     // + instance location).
@@ -127,8 +186,10 @@ pub async fn lint_mthds_with_env<E: Environment>(
                 message.clone(),
                 location.clone(),
             );
-            seen.insert(dedup_key)
-                .then(|| Diagnostic::schema(message, location, range))
+            seen.insert(dedup_key).then(|| {
+                Diagnostic::schema(err.code(), message, location, range)
+                    .with_fixes(content, &schema_fixes(&err))
+            })
         })
         .collect())
 }
@@ -195,7 +256,7 @@ fn semantic_error_range(error: &dom::Error) -> Option<TextRange> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tools::diagnostic::DiagnosticKind;
+    use crate::tools::diagnostic::{DiagnosticKind, Severity};
     use std::time::{Duration, Instant};
 
     const VALID: &str = include_str!("../../../../test-data/mthds/lint/valid.mthds");
@@ -223,6 +284,12 @@ mod tests {
             diagnostics.iter().any(|d| d.location.is_some()),
             "schema errors carry a dotted instance location"
         );
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.code.starts_with("schema/") && d.code_description.is_some()),
+            "schema errors carry a documented keyword code, got: {diagnostics:?}"
+        );
     }
 
     #[test]
@@ -230,6 +297,7 @@ mod tests {
         let diagnostics = lint_mthds_impl("key = ").expect("lint should succeed");
         assert!(!diagnostics.is_empty());
         assert_eq!(diagnostics[0].kind, DiagnosticKind::Syntax);
+        assert_eq!(diagnostics[0].code, "syntax/parse-error");
         assert!(diagnostics[0].range.is_some());
     }
 
@@ -239,6 +307,87 @@ mod tests {
         let diagnostics = lint_mthds_impl("a = 1\na = 2\n").expect("lint should succeed");
         assert!(!diagnostics.is_empty());
         assert_eq!(diagnostics[0].kind, DiagnosticKind::Semantic);
+        assert_eq!(diagnostics[0].code, "semantic/conflicting-keys");
+    }
+
+    #[test]
+    fn rule_stage_runs_after_a_clean_schema_stage() {
+        let content = "domain = \"d\"\n\n[pipe.p]\ntype = \"PipeLLM\"\ndescription = \"p\"\noutput = \"Summary\"\n";
+        let diagnostics = lint_mthds_impl(content).expect("lint should succeed");
+        assert_eq!(diagnostics.len(), 1, "got {diagnostics:?}");
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::Rule);
+        assert_eq!(diagnostics[0].code, "mthds/undefined-concept");
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].range.as_ref().unwrap().start_line, 6);
+    }
+
+    #[test]
+    fn missing_description_offers_a_placeholder_fix() {
+        let content = "domain = \"d\"\n\n[pipe.p]\ntype = \"PipeLLM\"\noutput = \"Text\"\n";
+        let diagnostics = lint_mthds_impl(content).expect("lint should succeed");
+        assert_eq!(diagnostics.len(), 1, "got {diagnostics:?}");
+        assert_eq!(diagnostics[0].code, "schema/required");
+        let fix = &diagnostics[0].fixes[0];
+        assert!(!fix.safe, "a placeholder still needs writing");
+        let edit = &fix.edits[0];
+        let mut fixed = content.to_string();
+        fixed.replace_range(
+            edit.range.start_offset..edit.range.end_offset,
+            &edit.new_text,
+        );
+        assert_eq!(
+            fixed,
+            "domain = \"d\"\n\n[pipe.p]\ntype = \"PipeLLM\"\ndescription = \"TODO\"\noutput = \"Text\"\n"
+        );
+    }
+
+    #[test]
+    fn declared_version_selects_the_embedded_schema() {
+        let legacy = "mthds_version = \"0.9\"\ndomain = \"d\"\n\n[pipe.p]\ntype = \"PipeLLM\"\n\
+                      description = \"p\"\noutput = \"Text\"\nprompt_template = \"Hi\"\n";
+        let codes = |content: &str| {
+            lint_mthds_offline(content)
+                .expect("lint should succeed")
+                .into_iter()
+                .map(|d| d.code)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(codes(legacy), ["mthds/deprecated-version"]);
+        // The latest schema only accepts the old field name as a deprecation.
+        assert_eq!(
+            codes(&legacy.replace("mthds_version = \"0.9\"\n", "")),
+            ["schema/deprecated"]
+        );
+        // A directive naming a remote schema is ignored, never fetched.
+        assert_eq!(
+            codes(&format!(
+                "#:schema https://example.com/mthds.json\n{}",
+                legacy.replace("mthds_version = \"0.9\"\n", "")
+            )),
+            ["schema/deprecated"]
+        );
+    }
+
+    #[test]
+    fn deprecated_fields_are_warnings_with_a_fix() {
+        let content = "domain = \"d\"\n\n[pipe.p]\ntype = \"PipeLLM\"\ndescription = \"p\"\n\
+                       output = \"Text\"\nprompt_template = \"Hi\"\n";
+        let diagnostics = lint_mthds_offline(content).expect("lint should succeed");
+        assert_eq!(diagnostics.len(), 1);
+        let deprecation = &diagnostics[0];
+        assert_eq!(deprecation.kind, DiagnosticKind::Schema);
+        assert_eq!(deprecation.severity, Severity::Warning);
+        assert_eq!(
+            deprecation.message,
+            "`prompt_template` is deprecated: renamed to `prompt` in MTHDS 1.0"
+        );
+        assert_eq!(
+            deprecation.location.as_deref(),
+            Some("pipe.p.prompt_template")
+        );
+        assert_eq!(deprecation.range.as_ref().unwrap().start_line, 7);
+        assert_eq!(deprecation.fixes.len(), 1);
+        assert_eq!(deprecation.fixes[0].edits[0].new_text, "prompt");
     }
 
     #[test]
@@ -0,0 +1,335 @@
+//! Migrations of MTHDS bundles between language versions.
+//!
+//! Runtime upgrades rename blueprint fields. Each [`Migration`] of the
+//! [`MIGRATIONS`] registry moves a bundle one version forward by renaming
+//! the fields of [`MTHDS_RENAMED_FIELDS`] its target version renamed;
+//! [`migrate`] chains them from one version to another and bumps the bundle's
+//! version declaration on the way.
+//!
+//! Renames are staged as [`Rewrite`] patches over the parsed document, so
+//! everything they do not touch (comments, formatting, key order) is kept as
+//! written. A change that cannot be made safely, such as a rename whose new key
+//! already exists, is left out and reported as a conflict.
+
+use anyhow::{anyhow, bail};
+use taplo::{
+    dom::{
+        node::{DomNode, Key},
+        rewrite::{self, Rewrite},
+        KeyOrIndex, Keys, Node,
+    },
+    parser,
+    util::escape,
+};
+
+use taplo_common::schema::builtins::{MthdsRenamedField, MthdsSchemaVersion, MTHDS_RENAMED_FIELDS};
+
+use super::bundle::BundleIndex;
+
+/// A move of a bundle from one MTHDS version to the next.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub from: &'static str,
+    pub to: &'static str,
+}
+
+impl Migration {
+    /// The fields the `to` version renamed.
+    pub fn renames(&self) -> impl Iterator<Item = &'static MthdsRenamedField> + '_ {
+        MTHDS_RENAMED_FIELDS
+            .iter()
+            .filter(|renamed| renamed.since == self.to)
+    }
+}
+
+/// Every known migration, oldest first.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: "0.9",
+    to: "1.0",
+}];
+
+/// The outcome of migrating one document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Migrated {
+    /// The migrated document.
+    pub source: String,
+    /// How many fields were renamed, the version declaration aside.
+    pub changes: usize,
+    /// The changes that were left out, e.g. a rename whose new key already
+    /// exists.
+    pub conflicts: Vec<String>,
+}
+
+/// The migrations to run, in order, to go from the `from` version to the `to`
+/// version. Empty when both are the same.
+///
+/// # Errors
+///
+/// If either version is not an embedded MTHDS version, or if no chain of
+/// migrations leads from one to the other (there are no downgrades).
+pub fn migration_path(from: &str, to: &str) -> anyhow::Result<Vec<&'static Migration>> {
+    for version in [from, to] {
+        if MthdsSchemaVersion::find(version).is_none() {
+            bail!(
+                "unknown MTHDS version `{version}`, expected one of: {}",
+                MthdsSchemaVersion::known_versions()
+            );
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut current = from;
+    while current != to {
+        let next = MIGRATIONS
+            .iter()
+            .find(|migration| migration.from == current)
+            .filter(|_| path.len() < MIGRATIONS.len())
+            .ok_or_else(|| anyhow!("no migration from MTHDS `{from}` to `{to}`"))?;
+        path.push(next);
+        current = next.to;
+    }
+    Ok(path)
+}
+
+/// Migrate the bundle `source`, written for the `from` MTHDS version, to the
+/// `to` version. A bundle already at `to` still gets the renames whose old
+/// name the schema of `to` only accepts as deprecated.
+///
+/// # Errors
+///
+/// If there is no migration path (see [`migration_path`]) or if `source` has
+/// syntax errors.
+pub fn migrate(source: &str, from: &str, to: &str) -> anyhow::Result<Migrated> {
+    let path = migration_path(from, to)?;
+
+    let mut migrated = Migrated {
+        source: source.to_string(),
+        ..Migrated::default()
+    };
+    for migration in path {
+        let parse = parser::parse(&migrated.source);
+        if !parse.errors.is_empty() {
+            bail!("the document has syntax errors");
+        }
+        migrated.source = run(&parse.into_dom(), migration, &mut migrated)
+            .map_err(|err| anyhow!("failed to migrate to MTHDS `{}`: {err}", migration.to))?;
+    }
+
+    if from == to {
+        let parse = parser::parse(source);
+        if !parse.errors.is_empty() {
+            bail!("the document has syntax errors");
+        }
+        migrated.source = rename_deprecated(&parse.into_dom(), to, &mut migrated)
+            .map_err(|err| anyhow!("failed to migrate to MTHDS `{to}`: {err}"))?;
+    }
+    Ok(migrated)
+}
+
+/// Run the renames whose old name the schema of the `version` marks
+/// deprecated over `dom`. Returns the new source.
+fn rename_deprecated(
+    dom: &Node,
+    version: &str,
+    migrated: &mut Migrated,
+) -> Result<String, rewrite::Error> {
+    let mut rewrite = Rewrite::new(dom.clone())?;
+    if let Some(schema) = MthdsSchemaVersion::find(version).map(MthdsSchemaVersion::schema) {
+        for renamed in MTHDS_RENAMED_FIELDS {
+            let pointer = format!(
+                "/definitions/{}/properties/{}/x-plxt/deprecated",
+                renamed.definition, renamed.old
+            );
+            if schema.pointer(&pointer).is_some() {
+                rename_field(renamed, dom, &mut rewrite, migrated)?;
+            }
+        }
+    }
+    Ok(rewrite.to_string())
+}
+
+/// Run the renames of `migration` over `dom`, then bump its version
+/// declaration. Returns the new source.
+fn run(
+    dom: &Node,
+    migration: &Migration,
+    migrated: &mut Migrated,
+) -> Result<String, rewrite::Error> {
+    let mut rewrite = Rewrite::new(dom.clone())?;
+    for renamed in migration.renames() {
+        rename_field(renamed, dom, &mut rewrite, migrated)?;
+    }
+    bump_declaration(dom, migration, &mut rewrite)?;
+    Ok(rewrite.to_string())
+}
+
+/// Stage the rename of `renamed` in every table of `dom` that holds its old
+/// name and has its `type`.
+fn rename_field(
+    renamed: &MthdsRenamedField,
+    dom: &Node,
+    rewrite: &mut Rewrite,
+    migrated: &mut Migrated,
+) -> Result<(), rewrite::Error> {
+    let pattern: Keys = format!("{}.{}", renamed.tables, renamed.old).parse()?;
+
+    for (keys, _) in dom.find_all_matches(pattern, false)? {
+        let Some(KeyOrIndex::Key(key)) = keys.iter().last().cloned() else {
+            continue;
+        };
+        let table = keys.skip_right(1);
+
+        let table_type = dom.path(&table.clone().join(Key::new("type")));
+        if table_type
+            .as_ref()
+            .and_then(Node::as_str)
+            .map_or(true, |string| string.value() != renamed.table_type)
+        {
+            continue;
+        }
+
+        if dom.path(&table.join(Key::new(renamed.new))).is_some() {
+            migrated.conflicts.push(format!(
+                "`{}` was not renamed to `{}`: the key already exists",
+                keys.dotted(),
+                renamed.new
+            ));
+            continue;
+        }
+
+        let new_text = Key::new(renamed.new).to_string();
+        for range in key.text_ranges() {
+            rewrite.replace_range(range, &new_text)?;
+        }
+        migrated.changes += 1;
+    }
+    Ok(())
+}
+
+/// Point the version declaration of `dom`, if any, at the version `migration`
+/// leads to: either the `mthds_version` string or the `#:schema` directive.
+fn bump_declaration(
+    dom: &Node,
+    migration: &Migration,
+    rewrite: &mut Rewrite,
+) -> Result<(), rewrite::Error> {
+    let (Some(declaration), Some(target)) = (
+        BundleIndex::from_dom(dom).mthds_version,
+        MthdsSchemaVersion::find(migration.to),
+    ) else {
+        return Ok(());
+    };
+
+    let is_directive = dom
+        .syntax()
+        .and_then(|syntax| syntax.as_node().cloned())
+        .and_then(|root| root.covering_element(declaration.range).into_token())
+        .is_some_and(|token| token.text().starts_with("#:schema"));
+    let new_text = if is_directive {
+        format!("#:schema {}", target.url)
+    } else {
+        format!("\"{}\"", escape(target.version))
+    };
+
+    if !rewrite.overlaps(declaration.range) {
+        rewrite.replace_range(declaration.range, &new_text)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY: &str = r#"mthds_version = "0.9"
+domain = "legacy"
+
+[pipe.summarize]
+type = "PipeLLM"
+description = "Summarize the text"
+output = "Text"
+# The prompt sent to the model.
+prompt_template = """
+Summarize @text.
+"""
+
+[pipe.translate]
+type        = "PipeLLM"
+description = "Translate the text"
+output      = "Text"
+prompt_template = "Translate @text."
+"#;
+
+    #[test]
+    fn renames_fields_and_bumps_the_declaration() {
+        let migrated = migrate(LEGACY, "0.9", "1.0").unwrap();
+
+        assert_eq!(migrated.changes, 2);
+        assert!(migrated.conflicts.is_empty());
+        assert_eq!(
+            migrated.source,
+            LEGACY
+                .replace("prompt_template", "prompt")
+                .replace("\"0.9\"", "\"1.0\"")
+        );
+    }
+
+    #[test]
+    fn schema_directives_are_bumped() {
+        let source =
+            "#:schema pipelex://mthds-0.9.schema.json\n\n[pipe.a]\ntype = \"PipeLLM\"\nprompt_template = \"x\"\n";
+        let migrated = migrate(source, "0.9", "1.0").unwrap();
+        assert_eq!(
+            migrated.source,
+            "#:schema pipelex://mthds.schema.json\n\n[pipe.a]\ntype = \"PipeLLM\"\nprompt = \"x\"\n"
+        );
+    }
+
+    #[test]
+    fn existing_keys_are_conflicts() {
+        let source = "[pipe.a]\ntype = \"PipeLLM\"\nprompt = \"new\"\nprompt_template = \"old\"\n";
+        let migrated = migrate(source, "0.9", "1.0").unwrap();
+
+        assert_eq!(migrated.source, source);
+        assert_eq!(migrated.changes, 0);
+        assert_eq!(
+            migrated.conflicts,
+            ["`pipe.a.prompt_template` was not renamed to `prompt`: the key already exists"]
+        );
+    }
+
+    #[test]
+    fn only_tables_of_the_renamed_type_are_changed() {
+        let source = "[pipe.a]\ntype = \"PipeCompose\"\nprompt_template = \"x\"\n\n[pipe.b]\nprompt_template = \"y\"\n";
+        let migrated = migrate(source, "0.9", "1.0").unwrap();
+
+        assert_eq!(migrated.source, source);
+        assert_eq!(migrated.changes, 0);
+        assert!(migrated.conflicts.is_empty());
+    }
+
+    #[test]
+    fn bundles_at_the_target_version_lose_deprecated_names() {
+        let source = "[pipe.a]\ntype = \"PipeLLM\"\nprompt_template = \"x\"\n";
+        let migrated = migrate(source, "1.0", "1.0").unwrap();
+        assert_eq!(migrated.source, source.replace("prompt_template", "prompt"));
+        assert_eq!(migrated.changes, 1);
+
+        // 0.9 does not know `prompt` yet.
+        assert_eq!(migrate(source, "0.9", "0.9").unwrap().source, source);
+    }
+
+    #[test]
+    fn migration_paths() {
+        assert!(migration_path("1.0", "1.0").unwrap().is_empty());
+        assert_eq!(migration_path("0.9", "1.0").unwrap().len(), 1);
+        assert!(migration_path("1.0", "0.9")
+            .unwrap_err()
+            .to_string()
+            .contains("no migration"));
+        assert!(migration_path("0.1", "1.0")
+            .unwrap_err()
+            .to_string()
+            .contains("unknown MTHDS version"));
+    }
+}
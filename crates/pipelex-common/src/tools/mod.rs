@@ -15,8 +15,32 @@
 //!
 //! Everything in here is fully offline: lint validates against the embedded
 //! MTHDS schema only, and format does no config discovery.
+//!
+//! The bindings also expose the [`concept_schema`] export of a document's
+//! concepts as JSON Schema, which `plxt concept-schema` runs over a bundle.
+//!
+//! The [`rules`] registry, its [`bundle`] index and the [`suppression`]
+//! comments are also consumed directly by the `plxt lint` CLI and the language
+//! server, which add the sibling bundle files and the `plxt.toml` rule levels
+//! the bindings don't have.
 
+pub mod bundle;
+pub mod codegen;
+pub mod codes;
+pub mod concept_schema;
+mod concepts;
 pub mod diagnostic;
+pub mod doc;
 pub mod environment;
+pub mod fixes;
+pub mod fixture;
 pub mod format;
+pub mod hover;
+pub mod jinja;
 pub mod lint;
+pub mod migrate;
+pub mod models;
+pub mod native_concepts;
+pub mod render_prompt;
+pub mod rules;
+pub mod suppression;
@@ -0,0 +1,553 @@
+//! The Pipelex model deck: the presets, aliases, waterfalls and handles a
+//! pipe's `model` can name.
+//!
+//! A deck is read from the TOML files listed under `[models] deck` in
+//! `plxt.toml` — files, or directories searched for `*.toml` files. Deck
+//! files declare presets per model kind, aliases and waterfalls:
+//!
+//! ```toml
+//! [aliases]
+//! best-claude = "claude-4.5-sonnet"
+//!
+//! [waterfalls]
+//! smart_llm = ["claude-4.5-opus", "gpt-5.1"]
+//!
+//! [llm.presets]
+//! writing-factual = { model = "best-claude", temperature = 0.1 }
+//! ```
+//!
+//! (`[img_gen.presets]`, `[extract.presets]` and `[search.presets]` work the
+//! same way). Backend files declare model handles: every top-level table with
+//! a `model_type` is a handle of the provider the file is named after.
+//!
+//! ```toml
+//! # anthropic.toml
+//! ["claude-4.5-sonnet"]
+//! model_type = "llm"
+//! model_id = "claude-sonnet-4-5"
+//! ```
+//!
+//! A model reference is a name with a `$` (preset), `@` (alias), `~`
+//! (waterfall) or `#` (handle) prefix; an unprefixed name is looked up as a
+//! preset, then an alias, a waterfall and a handle.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use taplo::{
+    dom::{node::DomNode, Node},
+    parser,
+};
+use taplo_common::environment::Environment;
+
+use super::bundle::string_of;
+
+/// The kind of model a pipe runs, and so the presets and handles its `model`
+/// can name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ModelKind {
+    Llm,
+    ImgGen,
+    Extract,
+    Search,
+}
+
+impl ModelKind {
+    /// The kind of model a pipe of type `pipe_type` runs.
+    #[must_use]
+    pub fn of_pipe_type(pipe_type: &str) -> Option<Self> {
+        match pipe_type {
+            "PipeLLM" => Some(Self::Llm),
+            "PipeImgGen" => Some(Self::ImgGen),
+            "PipeExtract" => Some(Self::Extract),
+            "PipeSearch" => Some(Self::Search),
+            _ => None,
+        }
+    }
+
+    /// How hover and completion name the kind.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Llm => "LLM",
+            Self::ImgGen => "image generation",
+            Self::Extract => "extraction",
+            Self::Search => "search",
+        }
+    }
+
+    /// The deck table holding the presets of this kind.
+    fn section(self) -> &'static str {
+        match self {
+            Self::Llm => "llm",
+            Self::ImgGen => "img_gen",
+            Self::Extract => "extract",
+            Self::Search => "search",
+        }
+    }
+
+    /// The kind a backend's `model_type` declares.
+    fn of_model_type(model_type: &str) -> Option<Self> {
+        match model_type {
+            "llm" => Some(Self::Llm),
+            "img_gen" => Some(Self::ImgGen),
+            "extract" | "text_extractor" => Some(Self::Extract),
+            "search" => Some(Self::Search),
+            _ => None,
+        }
+    }
+
+    const ALL: [Self; 4] = [Self::Llm, Self::ImgGen, Self::Extract, Self::Search];
+}
+
+/// What a model reference names, after its prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelRefKind {
+    Preset,
+    Alias,
+    Waterfall,
+    Handle,
+}
+
+impl ModelRefKind {
+    /// The prefix of a reference of this kind.
+    #[must_use]
+    pub fn sigil(self) -> char {
+        match self {
+            Self::Preset => '$',
+            Self::Alias => '@',
+            Self::Waterfall => '~',
+            Self::Handle => '#',
+        }
+    }
+
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Preset => "preset",
+            Self::Alias => "alias",
+            Self::Waterfall => "waterfall",
+            Self::Handle => "handle",
+        }
+    }
+
+    const ALL: [Self; 4] = [Self::Preset, Self::Alias, Self::Waterfall, Self::Handle];
+}
+
+/// Split a model reference into the kind its prefix names, if any, and the
+/// name after it.
+#[must_use]
+pub fn split_model_ref(value: &str) -> (Option<ModelRefKind>, &str) {
+    let kind = ModelRefKind::ALL
+        .into_iter()
+        .find(|kind| value.starts_with(kind.sigil()));
+    match kind {
+        Some(kind) => (Some(kind), &value[1..]),
+        None => (None, value),
+    }
+}
+
+/// A `[<kind>.presets]` entry: a model with its settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preset {
+    pub kind: ModelKind,
+    /// The model it runs: an alias, a waterfall or a handle.
+    pub model: String,
+    pub description: Option<String>,
+    /// The other settings, with their values as written.
+    pub settings: Vec<(String, String)>,
+}
+
+/// A model handle, declared by a backend file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handle {
+    pub kind: Option<ModelKind>,
+    /// The backend file's name.
+    pub provider: String,
+    /// The other settings, with their values as written.
+    pub settings: Vec<(String, String)>,
+}
+
+/// What a model reference names in the deck.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeckEntry<'d> {
+    Preset(&'d Preset),
+    /// The model the alias stands for.
+    Alias(&'d str),
+    /// The models of the waterfall, in the order they are tried.
+    Waterfall(&'d [String]),
+    Handle(&'d Handle),
+}
+
+/// A model deck. An empty deck is one that was not configured: nothing is
+/// checked against it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelDeck {
+    pub presets: BTreeMap<String, Preset>,
+    pub aliases: BTreeMap<String, String>,
+    pub waterfalls: BTreeMap<String, Vec<String>>,
+    pub handles: BTreeMap<String, Handle>,
+}
+
+impl ModelDeck {
+    /// Read the deck files at `paths`: `*.toml` files, or directories
+    /// searched recursively for them. Files that cannot be read or parsed
+    /// are skipped with a warning.
+    pub async fn load(env: &impl Environment, paths: &[PathBuf]) -> Self {
+        let mut files = Vec::new();
+        for path in paths {
+            let pattern = if path.extension().is_some_and(|ext| ext == "toml") {
+                path.clone()
+            } else {
+                path.join("**").join("*.toml")
+            };
+            match env.glob_files_normalized(&pattern.to_string_lossy()) {
+                Ok(found) => files.extend(found),
+                Err(error) => {
+                    tracing::warn!(%error, path = %path.display(), "invalid model deck path");
+                }
+            }
+        }
+        files.sort();
+        files.dedup();
+
+        let mut deck = Self::default();
+        for file in files {
+            let source = match env.read_file(&file).await.map(String::from_utf8) {
+                Ok(Ok(source)) => source,
+                Ok(Err(error)) => {
+                    tracing::warn!(%error, path = %file.display(), "model deck file is not UTF-8");
+                    continue;
+                }
+                Err(error) => {
+                    tracing::warn!(%error, path = %file.display(), "failed to read model deck file");
+                    continue;
+                }
+            };
+            if let Err(error) = deck.add_file(&file, &source) {
+                tracing::warn!(%error, path = %file.display(), "skipping model deck file");
+            }
+        }
+        deck
+    }
+
+    /// Add the declarations of the deck or backend file `path`, whose text
+    /// is `source`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first syntax error of `source`; nothing is added then.
+    pub fn add_file(&mut self, path: &Path, source: &str) -> Result<(), String> {
+        let parse = parser::parse(source);
+        if let Some(error) = parse.errors.first() {
+            return Err(error.to_string());
+        }
+        let dom = parse.into_dom();
+        let Some(table) = dom.as_table() else {
+            return Ok(());
+        };
+        let provider = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        for (key, node) in table.entries().read().iter() {
+            match key.value() {
+                "aliases" => {
+                    for (name, target) in entries(node) {
+                        if let Some(target) = string_of(&target) {
+                            self.aliases.insert(name, target);
+                        }
+                    }
+                }
+                "waterfalls" => {
+                    for (name, models) in entries(node) {
+                        if let Some(models) = models.as_array() {
+                            let models =
+                                models.items().read().iter().filter_map(string_of).collect();
+                            self.waterfalls.insert(name, models);
+                        }
+                    }
+                }
+                section => {
+                    if let Some(kind) = ModelKind::ALL.into_iter().find(|k| k.section() == section)
+                    {
+                        for (name, preset) in entries(&node.get("presets")) {
+                            if let Some(preset) = read_preset(kind, &preset) {
+                                self.presets.insert(name, preset);
+                            }
+                        }
+                    } else if let Some(model_type) = string_of(&node.get("model_type")) {
+                        self.handles.insert(
+                            section.to_string(),
+                            Handle {
+                                kind: ModelKind::of_model_type(&model_type),
+                                provider: provider.clone(),
+                                settings: settings(node, &["model_type"]),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+            && self.aliases.is_empty()
+            && self.waterfalls.is_empty()
+            && self.handles.is_empty()
+    }
+
+    /// What the model reference `value` names, and its kind: the one its
+    /// prefix says, or the first kind declaring the name for an unprefixed
+    /// one.
+    #[must_use]
+    pub fn lookup(&self, value: &str) -> Option<(ModelRefKind, DeckEntry<'_>)> {
+        let (kind, name) = split_model_ref(value);
+        let kinds = match kind {
+            Some(kind) => vec![kind],
+            None => ModelRefKind::ALL.to_vec(),
+        };
+        kinds.into_iter().find_map(|kind| {
+            let entry = match kind {
+                ModelRefKind::Preset => self.presets.get(name).map(DeckEntry::Preset),
+                ModelRefKind::Alias => self
+                    .aliases
+                    .get(name)
+                    .map(|target| DeckEntry::Alias(target)),
+                ModelRefKind::Waterfall => self
+                    .waterfalls
+                    .get(name)
+                    .map(|models| DeckEntry::Waterfall(models)),
+                ModelRefKind::Handle => self.handles.get(name).map(DeckEntry::Handle),
+            };
+            entry.map(|entry| (kind, entry))
+        })
+    }
+
+    /// The handle `value` ends up running, following presets and aliases and
+    /// taking the first model of a waterfall, with the names met on the way
+    /// (`value` first, the handle last).
+    #[must_use]
+    pub fn resolve(&self, value: &str) -> (Vec<String>, Option<&Handle>) {
+        let mut chain = vec![value.to_string()];
+        let mut current = value.to_string();
+        // Bounded, so a cycle of aliases ends.
+        for _ in 0..16 {
+            let next = match self.lookup(&current) {
+                Some((_, DeckEntry::Handle(handle))) => return (chain, Some(handle)),
+                Some((_, DeckEntry::Preset(preset))) => preset.model.clone(),
+                Some((_, DeckEntry::Alias(target))) => target.to_string(),
+                Some((_, DeckEntry::Waterfall(models))) => match models.first() {
+                    Some(first) => first.clone(),
+                    None => break,
+                },
+                None => break,
+            };
+            chain.push(next.clone());
+            current = next;
+        }
+        (chain, None)
+    }
+
+    /// The kind of model `value` runs, when the deck tells: a preset's own,
+    /// or the kind of the handle it resolves to.
+    #[must_use]
+    pub fn kind_of(&self, value: &str) -> Option<ModelKind> {
+        if let Some((_, DeckEntry::Preset(preset))) = self.lookup(value) {
+            return Some(preset.kind);
+        }
+        self.resolve(value).1.and_then(|handle| handle.kind)
+    }
+
+    /// Every reference the deck declares, prefix included, with its kind:
+    /// presets, aliases, waterfalls, then handles, each sorted by name.
+    #[must_use]
+    pub fn references(&self) -> Vec<(String, ModelRefKind)> {
+        let names = [
+            (
+                ModelRefKind::Preset,
+                self.presets.keys().collect::<Vec<_>>(),
+            ),
+            (ModelRefKind::Alias, self.aliases.keys().collect()),
+            (ModelRefKind::Waterfall, self.waterfalls.keys().collect()),
+            (ModelRefKind::Handle, self.handles.keys().collect()),
+        ];
+        names
+            .into_iter()
+            .flat_map(|(kind, names)| {
+                names
+                    .into_iter()
+                    .map(move |name| (format!("{}{name}", kind.sigil()), kind))
+            })
+            .collect()
+    }
+}
+
+/// The entries of the table `node`, keys as strings.
+fn entries(node: &Node) -> Vec<(String, Node)> {
+    node.as_table()
+        .map(|table| {
+            table
+                .entries()
+                .read()
+                .iter()
+                .map(|(key, value)| (key.value().to_string(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_preset(kind: ModelKind, node: &Node) -> Option<Preset> {
+    // `name = "model"` is a preset with no settings.
+    if let Some(model) = string_of(node) {
+        return Some(Preset {
+            kind,
+            model,
+            description: None,
+            settings: Vec::new(),
+        });
+    }
+    Some(Preset {
+        kind,
+        model: string_of(&node.get("model"))?,
+        description: string_of(&node.get("description")),
+        settings: settings(node, &["model", "description"]),
+    })
+}
+
+/// The entries of the table `node` but `skip`, values as written.
+fn settings(node: &Node, skip: &[&str]) -> Vec<(String, String)> {
+    entries(node)
+        .into_iter()
+        .filter(|(key, _)| !skip.contains(&key.as_str()))
+        .filter_map(|(key, value)| Some((key, value.syntax()?.to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECK: &str = r#"
+[aliases]
+best-claude = "claude-4.5-sonnet"
+loop-a = "loop-b"
+loop-b = "loop-a"
+
+[waterfalls]
+smart_llm = ["best-claude", "gpt-4o"]
+
+[llm.presets]
+writing-factual = { model = "@best-claude", temperature = 0.1, description = "Factual writing" }
+cheap = "gpt-4o"
+
+[img_gen.presets]
+gen-image = { model = "flux-pro", quality = "high" }
+"#;
+
+    const BACKEND: &str = r#"
+default-sdk = "anthropic"
+
+["claude-4.5-sonnet"]
+model_type = "llm"
+model_id = "claude-sonnet-4-5"
+
+[flux-pro]
+model_type = "img_gen"
+"#;
+
+    fn deck() -> ModelDeck {
+        let mut deck = ModelDeck::default();
+        deck.add_file(Path::new("deck/base_deck.toml"), DECK)
+            .unwrap();
+        deck.add_file(Path::new("backends/anthropic.toml"), BACKEND)
+            .unwrap();
+        deck
+    }
+
+    #[test]
+    fn reads_presets_aliases_waterfalls_and_handles() {
+        let deck = deck();
+        let preset = &deck.presets["writing-factual"];
+        assert_eq!(preset.kind, ModelKind::Llm);
+        assert_eq!(preset.model, "@best-claude");
+        assert_eq!(preset.description.as_deref(), Some("Factual writing"));
+        assert_eq!(preset.settings, [("temperature".into(), "0.1".into())]);
+        assert_eq!(deck.presets["cheap"].model, "gpt-4o");
+        assert_eq!(deck.waterfalls["smart_llm"], ["best-claude", "gpt-4o"]);
+        let handle = &deck.handles["claude-4.5-sonnet"];
+        assert_eq!(handle.provider, "anthropic");
+        assert_eq!(handle.kind, Some(ModelKind::Llm));
+        assert_eq!(
+            handle.settings,
+            [("model_id".into(), "\"claude-sonnet-4-5\"".into())]
+        );
+        assert!(!deck.handles.contains_key("default-sdk"));
+    }
+
+    #[test]
+    fn looks_references_up_by_prefix() {
+        let deck = deck();
+        assert!(matches!(
+            deck.lookup("$writing-factual"),
+            Some((ModelRefKind::Preset, DeckEntry::Preset(_)))
+        ));
+        assert!(deck.lookup("@writing-factual").is_none());
+        assert!(matches!(
+            deck.lookup("~smart_llm"),
+            Some((ModelRefKind::Waterfall, DeckEntry::Waterfall(_)))
+        ));
+        assert!(matches!(
+            deck.lookup("claude-4.5-sonnet"),
+            Some((ModelRefKind::Handle, DeckEntry::Handle(_)))
+        ));
+        assert!(deck.lookup("$unknown").is_none());
+    }
+
+    #[test]
+    fn resolves_to_the_handle_run() {
+        let deck = deck();
+        let (chain, handle) = deck.resolve("$writing-factual");
+        assert_eq!(
+            chain,
+            ["$writing-factual", "@best-claude", "claude-4.5-sonnet"]
+        );
+        assert_eq!(handle.unwrap().provider, "anthropic");
+        assert_eq!(deck.kind_of("~smart_llm"), Some(ModelKind::Llm));
+        assert_eq!(deck.kind_of("$gen-image"), Some(ModelKind::ImgGen));
+        assert_eq!(deck.resolve("@loop-a").1, None);
+    }
+
+    #[test]
+    fn lists_references_with_their_prefix() {
+        let references = deck().references();
+        let labels: Vec<_> = references.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(
+            labels,
+            [
+                "$cheap",
+                "$gen-image",
+                "$writing-factual",
+                "@best-claude",
+                "@loop-a",
+                "@loop-b",
+                "~smart_llm",
+                "#claude-4.5-sonnet",
+                "#flux-pro",
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_files_with_syntax_errors() {
+        let mut deck = ModelDeck::default();
+        assert!(deck.add_file(Path::new("bad.toml"), "[aliases\n").is_err());
+        assert!(deck.is_empty());
+    }
+}
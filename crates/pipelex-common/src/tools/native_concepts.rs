@@ -0,0 +1,240 @@
+//! The native concepts every MTHDS bundle can reference without defining them.
+//!
+//! Shared by the lint rules (a native concept is never "undefined") and the
+//! LSP's hover, completion and highlighting of concept references.
+//!
+//! The registry is data: [`NativeConcepts::embedded`] reads the
+//! `mthds_native_concepts.toml` shipped next to the embedded MTHDS schema
+//! (and versioned with it), and `[mthds] native_concepts` in `plxt.toml`
+//! points the CLI and the language server at a replacement file.
+
+use std::{path::Path, sync::OnceLock};
+
+use taplo::parser;
+use taplo_common::{
+    environment::Environment,
+    schema::builtins::{MTHDS_NATIVE_CONCEPTS_TOML, MTHDS_VERSION},
+};
+
+use super::bundle::string_of;
+
+/// A built-in concept with its content fields, as shown in hover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeConcept {
+    pub name: String,
+    pub description: String,
+    /// Its own fields, name and type, without the inherited ones.
+    pub fields: Vec<(String, String)>,
+    /// The native concept it extends.
+    pub refines: Option<String>,
+}
+
+/// A native concept registry, in the order hover and completion list them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeConcepts {
+    /// The MTHDS version the registry describes.
+    pub version: String,
+    pub concepts: Vec<NativeConcept>,
+}
+
+impl Default for NativeConcepts {
+    fn default() -> Self {
+        Self::embedded().clone()
+    }
+}
+
+impl NativeConcepts {
+    /// The registry embedded next to the MTHDS schema.
+    #[must_use]
+    pub fn embedded() -> &'static Self {
+        static EMBEDDED: OnceLock<NativeConcepts> = OnceLock::new();
+        EMBEDDED.get_or_init(|| {
+            Self::from_toml(MTHDS_NATIVE_CONCEPTS_TOML)
+                .expect("embedded native concept registry is invalid")
+        })
+    }
+
+    /// Read the registry file at `path`, or the embedded one without a path.
+    /// A file that cannot be read or is invalid is reported with a warning
+    /// and the embedded registry used instead.
+    pub async fn load(env: &impl Environment, path: Option<&Path>) -> Self {
+        let Some(path) = path else {
+            return Self::default();
+        };
+        let source = match env.read_file(path).await.map(String::from_utf8) {
+            Ok(Ok(source)) => source,
+            Ok(Err(error)) => {
+                tracing::warn!(%error, path = %path.display(), "native concept registry is not UTF-8");
+                return Self::default();
+            }
+            Err(error) => {
+                tracing::warn!(%error, path = %path.display(), "failed to read native concept registry");
+                return Self::default();
+            }
+        };
+        match Self::from_toml(&source) {
+            Ok(registry) => {
+                if registry.version != MTHDS_VERSION {
+                    tracing::warn!(
+                        path = %path.display(),
+                        version = registry.version,
+                        schema = MTHDS_VERSION,
+                        "native concept registry targets another MTHDS version than the schema"
+                    );
+                }
+                registry
+            }
+            Err(error) => {
+                tracing::warn!(%error, path = %path.display(), "invalid native concept registry");
+                Self::default()
+            }
+        }
+    }
+
+    /// Parse a registry file: an `mthds_version` and a `[[concept]]` array.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first syntax error, missing key or
+    /// dangling `refines`.
+    pub fn from_toml(source: &str) -> Result<Self, String> {
+        let parse = parser::parse(source);
+        if let Some(error) = parse.errors.first() {
+            return Err(error.to_string());
+        }
+        let dom = parse.into_dom();
+        let version = string_of(&dom.get("mthds_version"))
+            .ok_or_else(|| "missing `mthds_version`".to_string())?;
+
+        let mut concepts = Vec::new();
+        if let Some(array) = dom.get("concept").as_array() {
+            for (index, concept) in array.items().read().iter().enumerate() {
+                let name = string_of(&concept.get("name"))
+                    .ok_or_else(|| format!("concept #{} has no `name`", index + 1))?;
+                let fields = concept
+                    .get("fields")
+                    .as_table()
+                    .map(|fields| {
+                        fields
+                            .entries()
+                            .read()
+                            .iter()
+                            .filter_map(|(key, ty)| Some((key.value().to_string(), string_of(ty)?)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                concepts.push(NativeConcept {
+                    description: string_of(&concept.get("description")).unwrap_or_default(),
+                    refines: string_of(&concept.get("refines")),
+                    name,
+                    fields,
+                });
+            }
+        }
+
+        let registry = Self { version, concepts };
+        for concept in &registry.concepts {
+            if let Some(parent) = &concept.refines {
+                if registry.find(parent).is_none() {
+                    return Err(format!(
+                        "`{}` refines `{parent}`, which is not a native concept",
+                        concept.name
+                    ));
+                }
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Look up a native concept by its bare name (e.g. `Text`).
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<&NativeConcept> {
+        self.concepts.iter().find(|c| c.name == name)
+    }
+
+    /// The fields of the native concept `name`: those of the concepts it
+    /// refines first, then its own.
+    #[must_use]
+    pub fn fields(&self, name: &str) -> Vec<(&str, &str)> {
+        let mut lineage = Vec::new();
+        let mut current = self.find(name);
+        while let Some(concept) = current {
+            // A cycle of `refines` stops at the first concept seen again.
+            if lineage
+                .iter()
+                .any(|seen: &&NativeConcept| seen.name == concept.name)
+            {
+                break;
+            }
+            lineage.push(concept);
+            current = concept
+                .refines
+                .as_deref()
+                .and_then(|parent| self.find(parent));
+        }
+        lineage
+            .iter()
+            .rev()
+            .flat_map(|concept| &concept.fields)
+            .map(|(name, ty)| (name.as_str(), ty.as_str()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_registry_matches_the_schema_version() {
+        let registry = NativeConcepts::embedded();
+        assert_eq!(registry.version, MTHDS_VERSION);
+        let text = registry.find("Text").expect("Text is native");
+        assert_eq!(text.description, "Plain text content.");
+        assert_eq!(text.fields, [("text".to_string(), "str".to_string())]);
+        assert!(registry.find("ImgGenPrompt").is_none());
+        let names: Vec<_> = registry.concepts.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names.first(), Some(&"Text"));
+        assert!(names.contains(&"Anything"));
+    }
+
+    #[test]
+    fn fields_include_the_refined_concepts() {
+        let registry = NativeConcepts::from_toml(
+            r#"
+mthds_version = "1.0"
+
+[[concept]]
+name = "Text"
+fields = { text = "str" }
+
+[[concept]]
+name = "Markdown"
+description = "Text in Markdown."
+refines = "Text"
+fields = { flavor = "str?" }
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            registry.fields("Markdown"),
+            [("text", "str"), ("flavor", "str?")]
+        );
+        assert_eq!(registry.fields("Missing"), []);
+    }
+
+    #[test]
+    fn rejects_invalid_registries() {
+        assert_eq!(
+            NativeConcepts::from_toml("[[concept]]\nname = \"Text\"\n").unwrap_err(),
+            "missing `mthds_version`"
+        );
+        assert_eq!(
+            NativeConcepts::from_toml(
+                "mthds_version = \"1.0\"\n[[concept]]\nname = \"A\"\nrefines = \"B\"\n"
+            )
+            .unwrap_err(),
+            "`A` refines `B`, which is not a native concept"
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Previews of the prompts a pipe renders.
+//!
+//! [`render_prompts`] renders every template of a pipe (its `prompt`,
+//! `system_prompt`, `template`, …) with sample inputs, the way Pipelex does
+//! before calling a model: each input is a variable holding the `content` of
+//! its working memory entry, `@name` and `$name` write it through the pipe's
+//! `templating_style`. Without inputs, the placeholder [`fixture`] of the
+//! pipe is used.
+//!
+//! Undefined variables and render errors are reported with their range in
+//! the document, and a template with syntax errors is reported but not
+//! rendered.
+
+use anyhow::bail;
+use serde_json::{Map, Value};
+use taplo::dom::Node;
+
+use super::{
+    fixture::fixture,
+    jinja::{
+        self,
+        render::{render, TagStyle, TemplatingStyle, TextFormat},
+        TemplateError, TemplateSource,
+    },
+    native_concepts::NativeConcepts,
+};
+
+/// How to render the prompts of a pipe.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions<'a> {
+    /// The working memory to render with, as in `inputs.json`; the
+    /// placeholder fixture of the pipe when `None`.
+    pub inputs: Option<&'a Value>,
+    /// Overrides the tag style of the pipe.
+    pub tag_style: Option<TagStyle>,
+    /// Overrides the text format of the pipe.
+    pub text_format: Option<TextFormat>,
+}
+
+/// A rendered template of a pipe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedPrompt {
+    /// The pipe field holding the template, e.g. `prompt`.
+    pub field: &'static str,
+    /// The rendered text, empty when the template has syntax errors.
+    pub text: String,
+    /// Syntax errors, undefined variables and render errors, with ranges in
+    /// the document.
+    pub errors: Vec<TemplateError>,
+}
+
+/// Render the templates of `pipe`, a pipe of `document`. `siblings` are the
+/// other documents of the bundle, searched for the concepts of the inputs
+/// when no `inputs` are given.
+///
+/// # Errors
+///
+/// If `document` defines no pipe `pipe`, if the pipe has no template, or if
+/// the inputs are not an object keyed by input name.
+pub fn render_prompts(
+    document: &Node,
+    siblings: &[Node],
+    pipe: &str,
+    natives: &NativeConcepts,
+    options: RenderOptions<'_>,
+) -> anyhow::Result<Vec<RenderedPrompt>> {
+    let node = document.get("pipe").get(pipe);
+    if node.as_table().is_none() {
+        bail!("no pipe `{pipe}` in the bundle");
+    }
+    let templates = jinja::pipe_templates(&node);
+    if templates.is_empty() {
+        bail!("pipe `{pipe}` has no template to render");
+    }
+
+    let memory = match options.inputs {
+        Some(inputs) => inputs.clone(),
+        None => fixture(document, siblings, pipe, natives)?,
+    };
+    let Some(memory) = memory.as_object() else {
+        bail!("the inputs must be an object keyed by input name");
+    };
+    let context: Map<String, Value> = memory
+        .iter()
+        .map(|(name, entry)| {
+            let content = entry.get("content").unwrap_or(entry);
+            (name.clone(), content.clone())
+        })
+        .collect();
+    let style = templating_style(&node, options);
+
+    Ok(templates
+        .into_iter()
+        .filter_map(|(field, template)| {
+            let source = TemplateSource::from_node(&template)?;
+            let parsed = jinja::parse(&source.text);
+            let (text, errors) = if parsed.errors.is_empty() {
+                let rendered = render(&source.text, &parsed.template, &context, style);
+                (rendered.text, rendered.errors)
+            } else {
+                (String::new(), parsed.errors)
+            };
+            Some(RenderedPrompt {
+                field,
+                text,
+                errors: errors
+                    .into_iter()
+                    .map(|error| TemplateError {
+                        message: error.message,
+                        range: source.document_range(error.range),
+                    })
+                    .collect(),
+            })
+        })
+        .collect())
+}
+
+/// The `templating_style` of the `template` table of `pipe`, under the
+/// overrides of `options`.
+fn templating_style(pipe: &Node, options: RenderOptions<'_>) -> TemplatingStyle {
+    let declared = pipe.get("template").get("templating_style");
+    let tag_style = declared
+        .get("tag_style")
+        .as_str()
+        .and_then(|style| TagStyle::from_name(style.value()));
+    let text_format = declared
+        .get("text_format")
+        .as_str()
+        .and_then(|format| TextFormat::from_name(format.value()));
+    TemplatingStyle {
+        tag_style: options.tag_style.or(tag_style).unwrap_or_default(),
+        text_format: options.text_format.or(text_format).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use taplo::parser;
+
+    use super::*;
+
+    fn dom(source: &str) -> Node {
+        let parse = parser::parse(source);
+        assert!(parse.errors.is_empty(), "{:?}", parse.errors);
+        parse.into_dom()
+    }
+
+    const BUNDLE: &str = r#"
+domain = "reviews"
+
+[concept.Review]
+description = "A product review"
+
+[concept.Review.structure]
+text = { type = "text", description = "The review", required = true }
+stars = { type = "integer", description = "The rating", required = true }
+
+[pipe.summarize]
+type = "PipeLLM"
+inputs = { review = "Review" }
+output = "Text"
+system_prompt = "You summarize reviews."
+prompt = """
+Summarize @review.text ($review.stars stars).
+{% if review.stars > 3 %}Be upbeat.{% endif %}"""
+
+[pipe.compose]
+type = "PipeCompose"
+inputs = { review = "Review" }
+output = "Text"
+
+[pipe.compose.template]
+template = "{{ review.author }}: $review"
+templating_style = { tag_style = "xml", text_format = "markdown" }
+"#;
+
+    #[test]
+    fn renders_with_the_placeholder_fixture() {
+        let natives = NativeConcepts::default();
+        let prompts = render_prompts(
+            &dom(BUNDLE),
+            &[],
+            "summarize",
+            &natives,
+            RenderOptions::default(),
+        )
+        .unwrap();
+        let rendered: Vec<(&str, &str)> = prompts
+            .iter()
+            .map(|prompt| (prompt.field, prompt.text.as_str()))
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                (
+                    "prompt",
+                    "Summarize review.text: ```\nexample text\n``` (1 stars).\n"
+                ),
+                ("system_prompt", "You summarize reviews."),
+            ]
+        );
+        assert!(prompts.iter().all(|prompt| prompt.errors.is_empty()));
+    }
+
+    #[test]
+    fn uses_the_inputs_and_the_templating_style() {
+        let source = BUNDLE;
+        let inputs = json!({
+            "review": { "concept": "reviews.Review", "content": { "text": "Great", "stars": 5 } }
+        });
+        let natives = NativeConcepts::default();
+        let prompts = render_prompts(
+            &dom(source),
+            &[],
+            "compose",
+            &natives,
+            RenderOptions {
+                inputs: Some(&inputs),
+                ..RenderOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].field, "template");
+        assert_eq!(prompts[0].text, ": **text**: Great\n**stars**: 5");
+        let errors: Vec<(&str, &str)> = prompts[0]
+            .errors
+            .iter()
+            .map(|error| (error.message.as_str(), &source[error.range]))
+            .collect();
+        assert_eq!(errors, [("no field `author`", "author")]);
+
+        let prompts = render_prompts(
+            &dom(source),
+            &[],
+            "summarize",
+            &natives,
+            RenderOptions {
+                inputs: Some(&inputs),
+                tag_style: Some(TagStyle::SquareBrackets),
+                text_format: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            prompts[0].text,
+            "Summarize [review.text]\nGreat\n[/review.text] (5 stars).\nBe upbeat."
+        );
+    }
+
+    #[test]
+    fn reports_missing_pipes_and_syntax_errors() {
+        let natives = NativeConcepts::default();
+        let error = render_prompts(
+            &dom(BUNDLE),
+            &[],
+            "missing",
+            &natives,
+            RenderOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "no pipe `missing` in the bundle");
+
+        let source = "[pipe.broken]\ntype = \"PipeLLM\"\nprompt = \"{% if x %}\"\n";
+        let prompts = render_prompts(
+            &dom(source),
+            &[],
+            "broken",
+            &natives,
+            RenderOptions {
+                inputs: Some(&json!({})),
+                ..RenderOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(prompts[0].text, "");
+        assert_eq!(prompts[0].errors.len(), 1);
+    }
+}
@@ -0,0 +1,715 @@
+//! The MTHDS lint rule registry and its checks.
+//!
+//! Every check has a stable id (`mthds/<name>`) and a default severity. Users
+//! retune them per file through the `[lint.rules]` table of `plxt.toml` (or a
+//! path-scoped `[[rule]]`'s `[rule.lint.rules]`), resolved by
+//! [`Config::lint_options`](taplo_common::config::Config::lint_options):
+//!
+//! ```toml
+//! [lint.rules]
+//! "mthds/unused-concept" = "off"
+//! "mthds/undefined-pipe" = "error"
+//! ```
+//!
+//! Rules run after the syntax, semantic and schema stages are clean, on a
+//! [`BundleIndex`] of the document plus the indexes of its sibling bundle files
+//! (empty for the single-document bindings). Their violations then go through
+//! the document's [`Suppressions`] comments.
+
+use taplo::{dom::Node, rowan::TextRange};
+use taplo_common::{
+    config::{LintOptions, RuleLevel},
+    schema::builtins::{MthdsSchemaVersion, MTHDS_VERSION},
+};
+
+use crate::tools::bundle::{BundleIndex, ModelReference, RefKind, Reference};
+use crate::tools::diagnostic::{Diagnostic, Range, Severity};
+use crate::tools::fixes::{closest_name, Fix};
+use crate::tools::models::{split_model_ref, ModelDeck};
+use crate::tools::native_concepts::NativeConcepts;
+use crate::tools::suppression::Suppressions;
+
+/// A registered lint rule.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RuleInfo {
+    /// Stable id, used in `plxt.toml` and printed with each diagnostic.
+    pub id: &'static str,
+    /// The severity used when `plxt.toml` does not configure the rule.
+    pub default_severity: Severity,
+    /// One-line description of what the rule reports.
+    pub summary: &'static str,
+    /// Long-form description, as printed by `plxt explain`.
+    pub explanation: &'static str,
+}
+
+impl RuleInfo {
+    /// The effective severity under `options`, or `None` if the rule is off.
+    #[must_use]
+    pub fn severity(&self, options: &LintOptions) -> Option<Severity> {
+        match options.rule_level(self.id) {
+            None => Some(self.default_severity),
+            Some(RuleLevel::Off) => None,
+            Some(RuleLevel::Error) => Some(Severity::Error),
+            Some(RuleLevel::Warning) => Some(Severity::Warning),
+            Some(RuleLevel::Info) => Some(Severity::Info),
+        }
+    }
+}
+
+pub static UNDEFINED_PIPE: RuleInfo = RuleInfo {
+    id: "mthds/undefined-pipe",
+    default_severity: Severity::Warning,
+    summary: "A pipe reference does not match any pipe defined in the bundle.",
+    explanation: "A `main_pipe`, step, branch or outcome names a pipe that no `.mthds` file \
+of the bundle (the files in the same directory) defines. References qualified with another \
+domain, like `finance.audit`, point outside the bundle and are not checked.",
+};
+
+pub static UNDEFINED_CONCEPT: RuleInfo = RuleInfo {
+    id: "mthds/undefined-concept",
+    default_severity: Severity::Warning,
+    summary: "A concept reference matches neither a bundle concept nor a native concept.",
+    explanation: "An `inputs` value, an `output`, a `refines` or a structure field's \
+`concept_ref` names a concept that no file of the bundle defines and that is not a native \
+concept such as `Text` or `Image`. Domain qualifiers and multiplicity suffixes (`legal.Contract[]`) \
+are ignored when matching; references into other domains are not checked.",
+};
+
+pub static UNUSED_CONCEPT: RuleInfo = RuleInfo {
+    id: "mthds/unused-concept",
+    default_severity: Severity::Info,
+    summary: "A concept is defined but never referenced anywhere in the bundle.",
+    explanation: "No pipe input or output, `refines` or structure field anywhere in the \
+bundle uses this concept. Remove it, or keep it with a `# plxt-ignore: mthds/unused-concept` \
+comment when it exists for external consumers.",
+};
+
+pub static TEMPLATE_SYNTAX: RuleInfo = RuleInfo {
+    id: "mthds/template-syntax",
+    default_severity: Severity::Warning,
+    summary: "A prompt or template is not valid Jinja.",
+    explanation: "The `prompt`, `system_prompt`, `template` or `expression_template` of a pipe \
+does not parse as a Jinja template: a `{{`, `{%` or `{#` is never closed, a block such as \
+`{% if %}` or `{% for %}` has no matching end tag, an end tag closes nothing, a tag is unknown, \
+or an expression or a filter is malformed (`{{ text | }}`). The template would fail to render \
+at run time. The `@name` and `$name` shorthands are accepted anywhere in the text.",
+};
+
+pub static UNKNOWN_MODEL: RuleInfo = RuleInfo {
+    id: "mthds/unknown-model",
+    default_severity: Severity::Warning,
+    summary: "A model reference names nothing in the model deck.",
+    explanation: "The `model` or `model_to_structure` of a pipe names a preset (`$name`), alias \
+(`@name`), waterfall (`~name`) or handle (`#name`) that the model deck does not declare; an \
+unprefixed name matches none of them. The deck is read from the files listed under \
+`[models] deck` in `plxt.toml`; without one, model references are not checked.",
+};
+
+pub static DEPRECATED_VERSION: RuleInfo = RuleInfo {
+    id: "mthds/deprecated-version",
+    default_severity: Severity::Warning,
+    summary: "The bundle declares a deprecated MTHDS version.",
+    explanation: "The bundle's `mthds_version`, or the embedded schema its `#:schema` directive \
+names, is an older MTHDS version that the tools still validate against but that is on its way \
+out. Move the bundle to the latest version and update its declaration. Bundles that declare no \
+version follow `[mthds] version` in `plxt.toml`, which `plxt lint` warns about instead.",
+};
+
+/// Reported by [`Suppressions::apply`] rather than by [`check_bundle`].
+pub static UNUSED_SUPPRESSION: RuleInfo = RuleInfo {
+    id: "mthds/unused-suppression",
+    default_severity: Severity::Warning,
+    summary: "A plxt-ignore comment suppresses no diagnostic.",
+    explanation: "A `# plxt-ignore` or `# plxt-ignore-file` comment names a rule that reports \
+nothing where the comment applies, names an unknown rule, or is a `plxt-ignore-file` placed \
+below the comment block at the top of the file. Remove it, or fix the rule id.",
+};
+
+/// Every registered rule, in the order their checks run.
+pub static RULES: &[&RuleInfo] = &[
+    &UNDEFINED_PIPE,
+    &UNDEFINED_CONCEPT,
+    &UNUSED_CONCEPT,
+    &TEMPLATE_SYNTAX,
+    &UNKNOWN_MODEL,
+    &DEPRECATED_VERSION,
+    &UNUSED_SUPPRESSION,
+];
+
+/// Look up a rule by id.
+#[must_use]
+pub fn find_rule(id: &str) -> Option<&'static RuleInfo> {
+    RULES.iter().copied().find(|rule| rule.id == id)
+}
+
+/// Rule ids configured in `options` that are not in the registry — most
+/// likely typos, which would otherwise be ignored silently.
+#[must_use]
+pub fn unknown_rule_ids(options: &LintOptions) -> Vec<&str> {
+    options
+        .rules
+        .iter()
+        .flat_map(|rules| rules.keys())
+        .map(String::as_str)
+        .filter(|id| find_rule(id).is_none())
+        .collect()
+}
+
+/// One rule violation, at its configured severity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleViolation {
+    pub rule: &'static RuleInfo,
+    pub severity: Severity,
+    pub message: String,
+    pub range: TextRange,
+    pub fixes: Vec<Fix>,
+}
+
+/// Run every enabled rule over `document`, resolving references against the
+/// document itself, its `siblings` in the same bundle and the `natives`
+/// concepts, and model references against `models` unless it is empty.
+///
+/// Violations are returned in document order. An undefined reference with a
+/// single close match in the bundle (or the deck) carries a fix renaming it.
+#[must_use]
+pub fn check_bundle(
+    document: &BundleIndex,
+    siblings: &[BundleIndex],
+    natives: &NativeConcepts,
+    models: &ModelDeck,
+    options: &LintOptions,
+) -> Vec<RuleViolation> {
+    let bundle = || std::iter::once(document).chain(siblings);
+    let defined = |kind| {
+        bundle()
+            .flat_map(|index| &index.definitions)
+            .filter(move |definition| definition.kind == kind)
+            .map(|definition| definition.name.as_str())
+    };
+    let mut violations = Vec::new();
+
+    if let Some(severity) = UNDEFINED_PIPE.severity(options) {
+        for reference in local_references(document, RefKind::Pipe) {
+            if !bundle().any(|index| index.defines(RefKind::Pipe, &reference.name)) {
+                violations.push(RuleViolation {
+                    rule: &UNDEFINED_PIPE,
+                    severity,
+                    message: format!("pipe \"{}\" is not defined in this bundle", reference.raw),
+                    range: reference.range,
+                    fixes: typo_fix(reference, defined(RefKind::Pipe)),
+                });
+            }
+        }
+    }
+
+    if let Some(severity) = UNDEFINED_CONCEPT.severity(options) {
+        for reference in local_references(document, RefKind::Concept) {
+            if natives.find(&reference.name).is_some() {
+                continue;
+            }
+            if !bundle().any(|index| index.defines(RefKind::Concept, &reference.name)) {
+                violations.push(RuleViolation {
+                    rule: &UNDEFINED_CONCEPT,
+                    severity,
+                    message: format!(
+                        "concept \"{}\" is not defined in this bundle",
+                        reference.raw
+                    ),
+                    range: reference.range,
+                    fixes: typo_fix(
+                        reference,
+                        defined(RefKind::Concept)
+                            .chain(natives.concepts.iter().map(|concept| concept.name.as_str())),
+                    ),
+                });
+            }
+        }
+    }
+
+    if let Some(severity) = UNUSED_CONCEPT.severity(options) {
+        for definition in &document.definitions {
+            if definition.kind != RefKind::Concept {
+                continue;
+            }
+            if !bundle().any(|index| index.references(RefKind::Concept, &definition.name)) {
+                violations.push(RuleViolation {
+                    rule: &UNUSED_CONCEPT,
+                    severity,
+                    message: format!(
+                        "concept \"{}\" is never referenced in this bundle",
+                        definition.name
+                    ),
+                    range: definition.range,
+                    fixes: Vec::new(),
+                });
+            }
+        }
+    }
+
+    if let Some(severity) = TEMPLATE_SYNTAX.severity(options) {
+        for error in &document.template_errors {
+            violations.push(RuleViolation {
+                rule: &TEMPLATE_SYNTAX,
+                severity,
+                message: format!("invalid template: {}", error.message),
+                range: error.range,
+                fixes: Vec::new(),
+            });
+        }
+    }
+
+    if let Some(severity) = UNKNOWN_MODEL
+        .severity(options)
+        .filter(|_| !models.is_empty())
+    {
+        for reference in &document.model_references {
+            if models.lookup(&reference.value).is_some() {
+                continue;
+            }
+            let message = match split_model_ref(&reference.value) {
+                (Some(kind), name) => {
+                    format!("model {} \"{name}\" is not in the model deck", kind.label())
+                }
+                (None, name) => format!("model \"{name}\" is not in the model deck"),
+            };
+            violations.push(RuleViolation {
+                rule: &UNKNOWN_MODEL,
+                severity,
+                message,
+                range: reference.range,
+                fixes: model_typo_fix(reference, models),
+            });
+        }
+    }
+
+    if let Some(severity) = DEPRECATED_VERSION.severity(options) {
+        if let Some(declared) = &document.mthds_version {
+            if MthdsSchemaVersion::find(&declared.version).is_some_and(|v| v.deprecated) {
+                violations.push(RuleViolation {
+                    rule: &DEPRECATED_VERSION,
+                    severity,
+                    message: format!(
+                        "MTHDS {} is deprecated: move the bundle to MTHDS {MTHDS_VERSION}",
+                        declared.version
+                    ),
+                    range: declared.range,
+                    fixes: Vec::new(),
+                });
+            }
+        }
+    }
+
+    violations.sort_by_key(|violation| violation.range.start());
+    violations
+}
+
+/// Lint the rules over a single MTHDS document with no sibling files — the
+/// bindings' path — as binding [`Diagnostic`]s, honoring its suppression
+/// comments.
+#[must_use]
+pub fn rule_diagnostics(source: &str, dom: &Node, options: &LintOptions) -> Vec<Diagnostic> {
+    let violations = check_bundle(
+        &BundleIndex::from_dom(dom),
+        &[],
+        NativeConcepts::embedded(),
+        &ModelDeck::default(),
+        options,
+    );
+    Suppressions::from_dom(dom)
+        .apply(violations, options)
+        .into_iter()
+        .map(|violation| {
+            Diagnostic::rule(
+                violation.rule.id,
+                violation.severity,
+                violation.message,
+                Range::from_text_range(source, violation.range),
+            )
+            .with_fixes(source, &violation.fixes)
+        })
+        .collect()
+}
+
+/// A safe fix renaming `reference` to the one candidate it most likely
+/// misspells.
+fn typo_fix<'a>(reference: &Reference, candidates: impl IntoIterator<Item = &'a str>) -> Vec<Fix> {
+    let Some(range) = reference.name_range else {
+        return Vec::new();
+    };
+    closest_name(&reference.name, candidates)
+        .map(|name| {
+            Fix::replace(
+                format!("Replace with `{name}`"),
+                true,
+                range,
+                name.to_string(),
+            )
+        })
+        .into_iter()
+        .collect()
+}
+
+/// A safe fix renaming the model `reference` to the one deck reference of
+/// the same prefix it most likely misspells.
+fn model_typo_fix(reference: &ModelReference, models: &ModelDeck) -> Vec<Fix> {
+    let Some(range) = reference.value_range else {
+        return Vec::new();
+    };
+    let kind = split_model_ref(&reference.value).0;
+    let candidates = models.references();
+    let candidates = candidates
+        .iter()
+        .filter(|(_, candidate)| kind.map_or(true, |kind| kind == *candidate))
+        .map(|(label, _)| label.as_str());
+    let candidates: Vec<_> = match kind {
+        Some(_) => candidates.collect(),
+        // Unprefixed references compare to unprefixed names.
+        None => candidates.map(|label| &label[1..]).collect(),
+    };
+    closest_name(&reference.value, candidates)
+        .map(|name| {
+            Fix::replace(
+                format!("Replace with `{name}`"),
+                true,
+                range,
+                name.to_string(),
+            )
+        })
+        .into_iter()
+        .collect()
+}
+
+/// References that resolve inside this bundle: unqualified, or qualified with
+/// the document's own domain (or `native` for concepts). References into other
+/// domains point outside the bundle and are not checked.
+fn local_references(document: &BundleIndex, kind: RefKind) -> impl Iterator<Item = &Reference> {
+    document.references.iter().filter(move |reference| {
+        reference.kind == kind
+            && match reference.domain.as_deref() {
+                None => true,
+                Some("native") => kind == RefKind::Concept,
+                Some(domain) => document.domain.as_deref() == Some(domain),
+            }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use taplo::parser;
+
+    fn index(source: &str) -> BundleIndex {
+        BundleIndex::from_dom(&parser::parse(source).into_dom())
+    }
+
+    fn ids(violations: &[RuleViolation]) -> Vec<&'static str> {
+        violations.iter().map(|v| v.rule.id).collect()
+    }
+
+    fn options(rules: &[(&str, RuleLevel)]) -> LintOptions {
+        LintOptions {
+            rules: Some(
+                rules
+                    .iter()
+                    .map(|(id, level)| ((*id).to_string(), *level))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+        }
+    }
+
+    const BUNDLE: &str = r#"
+domain = "legal"
+
+[concept.Contract]
+description = "A contract"
+
+[concept.Orphan]
+description = "Never used"
+
+[pipe.review]
+type = "PipeSequence"
+description = "Review a contract"
+inputs = { contract = "legal.Contract", other = "finance.Invoice" }
+output = "Text"
+steps = [{ pipe = "summarize", result = "summary" }, { pipe = "finance.audit" }]
+"#;
+
+    #[test]
+    fn registry_ids_are_unique_and_namespaced() {
+        for rule in RULES {
+            assert!(
+                rule.id.starts_with("mthds/"),
+                "{} is not namespaced",
+                rule.id
+            );
+            assert_eq!(find_rule(rule.id), Some(*rule));
+        }
+    }
+
+    #[test]
+    fn reports_each_rule_at_its_default_severity() {
+        let violations = check_bundle(
+            &index(BUNDLE),
+            &[],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &LintOptions::default(),
+        );
+        assert_eq!(
+            ids(&violations),
+            ["mthds/unused-concept", "mthds/undefined-pipe"]
+        );
+        assert_eq!(violations[0].severity, Severity::Info);
+        assert_eq!(violations[1].severity, Severity::Warning);
+        assert!(violations[1].message.contains("\"summarize\""));
+    }
+
+    #[test]
+    fn siblings_resolve_cross_file_references() {
+        let sibling = index(
+            "domain = \"legal\"\n[pipe.summarize]\ntype = \"PipeLLM\"\ninputs = { c = \"Orphan\" }\n",
+        );
+        let violations = check_bundle(
+            &index(BUNDLE),
+            &[sibling],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &LintOptions::default(),
+        );
+        assert!(violations.is_empty(), "got {violations:?}");
+    }
+
+    #[test]
+    fn configured_levels_override_defaults() {
+        let options = options(&[
+            ("mthds/unused-concept", RuleLevel::Off),
+            ("mthds/undefined-pipe", RuleLevel::Error),
+        ]);
+        let violations = check_bundle(
+            &index(BUNDLE),
+            &[],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &options,
+        );
+        assert_eq!(ids(&violations), ["mthds/undefined-pipe"]);
+        assert_eq!(violations[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn native_concepts_are_never_undefined() {
+        let source = "[pipe.p]\ntype = \"PipeLLM\"\ninputs = { a = \"native.Image\" }\noutput = \"Text[]\"\n";
+        let violations = check_bundle(
+            &index(source),
+            &[],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &LintOptions::default(),
+        );
+        assert!(violations.is_empty(), "got {violations:?}");
+    }
+
+    #[test]
+    fn undefined_concept_is_reported_at_the_reference() {
+        let source = "[pipe.p]\ntype = \"PipeLLM\"\noutput = \"Summary\"\n";
+        let violations = check_bundle(
+            &index(source),
+            &[],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &LintOptions::default(),
+        );
+        assert_eq!(ids(&violations), ["mthds/undefined-concept"]);
+        let start = u32::from(violations[0].range.start()) as usize;
+        assert_eq!(&source[start..start + 9], "\"Summary\"");
+    }
+
+    #[test]
+    fn misspelled_references_carry_a_safe_fix() {
+        let source = "domain = \"legal\"\n[pipe.review]\ntype = \"PipeSequence\"\noutput = \"legal.Txt\"\nsteps = [{ pipe = \"sumarize\" }, { pipe = \"translate\" }]\n\n[pipe.summarize]\ntype = \"PipeLLM\"\n";
+        let violations = check_bundle(
+            &index(source),
+            &[],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &LintOptions::default(),
+        );
+        let fixes: Vec<_> = violations
+            .iter()
+            .map(|v| {
+                v.fixes
+                    .iter()
+                    .map(|fix| {
+                        (
+                            fix.safe,
+                            &source[fix.edits[0].range],
+                            &*fix.edits[0].new_text,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(
+            fixes,
+            [
+                vec![(true, "Txt", "Text")],
+                vec![(true, "sumarize", "summarize")],
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn template_syntax_errors_are_reported_in_the_document() {
+        let source = "[pipe.p]\ntype = \"PipeLLM\"\noutput = \"Text\"\nprompt = \"\"\"\n{% for x in @items %}\n{{ x | }}\n\"\"\"\n";
+        let violations = check_bundle(
+            &index(source),
+            &[],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &LintOptions::default(),
+        );
+        let found: Vec<_> = violations
+            .iter()
+            .map(|v| (v.rule.id, &source[v.range], v.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "mthds/template-syntax",
+                    "{% for",
+                    "invalid template: unclosed `{% for %}` block: expected `{% endfor %}`"
+                ),
+                (
+                    "mthds/template-syntax",
+                    "@",
+                    "invalid template: unexpected character `@`"
+                ),
+                (
+                    "mthds/template-syntax",
+                    "}}",
+                    "invalid template: expected a filter name after `|`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn deprecated_versions_are_reported_at_their_declaration() {
+        let check = |source: &str| {
+            check_bundle(
+                &index(source),
+                &[],
+                NativeConcepts::embedded(),
+                &ModelDeck::default(),
+                &LintOptions::default(),
+            )
+            .into_iter()
+            .map(|v| (v.rule.id, source[v.range].to_string(), v.message))
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            check("mthds_version = \"0.9\"\ndomain = \"d\"\n"),
+            [(
+                "mthds/deprecated-version",
+                "\"0.9\"".to_string(),
+                "MTHDS 0.9 is deprecated: move the bundle to MTHDS 1.0".to_string()
+            )]
+        );
+        assert_eq!(
+            check("#:schema pipelex://mthds-0.9.schema.json\ndomain = \"d\"\n")[0].1,
+            "#:schema pipelex://mthds-0.9.schema.json"
+        );
+        assert!(check("mthds_version = \"1.0\"\ndomain = \"d\"\n").is_empty());
+    }
+
+    #[test]
+    fn native_concepts_come_from_the_registry() {
+        let source =
+            "[pipe.p]\ntype = \"PipeLLM\"\noutput = \"Markdown\"\ninputs = { text = \"Text\" }\n";
+        let registry = NativeConcepts::from_toml(
+            "mthds_version = \"1.0\"\n[[concept]]\nname = \"Markdown\"\n",
+        )
+        .unwrap();
+        let violations = check_bundle(
+            &index(source),
+            &[],
+            &registry,
+            &ModelDeck::default(),
+            &LintOptions::default(),
+        );
+        let messages: Vec<_> = violations.iter().map(|v| v.message.as_str()).collect();
+        assert_eq!(messages, ["concept \"Text\" is not defined in this bundle"]);
+    }
+
+    #[test]
+    fn unknown_models_are_reported_against_the_deck() {
+        let source = r#"
+[pipe.write]
+type = "PipeLLM"
+output = "Text"
+model = "$writing-factul"
+model_to_structure = { model = "@best-claude", temperature = 0.2 }
+
+[pipe.draw]
+type = "PipeImgGen"
+output = "Image"
+model = "flux-dev"
+"#;
+        let mut models = ModelDeck::default();
+        models
+            .add_file(
+                std::path::Path::new("base_deck.toml"),
+                "[aliases]\nbest-claude = \"claude\"\n\n[llm.presets]\nwriting-factual = { model = \"@best-claude\", temperature = 0.1 }\n",
+            )
+            .unwrap();
+        let violations = check_bundle(
+            &index(source),
+            &[],
+            NativeConcepts::embedded(),
+            &models,
+            &LintOptions::default(),
+        );
+        let found: Vec<_> = violations
+            .iter()
+            .map(|v| (v.rule.id, v.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "mthds/unknown-model",
+                    "model preset \"writing-factul\" is not in the model deck"
+                ),
+                (
+                    "mthds/unknown-model",
+                    "model \"flux-dev\" is not in the model deck"
+                ),
+            ]
+        );
+        let fix = &violations[0].fixes[0];
+        assert_eq!(fix.edits[0].new_text, "$writing-factual");
+        assert_eq!(&source[fix.edits[0].range], "$writing-factul");
+
+        // Without a deck, nothing is checked.
+        let violations = check_bundle(
+            &index(source),
+            &[],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &LintOptions::default(),
+        );
+        assert!(violations.is_empty(), "{violations:?}");
+    }
+
+    #[test]
+    fn unknown_rule_ids_are_listed() {
+        let options = options(&[
+            ("mthds/unused-concept", RuleLevel::Off),
+            ("mthds/unused-conept", RuleLevel::Off),
+        ]);
+        assert_eq!(unknown_rule_ids(&options), ["mthds/unused-conept"]);
+    }
+}
@@ -0,0 +1,376 @@
+//! `# plxt-ignore` suppression comments for the MTHDS lint rules.
+//!
+//! Two directives are recognized, each naming one or more comma-separated rule
+//! ids:
+//!
+//! ```toml
+//! # plxt-ignore-file: mthds/undefined-pipe
+//!
+//! # plxt-ignore: mthds/unused-concept
+//! [concept.PublicReport]
+//! description = "Kept for external consumers"
+//! ```
+//!
+//! `plxt-ignore` must sit on its own line, directly above an entry or a table
+//! header (other comments may come between). Above an entry it covers that
+//! entry; above a table header it covers the whole table, up to the next
+//! header. `plxt-ignore-file` covers the whole document and is only honored in
+//! the comment block at the top of the file.
+//!
+//! A directive that suppresses nothing is itself reported, as
+//! [`UNUSED_SUPPRESSION`], so stale suppressions do not pile up.
+
+use taplo::{
+    dom::{node::DomNode, Node},
+    rowan::{TextRange, TextSize},
+    syntax::{SyntaxElement, SyntaxKind, SyntaxToken},
+};
+use taplo_common::config::LintOptions;
+
+use crate::tools::rules::{find_rule, RuleViolation, UNUSED_SUPPRESSION};
+
+const IGNORE: &str = "plxt-ignore:";
+const IGNORE_FILE: &str = "plxt-ignore-file:";
+
+/// The suppression directives of one document.
+#[derive(Debug, Clone, Default)]
+pub struct Suppressions {
+    directives: Vec<Directive>,
+}
+
+#[derive(Debug, Clone)]
+struct Directive {
+    rule_id: String,
+    /// The range of the rule id inside the comment.
+    range: TextRange,
+    scope: Scope,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Scope {
+    File,
+    Range(TextRange),
+    /// A `plxt-ignore-file` below the top of the file, which covers nothing.
+    Misplaced,
+}
+
+impl Suppressions {
+    /// Collect the directives from the comments of an MTHDS document.
+    #[must_use]
+    pub fn from_dom(dom: &Node) -> Self {
+        let mut suppressions = Self::default();
+        let Some(root) = dom.syntax().and_then(SyntaxElement::as_node) else {
+            return suppressions;
+        };
+
+        for token in root
+            .descendants_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|token| token.kind() == SyntaxKind::COMMENT)
+        {
+            let text = token.text();
+            let body = text.trim_start_matches('#').trim_start();
+            let (ids, scope) = if let Some(ids) = body.strip_prefix(IGNORE_FILE) {
+                let scope = if is_file_header(&token) {
+                    Scope::File
+                } else {
+                    Scope::Misplaced
+                };
+                (ids, scope)
+            } else if let Some(ids) = body.strip_prefix(IGNORE) {
+                if !is_own_line(&token) {
+                    continue;
+                }
+                match target_range(&token) {
+                    Some(range) => (ids, Scope::Range(range)),
+                    None => continue,
+                }
+            } else {
+                continue;
+            };
+
+            let ids_offset = text.len() - ids.len();
+            let mut offset = ids_offset;
+            for id in ids.split(',') {
+                let trimmed = id.trim();
+                if !trimmed.is_empty() {
+                    let start = offset + (id.len() - id.trim_start().len());
+                    let start = token.text_range().start() + TextSize::from(start as u32);
+                    suppressions.directives.push(Directive {
+                        rule_id: trimmed.to_string(),
+                        range: TextRange::at(start, TextSize::from(trimmed.len() as u32)),
+                        scope,
+                    });
+                }
+                offset += id.len() + 1;
+            }
+        }
+
+        suppressions
+    }
+
+    /// Drop the `violations` covered by a directive, then report the
+    /// directives that covered none (unless their rule is switched off, which
+    /// leaves them nothing to match).
+    ///
+    /// The result is in document order.
+    #[must_use]
+    pub fn apply(
+        &self,
+        violations: Vec<RuleViolation>,
+        options: &LintOptions,
+    ) -> Vec<RuleViolation> {
+        let mut used = vec![false; self.directives.len()];
+
+        let mut kept: Vec<RuleViolation> = violations
+            .into_iter()
+            .filter(|violation| {
+                let mut suppressed = false;
+                for (directive, used) in self.directives.iter().zip(&mut used) {
+                    if directive.covers(violation) {
+                        *used = true;
+                        suppressed = true;
+                    }
+                }
+                !suppressed
+            })
+            .collect();
+
+        if let Some(severity) = UNUSED_SUPPRESSION.severity(options) {
+            for (directive, _) in self.directives.iter().zip(used).filter(|(_, used)| !used) {
+                let message = match (directive.scope, find_rule(&directive.rule_id)) {
+                    (_, None) => format!("unknown lint rule \"{}\"", directive.rule_id),
+                    (_, Some(rule)) if rule.severity(options).is_none() => continue,
+                    (Scope::Misplaced, _) => format!(
+                        "plxt-ignore-file must be at the top of the file, \"{}\" is not suppressed",
+                        directive.rule_id
+                    ),
+                    _ => format!(
+                        "suppression of \"{}\" matches no diagnostic",
+                        directive.rule_id
+                    ),
+                };
+                kept.push(RuleViolation {
+                    rule: &UNUSED_SUPPRESSION,
+                    severity,
+                    message,
+                    range: directive.range,
+                    fixes: Vec::new(),
+                });
+            }
+        }
+
+        kept.sort_by_key(|violation| violation.range.start());
+        kept
+    }
+}
+
+impl Directive {
+    fn covers(&self, violation: &RuleViolation) -> bool {
+        if violation.rule.id != self.rule_id {
+            return false;
+        }
+        match self.scope {
+            Scope::File => true,
+            Scope::Range(range) => range.contains(violation.range.start()),
+            Scope::Misplaced => false,
+        }
+    }
+}
+
+/// Whether only whitespace precedes `token` on its line.
+fn is_own_line(token: &SyntaxToken) -> bool {
+    let mut previous = token.prev_sibling_or_token();
+    while let Some(element) = previous {
+        match element.kind() {
+            SyntaxKind::WHITESPACE => previous = element.prev_sibling_or_token(),
+            SyntaxKind::NEWLINE => return true,
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Whether `token` is in the leading comment block of the document.
+fn is_file_header(token: &SyntaxToken) -> bool {
+    token
+        .parent()
+        .is_some_and(|parent| parent.kind() == SyntaxKind::ROOT)
+        && std::iter::successors(
+            token.prev_sibling_or_token(),
+            SyntaxElement::prev_sibling_or_token,
+        )
+        .all(|element| is_trivia(element.kind()))
+}
+
+/// The range a `plxt-ignore` comment covers: the entry (or array value) that
+/// follows it, or the whole table when a header follows it.
+fn target_range(token: &SyntaxToken) -> Option<TextRange> {
+    let mut next = std::iter::successors(
+        token.next_sibling_or_token(),
+        SyntaxElement::next_sibling_or_token,
+    )
+    .skip_while(|element| is_trivia(element.kind()));
+    let target = next.next()?.into_node()?;
+
+    if !matches!(
+        target.kind(),
+        SyntaxKind::TABLE_HEADER | SyntaxKind::TABLE_ARRAY_HEADER
+    ) {
+        return Some(target.text_range());
+    }
+
+    let end = next
+        .find(|element| {
+            matches!(
+                element.kind(),
+                SyntaxKind::TABLE_HEADER | SyntaxKind::TABLE_ARRAY_HEADER
+            )
+        })
+        .map_or_else(
+            || {
+                target
+                    .parent()
+                    .map_or(target.text_range().end(), |p| p.text_range().end())
+            },
+            |header| header.text_range().start(),
+        );
+    Some(TextRange::new(target.text_range().start(), end))
+}
+
+fn is_trivia(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::WHITESPACE | SyntaxKind::NEWLINE | SyntaxKind::COMMENT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::bundle::BundleIndex;
+    use crate::tools::models::ModelDeck;
+    use crate::tools::native_concepts::NativeConcepts;
+    use crate::tools::rules::check_bundle;
+    use taplo::parser;
+    use taplo_common::config::RuleLevel;
+
+    fn lint(source: &str) -> Vec<(&'static str, String)> {
+        let dom = parser::parse(source).into_dom();
+        let options = LintOptions::default();
+        let violations = check_bundle(
+            &BundleIndex::from_dom(&dom),
+            &[],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &options,
+        );
+        Suppressions::from_dom(&dom)
+            .apply(violations, &options)
+            .into_iter()
+            .map(|v| {
+                let range = usize::from(v.range.start())..usize::from(v.range.end());
+                (v.rule.id, source[range].to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn header_directive_covers_the_whole_table() {
+        let source = r#"
+# plxt-ignore: mthds/unused-concept
+[concept.Orphan]
+description = "Kept for external consumers"
+
+# plxt-ignore: mthds/undefined-pipe, mthds/undefined-concept
+[pipe.review]
+type = "PipeSequence"
+output = "Summary"
+steps = [{ pipe = "summarize" }]
+"#;
+        assert_eq!(lint(source), []);
+    }
+
+    #[test]
+    fn entry_directive_covers_only_the_next_entry() {
+        let source = r#"
+[pipe.review]
+type = "PipeSequence"
+# A comment in between is fine.
+# plxt-ignore: mthds/undefined-pipe
+steps = [{ pipe = "summarize" }]
+default_outcome = "other"
+"#;
+        assert_eq!(
+            lint(source),
+            [("mthds/undefined-pipe", "\"other\"".to_string())]
+        );
+    }
+
+    #[test]
+    fn file_directive_covers_the_document() {
+        let source = "#:schema ./schema.json\n# plxt-ignore-file: mthds/unused-concept\n\n[concept.A]\ndescription = \"a\"\n\n[concept.B]\ndescription = \"b\"\n";
+        assert_eq!(lint(source), []);
+    }
+
+    #[test]
+    fn misplaced_file_directive_is_reported() {
+        let source = "[concept.A]\ndescription = \"a\"\n# plxt-ignore-file: mthds/unused-concept\n";
+        let result = lint(source);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].0, "mthds/unused-concept");
+        assert_eq!(
+            result[1],
+            (
+                "mthds/unused-suppression",
+                "mthds/unused-concept".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn unused_and_unknown_suppressions_are_reported() {
+        let source = r#"
+# plxt-ignore: mthds/undefined-pipe,mthds/nope
+[concept.A]
+description = "a"
+refines = "Text"
+"#;
+        assert_eq!(
+            lint(source),
+            [
+                (
+                    "mthds/unused-suppression",
+                    "mthds/undefined-pipe".to_string()
+                ),
+                ("mthds/unused-suppression", "mthds/nope".to_string()),
+                ("mthds/unused-concept", "A".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_comments_are_not_directives() {
+        let source = "[concept.A] # plxt-ignore: mthds/unused-concept\ndescription = \"a\"\n";
+        assert_eq!(lint(source), [("mthds/unused-concept", "A".to_string())]);
+    }
+
+    #[test]
+    fn suppressions_of_disabled_rules_are_not_reported() {
+        let source = "# plxt-ignore: mthds/unused-concept\n[concept.A]\ndescription = \"a\"\n";
+        let dom = parser::parse(source).into_dom();
+        let options = LintOptions {
+            rules: Some([("mthds/unused-concept".to_string(), RuleLevel::Off)].into()),
+        };
+        let violations = check_bundle(
+            &BundleIndex::from_dom(&dom),
+            &[],
+            NativeConcepts::embedded(),
+            &ModelDeck::default(),
+            &options,
+        );
+        assert!(Suppressions::from_dom(&dom)
+            .apply(violations, &options)
+            .is_empty());
+    }
+}
@@ -11,20 +11,21 @@ Because nothing enforces that this file matches the Rust, **if you change the
 exported surface you MUST update this stub in the same commit.** Map of what
 mirrors what:
 
-  - ``format_mthds`` / ``lint_mthds`` signatures  → ``src/python.rs``
-  - ``Diagnostic`` / ``Range`` / kind values      → ``pipelex-common/src/tools/diagnostic.rs``
+  - ``format_mthds`` / ``lint_mthds`` / ``concept_schema`` signatures → ``src/python.rs``
+  - ``Diagnostic`` / ``Range`` / kind / severity   → ``pipelex-common/src/tools/diagnostic.rs``
   - ``format_mthds`` return fields                 → ``pipelex-common/src/tools/format.rs`` (``FormatOutcome``)
   - ``lint_mthds`` return field                    → ``src/python.rs`` (``LintOutput``)
+  - ``concept_schema`` return                      → ``pipelex-common/src/tools/concept_schema.rs``
 
 Keys come from ``#[derive(Serialize)]`` field names handed to Python via
 ``pythonize`` (see ``src/python.rs::to_py``), so a Rust field rename is a
 breaking change to these dict shapes.
 """
 
-from typing import Literal, Optional, TypedDict
+from typing import Any, Literal, Optional, TypedDict
 
 # ``__all__`` mirrors the module's *runtime* export surface, which is only the
-# two functions (see ``src/python.rs::pipelex_tools``). The ``Diagnostic`` /
+# three functions (see ``src/python.rs::pipelex_tools``). The ``Diagnostic`` /
 # ``Range`` / ``FormatResult`` / ``LintResult`` TypedDicts below are
 # **type-checking-only** — defined here so downstream code can annotate against
 # the return shapes (typically under an ``if TYPE_CHECKING:`` import), but they
@@ -36,6 +37,7 @@ from typing import Literal, Optional, TypedDict
 __all__ = [
     "format_mthds",
     "lint_mthds",
+    "concept_schema",
 ]
 
 class Range(TypedDict):
@@ -50,20 +52,40 @@ class Range(TypedDict):
     end_line: int
     end_col: int
 
+class TextEdit(TypedDict):
+    """Mirror of ``TextEdit`` in ``pipelex-common/src/tools/diagnostic.rs`` —
+    replace the text in ``range`` with ``new_text`` (an empty range inserts)."""
+
+    range: Range
+    new_text: str
+
+class Fix(TypedDict):
+    """Mirror of ``DiagnosticFix`` in ``pipelex-common/src/tools/diagnostic.rs``.
+    ``safe`` fixes are the ones ``plxt lint --fix`` applies."""
+
+    title: str
+    safe: bool
+    edits: list[TextEdit]  # non-overlapping, in document order
+
 class Diagnostic(TypedDict):
     """Mirror of ``Diagnostic`` (+ ``DiagnosticKind``) in
     ``pipelex-common/src/tools/diagnostic.rs``.
 
-    ``kind`` is the serde ``rename_all = "lowercase"`` form of ``DiagnosticKind``.
-    ``location`` and ``range`` are always present (``None`` when absent) so the
-    shape is stable.
+    ``kind`` and ``severity`` are the serde ``rename_all = "lowercase"`` forms of
+    ``DiagnosticKind`` and ``Severity``. ``code`` is a stable code prefixed by the
+    stage (see ``docs/guide/diagnostic-codes.md``); ``code_description``,
+    ``location`` and ``range`` are always present (``None`` when absent) and
+    ``fixes`` is always a list (most often empty) so the shape is stable.
     """
 
-    kind: Literal["syntax", "semantic", "schema"]
-    severity: str  # always "error" today; lint has no warnings yet
+    kind: Literal["syntax", "semantic", "schema", "rule"]
+    severity: Literal["error", "warning", "info"]  # only rules and "schema/deprecated" go below "error"
+    code: str  # e.g. "syntax/parse-error", "schema/required", "mthds/undefined-pipe"
+    code_description: Optional[str]  # documentation URL for the code
     message: str
     location: Optional[str]  # dotted instance path for schema errors, else None
     range: Optional[Range]  # None for semantic/schema errors with no position
+    fixes: list[Fix]
 
 class FormatResult(TypedDict):
     """Mirror of ``FormatOutcome`` in ``pipelex-common/src/tools/format.rs`` —
@@ -103,3 +125,17 @@ def lint_mthds(
     ``src/python.rs``.
     """
     ...
+
+def concept_schema(
+    content: str,
+    *,
+    concept: Optional[str] = ...,
+) -> dict[str, Any]:
+    """Translate the concepts of MTHDS ``content`` into a JSON Schema document
+    (draft 2020-12) with one ``$defs`` entry per concept.
+
+    With ``concept``, only that concept and the concepts it references are
+    exported, and the document's ``$ref`` points at it. Raises ``ValueError``
+    on a syntax error or an unknown ``concept``. See ``src/python.rs``.
+    """
+    ...
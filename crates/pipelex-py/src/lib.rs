@@ -13,7 +13,7 @@
 //! [`python`] and is gated behind the `python` cargo feature so that plain
 //! `cargo build` / `cargo test` stay PyO3-free.
 
-pub use pipelex_common::tools::{diagnostic, format, lint};
+pub use pipelex_common::tools::{concept_schema, diagnostic, format, lint};
 
 #[cfg(feature = "python")]
 mod python;
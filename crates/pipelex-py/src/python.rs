@@ -4,13 +4,14 @@
 //! marshal Python arguments, release the GIL around the pure Rust impls (FastAPI
 //! calls these from a threadpool), and hand the `#[derive(Serialize)]` results
 //! back via `pythonize`. All the real work lives in [`crate::format`] /
-//! [`crate::lint`] and is unit-tested without a Python interpreter.
+//! [`crate::lint`] / [`crate::concept_schema`] and is unit-tested without a Python interpreter.
 
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::{PyBool, PyDict};
 use serde::Serialize;
 
+use crate::concept_schema::concept_schema_impl;
 use crate::diagnostic::Diagnostic;
 use crate::format::format_mthds_impl;
 use crate::lint::lint_mthds_impl;
@@ -103,17 +104,38 @@ fn lint_mthds(py: Python<'_>, content: String, source: Option<String>) -> PyResu
     to_py(py, &LintOutput { diagnostics })
 }
 
+// ⚠️ PUBLIC PYTHON SURFACE — keep this signature and its return shape in sync
+// with the hand-maintained stub `pipelex_tools.pyi` (`concept_schema`); the stub
+// is not compiler-checked against this function.
+/// `concept_schema(content, *, concept=None) -> dict`
+///
+/// Returns the JSON Schema of the document's concepts, or of `concept` and the
+/// concepts it references. Raises `ValueError` on a syntax error or an unknown
+/// `concept`.
+#[pyfunction]
+#[pyo3(signature = (content, *, concept=None))]
+fn concept_schema(py: Python<'_>, content: String, concept: Option<String>) -> PyResult<PyObject> {
+    let schema = py
+        .allow_threads(|| concept_schema_impl(&content, concept.as_deref()))
+        .map_err(|err| PyValueError::new_err(format!("{err:#}")))?;
+    to_py(py, &schema)
+}
+
 /// The `pipelex_tools` Python module. The function name must match the `[lib]`
 /// `name` so PyO3 emits the matching `PyInit_pipelex_tools` symbol.
 #[pymodule]
 fn pipelex_tools(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(format_mthds, m)?)?;
     m.add_function(wrap_pyfunction!(lint_mthds, m)?)?;
+    m.add_function(wrap_pyfunction!(concept_schema, m)?)?;
     // ⚠️ PUBLIC PYTHON SURFACE — the genuine *runtime* export surface. Only the
-    // two functions exist at runtime; the `Diagnostic`/`Range`/`FormatResult`/
+    // three functions exist at runtime; the `Diagnostic`/`Range`/`FormatResult`/
     // `LintResult` TypedDicts live only in `pipelex_tools.pyi` for type-checkers
     // and are NOT importable here. Keep this list in sync with the stub's
     // `__all__`; a `tests/test_smoke.py` guard asserts they match.
-    m.add("__all__", vec!["format_mthds", "lint_mthds"])?;
+    m.add(
+        "__all__",
+        vec!["format_mthds", "lint_mthds", "concept_schema"],
+    )?;
     Ok(())
 }
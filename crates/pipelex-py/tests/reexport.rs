@@ -1,9 +1,10 @@
 //! Guards the re-export wiring: the binding crate exposes the shared engine
 //! from `pipelex_common::tools` under the same paths the PyO3 glue and the
-//! `pipelex-cli` parity suite import (`pipelex_tools::{diagnostic, format,
-//! lint}`). A broken re-export fails to compile here before it can break a
+//! `pipelex-cli` parity suite import (`pipelex_tools::{concept_schema,
+//! diagnostic, format, lint}`). A broken re-export fails to compile here before it can break a
 //! downstream consumer.
 
+use pipelex_tools::concept_schema::concept_schema_impl;
 use pipelex_tools::diagnostic::DiagnosticKind;
 use pipelex_tools::format::format_mthds_impl;
 use pipelex_tools::lint::lint_mthds_impl;
@@ -20,3 +21,10 @@ fn reexported_lint_is_the_shared_engine() {
     let diagnostics = lint_mthds_impl("key = ").expect("lint should succeed");
     assert_eq!(diagnostics[0].kind, DiagnosticKind::Syntax);
 }
+
+#[test]
+fn reexported_concept_schema_is_the_shared_engine() {
+    let schema = concept_schema_impl("[concept.Greeting]\ndescription = \"g\"\n", None)
+        .expect("export should succeed");
+    assert_eq!(schema["$defs"]["Greeting"]["title"], "Greeting");
+}
@@ -51,7 +51,9 @@ class SmokeTest(unittest.TestCase):
         # what catches the stub/runtime `__all__` drift where the `.pyi` listed
         # type-check-only TypedDicts (Diagnostic/Range/FormatResult/LintResult)
         # that raise ImportError at runtime.
-        self.assertEqual(pipelex_tools.__all__, ["format_mthds", "lint_mthds"])
+        self.assertEqual(
+            pipelex_tools.__all__, ["format_mthds", "lint_mthds", "concept_schema"]
+        )
         for name in pipelex_tools.__all__:
             self.assertTrue(
                 callable(getattr(pipelex_tools, name)),
@@ -62,7 +64,7 @@ class SmokeTest(unittest.TestCase):
         # The flip side of the guard above: the TypedDicts are deliberately
         # absent at runtime (they exist only in pipelex_tools.pyi for
         # type-checkers), so importing them must fail rather than silently work.
-        for name in ("Diagnostic", "Range", "FormatResult", "LintResult"):
+        for name in ("Diagnostic", "Range", "Fix", "TextEdit", "FormatResult", "LintResult"):
             self.assertFalse(
                 hasattr(pipelex_tools, name),
                 f"{name} is type-check-only and must not be a runtime attribute",
@@ -133,7 +135,10 @@ class SmokeTest(unittest.TestCase):
     def test_lint_reports_syntax_error(self) -> None:
         result = pipelex_tools.lint_mthds("key = ")
         self.assertTrue(result["diagnostics"])
-        self.assertEqual(result["diagnostics"][0]["kind"], "syntax")
+        diag = result["diagnostics"][0]
+        self.assertEqual(diag["kind"], "syntax")
+        self.assertEqual(diag["code"], "syntax/parse-error")
+        self.assertTrue(diag["code_description"].endswith("diagnostic-codes.md#syntaxparse-error"))
 
     def test_lint_reports_semantic_error(self) -> None:
         # Duplicate keys parse cleanly but fail DOM validation.
@@ -141,6 +146,30 @@ class SmokeTest(unittest.TestCase):
         self.assertTrue(result["diagnostics"])
         self.assertEqual(result["diagnostics"][0]["kind"], "semantic")
 
+    def test_lint_reports_rule_violation_with_code(self) -> None:
+        # Schema-valid, but the output concept is defined nowhere.
+        content = 'domain = "d"\n\n[pipe.p]\ntype = "PipeLLM"\ndescription = "p"\noutput = "Summary"\n'
+        diagnostics = pipelex_tools.lint_mthds(content)["diagnostics"]
+        self.assertEqual(len(diagnostics), 1)
+        self.assertEqual(diagnostics[0]["kind"], "rule")
+        self.assertEqual(diagnostics[0]["code"], "mthds/undefined-concept")
+        self.assertEqual(diagnostics[0]["severity"], "warning")
+
+    def test_lint_honors_suppression_comments(self) -> None:
+        content = '# plxt-ignore-file: mthds/undefined-concept\ndomain = "d"\n\n[pipe.p]\ntype = "PipeLLM"\ndescription = "p"\noutput = "Summary"\n'
+        self.assertEqual(pipelex_tools.lint_mthds(content)["diagnostics"], [])
+
+    def test_lint_attaches_safe_fix_to_misspelled_reference(self) -> None:
+        content = 'domain = "d"\n\n[concept.Summary]\ndescription = "s"\n\n[pipe.p]\ntype = "PipeLLM"\ndescription = "p"\noutput = "Sumary"\n'
+        diagnostics = pipelex_tools.lint_mthds(content)["diagnostics"]
+        [diag] = [d for d in diagnostics if d["code"] == "mthds/undefined-concept"]
+        [fix] = diag["fixes"]
+        self.assertEqual(fix["title"], "Replace with `Summary`")
+        self.assertTrue(fix["safe"])
+        [edit] = fix["edits"]
+        self.assertEqual(edit["new_text"], "Summary")
+        self.assertEqual(content[edit["range"]["start_offset"] : edit["range"]["end_offset"]], "Sumary")
+
     def test_lint_accepts_source_kwarg(self) -> None:
         # `source` is accepted for API symmetry (a reserved locator); today it's
         # a no-op, so passing it must not change the verdict on clean input.
@@ -158,5 +187,15 @@ class SmokeTest(unittest.TestCase):
         self.assertIn("start_offset", rng)
 
 
+    def test_concept_schema_exports_structures(self) -> None:
+        content = 'domain = "d"\n\n[concept.Greeting]\ndescription = "A greeting"\n\n[concept.Greeting.structure]\ntext = { type = "text", description = "The words", required = true }\n'
+        schema = pipelex_tools.concept_schema(content)
+        greeting = schema["$defs"]["Greeting"]
+        self.assertEqual(greeting["properties"]["text"], {"type": "string", "description": "The words"})
+        self.assertEqual(greeting["required"], ["text"])
+        self.assertEqual(pipelex_tools.concept_schema(content, concept="Greeting")["$ref"], "#/$defs/Greeting")
+        with self.assertRaises(ValueError):
+            pipelex_tools.concept_schema(content, concept="Missing")
+
 if __name__ == "__main__":
     unittest.main()
@@ -16,6 +16,7 @@
 //! shapes match the Python/HTTP surfaces exactly — plain objects, and absent
 //! `location`/`range` serialized as `null`, never `undefined`.
 
+use pipelex_common::tools::concept_schema::concept_schema_impl;
 use pipelex_common::tools::diagnostic::Diagnostic;
 use pipelex_common::tools::format::format_mthds_impl;
 use pipelex_common::tools::lint::lint_mthds_offline;
@@ -116,6 +117,20 @@ pub fn lint_mthds(content: &str) -> Result<JsValue, JsError> {
     to_js(&LintOutput { diagnostics })
 }
 
+// ⚠️ PUBLIC BINDING SURFACE — keep the signature and return shape in sync with
+// `js/tools-wasm`'s TS wrapper (`conceptSchema`).
+/// `concept_schema(content, concept?) -> JSON Schema`
+///
+/// The JSON Schema (draft 2020-12) of the document's concepts, one `$defs`
+/// entry per concept, or of `concept` and the concepts it references. It
+/// throws on a syntax error or an unknown `concept`.
+#[wasm_bindgen]
+pub fn concept_schema(content: &str, concept: Option<String>) -> Result<JsValue, JsError> {
+    let schema = concept_schema_impl(content, concept.as_deref())
+        .map_err(|err| JsError::new(&format!("{err:#}")))?;
+    to_js(&schema)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
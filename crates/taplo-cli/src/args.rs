@@ -75,6 +75,32 @@ pub enum TaploCommand {
     /// Extract a value from the given TOML document.
     Get(GetCommand),
 
+    /// Print the concepts of an MTHDS bundle as a JSON Schema.
+    #[cfg(feature = "lint")]
+    ConceptSchema(ConceptSchemaCommand),
+
+    /// Print Python or TypeScript types for the concepts of an MTHDS bundle.
+    #[cfg(feature = "lint")]
+    Codegen(CodegenCommand),
+
+    /// Write documentation pages for the MTHDS bundles of a directory.
+    #[cfg(feature = "lint")]
+    Doc(DocCommand),
+
+    /// Print a sample input fixture for a pipe, or validate one.
+    #[cfg(feature = "lint")]
+    Fixture(FixtureCommand),
+
+    /// Render the prompts of a pipe with sample inputs.
+    #[cfg(feature = "lint")]
+    RenderPrompt(RenderPromptCommand),
+
+    /// Migrate MTHDS bundles from one MTHDS version to another.
+    ///
+    /// Files are modified in-place, keeping their comments and formatting.
+    #[cfg(feature = "lint")]
+    Migrate(MigrateCommand),
+
     /// Start a decoder for `toml-test` (https://github.com/BurntSushi/toml-test).
     #[cfg(feature = "toml-test")]
     TomlTest {},
@@ -175,12 +201,196 @@ pub struct LintCommand {
     #[clap(long)]
     pub no_schema: bool,
 
+    /// Validate every MTHDS bundle against the embedded schema of this MTHDS
+    /// version, whatever the bundle or the configuration declares.
+    #[clap(long, value_name = "VERSION", conflicts_with = "schema")]
+    pub mthds_version: Option<String>,
+
+    /// Apply the safe fixes of the reported issues in place, then report what
+    /// is left.
+    ///
+    /// Not available when linting the standard input.
+    #[clap(long)]
+    pub fix: bool,
+
     /// Paths or glob patterns to TOML documents.
     ///
     /// If the only argument is "-", the standard input will be used.
     pub files: Vec<String>,
 }
 
+#[cfg(feature = "lint")]
+#[derive(Clone, Args)]
+pub struct ConceptSchemaCommand {
+    #[clap(flatten)]
+    pub general: GeneralArgs,
+
+    /// Export only this concept and the concepts it references.
+    #[clap(long, value_name = "NAME")]
+    pub concept: Option<String>,
+
+    /// Path to the MTHDS bundle file.
+    ///
+    /// The other `.mthds` files of its directory are searched for the
+    /// concepts it references.
+    pub bundle: PathBuf,
+}
+
+#[cfg(feature = "lint")]
+#[derive(Clone, Args)]
+pub struct CodegenCommand {
+    #[clap(flatten)]
+    pub general: GeneralArgs,
+
+    /// The language to generate types in.
+    #[clap(long, value_enum)]
+    pub lang: CodegenLanguage,
+
+    /// Path to the MTHDS bundle file.
+    ///
+    /// The other `.mthds` files of its directory are searched for the
+    /// concepts it references.
+    pub bundle: PathBuf,
+}
+
+#[cfg(feature = "lint")]
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CodegenLanguage {
+    /// Pydantic `BaseModel` classes.
+    Python,
+    /// TypeScript interfaces.
+    Typescript,
+}
+
+#[cfg(feature = "lint")]
+#[derive(Clone, Args)]
+pub struct DocCommand {
+    #[clap(flatten)]
+    pub general: GeneralArgs,
+
+    /// The format of the pages.
+    #[clap(long, value_enum, default_value_t = DocFormat::Markdown)]
+    pub format: DocFormat,
+
+    /// The directory the pages are written to.
+    #[clap(short, long, value_name = "DIR", default_value = "docs")]
+    pub output: PathBuf,
+
+    /// The directory holding the MTHDS bundles, searched recursively.
+    pub dir: PathBuf,
+}
+
+#[cfg(feature = "lint")]
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+#[cfg(feature = "lint")]
+#[derive(Clone, Args)]
+pub struct FixtureCommand {
+    #[clap(flatten)]
+    pub general: GeneralArgs,
+
+    /// The code of the pipe whose inputs the fixture holds.
+    #[clap(long, value_name = "CODE")]
+    pub pipe: String,
+
+    /// Validate this fixture file instead of printing a new one.
+    #[clap(long, value_name = "FILE")]
+    pub validate: Option<PathBuf>,
+
+    /// Path to the MTHDS bundle file.
+    ///
+    /// The other `.mthds` files of its directory are searched for the
+    /// concepts of the pipe's inputs.
+    pub bundle: PathBuf,
+}
+
+#[cfg(feature = "lint")]
+#[derive(Clone, Args)]
+pub struct RenderPromptCommand {
+    #[clap(flatten)]
+    pub general: GeneralArgs,
+
+    /// The code of the pipe whose prompts are rendered.
+    #[clap(long, value_name = "CODE")]
+    pub pipe: String,
+
+    /// The inputs to render with, as an `inputs.json` fixture.
+    ///
+    /// Defaults to the placeholder fixture `plxt fixture` prints.
+    #[clap(long, value_name = "FILE")]
+    pub inputs: Option<PathBuf>,
+
+    /// Override the tag style of the pipe's `templating_style`.
+    #[clap(long, value_enum)]
+    pub tag_style: Option<PromptTagStyle>,
+
+    /// Override the text format of the pipe's `templating_style`.
+    #[clap(long, value_enum)]
+    pub text_format: Option<PromptTextFormat>,
+
+    /// Path to the MTHDS bundle file.
+    ///
+    /// The other `.mthds` files of its directory are searched for the
+    /// concepts of the pipe's inputs.
+    pub bundle: PathBuf,
+}
+
+#[cfg(feature = "lint")]
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PromptTagStyle {
+    #[value(name = "no_tag")]
+    NoTag,
+    Ticks,
+    Xml,
+    #[value(name = "square_brackets")]
+    SquareBrackets,
+}
+
+#[cfg(feature = "lint")]
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PromptTextFormat {
+    Plain,
+    Markdown,
+    Html,
+    Json,
+}
+
+#[cfg(feature = "lint")]
+#[derive(Clone, Args)]
+pub struct MigrateCommand {
+    #[clap(flatten)]
+    pub general: GeneralArgs,
+
+    /// The MTHDS version the bundles are written for.
+    ///
+    /// Defaults to the version each bundle declares, then to the version
+    /// selected by the configuration, then to the latest version, as `lint`
+    /// assumes.
+    #[clap(long, value_name = "VERSION")]
+    pub from: Option<String>,
+
+    /// The MTHDS version to migrate the bundles to, the latest by default.
+    #[clap(long, value_name = "VERSION")]
+    pub to: Option<String>,
+
+    /// Dry-run and report any bundles that need migrating.
+    #[clap(long)]
+    pub check: bool,
+
+    /// Print the differences in patch formatting to `stdout`
+    #[clap(long)]
+    pub diff: bool,
+
+    /// Paths or glob patterns to MTHDS bundles.
+    ///
+    /// Only `.mthds` files are migrated.
+    pub files: Vec<String>,
+}
+
 #[derive(Clone, Args)]
 pub struct GetCommand {
     /// The format specifying how the output is printed.
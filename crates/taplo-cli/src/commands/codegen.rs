@@ -0,0 +1,57 @@
+use crate::{
+    args::{CodegenCommand, CodegenLanguage},
+    Taplo,
+};
+use anyhow::anyhow;
+use codespan_reporting::files::SimpleFile;
+use pipelex_common::tools::{
+    codegen::{codegen, Language},
+    native_concepts::NativeConcepts,
+};
+use taplo::parser;
+use taplo_common::{environment::Environment, util::Normalize};
+use tokio::io::AsyncWriteExt;
+
+impl<E: Environment> Taplo<E> {
+    pub async fn execute_codegen(&mut self, cmd: CodegenCommand) -> Result<(), anyhow::Error> {
+        let config = self.load_config(&cmd.general).await?;
+        let natives =
+            NativeConcepts::load(&self.env, config.native_concepts_path().as_deref()).await;
+
+        let path = if self.env.is_absolute(&cmd.bundle) {
+            cmd.bundle.normalize()
+        } else {
+            self.env
+                .cwd_normalized()
+                .ok_or_else(|| anyhow!("could not figure the current working directory"))?
+                .join(&cmd.bundle)
+                .normalize()
+        };
+
+        let source = String::from_utf8(self.env.read_file(&path).await?)?;
+        let p = parser::parse(&source);
+        if !p.errors.is_empty() {
+            self.print_parse_errors(
+                &SimpleFile::new(&*path.to_string_lossy(), source.as_str()),
+                &p.errors,
+            )
+            .await?;
+            return Err(anyhow!("no types were generated due to syntax errors"));
+        }
+
+        let language = match cmd.lang {
+            CodegenLanguage::Python => Language::Python,
+            CodegenLanguage::Typescript => Language::Typescript,
+        };
+        let file_name = path
+            .file_name()
+            .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy());
+        let siblings = self.bundle_sibling_documents(&path).await;
+        let types = codegen(&p.into_dom(), &siblings, language, &natives, &file_name);
+
+        let mut stdout = self.env.stdout();
+        stdout.write_all(types.as_bytes()).await?;
+        stdout.flush().await?;
+        Ok(())
+    }
+}
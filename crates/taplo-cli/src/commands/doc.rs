@@ -0,0 +1,95 @@
+use std::path::{Component, Path};
+
+use crate::{
+    args::{DocCommand, DocFormat},
+    Taplo,
+};
+use anyhow::anyhow;
+use pipelex_common::tools::doc::{self, bundle_docs, SourceFile};
+use taplo::parser;
+use taplo_common::{environment::Environment, util::Normalize};
+
+impl<E: Environment> Taplo<E> {
+    pub async fn execute_doc(&mut self, cmd: DocCommand) -> Result<(), anyhow::Error> {
+        let config = self.load_config(&cmd.general).await?;
+
+        let cwd = self
+            .env
+            .cwd_normalized()
+            .ok_or_else(|| anyhow!("could not figure the current working directory"))?;
+        let absolute = |path: &Path| {
+            if self.env.is_absolute(path) {
+                path.to_path_buf().normalize()
+            } else {
+                cwd.join(path).normalize()
+            }
+        };
+        let dir = absolute(&cmd.dir);
+        let output = absolute(&cmd.output);
+
+        let pattern = dir.join("**/*.mthds").to_string_lossy().into_owned();
+        let mut paths = self
+            .collect_files(&cwd, &config, std::iter::once(pattern))
+            .await?;
+        paths.sort();
+        if paths.is_empty() {
+            return Err(anyhow!("no MTHDS files found in {}", dir.display()));
+        }
+
+        let mut sources = Vec::new();
+        for path in paths {
+            let source = String::from_utf8(self.env.read_file(&path).await?)?;
+            let p = parser::parse(&source);
+            if !p.errors.is_empty() {
+                tracing::warn!(path = ?path, "skipping a file with syntax errors");
+                continue;
+            }
+            sources.push((path, source, p.into_dom()));
+        }
+        let files: Vec<SourceFile<'_>> = sources
+            .iter()
+            .map(|(path, source, document)| SourceFile {
+                name: relative_link(&dir, path),
+                link: relative_link(&output, path),
+                source,
+                document: document.clone(),
+            })
+            .collect();
+
+        let format = match cmd.format {
+            DocFormat::Markdown => doc::DocFormat::Markdown,
+            DocFormat::Html => doc::DocFormat::Html,
+        };
+        self.create_dir_all(&output).await?;
+        for page in bundle_docs(&files, format) {
+            let path = output.join(&page.file_name);
+            self.env.write_file(&path, page.content.as_bytes()).await?;
+            tracing::info!(path = ?path, "wrote page");
+        }
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn create_dir_all(&self, _path: &Path) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn create_dir_all(&self, path: &Path) -> Result<(), anyhow::Error> {
+        Ok(tokio::fs::create_dir_all(path).await?)
+    }
+}
+
+/// The URL of `path` from the directory `from`, e.g. `../reviews.mthds`.
+fn relative_link(from: &Path, path: &Path) -> String {
+    let from: Vec<Component<'_>> = from.components().collect();
+    let to: Vec<Component<'_>> = path.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut segments: Vec<String> = vec!["..".to_string(); from.len() - common];
+    segments.extend(
+        to[common..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned()),
+    );
+    segments.join("/")
+}
@@ -0,0 +1,71 @@
+use crate::{args::FixtureCommand, Taplo};
+use anyhow::anyhow;
+use codespan_reporting::files::SimpleFile;
+use pipelex_common::tools::{
+    fixture::{check_fixture, fixture},
+    native_concepts::NativeConcepts,
+};
+use taplo::parser;
+use taplo_common::{environment::Environment, util::Normalize};
+use tokio::io::AsyncWriteExt;
+
+impl<E: Environment> Taplo<E> {
+    pub async fn execute_fixture(&mut self, cmd: FixtureCommand) -> Result<(), anyhow::Error> {
+        let config = self.load_config(&cmd.general).await?;
+        let natives =
+            NativeConcepts::load(&self.env, config.native_concepts_path().as_deref()).await;
+
+        let cwd = self
+            .env
+            .cwd_normalized()
+            .ok_or_else(|| anyhow!("could not figure the current working directory"))?;
+        let path = if self.env.is_absolute(&cmd.bundle) {
+            cmd.bundle.normalize()
+        } else {
+            cwd.join(&cmd.bundle).normalize()
+        };
+
+        let source = String::from_utf8(self.env.read_file(&path).await?)?;
+        let p = parser::parse(&source);
+        if !p.errors.is_empty() {
+            self.print_parse_errors(
+                &SimpleFile::new(&*path.to_string_lossy(), source.as_str()),
+                &p.errors,
+            )
+            .await?;
+            return Err(anyhow!("the bundle has syntax errors"));
+        }
+        let document = p.into_dom();
+        let siblings = self.bundle_sibling_documents(&path).await;
+
+        let Some(fixture_path) = cmd.validate else {
+            let fixture = fixture(&document, &siblings, &cmd.pipe, &natives)?;
+            let mut stdout = self.env.stdout();
+            stdout
+                .write_all(format!("{}\n", serde_json::to_string_pretty(&fixture)?).as_bytes())
+                .await?;
+            stdout.flush().await?;
+            return Ok(());
+        };
+
+        let fixture_path = if self.env.is_absolute(&fixture_path) {
+            fixture_path.normalize()
+        } else {
+            cwd.join(&fixture_path).normalize()
+        };
+        let fixture = serde_json::from_slice(&self.env.read_file(&fixture_path).await?)
+            .map_err(|err| anyhow!("the fixture is not valid JSON: {err}"))?;
+        let errors = check_fixture(&document, &siblings, &cmd.pipe, &natives, &fixture)?;
+        if errors.is_empty() {
+            tracing::info!(path = ?fixture_path, pipe = %cmd.pipe, "the fixture matches");
+            return Ok(());
+        }
+        for error in &errors {
+            tracing::error!(path = ?fixture_path, pointer = %error.path, "{}", error.message);
+        }
+        Err(anyhow!(
+            "the fixture does not match the inputs of pipe `{}`",
+            cmd.pipe
+        ))
+    }
+}
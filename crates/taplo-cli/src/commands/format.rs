@@ -81,7 +81,7 @@ impl<E: Environment> Taplo<E> {
     }
 
     #[cfg(target_arch = "wasm32")]
-    async fn print_diff(
+    pub(crate) async fn print_diff(
         &self,
         _path: impl AsRef<Path>,
         _original: &str,
@@ -92,7 +92,7 @@ impl<E: Environment> Taplo<E> {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    async fn print_diff(
+    pub(crate) async fn print_diff(
         &self,
         path: impl AsRef<Path>,
         original: &str,
@@ -3,16 +3,34 @@ use std::path::{Path, PathBuf};
 use crate::{args::LintCommand, Taplo};
 use anyhow::{anyhow, Context};
 use codespan_reporting::files::SimpleFile;
+use pipelex_common::tools::{
+    bundle::BundleIndex,
+    diagnostic::Severity,
+    fixes::{apply_fixes, deprecation_fixes, schema_fixes, Fix},
+    models::ModelDeck,
+    native_concepts::NativeConcepts,
+    rules::{check_bundle, unknown_rule_ids, RuleViolation},
+    suppression::Suppressions,
+};
 use serde_json::json;
-use taplo::parser;
+use taplo::{dom::Node, parser};
 use taplo_common::{
     config::{Config, SchemaOptions},
     environment::Environment,
-    schema::associations::{AssociationRule, SchemaAssociation, DEFAULT_CATALOGS},
+    schema::{
+        associations::{AssociationRule, SchemaAssociation, DEFAULT_CATALOGS},
+        builtins::{is_mthds_schema_url, MthdsSchemaVersion, MTHDS_VERSION},
+        NodeValidationError,
+    },
+    util::Normalize,
 };
 use tokio::io::AsyncReadExt;
 use url::Url;
 
+/// How many times `--fix` re-lints a file: applying a fix can let a later
+/// stage run and report fixes of its own.
+const MAX_FIX_PASSES: usize = 8;
+
 impl<E: Environment> Taplo<E> {
     pub async fn execute_lint(&mut self, cmd: LintCommand) -> Result<(), anyhow::Error> {
         self.schemas()?
@@ -20,6 +38,30 @@ impl<E: Environment> Taplo<E> {
             .set_cache_path(cmd.general.cache_path.clone());
 
         let config = self.load_config(&cmd.general).await?;
+        warn_unknown_lint_rules(&config);
+        let pinned = match &cmd.mthds_version {
+            Some(version) => Some(MthdsSchemaVersion::find(version).ok_or_else(|| {
+                anyhow!(
+                    "unknown MTHDS version `{version}`, expected one of: {}",
+                    MthdsSchemaVersion::known_versions()
+                )
+            })?),
+            None => None,
+        };
+        if let Some(version) =
+            pinned.or_else(|| config.mthds_version().and_then(MthdsSchemaVersion::find))
+        {
+            if version.deprecated {
+                tracing::warn!(
+                    version = version.version,
+                    latest = MTHDS_VERSION,
+                    "linting against a deprecated MTHDS version"
+                );
+            }
+        }
+        self.models = ModelDeck::load(&self.env, &config.model_deck_paths()).await;
+        self.natives =
+            NativeConcepts::load(&self.env, config.native_concepts_path().as_deref()).await;
 
         if !cmd.no_schema {
             if let Some(schema_url) = cmd.schema.clone() {
@@ -41,6 +83,18 @@ impl<E: Environment> Taplo<E> {
                 }
                 self.schemas()?.associations().add_from_config(&config);
 
+                if let Some(version) = pinned {
+                    self.schemas()?.associations().add(
+                        AssociationRule::regex(r".*\.mthds$")?,
+                        SchemaAssociation {
+                            meta: json!({"source": "command-line"}),
+                            url: version.url.parse()?,
+                            priority: 999,
+                            fallback_urls: vec![],
+                        },
+                    );
+                }
+
                 for catalog in &cmd.schema_catalog {
                     self.schemas_with_http()?
                         .associations()
@@ -62,21 +116,25 @@ impl<E: Environment> Taplo<E> {
         }
 
         if matches!(cmd.files.first().map(|it| it.as_str()), Some("-")) {
-            self.lint_stdin(cmd).await
+            self.lint_stdin(cmd, &config).await
         } else {
             self.lint_files(cmd).await
         }
     }
 
     #[tracing::instrument(skip_all)]
-    async fn lint_stdin(&mut self, _cmd: LintCommand) -> Result<(), anyhow::Error> {
+    async fn lint_stdin(&mut self, cmd: LintCommand, config: &Config) -> Result<(), anyhow::Error> {
+        if cmd.fix {
+            return Err(anyhow!("`--fix` cannot be used with the standard input"));
+        }
+
         let mut source = String::new();
         self.env.stdin().read_to_string(&mut source).await?;
         let cwd = self
             .env
             .cwd_normalized()
             .unwrap_or_else(|| PathBuf::from("."));
-        self.lint_source("-", &source, &cwd).await
+        self.lint_source(config, "-", &source, &cwd).await
     }
 
     #[tracing::instrument(skip_all)]
@@ -95,7 +153,7 @@ impl<E: Environment> Taplo<E> {
         let mut result = Ok(());
 
         for file in files {
-            if let Err(error) = self.lint_file(&file, &cwd).await {
+            if let Err(error) = self.lint_file(&config, &file, &cwd, cmd.fix).await {
                 tracing::error!(%error, path = ?file, "invalid file");
                 result = Err(anyhow!("some files were not valid"));
             }
@@ -104,15 +162,104 @@ impl<E: Environment> Taplo<E> {
         result
     }
 
-    async fn lint_file(&mut self, file: &Path, cwd: &Path) -> Result<(), anyhow::Error> {
+    async fn lint_file(
+        &mut self,
+        config: &Config,
+        file: &Path,
+        cwd: &Path,
+        fix: bool,
+    ) -> Result<(), anyhow::Error> {
         let source = self.env.read_file(file).await?;
-        let source = String::from_utf8(source)?;
-        self.lint_source(&file.to_string_lossy(), &source, cwd)
+        let mut source = String::from_utf8(source)?;
+        if fix {
+            source = self.fix_file(config, file, source).await?;
+        }
+        self.lint_source(config, &file.to_string_lossy(), &source, cwd)
             .await
     }
 
+    /// Apply the safe fixes of `source` and write the result back to `file`.
+    /// Returns the fixed source.
+    async fn fix_file(
+        &mut self,
+        config: &Config,
+        file: &Path,
+        source: String,
+    ) -> Result<String, anyhow::Error> {
+        let file_path = file.to_string_lossy();
+        let mut fixed = source.clone();
+        let mut applied = 0;
+
+        for _ in 0..MAX_FIX_PASSES {
+            let parse = parser::parse(&fixed);
+            if !parse.errors.is_empty() {
+                break;
+            }
+            let dom = parse.into_dom();
+            if dom.validate().is_err() {
+                break;
+            }
+
+            let fixes = self.collect_fixes(config, &file_path, &dom).await?;
+            let (next, count) = apply_fixes(&dom, fixes.iter().filter(|fix| fix.safe))
+                .map_err(|err| anyhow!("failed to apply fixes: {err}"))?;
+            if count == 0 {
+                break;
+            }
+            fixed = next;
+            applied += count;
+        }
+
+        if fixed != source {
+            self.env.write_file(file, fixed.as_bytes()).await?;
+            tracing::info!(path = ?file, fixes = applied, "applied fixes");
+        }
+
+        Ok(fixed)
+    }
+
+    /// The fixes of the first stage that reports errors, or else of the
+    /// schema's deprecations and the rules, mirroring
+    /// [`lint_source`](Self::lint_source) without printing.
+    async fn collect_fixes(
+        &mut self,
+        config: &Config,
+        file_path: &str,
+        dom: &Node,
+    ) -> Result<Vec<Fix>, anyhow::Error> {
+        let mut fixes = Vec::new();
+        let mut validated_as_mthds = false;
+        if config.is_schema_enabled(Path::new(file_path)) {
+            if let Some((schema_url, errors)) = self.validate_schema(file_path, dom).await? {
+                if !errors.is_empty() {
+                    return Ok(errors.iter().flat_map(schema_fixes).collect());
+                }
+                fixes.extend(
+                    self.schemas()?
+                        .deprecations(&schema_url, dom)
+                        .await?
+                        .iter()
+                        .flat_map(|deprecation| deprecation_fixes(dom, deprecation)),
+                );
+                validated_as_mthds = is_mthds_schema_url(schema_url.as_str());
+            }
+        }
+
+        if validated_as_mthds || is_mthds_path(file_path) {
+            fixes.extend(
+                self.rule_violations(config, file_path, dom)
+                    .await
+                    .into_iter()
+                    .flat_map(|violation| violation.fixes),
+            );
+        }
+
+        Ok(fixes)
+    }
+
     async fn lint_source(
         &mut self,
+        config: &Config,
         file_path: &str,
         source: &str,
         cwd: &Path,
@@ -144,66 +291,224 @@ impl<E: Environment> Taplo<E> {
             return Err(anyhow!("semantic errors found"));
         }
 
-        let config = self.config.as_ref().unwrap();
-
-        if !config.is_schema_enabled(Path::new(file_path)) {
+        let mut validated_as_mthds = false;
+        if config.is_schema_enabled(Path::new(file_path)) {
+            validated_as_mthds = self.lint_schema(file_path, source, &dom, cwd).await?;
+        } else {
             tracing::debug!("schema validation disabled for config file");
-            return Ok(());
         }
 
+        if validated_as_mthds || is_mthds_path(file_path) {
+            self.lint_mthds_rules(config, file_path, source, &dom, cwd)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Validate `dom` against its associated schema, if any. Returns whether
+    /// the schema was one of the builtin MTHDS schemas.
+    async fn lint_schema(
+        &mut self,
+        file_path: &str,
+        source: &str,
+        dom: &Node,
+        cwd: &Path,
+    ) -> Result<bool, anyhow::Error> {
+        let Some((schema_url, errors)) = self.validate_schema(file_path, dom).await? else {
+            return Ok(false);
+        };
+
+        if !errors.is_empty() {
+            if !self.compact {
+                self.print_schema_errors(&SimpleFile::new(file_path, source), &errors)
+                    .await?;
+            } else {
+                self.print_schema_errors_compact(file_path, source, &errors, cwd)
+                    .await?;
+            }
+
+            return Err(anyhow!("schema validation failed"));
+        }
+
+        let deprecations = self.schemas()?.deprecations(&schema_url, dom).await?;
+        if !deprecations.is_empty() {
+            if !self.compact {
+                self.print_schema_deprecations(
+                    &SimpleFile::new(file_path, source),
+                    dom,
+                    &deprecations,
+                )
+                .await?;
+            } else {
+                self.print_schema_deprecations_compact(file_path, source, &deprecations, cwd)
+                    .await?;
+            }
+        }
+
+        Ok(is_mthds_schema_url(schema_url.as_str()))
+    }
+
+    /// Validate `dom` against its associated schema. Returns the URL of the
+    /// schema used and the errors, or `None` if no schema is associated.
+    async fn validate_schema(
+        &mut self,
+        file_path: &str,
+        dom: &Node,
+    ) -> Result<Option<(Url, Vec<NodeValidationError>)>, anyhow::Error> {
         let file_uri: Url = format!("file://{file_path}").parse().unwrap();
 
         self.schemas()?
             .associations()
-            .add_from_document(&file_uri, &dom);
+            .add_from_document(&file_uri, dom);
+
+        let Some(schema_association) = self.schemas()?.associations().association_for(&file_uri)
+        else {
+            return Ok(None);
+        };
 
-        if let Some(schema_association) = self.schemas()?.associations().association_for(&file_uri)
+        if url_needs_http(&schema_association.url)
+            || schema_association.fallback_urls.iter().any(url_needs_http)
         {
-            if url_needs_http(&schema_association.url)
-                || schema_association.fallback_urls.iter().any(url_needs_http)
+            // Ensure HTTP client is ready before resolve/validate below
+            self.schemas_with_http()?;
+        }
+        tracing::debug!(
+            schema.url = %schema_association.url,
+            schema.name = schema_association.meta["name"].as_str().unwrap_or(""),
+            schema.source = schema_association.meta["source"].as_str().unwrap_or(""),
+            "using schema"
+        );
+
+        let schema_url = if schema_association.fallback_urls.is_empty() {
+            schema_association.url.clone()
+        } else {
+            match self
+                .schemas()?
+                .resolve_association(&schema_association)
+                .await
             {
-                // Ensure HTTP client is ready before resolve/validate below
-                self.schemas_with_http()?;
+                Ok((url, _)) => url,
+                Err(error) => {
+                    return Err(error.context("schema waterfall resolution failed"));
+                }
             }
-            tracing::debug!(
-                schema.url = %schema_association.url,
-                schema.name = schema_association.meta["name"].as_str().unwrap_or(""),
-                schema.source = schema_association.meta["source"].as_str().unwrap_or(""),
-                "using schema"
-            );
+        };
 
-            let schema_url = if schema_association.fallback_urls.is_empty() {
-                schema_association.url.clone()
-            } else {
-                match self
-                    .schemas()?
-                    .resolve_association(&schema_association)
-                    .await
-                {
-                    Ok((url, _)) => url,
-                    Err(error) => {
-                        return Err(error.context("schema waterfall resolution failed"));
-                    }
-                }
-            };
+        let errors = self.schemas()?.validate_root(&schema_url, dom).await?;
+        Ok(Some((schema_url, errors)))
+    }
 
-            let errors = self.schemas()?.validate_root(&schema_url, &dom).await?;
+    /// Run the MTHDS lint rules over a schema-valid document, resolving
+    /// references against the other `.mthds` files of its bundle directory.
+    /// Only error-severity violations fail the file.
+    async fn lint_mthds_rules(
+        &mut self,
+        config: &Config,
+        file_path: &str,
+        source: &str,
+        dom: &Node,
+        cwd: &Path,
+    ) -> Result<(), anyhow::Error> {
+        let violations = self.rule_violations(config, file_path, dom).await;
+        if violations.is_empty() {
+            return Ok(());
+        }
 
-            if !errors.is_empty() {
-                if !self.compact {
-                    self.print_schema_errors(&SimpleFile::new(file_path, source), &errors)
-                        .await?;
-                } else {
-                    self.print_schema_errors_compact(file_path, source, &errors, cwd)
-                        .await?;
-                }
+        if !self.compact {
+            self.print_rule_violations(&SimpleFile::new(file_path, source), &violations)
+                .await?;
+        } else {
+            self.print_rule_violations_compact(file_path, source, &violations, cwd)
+                .await?;
+        }
 
-                return Err(anyhow!("schema validation failed"));
-            }
+        if violations.iter().any(|v| v.severity == Severity::Error) {
+            return Err(anyhow!("lint rule violations found"));
         }
 
         Ok(())
     }
+
+    /// The rule violations of `dom`, after its suppression comments.
+    async fn rule_violations(
+        &self,
+        config: &Config,
+        file_path: &str,
+        dom: &Node,
+    ) -> Vec<RuleViolation> {
+        let options = config.lint_options(Path::new(file_path));
+        let siblings = if file_path == "-" {
+            Vec::new()
+        } else {
+            self.bundle_siblings(Path::new(file_path)).await
+        };
+
+        Suppressions::from_dom(dom).apply(
+            check_bundle(
+                &BundleIndex::from_dom(dom),
+                &siblings,
+                &self.natives,
+                &self.models,
+                &options,
+            ),
+            &options,
+        )
+    }
+
+    /// Index the other `.mthds` files in the directory of `file`.
+    async fn bundle_siblings(&self, file: &Path) -> Vec<BundleIndex> {
+        self.bundle_sibling_documents(file)
+            .await
+            .iter()
+            .map(BundleIndex::from_dom)
+            .collect()
+    }
+
+    /// Parse the other `.mthds` files in the directory of `file`.
+    pub(crate) async fn bundle_sibling_documents(&self, file: &Path) -> Vec<Node> {
+        let Some(dir) = file.parent() else {
+            return Vec::new();
+        };
+        let pattern = dir.join("*.mthds");
+        let paths = self
+            .env
+            .glob_files_normalized(&pattern.to_string_lossy())
+            .unwrap_or_default();
+
+        let file = file.to_path_buf().normalize();
+        let mut siblings = Vec::new();
+        for path in paths {
+            if path == file {
+                continue;
+            }
+            let Ok(bytes) = self.env.read_file(&path).await else {
+                continue;
+            };
+            let Ok(source) = String::from_utf8(bytes) else {
+                continue;
+            };
+            siblings.push(parser::parse(&source).into_dom());
+        }
+        siblings
+    }
+}
+
+fn is_mthds_path(file_path: &str) -> bool {
+    Path::new(file_path)
+        .extension()
+        .is_some_and(|ext| ext == "mthds")
+}
+
+fn warn_unknown_lint_rules(config: &Config) {
+    let options = std::iter::once(&config.global_options)
+        .chain(config.rule.iter().map(|rule| &rule.options))
+        .filter_map(|options| options.lint.as_ref());
+    for options in options {
+        for id in unknown_rule_ids(options) {
+            tracing::warn!(rule = id, "unknown lint rule in configuration");
+        }
+    }
 }
 
 fn url_needs_http(url: &Url) -> bool {
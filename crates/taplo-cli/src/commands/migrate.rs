@@ -0,0 +1,100 @@
+use crate::{args::MigrateCommand, Taplo};
+use anyhow::anyhow;
+use codespan_reporting::files::SimpleFile;
+use pipelex_common::tools::{bundle::BundleIndex, migrate::migrate};
+use taplo::parser;
+use taplo_common::{environment::Environment, schema::builtins::MTHDS_VERSION};
+use tokio::io::AsyncWriteExt;
+
+impl<E: Environment> Taplo<E> {
+    #[tracing::instrument(skip_all)]
+    pub async fn execute_migrate(&mut self, cmd: MigrateCommand) -> Result<(), anyhow::Error> {
+        let config = self.load_config(&cmd.general).await?;
+
+        let cwd = self
+            .env
+            .cwd_normalized()
+            .ok_or_else(|| anyhow!("could not figure the current working directory"))?;
+
+        // Only bundles are migrated, so look for nothing else by default.
+        let patterns = if cmd.files.is_empty() && config.include.is_none() {
+            vec!["**/*.mthds".to_string()]
+        } else {
+            cmd.files
+        };
+        let files = self
+            .collect_files(&cwd, &config, patterns.into_iter())
+            .await?;
+
+        let to = cmd.to.as_deref().unwrap_or(MTHDS_VERSION);
+        let mut result = Ok(());
+
+        for path in files
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "mthds"))
+        {
+            let f = self.env.read_file(&path).await?;
+            let source = String::from_utf8_lossy(&f).into_owned();
+
+            let p = parser::parse(&source);
+            if !p.errors.is_empty() {
+                self.print_parse_errors(
+                    &SimpleFile::new(&*path.to_string_lossy(), source.as_str()),
+                    &p.errors,
+                )
+                .await?;
+                result = Err(anyhow!("some files were not migrated due to syntax errors"));
+                continue;
+            }
+
+            let from = cmd
+                .from
+                .clone()
+                .or_else(|| {
+                    BundleIndex::from_dom(&p.into_dom())
+                        .mthds_version
+                        .map(|declaration| declaration.version)
+                })
+                .or_else(|| config.mthds_version().map(ToString::to_string))
+                .unwrap_or_else(|| MTHDS_VERSION.to_string());
+
+            let migrated = match migrate(&source, &from, to) {
+                Ok(migrated) => migrated,
+                Err(error) => {
+                    tracing::error!(?path, %error, "the file could not be migrated");
+                    result = Err(anyhow!("some files could not be migrated"));
+                    continue;
+                }
+            };
+
+            for conflict in &migrated.conflicts {
+                tracing::warn!(?path, "{conflict}");
+            }
+
+            if source == migrated.source {
+                continue;
+            }
+
+            if cmd.diff {
+                if let Err(e) = self.print_diff(&path, &source, &migrated.source).await {
+                    self.env
+                        .stderr()
+                        .write_all(format!("Failed to write diff to stdout: {e:?}").as_bytes())
+                        .await?;
+                }
+            }
+
+            if cmd.check {
+                tracing::error!(?path, %from, to, "the file needs migrating");
+                result = Err(anyhow!("some files need migrating to MTHDS `{to}`"));
+            } else {
+                self.env
+                    .write_file(&path, migrated.source.as_bytes())
+                    .await?;
+                tracing::info!(?path, %from, to, changes = migrated.changes, "migrated");
+            }
+        }
+
+        result
+    }
+}
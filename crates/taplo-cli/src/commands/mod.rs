@@ -5,13 +5,25 @@ use crate::{
     Taplo,
 };
 
+#[cfg(feature = "lint")]
+mod codegen;
+#[cfg(feature = "lint")]
+mod concept_schema;
 mod config;
+#[cfg(feature = "lint")]
+mod doc;
+#[cfg(feature = "lint")]
+mod fixture;
 mod format;
 #[cfg(feature = "lint")]
 mod lint;
 #[cfg(feature = "lsp")]
 mod lsp;
+#[cfg(feature = "lint")]
+mod migrate;
 mod queries;
+#[cfg(feature = "lint")]
+mod render_prompt;
 
 #[cfg(feature = "toml-test")]
 mod toml_test;
@@ -41,13 +53,25 @@ impl<E: Environment> Taplo<E> {
                 );
                 Ok(())
             }
+            #[cfg(feature = "lint")]
+            TaploCommand::Codegen(cmd) => self.execute_codegen(cmd).await,
+            #[cfg(feature = "lint")]
+            TaploCommand::ConceptSchema(cmd) => self.execute_concept_schema(cmd).await,
             TaploCommand::Config { cmd } => self.execute_config(cmd).await,
+            #[cfg(feature = "lint")]
+            TaploCommand::Doc(cmd) => self.execute_doc(cmd).await,
+            #[cfg(feature = "lint")]
+            TaploCommand::Fixture(cmd) => self.execute_fixture(cmd).await,
             TaploCommand::Format(fmt) => self.execute_format(fmt).await,
             TaploCommand::Get(cmd) => self.execute_get(cmd).await,
             #[cfg(feature = "lint")]
             TaploCommand::Lint(cmd) => self.execute_lint(cmd).await,
+            #[cfg(feature = "lint")]
+            TaploCommand::Migrate(cmd) => self.execute_migrate(cmd).await,
             #[cfg(feature = "lsp")]
             TaploCommand::Lsp { cmd } => self.execute_lsp(cmd).await,
+            #[cfg(feature = "lint")]
+            TaploCommand::RenderPrompt(cmd) => self.execute_render_prompt(cmd).await,
             #[cfg(feature = "toml-test")]
             TaploCommand::TomlTest {} => self.execute_toml_test().await,
         }
@@ -0,0 +1,101 @@
+use crate::{
+    args::{PromptTagStyle, PromptTextFormat, RenderPromptCommand},
+    Taplo,
+};
+use anyhow::anyhow;
+use codespan_reporting::files::SimpleFile;
+use pipelex_common::tools::{
+    jinja::render::{TagStyle, TextFormat},
+    native_concepts::NativeConcepts,
+    render_prompt::{render_prompts, RenderOptions},
+};
+use taplo::parser;
+use taplo_common::{environment::Environment, util::Normalize};
+use tokio::io::AsyncWriteExt;
+
+impl<E: Environment> Taplo<E> {
+    pub async fn execute_render_prompt(
+        &mut self,
+        cmd: RenderPromptCommand,
+    ) -> Result<(), anyhow::Error> {
+        let config = self.load_config(&cmd.general).await?;
+        let natives =
+            NativeConcepts::load(&self.env, config.native_concepts_path().as_deref()).await;
+
+        let cwd = self
+            .env
+            .cwd_normalized()
+            .ok_or_else(|| anyhow!("could not figure the current working directory"))?;
+        let path = if self.env.is_absolute(&cmd.bundle) {
+            cmd.bundle.normalize()
+        } else {
+            cwd.join(&cmd.bundle).normalize()
+        };
+
+        let source = String::from_utf8(self.env.read_file(&path).await?)?;
+        let file_name = path.to_string_lossy();
+        let file = SimpleFile::new(&*file_name, source.as_str());
+        let p = parser::parse(&source);
+        if !p.errors.is_empty() {
+            self.print_parse_errors(&file, &p.errors).await?;
+            return Err(anyhow!("the bundle has syntax errors"));
+        }
+        let document = p.into_dom();
+        let siblings = self.bundle_sibling_documents(&path).await;
+
+        let inputs = match cmd.inputs {
+            Some(inputs_path) => {
+                let inputs_path = if self.env.is_absolute(&inputs_path) {
+                    inputs_path.normalize()
+                } else {
+                    cwd.join(&inputs_path).normalize()
+                };
+                let inputs: serde_json::Value =
+                    serde_json::from_slice(&self.env.read_file(&inputs_path).await?)
+                        .map_err(|err| anyhow!("the inputs are not valid JSON: {err}"))?;
+                Some(inputs)
+            }
+            None => None,
+        };
+        let options = RenderOptions {
+            inputs: inputs.as_ref(),
+            tag_style: cmd.tag_style.map(|style| match style {
+                PromptTagStyle::NoTag => TagStyle::NoTag,
+                PromptTagStyle::Ticks => TagStyle::Ticks,
+                PromptTagStyle::Xml => TagStyle::Xml,
+                PromptTagStyle::SquareBrackets => TagStyle::SquareBrackets,
+            }),
+            text_format: cmd.text_format.map(|format| match format {
+                PromptTextFormat::Plain => TextFormat::Plain,
+                PromptTextFormat::Markdown => TextFormat::Markdown,
+                PromptTextFormat::Html => TextFormat::Html,
+                PromptTextFormat::Json => TextFormat::Json,
+            }),
+        };
+        let prompts = render_prompts(&document, &siblings, &cmd.pipe, &natives, options)?;
+
+        let mut stdout = self.env.stdout();
+        for (index, prompt) in prompts.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "\n" };
+            stdout
+                .write_all(
+                    format!("{separator}--- {} ---\n{}\n", prompt.field, prompt.text).as_bytes(),
+                )
+                .await?;
+        }
+        stdout.flush().await?;
+
+        let mut error_count = 0;
+        for prompt in &prompts {
+            self.print_render_errors(&file, prompt).await?;
+            error_count += prompt.errors.len();
+        }
+        if error_count > 0 {
+            return Err(anyhow!(
+                "rendering the prompts of pipe `{}` failed with {error_count} error(s)",
+                cmd.pipe
+            ));
+        }
+        Ok(())
+    }
+}
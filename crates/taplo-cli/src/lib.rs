@@ -22,6 +22,12 @@ pub struct Taplo<E: Environment> {
     compact: bool,
     #[cfg(feature = "lint")]
     schemas: Option<Schemas<E>>,
+    /// The model deck of the configuration, loaded by `lint`.
+    #[cfg(feature = "lint")]
+    models: pipelex_common::tools::models::ModelDeck,
+    /// The native concept registry of the configuration, loaded by `lint`.
+    #[cfg(feature = "lint")]
+    natives: pipelex_common::tools::native_concepts::NativeConcepts,
     config: Option<Arc<Config>>,
 }
 
@@ -35,6 +41,10 @@ impl<E: Environment> Taplo<E> {
         Self {
             #[cfg(feature = "lint")]
             schemas: None,
+            #[cfg(feature = "lint")]
+            models: Default::default(),
+            #[cfg(feature = "lint")]
+            natives: Default::default(),
             colors: env.atty_stderr(),
             compact: false,
             config: None,
@@ -9,6 +9,11 @@ use codespan_reporting::{
 };
 use itertools::Itertools;
 #[cfg(feature = "lint")]
+use pipelex_common::tools::{
+    diagnostic::Severity as RuleSeverity, fixes::deprecation_fixes, render_prompt::RenderedPrompt,
+    rules::RuleViolation,
+};
+#[cfg(feature = "lint")]
 use std::collections::HashSet;
 use std::ops::Range;
 #[cfg(feature = "lint")]
@@ -16,7 +21,7 @@ use std::path::Path;
 use taplo::{dom, parser, rowan::TextRange};
 use taplo_common::environment::Environment;
 #[cfg(feature = "lint")]
-use taplo_common::schema::NodeValidationError;
+use taplo_common::schema::{NodeValidationError, SchemaDeprecation};
 use tokio::io::AsyncWriteExt;
 
 impl<E: Environment> Taplo<E> {
@@ -31,6 +36,7 @@ impl<E: Environment> Taplo<E> {
 
         for error in errors.iter().unique_by(|e| e.range) {
             let diag = Diagnostic::error()
+                .with_code(parser::Error::CODE)
                 .with_message("invalid TOML")
                 .with_labels(Vec::from([
                     Label::primary((), std_range(error.range)).with_message(&error.message)
@@ -63,6 +69,7 @@ impl<E: Environment> Taplo<E> {
         for error in errors {
             let diag = match &error {
                 dom::Error::ConflictingKeys { key, other } => Diagnostic::error()
+                    .with_code(error.code())
                     .with_message(error.to_string())
                     .with_labels(Vec::from([
                         Label::primary((), std_range(key.text_ranges().next().unwrap()))
@@ -74,6 +81,7 @@ impl<E: Environment> Taplo<E> {
                     not_array_of_tables,
                     required_by,
                 } => Diagnostic::error()
+                    .with_code(error.code())
                     .with_message(error.to_string())
                     .with_labels(Vec::from([
                         Label::primary(
@@ -88,6 +96,7 @@ impl<E: Environment> Taplo<E> {
                     not_table,
                     required_by,
                 } => Diagnostic::error()
+                    .with_code(error.code())
                     .with_message(error.to_string())
                     .with_labels(Vec::from([
                         Label::primary((), std_range(not_table.text_ranges().next().unwrap()))
@@ -96,6 +105,7 @@ impl<E: Environment> Taplo<E> {
                             .with_message("required by this key"),
                     ])),
                 dom::Error::InvalidEscapeSequence { string } => Diagnostic::error()
+                    .with_code(error.code())
                     .with_message(error.to_string())
                     .with_labels(Vec::from([Label::primary(
                         (),
@@ -132,6 +142,7 @@ impl<E: Environment> Taplo<E> {
             let msg = err.display_message();
             for text_range in err.text_ranges() {
                 let diag = Diagnostic::error()
+                    .with_code(err.code())
                     .with_message(&msg)
                     .with_labels(Vec::from([
                         Label::primary((), std_range(text_range)).with_message(&msg)
@@ -150,6 +161,121 @@ impl<E: Environment> Taplo<E> {
 
         Ok(())
     }
+
+    #[cfg(feature = "lint")]
+    pub(crate) async fn print_schema_deprecations(
+        &self,
+        file: &SimpleFile<&str, &str>,
+        dom: &dom::Node,
+        deprecations: &[SchemaDeprecation],
+    ) -> Result<(), anyhow::Error> {
+        let config = codespan_reporting::term::Config::default();
+
+        let mut out_diag = Vec::<u8>::new();
+        for deprecation in deprecations {
+            let mut diag = Diagnostic::warning()
+                .with_code(SchemaDeprecation::CODE)
+                .with_message(deprecation.message())
+                .with_notes(
+                    deprecation_fixes(dom, deprecation)
+                        .iter()
+                        .map(|fix| format!("help: {} (applied by `--fix`)", fix.title))
+                        .collect(),
+                );
+            if let Some(range) = deprecation.text_range() {
+                diag = diag.with_labels(Vec::from([
+                    Label::primary((), std_range(range)).with_message("deprecated")
+                ]));
+            }
+
+            if self.colors {
+                term::emit(&mut Ansi::new(&mut out_diag), &config, file, &diag)?;
+            } else {
+                term::emit(&mut NoColor::new(&mut out_diag), &config, file, &diag)?;
+            };
+        }
+        let mut stderr = self.env.stderr();
+        stderr.write_all(&out_diag).await?;
+        stderr.flush().await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "lint")]
+    pub(crate) async fn print_rule_violations(
+        &self,
+        file: &SimpleFile<&str, &str>,
+        violations: &[RuleViolation],
+    ) -> Result<(), anyhow::Error> {
+        let config = codespan_reporting::term::Config::default();
+
+        let mut out_diag = Vec::<u8>::new();
+        for violation in violations {
+            let severity = match violation.severity {
+                RuleSeverity::Error => codespan_reporting::diagnostic::Severity::Error,
+                RuleSeverity::Warning => codespan_reporting::diagnostic::Severity::Warning,
+                RuleSeverity::Info => codespan_reporting::diagnostic::Severity::Note,
+            };
+            let diag = Diagnostic::new(severity)
+                .with_code(violation.rule.id)
+                .with_message(&violation.message)
+                .with_labels(Vec::from([Label::primary((), std_range(violation.range))
+                    .with_message(violation.rule.summary)]))
+                .with_notes(
+                    violation
+                        .fixes
+                        .iter()
+                        .map(|fix| {
+                            let hint = if fix.safe {
+                                " (applied by `--fix`)"
+                            } else {
+                                ""
+                            };
+                            format!("help: {}{hint}", fix.title)
+                        })
+                        .collect(),
+                );
+
+            if self.colors {
+                term::emit(&mut Ansi::new(&mut out_diag), &config, file, &diag)?;
+            } else {
+                term::emit(&mut NoColor::new(&mut out_diag), &config, file, &diag)?;
+            };
+        }
+        let mut stderr = self.env.stderr();
+        stderr.write_all(&out_diag).await?;
+        stderr.flush().await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "lint")]
+    pub(crate) async fn print_render_errors(
+        &self,
+        file: &SimpleFile<&str, &str>,
+        prompt: &RenderedPrompt,
+    ) -> Result<(), anyhow::Error> {
+        let config = codespan_reporting::term::Config::default();
+
+        let mut out_diag = Vec::<u8>::new();
+        for error in &prompt.errors {
+            let diag = Diagnostic::error()
+                .with_message(&error.message)
+                .with_labels(Vec::from([Label::primary((), std_range(error.range))
+                    .with_message(format!("in `{}`", prompt.field))]));
+
+            if self.colors {
+                term::emit(&mut Ansi::new(&mut out_diag), &config, file, &diag)?;
+            } else {
+                term::emit(&mut NoColor::new(&mut out_diag), &config, file, &diag)?;
+            };
+        }
+        let mut stderr = self.env.stderr();
+        stderr.write_all(&out_diag).await?;
+        stderr.flush().await?;
+
+        Ok(())
+    }
 }
 
 fn std_range(range: TextRange) -> Range<usize> {
@@ -189,7 +315,7 @@ fn relative_path(file_path: &str, cwd: &Path) -> String {
 
 #[cfg(feature = "lint")]
 impl<E: Environment> Taplo<E> {
-    /// Compact one-line format for parse errors: `file:line:col: error[syntax]: message`
+    /// Compact one-line format for parse errors: `file:line:col: error[syntax/parse-error]: message`
     pub(crate) async fn print_parse_errors_compact(
         &self,
         file_path: &str,
@@ -207,8 +333,12 @@ impl<E: Environment> Taplo<E> {
             let (line, col) = offset_to_line_col(source, start);
             out.extend_from_slice(
                 format!(
-                    "{}:{}:{}: error[syntax]: {}\n",
-                    rel, line, col, error.message
+                    "{}:{}:{}: error[{}]: {}\n",
+                    rel,
+                    line,
+                    col,
+                    parser::Error::CODE,
+                    error.message
                 )
                 .as_bytes(),
             );
@@ -224,7 +354,7 @@ impl<E: Environment> Taplo<E> {
         Ok(())
     }
 
-    /// Compact one-line format for semantic errors: `file:line:col: error[semantic]: message`
+    /// Compact one-line format for semantic errors: `file:line:col: error[semantic/conflicting-keys]: message`
     pub(crate) async fn print_semantic_errors_compact(
         &self,
         file_path: &str,
@@ -253,7 +383,15 @@ impl<E: Environment> Taplo<E> {
                 None => (1, 1),
             };
             out.extend_from_slice(
-                format!("{}:{}:{}: error[semantic]: {}\n", rel, line, col, error).as_bytes(),
+                format!(
+                    "{}:{}:{}: error[{}]: {}\n",
+                    rel,
+                    line,
+                    col,
+                    error.code(),
+                    error
+                )
+                .as_bytes(),
             );
         }
 
@@ -267,7 +405,7 @@ impl<E: Environment> Taplo<E> {
         Ok(())
     }
 
-    /// Compact one-line format for schema errors: `file:line:col: error[schema]: message (in pipe.name)`
+    /// Compact one-line format for schema errors: `file:line:col: error[schema/required]: message (in pipe.name)`
     /// Deduplicates errors with the same message and location.
     pub(crate) async fn print_schema_errors_compact(
         &self,
@@ -303,8 +441,13 @@ impl<E: Environment> Taplo<E> {
 
             out.extend_from_slice(
                 format!(
-                    "{}:{}:{}: error[schema]: {}{}\n",
-                    rel, line, col, msg, location_suffix
+                    "{}:{}:{}: error[{}]: {}{}\n",
+                    rel,
+                    line,
+                    col,
+                    err.code(),
+                    msg,
+                    location_suffix
                 )
                 .as_bytes(),
             );
@@ -319,4 +462,101 @@ impl<E: Environment> Taplo<E> {
         stderr.flush().await?;
         Ok(())
     }
+
+    /// Compact one-line format for schema deprecations:
+    /// `file:line:col: warning[schema/deprecated]: message (in pipe.name.key)`
+    pub(crate) async fn print_schema_deprecations_compact(
+        &self,
+        file_path: &str,
+        source: &str,
+        deprecations: &[SchemaDeprecation],
+        cwd: &Path,
+    ) -> Result<(), anyhow::Error> {
+        let rel = relative_path(file_path, cwd);
+        let mut out = Vec::<u8>::new();
+
+        for deprecation in deprecations {
+            let (line, col) = match deprecation.text_range() {
+                Some(r) => offset_to_line_col(source, u32::from(r.start()) as usize),
+                None => (1, 1),
+            };
+            let location_suffix = match deprecation.instance_location() {
+                Some(loc) => format!(" (in {})", loc),
+                None => String::new(),
+            };
+            out.extend_from_slice(
+                format!(
+                    "{}:{}:{}: warning[{}]: {}{}\n",
+                    rel,
+                    line,
+                    col,
+                    SchemaDeprecation::CODE,
+                    deprecation.message(),
+                    location_suffix
+                )
+                .as_bytes(),
+            );
+        }
+
+        if !deprecations.is_empty() {
+            out.extend_from_slice(
+                format!("Found {} warning(s) in {}\n", deprecations.len(), rel).as_bytes(),
+            );
+        }
+
+        let mut stderr = self.env.stderr();
+        stderr.write_all(&out).await?;
+        stderr.flush().await?;
+        Ok(())
+    }
+
+    /// Compact one-line format for MTHDS rule violations:
+    /// `file:line:col: warning[mthds/undefined-pipe]: message`
+    pub(crate) async fn print_rule_violations_compact(
+        &self,
+        file_path: &str,
+        source: &str,
+        violations: &[RuleViolation],
+        cwd: &Path,
+    ) -> Result<(), anyhow::Error> {
+        let rel = relative_path(file_path, cwd);
+        let mut out = Vec::<u8>::new();
+
+        for violation in violations {
+            let (line, col) =
+                offset_to_line_col(source, u32::from(violation.range.start()) as usize);
+            out.extend_from_slice(
+                format!(
+                    "{}:{}:{}: {}[{}]: {}\n",
+                    rel,
+                    line,
+                    col,
+                    violation.severity.as_str(),
+                    violation.rule.id,
+                    violation.message
+                )
+                .as_bytes(),
+            );
+        }
+
+        let counts = [
+            (RuleSeverity::Error, "error(s)"),
+            (RuleSeverity::Warning, "warning(s)"),
+            (RuleSeverity::Info, "info(s)"),
+        ]
+        .into_iter()
+        .filter_map(|(severity, noun)| {
+            let count = violations.iter().filter(|v| v.severity == severity).count();
+            (count > 0).then(|| format!("{count} {noun}"))
+        })
+        .join(", ");
+        if !counts.is_empty() {
+            out.extend_from_slice(format!("Found {} in {}\n", counts, rel).as_bytes());
+        }
+
+        let mut stderr = self.env.stderr();
+        stderr.write_all(&out).await?;
+        stderr.flush().await?;
+        Ok(())
+    }
 }
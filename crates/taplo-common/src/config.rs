@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     fmt::Debug,
     path::{Path, PathBuf},
 };
@@ -57,6 +58,16 @@ pub struct Config {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub plugins: Option<HashMap<String, Plugin>>,
+
+    /// The Pipelex model deck that `model` references are checked against.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub models: Option<ModelsOptions>,
+
+    /// MTHDS language options.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mthds: Option<MthdsOptions>,
 }
 
 impl Debug for Config {
@@ -66,6 +77,8 @@ impl Debug for Config {
             .field("exclude", &self.exclude)
             .field("rule", &self.rule)
             .field("global_options", &self.global_options)
+            .field("models", &self.models)
+            .field("mthds", &self.mthds)
             .finish()
     }
 }
@@ -90,6 +103,34 @@ impl Config {
 
         self.global_options.prepare(e, base)?;
 
+        let resolve = |path: &mut String| {
+            let expanded = expand_tilde(e, path);
+            *path = if e.is_absolute(Path::new(&expanded)) {
+                expanded
+            } else {
+                base.join(expanded)
+                    .normalize()
+                    .to_string_lossy()
+                    .into_owned()
+            };
+        };
+        if let Some(deck) = self.models.as_mut().and_then(|m| m.deck.as_mut()) {
+            deck.iter_mut().for_each(resolve);
+        }
+        if let Some(path) = self.mthds.as_mut().and_then(|m| m.native_concepts.as_mut()) {
+            resolve(path);
+        }
+        #[cfg(feature = "schema")]
+        if let Some(version) = self.mthds_version() {
+            use crate::schema::builtins::MthdsSchemaVersion;
+            if MthdsSchemaVersion::find(version).is_none() {
+                return Err(anyhow::anyhow!(
+                    "unknown MTHDS version `{version}` in `[mthds]`, expected one of: {}",
+                    MthdsSchemaVersion::known_versions()
+                ));
+            }
+        }
+
         Ok(())
     }
 
@@ -126,6 +167,58 @@ impl Config {
         }
     }
 
+    /// The files and directories of the model deck, made absolute by
+    /// [`Config::prepare`].
+    #[must_use]
+    pub fn model_deck_paths(&self) -> Vec<PathBuf> {
+        self.models
+            .as_ref()
+            .and_then(|models| models.deck.as_ref())
+            .map(|deck| deck.iter().map(PathBuf::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// The MTHDS version `[mthds] version` selects for the bundles that do
+    /// not declare one.
+    #[must_use]
+    pub fn mthds_version(&self) -> Option<&str> {
+        self.mthds
+            .as_ref()
+            .and_then(|mthds| mthds.version.as_deref())
+    }
+
+    /// The native concept registry file replacing the embedded one, made
+    /// absolute by [`Config::prepare`].
+    #[must_use]
+    pub fn native_concepts_path(&self) -> Option<PathBuf> {
+        self.mthds
+            .as_ref()
+            .and_then(|mthds| mthds.native_concepts.as_ref())
+            .map(PathBuf::from)
+    }
+
+    /// Resolve the lint rule levels for a file: the global `[lint.rules]`
+    /// table first, then every matching rule without `keys`, later entries
+    /// overriding earlier ones.
+    #[must_use]
+    pub fn lint_options(&self, path: &Path) -> LintOptions {
+        let mut options = LintOptions::default();
+
+        if let Some(opts) = &self.global_options.lint {
+            options.update(opts);
+        }
+
+        for rule in self.rules_for(path) {
+            if rule.keys.is_none() {
+                if let Some(rule_opts) = &rule.options.lint {
+                    options.update(rule_opts);
+                }
+            }
+        }
+
+        options
+    }
+
     pub fn format_scopes<'s>(
         &'s self,
         path: &'s Path,
@@ -236,6 +329,8 @@ pub struct Options {
     pub schema: Option<SchemaOptions>,
     /// Formatting options.
     pub formatting: Option<formatter::OptionsIncomplete>,
+    /// Lint rule options.
+    pub lint: Option<LintOptions>,
 }
 
 impl Options {
@@ -421,6 +516,74 @@ pub struct SchemaOptions {
     pub resolved_sources: Option<Vec<Url>>,
 }
 
+/// Options for the MTHDS lint rules.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LintOptions {
+    /// Per-rule levels keyed by rule id (e.g. `mthds/unused-concept`).
+    ///
+    /// Rules that are not listed keep their default severity.
+    pub rules: Option<BTreeMap<String, RuleLevel>>,
+}
+
+impl LintOptions {
+    /// Overlay `other` on top of these options, rule by rule.
+    pub fn update(&mut self, other: &LintOptions) {
+        if let Some(other_rules) = &other.rules {
+            self.rules
+                .get_or_insert_with(BTreeMap::new)
+                .extend(other_rules.iter().map(|(id, level)| (id.clone(), *level)));
+        }
+    }
+
+    /// The configured level for a rule, if any.
+    #[must_use]
+    pub fn rule_level(&self, id: &str) -> Option<RuleLevel> {
+        self.rules.as_ref().and_then(|rules| rules.get(id).copied())
+    }
+}
+
+/// The level a lint rule is reported at.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    /// The rule is disabled.
+    Off,
+    Error,
+    Warning,
+    Info,
+}
+
+/// Where to find the Pipelex model deck.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ModelsOptions {
+    /// Deck files, or directories searched recursively for `*.toml` files.
+    ///
+    /// Relative paths are relative to the configuration file. The files
+    /// declare model presets, aliases and waterfalls, and backend files
+    /// declare model handles; the `model` references of `.mthds` files are
+    /// checked, completed and described against them.
+    pub deck: Option<Vec<String>>,
+}
+
+/// MTHDS language options.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MthdsOptions {
+    /// The MTHDS version of the bundles that do not declare one, selecting
+    /// the embedded schema they are validated against. The latest by default.
+    pub version: Option<String>,
+
+    /// A native concept registry file replacing the one embedded with the
+    /// MTHDS schema.
+    ///
+    /// Relative to the configuration file. It lists the concepts every
+    /// bundle can reference without defining them, with their descriptions,
+    /// fields and refinements.
+    pub native_concepts: Option<String>,
+}
+
 /// A plugin to extend Taplo's capabilities.
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Plugin {
@@ -598,6 +761,7 @@ mod tests {
                 resolved_sources: None,
             }),
             formatting: None,
+            lint: None,
         };
 
         opts.prepare(&env, base).unwrap();
@@ -627,6 +791,7 @@ mod tests {
                 resolved_sources: None,
             }),
             formatting: None,
+            lint: None,
         };
 
         opts.prepare(&env, base).unwrap();
@@ -652,6 +817,7 @@ mod tests {
                 resolved_sources: None,
             }),
             formatting: None,
+            lint: None,
         };
 
         opts.prepare(&env, base).unwrap();
@@ -675,6 +841,7 @@ mod tests {
                 resolved_sources: None,
             }),
             formatting: None,
+            lint: None,
         };
 
         opts.prepare(&env, base).unwrap();
@@ -682,6 +849,100 @@ mod tests {
         assert_eq!(resolved[0].as_str(), "file:///absolute/schema.json");
     }
 
+    #[test]
+    fn lint_options_rules_override_global_levels() {
+        let env = MockEnv::empty();
+        let mut config: Config = serde_json::from_str(
+            r#"{
+                "lint": {
+                    "rules": {
+                        "mthds/unused-concept": "warning",
+                        "mthds/undefined-pipe": "error"
+                    }
+                },
+                "rule": [{
+                    "include": ["legacy/**"],
+                    "lint": { "rules": { "mthds/unused-concept": "off" } }
+                }]
+            }"#,
+        )
+        .unwrap();
+        config.prepare(&env, Path::new("/project")).unwrap();
+
+        let legacy = config.lint_options(Path::new("/project/legacy/old.mthds"));
+        assert_eq!(
+            legacy.rule_level("mthds/unused-concept"),
+            Some(RuleLevel::Off)
+        );
+        assert_eq!(
+            legacy.rule_level("mthds/undefined-pipe"),
+            Some(RuleLevel::Error)
+        );
+
+        let other = config.lint_options(Path::new("/project/src/new.mthds"));
+        assert_eq!(
+            other.rule_level("mthds/unused-concept"),
+            Some(RuleLevel::Warning)
+        );
+        assert_eq!(other.rule_level("mthds/undefined-concept"), None);
+    }
+
+    #[test]
+    fn model_deck_paths_are_relative_to_the_config() {
+        let env = MockEnv::with_home("/Users/alice");
+        let mut config: Config = serde_json::from_str(
+            r#"{ "models": { "deck": [".pipelex/inference", "~/decks/base.toml", "/opt/deck"] } }"#,
+        )
+        .unwrap();
+        config.prepare(&env, Path::new("/project")).unwrap();
+        assert_eq!(
+            config.model_deck_paths(),
+            [
+                PathBuf::from("/project/.pipelex/inference"),
+                PathBuf::from("/Users/alice/decks/base.toml"),
+                PathBuf::from("/opt/deck"),
+            ]
+        );
+    }
+
+    #[test]
+    fn native_concepts_path_is_relative_to_the_config() {
+        let env = MockEnv::empty();
+        let mut config: Config =
+            serde_json::from_str(r#"{ "mthds": { "native_concepts": "natives.toml" } }"#).unwrap();
+        config.prepare(&env, Path::new("/project")).unwrap();
+        assert_eq!(
+            config.native_concepts_path(),
+            Some(PathBuf::from("/project/natives.toml"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "schema")]
+    fn mthds_version_must_be_embedded() {
+        let env = MockEnv::empty();
+        let mut config: Config =
+            serde_json::from_str(r#"{ "mthds": { "version": "0.9" } }"#).unwrap();
+        config.prepare(&env, Path::new("/project")).unwrap();
+        assert_eq!(config.mthds_version(), Some("0.9"));
+
+        let mut config: Config =
+            serde_json::from_str(r#"{ "mthds": { "version": "7.0" } }"#).unwrap();
+        let error = config.prepare(&env, Path::new("/project")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown MTHDS version `7.0` in `[mthds]`, expected one of: 1.0, 0.9"
+        );
+    }
+
+    #[test]
+    fn lint_options_reject_unknown_levels() {
+        let result = serde_json::from_str::<Config>(
+            r#"{ "lint": { "rules": { "mthds/unused-concept": "loud" } } }"#,
+        );
+        assert!(result.is_err(), "unknown rule levels should be rejected");
+    }
+
     #[test]
     fn schema_options_deny_unknown_fields() {
         let input = r#"{ "enabled": true, "bogus_field": 42 }"#;
@@ -25,6 +25,7 @@ pub mod priority {
     pub const CONFIG: usize = 50;
     pub const CONFIG_RULE: usize = 51;
     pub const LSP_CONFIG: usize = 60;
+    pub const MTHDS_VERSION: usize = 65;
     pub const SCHEMA_FIELD: usize = 70;
     pub const DIRECTIVE: usize = 75;
     pub const MAX: usize = usize::MAX;
@@ -37,6 +38,7 @@ pub mod source {
     pub const LSP_CONFIG: &str = "lsp_config";
     pub const MANUAL: &str = "manual";
     pub const SCHEMA_FIELD: &str = "$schema";
+    pub const MTHDS_VERSION: &str = "mthds_version";
     pub const DIRECTIVE: &str = "directive";
 }
 
@@ -194,7 +196,8 @@ impl<E: Environment> SchemaAssociations<E> {
             AssociationRule::Url(u) => {
                 !(u == doc_url
                     && (assoc.meta["source"] == source::DIRECTIVE
-                        || assoc.meta["source"] == source::SCHEMA_FIELD))
+                        || assoc.meta["source"] == source::SCHEMA_FIELD
+                        || assoc.meta["source"] == source::MTHDS_VERSION))
             }
             _ => true,
         });
@@ -246,6 +249,26 @@ impl<E: Environment> SchemaAssociations<E> {
             }
         }
 
+        // An MTHDS bundle picks the embedded schema of the version it declares.
+        if Path::new(doc_url.path())
+            .extension()
+            .is_some_and(|ext| ext == "mthds")
+        {
+            if let Node::Str(s) = root.get(builtins::MTHDS_VERSION_KEY) {
+                if let Some(version) = builtins::MthdsSchemaVersion::find(s.value()) {
+                    self.associations.write().push((
+                        AssociationRule::Url(doc_url.clone()),
+                        SchemaAssociation {
+                            url: version.url.parse().unwrap(),
+                            priority: priority::MTHDS_VERSION,
+                            meta: json!({ "source": source::MTHDS_VERSION }),
+                            fallback_urls: vec![],
+                        },
+                    ));
+                }
+            }
+        }
+
         if let Node::Str(s) = root.get("$schema") {
             let schema_url: Url = if s.value().starts_with('.') {
                 match doc_url.join(s.value()) {
@@ -278,6 +301,25 @@ impl<E: Environment> SchemaAssociations<E> {
     }
 
     pub fn add_from_config(&self, config: &Config) {
+        if let Some(version) = config
+            .mthds_version()
+            .and_then(builtins::MthdsSchemaVersion::find)
+        {
+            self.associations.write().push((
+                AssociationRule::Regex(Regex::new(r".*\.mthds$").unwrap()),
+                SchemaAssociation {
+                    url: version.url.parse().unwrap(),
+                    meta: json!({
+                        "name": format!("MTHDS {}", version.version),
+                        "description": "Pipelex MTHDS file.",
+                        "source": source::CONFIG
+                    }),
+                    priority: priority::CONFIG,
+                    fallback_urls: vec![],
+                },
+            ));
+        }
+
         for rule in &config.rule {
             let Some(file_rule) = rule.file_rule.clone() else {
                 continue;
@@ -14,6 +14,7 @@ pub struct TaploSchemaExt {
     pub init_keys: Option<Vec<String>>,
     #[serde(default)]
     pub plugins: Vec<String>,
+    pub deprecated: Option<ExtDeprecated>,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -32,6 +33,19 @@ pub struct ExtLinks {
     pub enum_values: Option<Vec<Option<String>>>,
 }
 
+/// Marks what a schema matches as deprecated: the values it lists with
+/// `const` or `enum`, or else the whole entry it describes.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtDeprecated {
+    /// Why, or what to do instead.
+    pub message: Option<String>,
+    /// The key to rename a deprecated entry to.
+    pub replacement_key: Option<String>,
+    /// The string to replace a deprecated value with.
+    pub replacement_value: Option<String>,
+}
+
 fn try_schema_ext(schema: &Value, key: &str) -> Option<TaploSchemaExt> {
     schema.get(key).and_then(|val| {
         if val.is_object() {
@@ -49,6 +63,30 @@ pub fn schema_ext_of(schema: &Value) -> Option<TaploSchemaExt> {
     try_schema_ext(schema, PLXT_EXTENSION_KEY).or_else(|| try_schema_ext(schema, EXTENSION_KEY))
 }
 
+/// Whether `schema` restricts the values it matches with `const` or `enum`,
+/// so that its deprecation targets those values rather than the entry.
+fn lists_values(schema: &Value) -> bool {
+    schema.get("const").is_some() || schema.get("enum").is_some()
+}
+
+/// The deprecation of the entry `schema` describes.
+#[must_use]
+pub fn key_deprecation(schema: &Value) -> Option<ExtDeprecated> {
+    if lists_values(schema) {
+        return None;
+    }
+    schema_ext_of(schema)?.deprecated
+}
+
+/// The deprecation of the `const` or `enum` values `schema` lists.
+#[must_use]
+pub fn value_deprecation(schema: &Value) -> Option<ExtDeprecated> {
+    if !lists_values(schema) {
+        return None;
+    }
+    schema_ext_of(schema)?.deprecated
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,4 +145,29 @@ mod tests {
         let ext = schema_ext_of(&schema).unwrap();
         assert_eq!(ext.docs.unwrap().main.unwrap(), "taplo docs");
     }
+
+    #[test]
+    fn deprecation_targets_listed_values_or_the_entry() {
+        let entry = json!({
+            "type": "string",
+            "x-plxt": { "deprecated": { "message": "gone", "replacementKey": "prompt" } },
+        });
+        let deprecated = key_deprecation(&entry).unwrap();
+        assert_eq!(deprecated.message.as_deref(), Some("gone"));
+        assert_eq!(deprecated.replacement_key.as_deref(), Some("prompt"));
+        assert!(value_deprecation(&entry).is_none());
+
+        let value = json!({
+            "const": "old",
+            "x-plxt": { "deprecated": { "replacementValue": "new" } },
+        });
+        assert!(key_deprecation(&value).is_none());
+        assert_eq!(
+            value_deprecation(&value)
+                .unwrap()
+                .replacement_value
+                .as_deref(),
+            Some("new")
+        );
+    }
 }
@@ -28,34 +28,194 @@ pub mod cache;
 pub mod ext;
 
 pub mod builtins {
-    use serde_json::Value;
+    use serde_json::{json, Value};
     use std::sync::Arc;
     use url::Url;
 
     pub const TAPLO_CONFIG_URL: &str = "taplo://taplo.toml";
 
+    /// The URL of the latest embedded MTHDS schema.
     pub const MTHDS_SCHEMA_URL: &str = "pipelex://mthds.schema.json";
 
-    const MTHDS_SCHEMA_JSON: &str = include_str!("../../schemas/mthds_schema.json");
+    /// The MTHDS version of the latest embedded schema.
+    pub const MTHDS_VERSION: &str = "1.0";
+
+    /// The top-level key a bundle declares its MTHDS version with.
+    pub const MTHDS_VERSION_KEY: &str = "mthds_version";
+
+    /// The native concept registry of [`MTHDS_VERSION`], embedded next to its
+    /// schema.
+    pub const MTHDS_NATIVE_CONCEPTS_TOML: &str =
+        include_str!("../../schemas/mthds_native_concepts.toml");
+
+    /// An embedded MTHDS schema, for one version of the language.
+    #[derive(Debug)]
+    pub struct MthdsSchemaVersion {
+        /// The version, as bundles and `plxt.toml` name it.
+        pub version: &'static str,
+        pub url: &'static str,
+        /// Whether bundles written for it should move to [`MTHDS_VERSION`].
+        pub deprecated: bool,
+        json: &'static str,
+    }
+
+    /// The embedded MTHDS schemas, latest first.
+    pub const MTHDS_SCHEMAS: &[MthdsSchemaVersion] = &[
+        MthdsSchemaVersion {
+            version: MTHDS_VERSION,
+            url: MTHDS_SCHEMA_URL,
+            deprecated: false,
+            json: include_str!("../../schemas/mthds_schema.json"),
+        },
+        MthdsSchemaVersion {
+            version: "0.9",
+            url: "pipelex://mthds-0.9.schema.json",
+            deprecated: true,
+            json: include_str!("../../schemas/mthds_schema_0.9.json"),
+        },
+    ];
+
+    /// A field an MTHDS version renamed.
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct MthdsRenamedField {
+        /// The schema definition of the field.
+        pub definition: &'static str,
+        /// The dotted key pattern of the tables that definition describes,
+        /// where `*` matches any key, e.g. `pipe.*`.
+        pub tables: &'static str,
+        /// The `type` of the tables that definition describes, e.g. `PipeLLM`.
+        pub table_type: &'static str,
+        pub old: &'static str,
+        pub new: &'static str,
+        /// The version that renamed it.
+        pub since: &'static str,
+    }
+
+    /// Every field renamed between the embedded versions. Schemas that define
+    /// the new name keep accepting the old one, marked deprecated with the new
+    /// name as its replacement, but not both together; `plxt migrate` renames
+    /// the old one.
+    pub const MTHDS_RENAMED_FIELDS: &[MthdsRenamedField] = &[MthdsRenamedField {
+        definition: "PipeLLMBlueprint",
+        tables: "pipe.*",
+        table_type: "PipeLLM",
+        old: "prompt_template",
+        new: "prompt",
+        since: "1.0",
+    }];
+
+    impl MthdsSchemaVersion {
+        /// The latest version.
+        #[must_use]
+        pub fn latest() -> &'static Self {
+            &MTHDS_SCHEMAS[0]
+        }
+
+        /// The embedded schema of `version`.
+        #[must_use]
+        pub fn find(version: &str) -> Option<&'static Self> {
+            MTHDS_SCHEMAS
+                .iter()
+                .find(|schema| schema.version == version)
+        }
+
+        /// The embedded schema served at `url`.
+        #[must_use]
+        pub fn by_url(url: &str) -> Option<&'static Self> {
+            MTHDS_SCHEMAS.iter().find(|schema| schema.url == url)
+        }
+
+        /// The known versions, latest first, for error messages.
+        #[must_use]
+        pub fn known_versions() -> String {
+            MTHDS_SCHEMAS
+                .iter()
+                .map(|schema| schema.version)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+
+        /// The schema, with the [`MTHDS_VERSION_KEY`] every version accepts
+        /// at the top level and the deprecated names of renamed fields.
+        #[must_use]
+        pub fn schema(&self) -> Arc<Value> {
+            let mut schema: Value =
+                serde_json::from_str(self.json).expect("embedded MTHDS schema is invalid JSON");
+            for renamed in MTHDS_RENAMED_FIELDS {
+                let Some(definition) = schema
+                    .pointer_mut(&format!("/definitions/{}", renamed.definition))
+                    .and_then(Value::as_object_mut)
+                else {
+                    continue;
+                };
+                let Some(properties) = definition
+                    .get_mut("properties")
+                    .and_then(Value::as_object_mut)
+                else {
+                    continue;
+                };
+                if properties.contains_key(renamed.old) {
+                    continue;
+                }
+                let Some(mut field) = properties.get(renamed.new).cloned() else {
+                    continue;
+                };
+                field["x-plxt"] = json!({
+                    "deprecated": {
+                        "message": format!("renamed to `{}` in MTHDS {}", renamed.new, renamed.since),
+                        "replacementKey": renamed.new,
+                    },
+                });
+                properties.insert(renamed.old.into(), field);
+                if let Some(dependencies) = definition
+                    .entry("dependencies")
+                    .or_insert_with(|| json!({}))
+                    .as_object_mut()
+                {
+                    dependencies.insert(
+                        renamed.new.into(),
+                        json!({ "not": { "required": [renamed.old] } }),
+                    );
+                }
+            }
+            if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+                properties.insert(
+                    MTHDS_VERSION_KEY.into(),
+                    json!({
+                        "title": "MTHDS Version",
+                        "description": "The MTHDS version the bundle is written for. \
+                            It selects the schema the bundle is validated against.",
+                        "type": "string",
+                        "enum": MTHDS_SCHEMAS.iter().map(|schema| schema.version).collect::<Vec<_>>(),
+                    }),
+                );
+            }
+            Arc::new(schema)
+        }
+    }
+
+    /// Whether `url` is one of the embedded MTHDS schemas.
+    #[must_use]
+    pub fn is_mthds_schema_url(url: &str) -> bool {
+        MthdsSchemaVersion::by_url(url).is_some()
+    }
 
     #[must_use]
     pub fn taplo_config_schema() -> Arc<Value> {
         Arc::new(serde_json::to_value(schemars::schema_for!(crate::config::Config)).unwrap())
     }
 
+    /// The latest embedded MTHDS schema.
     #[must_use]
     pub fn mthds_schema() -> Arc<Value> {
-        Arc::new(
-            serde_json::from_str(MTHDS_SCHEMA_JSON).expect("embedded MTHDS schema is invalid JSON"),
-        )
+        MthdsSchemaVersion::latest().schema()
     }
 
     #[must_use]
     pub fn builtin_schema(url: &Url) -> Option<Arc<Value>> {
         match url.as_str() {
             TAPLO_CONFIG_URL => Some(taplo_config_schema()),
-            MTHDS_SCHEMA_URL => Some(mthds_schema()),
-            _ => None,
+            url => MthdsSchemaVersion::by_url(url).map(MthdsSchemaVersion::schema),
         }
     }
 }
@@ -199,7 +359,7 @@ impl<E: Environment> Schemas<E> {
         root: &dom::Node,
         value: &Value,
     ) -> Option<Vec<NodeValidationError>> {
-        if schema_url.as_str() != builtins::MTHDS_SCHEMA_URL {
+        if !builtins::is_mthds_schema_url(schema_url.as_str()) {
             return None;
         }
 
@@ -1018,6 +1178,49 @@ impl<E: Environment> Schemas<E> {
         Ok(())
     }
 
+    /// The entries and values of `root` that its schema marks deprecated with
+    /// `x-plxt.deprecated`, in document order.
+    #[tracing::instrument(skip_all, fields(%schema_url))]
+    pub async fn deprecations(
+        &self,
+        schema_url: &Url,
+        root: &dom::Node,
+    ) -> Result<Vec<SchemaDeprecation>, anyhow::Error> {
+        let value = serde_json::to_value(root)?;
+        let mut deprecations = Vec::new();
+        let mut pending = children_of(&Keys::empty(), root);
+
+        while let Some((keys, key, node)) = pending.pop() {
+            let schemas = self.schemas_at_path(schema_url, &value, &keys).await?;
+            let node_value = serde_json::to_value(&node)?;
+            let found = schemas.iter().find_map(|(_, schema)| {
+                if let Some(deprecated) = ext::key_deprecation(schema) {
+                    return Some((deprecated, false));
+                }
+                let listed = schema["const"] == node_value
+                    || schema["enum"]
+                        .as_array()
+                        .is_some_and(|values| values.contains(&node_value));
+                ext::value_deprecation(schema)
+                    .filter(|_| listed)
+                    .map(|deprecated| (deprecated, true))
+            });
+            if let Some((deprecated, targets_value)) = found {
+                deprecations.push(SchemaDeprecation {
+                    keys: keys.clone(),
+                    key: key.clone(),
+                    node: node.clone(),
+                    deprecated,
+                    targets_value,
+                });
+            }
+            pending.extend(children_of(&keys, &node));
+        }
+
+        deprecations.sort_by_key(|deprecation| deprecation.text_range().map(TextRange::start));
+        Ok(deprecations)
+    }
+
     #[tracing::instrument(skip_all, fields(%schema_url, %path))]
     pub async fn possible_schemas_from(
         &self,
@@ -1184,6 +1387,26 @@ impl<E: Environment> Schemas<E> {
     }
 }
 
+/// The entries of a table, or the items of an array, under `keys`.
+fn children_of(keys: &Keys, node: &dom::Node) -> Vec<(Keys, Option<Key>, dom::Node)> {
+    match node {
+        dom::Node::Table(table) => table
+            .entries()
+            .read()
+            .iter()
+            .map(|(key, entry)| (keys.join(key.clone()), Some(key.clone()), entry.clone()))
+            .collect(),
+        dom::Node::Array(array) => array
+            .items()
+            .read()
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| (keys.join(idx), None, item.clone()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 fn reference_url(root_url: &Url, reference: &str) -> Option<Url> {
     if !reference.starts_with('#') {
         return Url::parse(reference).ok();
@@ -1357,6 +1580,9 @@ impl NodeValidationError {
         if let ValidationErrorKind::AdditionalProperties { unexpected } = &error.kind {
             keys = keys.extend(unexpected.iter().map(Key::from).map(KeyOrIndex::Key));
         }
+        if let Some((excluded, _)) = excluded_property(&error) {
+            keys = keys.join(Key::new(excluded));
+        }
 
         let (keys, node) = Self::walk_instance_path(keys, base_node.clone(), &error.instance_path)?;
 
@@ -1449,6 +1675,11 @@ impl NodeValidationError {
     /// This method produces concise messages suitable for diagnostics.
     #[must_use]
     pub fn display_message(&self) -> String {
+        if let ErrorSource::Validation(error) = &self.source {
+            if let Some((excluded, dependent)) = excluded_property(error) {
+                return format!("`{excluded}` cannot be used together with `{dependent}`");
+            }
+        }
         match &self.source {
             ErrorSource::Applied {
                 message,
@@ -1557,6 +1788,75 @@ impl NodeValidationError {
         }
     }
 
+    /// The JSON Schema keyword that failed (e.g. `required`, `additionalProperties`).
+    #[must_use]
+    pub fn keyword(&self) -> &str {
+        match &self.source {
+            ErrorSource::Validation(error) => error
+                .schema_path
+                .iter()
+                .rev()
+                .find_map(|chunk| match chunk {
+                    PathChunk::Keyword(keyword) => Some(*keyword),
+                    PathChunk::Property(_) | PathChunk::Index(_) => None,
+                })
+                .unwrap_or_default(),
+            ErrorSource::Applied {
+                message,
+                keyword_location,
+            } => {
+                let keyword = keyword_location
+                    .rsplit('/')
+                    .find(|segment| segment.parse::<usize>().is_err())
+                    .unwrap_or_default();
+                // `apply()` reports errors inside a referenced schema at the `$ref`
+                // itself, so the failed keyword only survives in the message.
+                if keyword == "$ref" {
+                    keyword_from_message(message).unwrap_or(keyword)
+                } else {
+                    keyword
+                }
+            }
+        }
+    }
+
+    /// The property a `required` error reports as missing.
+    #[must_use]
+    pub fn missing_property(&self) -> Option<String> {
+        match &self.source {
+            ErrorSource::Validation(error) => match &error.kind {
+                ValidationErrorKind::Required { property } => {
+                    property.as_str().map(ToString::to_string)
+                }
+                _ => None,
+            },
+            ErrorSource::Applied { message, .. } => message
+                .strip_suffix(" is a required property")
+                .and_then(|property| serde_json::from_str(property).ok()),
+        }
+    }
+
+    /// A stable diagnostic code derived from the failed [`keyword`](Self::keyword),
+    /// e.g. `schema/required`. Keywords with no dedicated code map to `schema/invalid`.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self.keyword() {
+            "type" => "schema/type",
+            "enum" | "const" => "schema/enum",
+            "required" => "schema/required",
+            "additionalProperties" | "unevaluatedProperties" | "propertyNames" => {
+                "schema/additional-properties"
+            }
+            "oneOf" | "anyOf" | "not" => "schema/no-match",
+            "pattern" => "schema/pattern",
+            "format" => "schema/format",
+            "minLength" | "maxLength" | "minimum" | "maximum" | "exclusiveMinimum"
+            | "exclusiveMaximum" | "multipleOf" | "minItems" | "maxItems" | "uniqueItems"
+            | "minProperties" | "maxProperties" | "contains" => "schema/bounds",
+            _ => "schema/invalid",
+        }
+    }
+
     /// Return the most specific (narrowest) text range for this error.
     /// Picks the last range, which is typically the most specific.
     #[must_use]
@@ -1565,6 +1865,121 @@ impl NodeValidationError {
     }
 }
 
+/// An entry, or a value, that the schema marks deprecated.
+#[derive(Debug, Clone)]
+pub struct SchemaDeprecation {
+    pub keys: Keys,
+    /// The key of the entry, `None` for array items.
+    pub key: Option<Key>,
+    pub node: dom::Node,
+    pub deprecated: ext::ExtDeprecated,
+    /// Whether the deprecation targets the value (listed with `const` or
+    /// `enum`) rather than the entry.
+    pub targets_value: bool,
+}
+
+impl SchemaDeprecation {
+    /// The diagnostic code of every deprecation.
+    pub const CODE: &'static str = "schema/deprecated";
+
+    /// Where the deprecation is reported: the value when it is deprecated,
+    /// the key otherwise.
+    #[must_use]
+    pub fn text_range(&self) -> Option<TextRange> {
+        match &self.key {
+            Some(key) if !self.targets_value => key.text_ranges().next(),
+            _ => self.node.text_ranges(false).next(),
+        }
+    }
+
+    /// The deprecated key or value, as written.
+    #[must_use]
+    pub fn name(&self) -> String {
+        match (&self.key, self.targets_value) {
+            (Some(key), false) => key.value().to_string(),
+            _ => match &self.node {
+                dom::Node::Str(string) => string.value().to_string(),
+                node => node.to_toml(true, false),
+            },
+        }
+    }
+
+    /// The dotted path of the entry, e.g. `pipe.summarize.prompt_template`.
+    #[must_use]
+    pub fn instance_location(&self) -> Option<String> {
+        (!self.keys.is_empty()).then(|| self.keys.dotted().to_string())
+    }
+
+    /// The replacement the schema names: a key, or a value.
+    #[must_use]
+    pub fn replacement(&self) -> Option<&str> {
+        if self.targets_value {
+            self.deprecated.replacement_value.as_deref()
+        } else {
+            self.deprecated.replacement_key.as_deref()
+        }
+    }
+
+    /// E.g. ``"`prompt_template` is deprecated: renamed to `prompt` in MTHDS 1.0"``.
+    #[must_use]
+    pub fn message(&self) -> String {
+        let name = self.name();
+        match (&self.deprecated.message, self.replacement()) {
+            (Some(message), _) => format!("`{name}` is deprecated: {message}"),
+            (None, Some(replacement)) => {
+                format!("`{name}` is deprecated, use `{replacement}` instead")
+            }
+            (None, None) => format!("`{name}` is deprecated"),
+        }
+    }
+}
+
+/// The `(excluded, dependent)` properties of an error raised by a
+/// `"dependencies": { dependent: { "not": { "required": [excluded] } } }`
+/// schema, which forbids setting both.
+fn excluded_property(error: &ValidationError<'_>) -> Option<(String, String)> {
+    let ValidationErrorKind::Not { schema } = &error.kind else {
+        return None;
+    };
+    let [Value::String(excluded)] = schema.get("required")?.as_array()?.as_slice() else {
+        return None;
+    };
+    let chunks: Vec<_> = error.schema_path.iter().rev().take(3).collect();
+    let [PathChunk::Keyword("not"), PathChunk::Property(dependent), PathChunk::Keyword("dependencies")] =
+        chunks.as_slice()
+    else {
+        return None;
+    };
+    Some((excluded.clone(), dependent.to_string()))
+}
+
+/// Recover the failed keyword from a `jsonschema` error message, for errors
+/// whose keyword location stops at a `$ref`.
+fn keyword_from_message(message: &str) -> Option<&'static str> {
+    let keyword = if message.starts_with("Additional properties are not allowed") {
+        "additionalProperties"
+    } else if message.starts_with("Unevaluated properties are not allowed") {
+        "unevaluatedProperties"
+    } else if message.ends_with(" is a required property") {
+        "required"
+    } else if message.contains(" is not of type") {
+        "type"
+    } else if message.contains(" is not one of ") {
+        "enum"
+    } else if message.ends_with(" was expected") {
+        "const"
+    } else if message.contains("of the schemas listed in the 'oneOf' keyword") {
+        "oneOf"
+    } else if message.contains("of the schemas listed in the 'anyOf' keyword") {
+        "anyOf"
+    } else if message.contains(" does not match \"") {
+        "pattern"
+    } else {
+        return None;
+    };
+    Some(keyword)
+}
+
 mod formats {
     pub(super) fn semver(value: &str) -> bool {
         semver::Version::parse(value).is_ok()
@@ -1990,4 +2405,289 @@ model = "$default"
             "Should locate the discriminator error at the offending `type` key — got: {locations:?}",
         );
     }
+    #[tokio::test]
+    async fn mthds_validation_errors_carry_keyword_codes() {
+        let env = MockEnv {
+            files: std::collections::HashMap::new(),
+        };
+        let schemas = super::Schemas::new(env, None);
+
+        let mthds_content = r#"
+domain = "rec"
+bogus_key = 1
+
+[pipe.bad_pipe]
+type = "UnknownPipeType"
+description = "This pipe has an invalid type"
+output = "Text"
+"#;
+
+        let dom = taplo::parser::parse(mthds_content).into_dom();
+        let schema_url: Url = super::builtins::MTHDS_SCHEMA_URL.parse().unwrap();
+        schemas
+            .add_schema(&schema_url, super::builtins::mthds_schema())
+            .await;
+
+        let errors = schemas.validate_root(&schema_url, &dom).await.unwrap();
+        let codes: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|error| (error.keyword(), error.code()))
+            .collect();
+
+        assert!(
+            codes.contains(&("additionalProperties", "schema/additional-properties")),
+            "got: {codes:?}"
+        );
+        assert!(
+            codes.iter().any(|(_, code)| *code == "schema/enum"),
+            "got: {codes:?}"
+        );
+
+        // Errors inside a `$ref`'d concept blueprint are reported at the `$ref`.
+        let dom = taplo::parser::parse(
+            "domain = \"rec\"\n\n[concept.Plain]\ndescription = \"p\"\nremark = \"r\"\n",
+        )
+        .into_dom();
+        let errors = schemas.validate_root(&schema_url, &dom).await.unwrap();
+        let codes: Vec<&str> = errors
+            .iter()
+            .map(super::NodeValidationError::code)
+            .collect();
+        assert_eq!(codes, ["schema/additional-properties"]);
+
+        let dom = taplo::parser::parse(
+            "domain = \"rec\"\n\n[pipe.p]\ntype = \"PipeLLM\"\noutput = \"Text\"\n",
+        )
+        .into_dom();
+        let errors = schemas.validate_root(&schema_url, &dom).await.unwrap();
+        let missing: Vec<Option<String>> = errors
+            .iter()
+            .map(super::NodeValidationError::missing_property)
+            .collect();
+        assert_eq!(missing, [Some("description".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn mthds_bundles_select_their_schema_version() {
+        let env = MockEnv {
+            files: std::collections::HashMap::new(),
+        };
+        let schemas = super::Schemas::new(env, None);
+        let url_for = |source: &str| {
+            let doc_url: Url = "file:///bundles/b.mthds".parse().unwrap();
+            let dom = taplo::parser::parse(source).into_dom();
+            schemas.associations().add_from_document(&doc_url, &dom);
+            schemas
+                .associations()
+                .association_for(&doc_url)
+                .unwrap()
+                .url
+                .to_string()
+        };
+
+        assert_eq!(url_for("domain = \"d\"\n"), "pipelex://mthds.schema.json");
+        assert_eq!(
+            url_for("mthds_version = \"0.9\"\ndomain = \"d\"\n"),
+            "pipelex://mthds-0.9.schema.json"
+        );
+        // An unknown version is left to the schema to report.
+        assert_eq!(
+            url_for("mthds_version = \"7.0\"\ndomain = \"d\"\n"),
+            "pipelex://mthds.schema.json"
+        );
+        // A `#:schema` directive wins over the declaration.
+        assert_eq!(
+            url_for(
+                "#:schema pipelex://mthds.schema.json\nmthds_version = \"0.9\"\ndomain = \"d\"\n"
+            ),
+            "pipelex://mthds.schema.json"
+        );
+
+        // `[mthds] version` sets the version of bundles declaring none.
+        let mut config: crate::config::Config =
+            serde_json::from_value(json!({ "mthds": { "version": "0.9" } })).unwrap();
+        config.prepare(&schemas.env, Path::new("/")).unwrap();
+        schemas.associations().add_from_config(&config);
+        assert_eq!(
+            url_for("domain = \"d\"\n"),
+            "pipelex://mthds-0.9.schema.json"
+        );
+        assert_eq!(
+            url_for("mthds_version = \"1.0\"\ndomain = \"d\"\n"),
+            "pipelex://mthds.schema.json"
+        );
+    }
+
+    #[tokio::test]
+    async fn mthds_schema_versions_differ() {
+        let env = MockEnv {
+            files: std::collections::HashMap::new(),
+        };
+        let schemas = super::Schemas::new(env, None);
+        let codes = |url: &'static str, source: &'static str| {
+            let schemas = schemas.clone();
+            async move {
+                let url: Url = url.parse().unwrap();
+                let dom = taplo::parser::parse(source).into_dom();
+                let errors = schemas.validate_root(&url, &dom).await.unwrap();
+                errors
+                    .iter()
+                    .map(|error| error.code().to_string())
+                    .collect::<Vec<_>>()
+            }
+        };
+        let prompt = "mthds_version = \"1.0\"\ndomain = \"d\"\n\n[pipe.p]\ntype = \"PipeLLM\"\n\
+                      description = \"P\"\noutput = \"Text\"\nprompt = \"Hi\"\n";
+        let prompt_template = prompt
+            .replace("\"1.0\"", "\"0.9\"")
+            .replace("prompt =", "prompt_template =")
+            .leak();
+
+        assert!(codes(super::builtins::MTHDS_SCHEMA_URL, prompt)
+            .await
+            .is_empty());
+        assert!(codes("pipelex://mthds-0.9.schema.json", prompt_template)
+            .await
+            .is_empty());
+        assert_eq!(
+            codes("pipelex://mthds-0.9.schema.json", prompt).await,
+            ["schema/additional-properties"]
+        );
+        assert_eq!(
+            codes(
+                super::builtins::MTHDS_SCHEMA_URL,
+                "mthds_version = \"7.0\"\ndomain = \"d\"\n"
+            )
+            .await,
+            ["schema/enum"]
+        );
+    }
+
+    #[tokio::test]
+    async fn deprecated_entries_and_values_are_found() {
+        let env = MockEnv {
+            files: std::collections::HashMap::new(),
+        };
+        let schemas = super::Schemas::new(env, None);
+        let url: Url = "file:///schemas/s.json".parse().unwrap();
+        schemas
+            .add_schema(
+                &url,
+                std::sync::Arc::new(json!({
+                    "type": "object",
+                    "properties": {
+                        "old": {
+                            "type": "string",
+                            "x-plxt": { "deprecated": { "replacementKey": "new" } },
+                        },
+                        "new": { "type": "string" },
+                        "mode": {
+                            "anyOf": [
+                                { "enum": ["fast", "slow"] },
+                                {
+                                    "const": "quick",
+                                    "x-plxt": { "deprecated": {
+                                        "message": "say `fast`",
+                                        "replacementValue": "fast",
+                                    } },
+                                },
+                            ],
+                        },
+                    },
+                })),
+            )
+            .await;
+
+        let source = "old = \"a\"\nnew = \"b\"\nmode = \"quick\"\n";
+        let dom = taplo::parser::parse(source).into_dom();
+        let deprecations = schemas.deprecations(&url, &dom).await.unwrap();
+        let found = deprecations
+            .iter()
+            .map(|deprecation| {
+                (
+                    &source[deprecation.text_range().unwrap()],
+                    deprecation.message(),
+                    deprecation.replacement(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (
+                    "old",
+                    "`old` is deprecated, use `new` instead".into(),
+                    Some("new")
+                ),
+                (
+                    "\"quick\"",
+                    "`quick` is deprecated: say `fast`".into(),
+                    Some("fast")
+                ),
+            ]
+        );
+
+        let dom = taplo::parser::parse("new = \"b\"\nmode = \"fast\"\n").into_dom();
+        assert!(schemas.deprecations(&url, &dom).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn mthds_renamed_fields_are_deprecated() {
+        let env = MockEnv {
+            files: std::collections::HashMap::new(),
+        };
+        let schemas = super::Schemas::new(env, None);
+        let source = "domain = \"d\"\n\n[pipe.p]\ntype = \"PipeLLM\"\ndescription = \"P\"\n\
+                      output = \"Text\"\nprompt_template = \"Hi\"\n";
+        let dom = taplo::parser::parse(source).into_dom();
+        let deprecations = |url: &'static str| {
+            let schemas = schemas.clone();
+            let dom = dom.clone();
+            async move {
+                let url: Url = url.parse().unwrap();
+                schemas
+                    .deprecations(&url, &dom)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(super::SchemaDeprecation::message)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            deprecations(super::builtins::MTHDS_SCHEMA_URL).await,
+            ["`prompt_template` is deprecated: renamed to `prompt` in MTHDS 1.0"]
+        );
+        assert!(deprecations("pipelex://mthds-0.9.schema.json")
+            .await
+            .is_empty());
+
+        // 1.0 accepts the old name alone, but not next to the new one.
+        let url: Url = super::builtins::MTHDS_SCHEMA_URL.parse().unwrap();
+        assert!(schemas.validate_root(&url, &dom).await.unwrap().is_empty());
+        let both = source.replace("prompt_template", "prompt = \"Hey\"\nprompt_template");
+        let errors = schemas
+            .validate_root(&url, &taplo::parser::parse(&both).into_dom())
+            .await
+            .unwrap();
+        let found = errors
+            .iter()
+            .map(|error| {
+                (
+                    error.code(),
+                    error.display_message(),
+                    &both[error.primary_text_range().unwrap()],
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [(
+                "schema/no-match",
+                "`prompt_template` cannot be used together with `prompt`".to_string(),
+                "\"Hi\""
+            )]
+        );
+    }
 }
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::PathBuf;
 use taplo_common::{
-    config::Rule,
+    config::{LintOptions, Rule},
     schema::{associations::DEFAULT_CATALOGS, cache::DEFAULT_LRU_CACHE_EXPIRATION_TIME},
     HashMap,
 };
@@ -39,6 +39,8 @@ pub struct LspConfig {
     pub syntax: SyntaxConfig,
     pub formatter: taplo::formatter::OptionsIncompleteCamel,
     pub rules: Vec<Rule>,
+    /// MTHDS lint rule levels, layered over the config file's `[lint.rules]`.
+    pub lint: LintOptions,
 }
 
 impl LspConfig {
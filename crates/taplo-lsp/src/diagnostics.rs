@@ -1,11 +1,23 @@
-use crate::world::{DocumentState, WorkspaceState, World};
+use crate::{
+    handlers::{bundle_mthds_paths, fix_data},
+    world::{DocumentState, WorkspaceState, World},
+};
 use lsp_async_stub::{util::LspExt, Context, RequestWriter};
 use lsp_types::{
-    notification, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location,
-    PublishDiagnosticsParams, Url,
+    notification, CodeDescription, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity,
+    DiagnosticTag, Location, NumberOrString, PublishDiagnosticsParams, Url,
+};
+use pipelex_common::tools::{
+    bundle::BundleIndex,
+    codes::code_description,
+    diagnostic::Severity,
+    fixes::{deprecation_fixes, schema_fixes},
+    rules::{check_bundle, UNUSED_SUPPRESSION},
+    suppression::Suppressions,
 };
-use taplo::dom::Node;
-use taplo_common::environment::Environment;
+use std::{collections::HashMap, path::PathBuf};
+use taplo::{dom::Node, parser};
+use taplo_common::{environment::Environment, schema::SchemaDeprecation};
 
 #[tracing::instrument(skip_all)]
 pub(crate) async fn publish_diagnostics<E: Environment>(
@@ -79,6 +91,31 @@ pub(crate) async fn publish_diagnostics<E: Environment>(
     collect_schema_errors(ws, doc, &dom, &document_url, &mut diags).await;
     drop(workspaces);
 
+    context
+        .write_notification::<notification::PublishDiagnostics, _>(Some(PublishDiagnosticsParams {
+            uri: document_url.clone(),
+            diagnostics: diags.clone(),
+            version: None,
+        }))
+        .await
+        .unwrap_or_else(|err| tracing::error!("{err}"));
+
+    // Deprecation warnings do not stop the rules.
+    let schema_warnings = diags.len();
+    if diags
+        .iter()
+        .any(|diag| diag.severity == Some(DiagnosticSeverity::ERROR))
+        || !document_url.as_str().ends_with(".mthds")
+    {
+        return;
+    }
+
+    collect_rule_violations(&context, &ws_url, &document_url, &mut diags).await;
+
+    if diags.len() == schema_warnings {
+        return;
+    }
+
     context
         .write_notification::<notification::PublishDiagnostics, _>(Some(PublishDiagnosticsParams {
             uri: document_url.clone(),
@@ -111,13 +148,8 @@ fn collect_syntax_errors(doc: &DocumentState, diags: &mut Vec<Diagnostic>) {
         Diagnostic {
             range,
             severity: Some(DiagnosticSeverity::ERROR),
-            code: None,
-            code_description: None,
-            source: Some("Pipelex".into()),
             message: e.message.clone(),
-            related_information: None,
-            tags: None,
-            data: None,
+            ..coded(taplo::parser::Error::CODE)
         }
     }));
 }
@@ -148,7 +180,6 @@ fn collect_dom_errors(
                     diags.push(Diagnostic {
                         range,
                         severity: Some(DiagnosticSeverity::ERROR),
-                        source: Some("Pipelex".into()),
                         message: error.to_string(),
                         related_information: Some(Vec::from([DiagnosticRelatedInformation {
                             location: Location {
@@ -157,13 +188,12 @@ fn collect_dom_errors(
                             },
                             message: "other key defined here".into(),
                         }])),
-                        ..Default::default()
+                        ..coded(error.code())
                     });
 
                     diags.push(Diagnostic {
                         range: other_range,
                         severity: Some(DiagnosticSeverity::HINT),
-                        source: Some("Pipelex".into()),
                         message: error.to_string(),
                         related_information: Some(Vec::from([DiagnosticRelatedInformation {
                             location: Location {
@@ -172,7 +202,7 @@ fn collect_dom_errors(
                             },
                             message: "other key defined here".into(),
                         }])),
-                        ..Default::default()
+                        ..coded(error.code())
                     });
                 }
                 taplo::dom::Error::ExpectedTable {
@@ -194,7 +224,6 @@ fn collect_dom_errors(
                     diags.push(Diagnostic {
                         range,
                         severity: Some(DiagnosticSeverity::ERROR),
-                        source: Some("Pipelex".into()),
                         message: error.to_string(),
                         related_information: Some(Vec::from([DiagnosticRelatedInformation {
                             location: Location {
@@ -203,13 +232,12 @@ fn collect_dom_errors(
                             },
                             message: "required by this key".into(),
                         }])),
-                        ..Default::default()
+                        ..coded(error.code())
                     });
 
                     diags.push(Diagnostic {
                         range: other_range,
                         severity: Some(DiagnosticSeverity::HINT),
-                        source: Some("Pipelex".into()),
                         message: error.to_string(),
                         related_information: Some(Vec::from([DiagnosticRelatedInformation {
                             location: Location {
@@ -218,7 +246,7 @@ fn collect_dom_errors(
                             },
                             message: "table defined here".into(),
                         }])),
-                        ..Default::default()
+                        ..coded(error.code())
                     });
                 }
                 taplo::dom::Error::ExpectedArrayOfTables {
@@ -240,7 +268,6 @@ fn collect_dom_errors(
                     diags.push(Diagnostic {
                         range,
                         severity: Some(DiagnosticSeverity::ERROR),
-                        source: Some("Pipelex".into()),
                         message: error.to_string(),
                         related_information: Some(Vec::from([DiagnosticRelatedInformation {
                             location: Location {
@@ -249,13 +276,12 @@ fn collect_dom_errors(
                             },
                             message: "required by this key".into(),
                         }])),
-                        ..Default::default()
+                        ..coded(error.code())
                     });
 
                     diags.push(Diagnostic {
                         range: other_range,
                         severity: Some(DiagnosticSeverity::HINT),
-                        source: Some("Pipelex".into()),
                         message: error.to_string(),
                         related_information: Some(Vec::from([DiagnosticRelatedInformation {
                             location: Location {
@@ -264,7 +290,7 @@ fn collect_dom_errors(
                             },
                             message: "array of tables defined here".into(),
                         }])),
-                        ..Default::default()
+                        ..coded(error.code())
                     });
                 }
                 taplo::dom::Error::InvalidEscapeSequence { string: _ }
@@ -327,10 +353,142 @@ async fn collect_schema_errors<E: Environment>(
             diags.push(Diagnostic {
                 range,
                 severity: Some(DiagnosticSeverity::ERROR),
-                source: Some("Pipelex".into()),
                 message: error.display_message(),
-                ..Default::default()
+                data: fix_data(&schema_fixes(&error)),
+                ..coded(error.code())
+            });
+        }
+        if !diags.is_empty() {
+            return;
+        }
+
+        let deprecations = match ws.schemas.deprecations(&schema_url, dom).await {
+            Ok(deprecations) => deprecations,
+            Err(error) => {
+                tracing::error!(?error, "failed to collect deprecations");
+                return;
+            }
+        };
+        for deprecation in deprecations {
+            let Some(range) = deprecation
+                .text_range()
+                .and_then(|range| doc.mapper.range(range))
+            else {
+                continue;
+            };
+            diags.push(Diagnostic {
+                range: range.into_lsp(),
+                severity: Some(DiagnosticSeverity::WARNING),
+                message: deprecation.message(),
+                data: fix_data(&deprecation_fixes(dom, &deprecation)),
+                tags: Some(vec![DiagnosticTag::DEPRECATED]),
+                ..coded(SchemaDeprecation::CODE)
             });
         }
     }
 }
+
+/// Run the MTHDS lint rules over a schema-valid `.mthds` document. Sibling
+/// bundle files are taken from the open documents when possible (so unsaved
+/// edits count) and read from disk otherwise. The document's `plxt-ignore`
+/// comments are applied last.
+#[tracing::instrument(skip_all, fields(%document_url))]
+async fn collect_rule_violations<E: Environment>(
+    context: &Context<World<E>>,
+    ws_url: &Url,
+    document_url: &Url,
+    diags: &mut Vec<Diagnostic>,
+) {
+    let path = context
+        .env
+        .to_file_path_normalized(document_url)
+        .unwrap_or_else(|| PathBuf::from(document_url.path()));
+
+    let (index, suppressions, mapper, options, natives, models, mut open_siblings) = {
+        let workspaces = context.workspaces.read().await;
+        let Some(ws) = workspaces.get(ws_url) else {
+            return;
+        };
+        let Some(doc) = ws.documents.get(document_url) else {
+            return;
+        };
+        let open_siblings = ws
+            .documents
+            .iter()
+            .filter(|(uri, _)| *uri != document_url && uri.as_str().ends_with(".mthds"))
+            .filter_map(|(uri, doc)| {
+                let sibling_path = context.env.to_file_path_normalized(uri)?;
+                (sibling_path.parent() == path.parent())
+                    .then(|| (sibling_path, BundleIndex::from_dom(&doc.dom)))
+            })
+            .collect::<HashMap<_, _>>();
+        (
+            BundleIndex::from_dom(&doc.dom),
+            Suppressions::from_dom(&doc.dom),
+            doc.mapper.clone(),
+            ws.lint_options(&path),
+            ws.natives.clone(),
+            ws.models.clone(),
+            open_siblings,
+        )
+    };
+
+    let mut siblings = Vec::new();
+    if let Some(dir) = path.parent() {
+        for sibling_path in bundle_mthds_paths(&context.env, dir) {
+            if sibling_path == path {
+                continue;
+            }
+            if let Some(index) = open_siblings.remove(&sibling_path) {
+                siblings.push(index);
+                continue;
+            }
+            let Ok(bytes) = context.env.read_file(&sibling_path).await else {
+                continue;
+            };
+            let Ok(source) = String::from_utf8(bytes) else {
+                continue;
+            };
+            siblings.push(BundleIndex::from_dom(&parser::parse(&source).into_dom()));
+        }
+    }
+    // Open documents that are not on disk yet still belong to the bundle.
+    siblings.extend(open_siblings.into_values());
+
+    let violations = suppressions.apply(
+        check_bundle(&index, &siblings, &natives, &models, &options),
+        &options,
+    );
+    for violation in violations {
+        let Some(range) = mapper.range(violation.range) else {
+            continue;
+        };
+        diags.push(Diagnostic {
+            range: range.into_lsp(),
+            severity: Some(match violation.severity {
+                Severity::Error => DiagnosticSeverity::ERROR,
+                Severity::Warning => DiagnosticSeverity::WARNING,
+                Severity::Info => DiagnosticSeverity::INFORMATION,
+            }),
+            message: violation.message,
+            data: fix_data(&violation.fixes),
+            // Editors fade unused suppressions out like dead code.
+            tags: std::ptr::eq(violation.rule, &UNUSED_SUPPRESSION)
+                .then(|| vec![DiagnosticTag::UNNECESSARY]),
+            ..coded(violation.rule.id)
+        });
+    }
+}
+
+/// An empty Pipelex diagnostic carrying the stable `code` and its
+/// documentation link.
+fn coded(code: &str) -> Diagnostic {
+    Diagnostic {
+        code: Some(NumberOrString::String(code.into())),
+        code_description: code_description(code)
+            .and_then(|url| url.parse().ok())
+            .map(|href| CodeDescription { href }),
+        source: Some("Pipelex".into()),
+        ..Default::default()
+    }
+}
@@ -40,7 +40,26 @@ pub(crate) use rename::*;
 mod conversion;
 pub(crate) use conversion::*;
 
+mod fixture;
+pub(crate) use fixture::*;
+
+mod render_prompt;
+pub(crate) use render_prompt::*;
+
+mod code_actions;
+pub(crate) use code_actions::*;
+
+mod inlay_hints;
+pub(crate) use inlay_hints::*;
+
+mod signature_help;
+pub(crate) use signature_help::*;
+
+pub(crate) mod mthds_actions;
+pub(crate) mod mthds_completion;
+pub(crate) mod mthds_move;
 pub(crate) mod mthds_resolution;
+pub(crate) mod mthds_templates;
 
 mod goto_definition;
 pub(crate) use goto_definition::*;
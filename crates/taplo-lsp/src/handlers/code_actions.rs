@@ -0,0 +1,316 @@
+//! Quick fixes for the diagnostics that carry fixes, a `source.fixAll.plxt`
+//! action applying every safe one, and the MTHDS actions of
+//! [`mthds_actions`](super::mthds_actions).
+//!
+//! The fixes travel with each published diagnostic in its `data` (see
+//! [`fix_data`]), so the code action request, which sends the diagnostics
+//! back, can offer them without linting the document again.
+
+use super::{
+    mthds_actions::{add_missing_input, create_definition, expand_structure_field, extract_steps},
+    mthds_move::{move_to_file_actions, REFACTOR_MOVE},
+};
+use crate::world::{DocumentState, World};
+use lsp_async_stub::{
+    rpc::Error,
+    util::{LspExt, Mapper, Position, Range},
+    Context, Params,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse,
+    Diagnostic, NumberOrString, TextEdit, Url, WorkspaceEdit,
+};
+use pipelex_common::tools::{
+    fixes::{rewrite_fixes, Edit, Fix},
+    format::mthds_format_options,
+    rules::{UNDEFINED_CONCEPT, UNDEFINED_PIPE},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use taplo::{
+    dom::{rewrite::PendingPatchKind, Node},
+    formatter,
+    rowan::{TextRange, TextSize},
+};
+use taplo_common::environment::Environment;
+
+/// The kind of the action applying every safe fix of a document.
+pub const FIX_ALL: &str = "source.fixAll.plxt";
+
+/// The fixes of one diagnostic, as stored in its `data`.
+#[derive(Serialize, Deserialize)]
+struct FixData {
+    fixes: Vec<FixEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FixEntry {
+    title: String,
+    safe: bool,
+    /// `(start, end, new_text)`, as byte offsets into the linted text.
+    edits: Vec<(u32, u32, String)>,
+}
+
+/// The `data` of a diagnostic with `fixes`, `None` when there are none.
+pub(crate) fn fix_data(fixes: &[Fix]) -> Option<Value> {
+    if fixes.is_empty() {
+        return None;
+    }
+    let data = FixData {
+        fixes: fixes
+            .iter()
+            .map(|fix| FixEntry {
+                title: fix.title.clone(),
+                safe: fix.safe,
+                edits: fix
+                    .edits
+                    .iter()
+                    .map(|edit| {
+                        (
+                            edit.range.start().into(),
+                            edit.range.end().into(),
+                            edit.new_text.clone(),
+                        )
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+    serde_json::to_value(data).ok()
+}
+
+/// The fixes a diagnostic carries in its `data`.
+pub(crate) fn diagnostic_fixes(diagnostic: &Diagnostic) -> Vec<Fix> {
+    let Some(data) = diagnostic
+        .data
+        .clone()
+        .and_then(|data| serde_json::from_value::<FixData>(data).ok())
+    else {
+        return Vec::new();
+    };
+    data.fixes
+        .into_iter()
+        .map(|entry| Fix {
+            title: entry.title,
+            safe: entry.safe,
+            edits: entry
+                .edits
+                .into_iter()
+                .map(|(start, end, new_text)| Edit {
+                    range: TextRange::new(TextSize::from(start), TextSize::from(end)),
+                    new_text,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn code_action<E: Environment>(
+    context: Context<World<E>>,
+    params: Params<CodeActionParams>,
+) -> Result<Option<CodeActionResponse>, Error> {
+    let p = params.required()?;
+    let document_uri = p.text_document.uri;
+
+    let workspaces = context.workspaces.read().await;
+    let ws = workspaces.by_document(&document_uri);
+    let doc = match ws.document(&document_uri) {
+        Ok(d) => d,
+        Err(error) => {
+            tracing::debug!(%error, "failed to get document from workspace");
+            return Ok(None);
+        }
+    };
+
+    let only = p.context.only.as_deref();
+    let mut actions = Vec::new();
+
+    if requested(only, &CodeActionKind::QUICKFIX, true) {
+        for diagnostic in &p.context.diagnostics {
+            for fix in diagnostic_fixes(diagnostic) {
+                let Some(edits) = text_edits(&doc.mapper, &fix.edits) else {
+                    continue;
+                };
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: fix.title,
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    edit: Some(workspace_edit(&document_uri, edits)),
+                    is_preferred: Some(fix.safe),
+                    ..Default::default()
+                }));
+            }
+        }
+    }
+
+    if document_uri.as_str().ends_with(".mthds") {
+        let mut format_options = mthds_format_options();
+        format_options.update_camel(ws.config.formatter.clone());
+        if let Some(path) = context.env.to_file_path_normalized(&document_uri) {
+            ws.taplo_config
+                .update_format_options(&path, &mut format_options);
+        }
+        actions.extend(mthds_code_actions(
+            doc,
+            &document_uri,
+            &p.range,
+            &p.context.diagnostics,
+            only,
+            &format_options,
+        ));
+
+        let offset = doc.mapper.offset(Position::from_lsp(p.range.start));
+        if let (true, Some(offset)) = (requested(only, &REFACTOR_MOVE.into(), true), offset) {
+            actions
+                .extend(move_to_file_actions(&context.env, ws, &document_uri, doc, offset).await);
+        }
+    }
+
+    let fix_all = CodeActionKind::from(FIX_ALL);
+    if requested(only, &fix_all, false) {
+        let fixes: Vec<Fix> = p
+            .context
+            .diagnostics
+            .iter()
+            .flat_map(diagnostic_fixes)
+            .filter(|fix| fix.safe)
+            .collect();
+        let edits = fix_all_edits(&doc.dom, &doc.mapper, &fixes);
+        if !edits.is_empty() {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Fix all auto-fixable problems".into(),
+                kind: Some(fix_all),
+                edit: Some(workspace_edit(&document_uri, edits)),
+                ..Default::default()
+            }));
+        }
+    }
+
+    Ok(Some(actions))
+}
+
+/// The edits applying every fix that does not overlap an earlier one.
+pub(crate) fn fix_all_edits(dom: &Node, mapper: &Mapper, fixes: &[Fix]) -> Vec<TextEdit> {
+    // Fixes with a range outside the current text, left over from a longer
+    // version of it, are dropped.
+    let fixes = fixes
+        .iter()
+        .filter(|fix| text_edits(mapper, &fix.edits).is_some());
+    let Ok((rewrite, _)) = rewrite_fixes(dom, fixes) else {
+        return Vec::new();
+    };
+    rewrite
+        .patches()
+        .iter()
+        .rev()
+        .filter_map(|patch| match &patch.kind {
+            PendingPatchKind::Replace(new_text) => Some(TextEdit {
+                range: mapper.range(patch.range)?.into_lsp(),
+                new_text: new_text.to_string(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Create the pipe or concept of each unresolved reference among
+/// `diagnostics`, the cursor-based MTHDS actions at the start of `range`, and
+/// the extraction of the steps `range` covers.
+fn mthds_code_actions(
+    doc: &DocumentState,
+    uri: &Url,
+    range: &lsp_types::Range,
+    diagnostics: &[Diagnostic],
+    only: Option<&[CodeActionKind]>,
+    format_options: &formatter::Options,
+) -> Vec<CodeActionOrCommand> {
+    let mut actions = Vec::new();
+    let mut push = |fix: Fix, kind: CodeActionKind, diagnostic: Option<&Diagnostic>| {
+        if let Some(edits) = text_edits(&doc.mapper, &fix.edits) {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: fix.title,
+                kind: Some(kind),
+                diagnostics: diagnostic.map(|diagnostic| vec![diagnostic.clone()]),
+                edit: Some(workspace_edit(uri, edits)),
+                ..Default::default()
+            }));
+        }
+    };
+
+    if requested(only, &CodeActionKind::QUICKFIX, true) {
+        for diagnostic in diagnostics {
+            let unresolved = matches!(
+                &diagnostic.code,
+                Some(NumberOrString::String(code))
+                    if *code == UNDEFINED_PIPE.id || *code == UNDEFINED_CONCEPT.id
+            );
+            let fix = unresolved
+                .then(|| doc.mapper.text_range(Range::from_lsp(diagnostic.range)))
+                .flatten()
+                .and_then(|range| create_definition(&doc.dom, range));
+            if let Some(fix) = fix {
+                push(fix, CodeActionKind::QUICKFIX, Some(diagnostic));
+            }
+        }
+    }
+
+    let Some(selection) = doc.mapper.text_range(Range::from_lsp(*range)) else {
+        return actions;
+    };
+    let offset = selection.start();
+    if requested(only, &CodeActionKind::QUICKFIX, true) {
+        if let Some(fix) = add_missing_input(&doc.dom, offset) {
+            push(fix, CodeActionKind::QUICKFIX, None);
+        }
+    }
+    if requested(only, &CodeActionKind::REFACTOR_REWRITE, true) {
+        if let Some(fix) = expand_structure_field(&doc.dom, offset) {
+            push(fix, CodeActionKind::REFACTOR_REWRITE, None);
+        }
+    }
+    if requested(only, &CodeActionKind::REFACTOR_EXTRACT, true) {
+        if let Some(fix) = extract_steps(&doc.dom, selection, format_options) {
+            push(fix, CodeActionKind::REFACTOR_EXTRACT, None);
+        }
+    }
+    actions
+}
+
+/// Whether an action of `kind` was asked for. Without an `only` filter, only
+/// the actions `shown_by_default` are returned.
+fn requested(
+    only: Option<&[CodeActionKind]>,
+    kind: &CodeActionKind,
+    shown_by_default: bool,
+) -> bool {
+    only.map_or(shown_by_default, |only| {
+        only.iter().any(|requested| {
+            kind.as_str() == requested.as_str()
+                || kind
+                    .as_str()
+                    .strip_prefix(requested.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    })
+}
+
+fn text_edits(mapper: &Mapper, edits: &[Edit]) -> Option<Vec<TextEdit>> {
+    edits
+        .iter()
+        .map(|edit| {
+            Some(TextEdit {
+                range: mapper.range(edit.range)?.into_lsp(),
+                new_text: edit.new_text.clone(),
+            })
+        })
+        .collect()
+}
+
+fn workspace_edit(uri: &Url, edits: Vec<TextEdit>) -> WorkspaceEdit {
+    WorkspaceEdit {
+        changes: Some(HashMap::from([(uri.clone(), edits)])),
+        ..Default::default()
+    }
+}
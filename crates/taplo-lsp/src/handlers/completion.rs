@@ -4,8 +4,8 @@ use lsp_async_stub::{
     Context, Params,
 };
 use lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse, CompletionTextEdit,
-    Documentation, InsertTextFormat, MarkupContent, Range, TextEdit,
+    CompletionItem, CompletionItemKind, CompletionItemTag, CompletionParams, CompletionResponse,
+    CompletionTextEdit, Documentation, InsertTextFormat, MarkupContent, Range, TextEdit,
 };
 use serde_json::Value;
 use std::borrow::Cow;
@@ -13,10 +13,14 @@ use std::fmt::Write as _;
 use taplo::dom::{node::TableKind, Keys, Node};
 use taplo_common::{
     environment::Environment,
-    schema::{ext::schema_ext_of, ValueExt},
+    schema::{
+        ext::{key_deprecation, schema_ext_of, value_deprecation, ExtDeprecated},
+        ValueExt,
+    },
 };
 
 use crate::{
+    handlers::mthds_completion::mthds_completions,
     query::{lookup_keys, Query},
     world::World,
 };
@@ -33,6 +37,21 @@ pub async fn completion<E: Environment>(
     let workspaces = context.workspaces.read().await;
     let ws = workspaces.by_document(&document_uri);
 
+    // References to the pipes and concepts of a `.mthds` bundle are completed
+    // from the bundle itself, schema or not.
+    if document_uri.as_str().ends_with(".mthds") {
+        if let Ok(doc) = ws.document(&document_uri) {
+            let position = p.text_document_position.position;
+            if let Some(offset) = doc.mapper.offset(Position::from_lsp(position)) {
+                if let Some(items) =
+                    mthds_completions(&context.env, ws, &document_uri, doc, offset).await
+                {
+                    return Ok(Some(CompletionResponse::Array(items)));
+                }
+            }
+        }
+    }
+
     // All completions are tied to schemas.
     if !ws.config.schema.enabled {
         return Ok(None);
@@ -130,6 +149,7 @@ pub async fn completion<E: Environment>(
                     label: full_key.to_string(),
                     kind: Some(CompletionItemKind::STRUCT),
                     documentation: documentation(&s),
+                    tags: deprecated_tags(key_deprecation(&s)),
                     text_edit: key_range.map(|r| {
                         CompletionTextEdit::Edit(TextEdit {
                             range: doc.mapper.range(r).unwrap().into_lsp(),
@@ -177,6 +197,7 @@ pub async fn completion<E: Environment>(
                     label: full_key.to_string(),
                     kind: Some(CompletionItemKind::STRUCT),
                     documentation: documentation(&s),
+                    tags: deprecated_tags(key_deprecation(&s)),
                     text_edit: key_range.map(|r| {
                         CompletionTextEdit::Edit(TextEdit {
                             range: doc.mapper.range(r).unwrap().into_lsp(),
@@ -221,6 +242,7 @@ pub async fn completion<E: Environment>(
                     label: relative_keys.to_string(),
                     kind: Some(CompletionItemKind::VARIABLE),
                     documentation: documentation(&schema),
+                    tags: deprecated_tags(key_deprecation(&schema)),
                     insert_text_format: Some(InsertTextFormat::SNIPPET),
                     insert_text: Some(new_entry_snippet(&relative_keys, &schema, false)),
                     ..Default::default()
@@ -268,6 +290,7 @@ pub async fn completion<E: Environment>(
                     label: relative_keys.to_string(),
                     kind: Some(CompletionItemKind::VARIABLE),
                     documentation: documentation(&schema),
+                    tags: deprecated_tags(key_deprecation(&schema)),
                     text_edit: key_range.map(|r| {
                         CompletionTextEdit::Edit(TextEdit {
                             range: doc.mapper.range(r).unwrap().into_lsp(),
@@ -328,6 +351,7 @@ pub async fn completion<E: Environment>(
                         label: relative_keys.to_string(),
                         kind: Some(CompletionItemKind::VARIABLE),
                         documentation: documentation(&schema),
+                        tags: deprecated_tags(key_deprecation(&schema)),
                         insert_text_format: Some(InsertTextFormat::SNIPPET),
                         insert_text: Some(new_entry_snippet(&relative_keys, &schema, false)),
                         ..Default::default()
@@ -426,6 +450,7 @@ pub async fn completion<E: Environment>(
                 label: relative_keys.to_string(),
                 kind: Some(CompletionItemKind::VARIABLE),
                 documentation: documentation(&schema),
+                tags: deprecated_tags(key_deprecation(&schema)),
                 insert_text_format: Some(InsertTextFormat::SNIPPET),
                 text_edit: Some(CompletionTextEdit::Edit(TextEdit {
                     range: doc
@@ -463,6 +488,11 @@ fn documentation(schema: &Value) -> Option<Documentation> {
     None
 }
 
+/// Strikes deprecated keys and values through in the completion list.
+fn deprecated_tags(deprecated: Option<ExtDeprecated>) -> Option<Vec<CompletionItemTag>> {
+    deprecated.map(|_| vec![CompletionItemTag::DEPRECATED])
+}
+
 fn add_value_completions(
     schema: &Value,
     range: Option<Range>,
@@ -496,6 +526,7 @@ fn add_value_completions(
                     Node::Table(_) => CompletionItemKind::STRUCT,
                     _ => CompletionItemKind::VALUE,
                 }),
+                tags: deprecated_tags(value_deprecation(schema)),
                 documentation: enum_docs
                     .get(idx)
                     .cloned()
@@ -529,6 +560,7 @@ fn add_value_completions(
                     Node::Table(_) => CompletionItemKind::STRUCT,
                     _ => CompletionItemKind::VALUE,
                 }),
+                tags: deprecated_tags(value_deprecation(schema)),
                 documentation: ext_docs
                     .const_value
                     .or_else(|| schema_docs.clone())
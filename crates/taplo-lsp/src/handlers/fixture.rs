@@ -0,0 +1,47 @@
+use lsp_async_stub::{rpc::Error, Context, Params};
+use pipelex_common::tools::fixture::fixture;
+use taplo_common::environment::Environment;
+
+use super::sibling_files;
+use crate::{
+    lsp_ext::request::{PipeFixtureParams, PipeFixtureResponse},
+    world::World,
+};
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn pipe_fixture<E: Environment>(
+    context: Context<World<E>>,
+    params: Params<PipeFixtureParams>,
+) -> Result<PipeFixtureResponse, Error> {
+    let p = params.required()?;
+
+    let workspaces = context.workspaces.read().await;
+    let ws = workspaces.by_document(&p.document_uri);
+    let doc = match ws.document(&p.document_uri) {
+        Ok(d) => d,
+        Err(error) => {
+            return Ok(PipeFixtureResponse {
+                fixture: None,
+                error: Some(error.to_string()),
+            })
+        }
+    };
+
+    let mut siblings = Vec::new();
+    if let Some(path) = context.env.to_file_path_normalized(&p.document_uri) {
+        for sibling in sibling_files(&context.env, ws, &path).await {
+            siblings.push(sibling.dom);
+        }
+    }
+
+    match fixture(&doc.dom, &siblings, &p.pipe, &ws.natives) {
+        Ok(fixture) => Ok(PipeFixtureResponse {
+            fixture: Some(fixture),
+            error: None,
+        }),
+        Err(error) => Ok(PipeFixtureResponse {
+            fixture: None,
+            error: Some(error.to_string()),
+        }),
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     handlers::mthds_resolution::{classify_reference, resolve_reference, ReferenceKind},
     query::Query,
-    world::{DocumentState, World},
+    world::{DocumentState, WorkspaceState, World},
 };
 use lsp_async_stub::{
     rpc::Error,
@@ -199,14 +199,65 @@ async fn resolve_pipe_definition_across_bundle<E: Environment>(
     select_preferred_pipe_definition(definitions, preferred_domain)?.into_location()
 }
 
-fn bundle_mthds_paths<E: Environment>(env: &E, current_dir: &Path) -> Vec<PathBuf> {
+pub(crate) fn bundle_mthds_paths<E: Environment>(env: &E, current_dir: &Path) -> Vec<PathBuf> {
     let mut pattern = current_dir.to_path_buf();
     pattern.push("*.mthds");
     env.glob_files_normalized(&pattern.to_string_lossy())
         .unwrap_or_default()
 }
 
-fn file_url_from_path(path: &Path) -> Option<Url> {
+/// A `.mthds` file of a bundle, open in the editor or read from disk.
+pub(crate) struct BundleFile {
+    pub(crate) uri: Url,
+    pub(crate) dom: Node,
+    pub(crate) mapper: Mapper,
+}
+
+/// The other `.mthds` files of the bundle `path` belongs to, taking the open
+/// document over the file on disk when there is one.
+pub(crate) async fn sibling_files<E: Environment>(
+    env: &E,
+    ws: &WorkspaceState<E>,
+    path: &Path,
+) -> Vec<BundleFile> {
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut siblings = Vec::new();
+    for sibling_path in bundle_mthds_paths(env, dir) {
+        if sibling_path == path {
+            continue;
+        }
+        let Some(sibling_uri) = file_url_from_path(&sibling_path) else {
+            continue;
+        };
+        let open = ws.documents.iter().find(|(open_uri, _)| {
+            env.to_file_path_normalized(open_uri).as_ref() == Some(&sibling_path)
+        });
+        if let Some((open_uri, open)) = open {
+            siblings.push(BundleFile {
+                uri: open_uri.clone(),
+                dom: open.dom.clone(),
+                mapper: open.mapper.clone(),
+            });
+            continue;
+        }
+        let Ok(bytes) = env.read_file(&sibling_path).await else {
+            continue;
+        };
+        let Ok(source) = String::from_utf8(bytes) else {
+            continue;
+        };
+        siblings.push(BundleFile {
+            uri: sibling_uri,
+            dom: parser::parse(&source).into_dom(),
+            mapper: Mapper::new_utf16(&source, false),
+        });
+    }
+    siblings
+}
+
+pub(crate) fn file_url_from_path(path: &Path) -> Option<Url> {
     let path = path.to_string_lossy().replace('\\', "/");
     let mut url = String::from("file://");
     if !path.starts_with('/') {
@@ -1,7 +1,13 @@
 use crate::{
-    handlers::mthds_resolution::{
-        classify_reference, extract_string_value, find_native_concept, find_string_position_info,
-        is_model_field, resolve_reference, NativeConcept, ReferenceKind, ResolvedReference,
+    handlers::{
+        mthds_actions::declared_inputs,
+        mthds_completion::{concept_candidates, ConceptCandidate},
+        mthds_resolution::{
+            classify_reference, extract_string_value, find_string_position_info, is_model_field,
+            resolve_reference, NativeConcepts, ReferenceKind, ResolvedReference,
+        },
+        mthds_templates::{concept_fields, template_variable_at, TemplateVariable},
+        sibling_files,
     },
     query::{lookup_keys, Query},
     world::World,
@@ -13,6 +19,11 @@ use lsp_async_stub::{
     Context, Params,
 };
 use lsp_types::{Hover, HoverContents, HoverParams, MarkupContent, MarkupKind};
+use pipelex_common::tools::{
+    bundle::strip_concept_qualifiers,
+    hover::{concept_hover, native_concept_hover, pipe_hover},
+    models::{split_model_ref, DeckEntry, ModelDeck},
+};
 use serde_json::Value;
 use taplo::{
     dom::{KeyOrIndex, Keys},
@@ -22,7 +33,10 @@ use taplo::{
         STRING_LITERAL, TIME,
     },
 };
-use taplo_common::{environment::Environment, schema::ext::schema_ext_of};
+use taplo_common::{
+    environment::Environment,
+    schema::ext::{key_deprecation, schema_ext_of},
+};
 
 #[tracing::instrument(skip_all)]
 pub(crate) async fn hover<E: Environment>(
@@ -61,6 +75,27 @@ pub(crate) async fn hover<E: Environment>(
                 .map(|r| r.into_lsp())
         };
 
+        // Template variables resolve to the input they bind to, or to the
+        // structure field their `.field` segments reach.
+        if let Some(variable) = template_variable_at(&doc.dom, offset) {
+            let mut concepts = concept_candidates(&doc.dom, true);
+            if let Some(path) = context.env.to_file_path_normalized(&document_uri) {
+                for sibling in sibling_files(&context.env, ws, &path).await {
+                    concepts.extend(concept_candidates(&sibling.dom, false));
+                }
+            }
+            if let Some(content) = build_template_variable_hover(&variable, &concepts, &ws.natives)
+            {
+                return Ok(Some(Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: content,
+                    }),
+                    range: doc.mapper.range(variable.range).map(|r| r.into_lsp()),
+                }));
+            }
+        }
+
         if let Some(resolved) = resolve_reference(&doc.dom, &query) {
             let content = build_mthds_hover_content(&resolved);
             if !content.is_empty() {
@@ -74,8 +109,8 @@ pub(crate) async fn hover<E: Environment>(
             }
         } else if let Some(classified) = classify_reference(&query) {
             if matches!(classified.kind, ReferenceKind::Concept) {
-                if let Some(native) = find_native_concept(&classified.ref_name) {
-                    let content = build_native_concept_hover(native);
+                if let Some(native) = ws.natives.find(&classified.ref_name) {
+                    let content = native_concept_hover(native, &ws.natives);
                     return Ok(Some(Hover {
                         contents: HoverContents::Markup(MarkupContent {
                             kind: MarkupKind::Markdown,
@@ -87,23 +122,23 @@ pub(crate) async fn hover<E: Environment>(
             }
         }
 
-        // TODO: Improve model hover with model deck data (preset descriptions,
-        // resolved model names, etc.) once model deck access is available.
         if is_model_field(&query) {
             if let Some(pi) = find_string_position_info(&query) {
                 let value = extract_string_value(pi);
                 if !value.is_empty() {
-                    // Look up the pipe type from the parent table's "type" field.
+                    // Look up the pipe type from the table holding the field:
+                    // `pipe.xyz.model`, or `pipe.xyz.model.model` in a setting
+                    // table.
                     let pipe_type = pi.dom_node.as_ref().and_then(|(keys, _)| {
-                        // keys points to e.g. pipe.xyz.model — skip last to get pipe.xyz
-                        let parent_keys = keys.skip_right(1);
-                        let parent = doc.dom.path(&parent_keys)?;
-                        let table = parent.as_table()?;
-                        table
-                            .get("type")
-                            .and_then(|n| n.as_str().map(|s| s.value().to_string()))
+                        (1..=2).find_map(|depth| {
+                            let parent = doc.dom.path(&keys.skip_right(depth))?;
+                            let table = parent.as_table()?;
+                            table
+                                .get("type")
+                                .and_then(|n| n.as_str().map(|s| s.value().to_string()))
+                        })
                     });
-                    let content = build_model_hover(&value, pipe_type.as_deref());
+                    let content = build_model_hover(&value, pipe_type.as_deref(), &ws.models);
                     return Ok(Some(Hover {
                         contents: HoverContents::Markup(MarkupContent {
                             kind: MarkupKind::Markdown,
@@ -215,6 +250,18 @@ pub(crate) async fn hover<E: Environment>(
                         s += desc;
                     }
 
+                    if let Some(deprecated) = key_deprecation(schema) {
+                        let note = deprecated.message.map_or_else(
+                            || "**Deprecated**".to_string(),
+                            |message| format!("**Deprecated:** {message}"),
+                        );
+                        s = if s.is_empty() {
+                            note
+                        } else {
+                            format!("{note}\n\n{s}")
+                        };
+                    }
+
                     let link_title = schema["title"].as_str().unwrap_or("...");
 
                     if links_in_hover {
@@ -351,153 +398,149 @@ pub(crate) async fn hover<E: Environment>(
 
 /// Build rich Markdown hover content for a resolved MTHDS reference.
 pub(crate) fn build_mthds_hover_content(resolved: &ResolvedReference) -> String {
-    let table = match resolved.target_node.as_table() {
-        Some(t) => t,
-        None => return String::new(),
-    };
-
-    let mut parts: Vec<String> = Vec::new();
-
     match resolved.kind {
-        ReferenceKind::Pipe => {
-            // Header: **pipe_name** `PipeType`
-            let type_str = table
-                .get("type")
-                .and_then(|n| n.as_str().map(|s| s.value().to_string()));
-            let header = match &type_str {
-                Some(t) => format!("**{}** `{}`", resolved.ref_name, t),
-                None => format!("**{}**", resolved.ref_name),
-            };
-            parts.push(header);
-
-            // Description
-            if let Some(desc) = table
-                .get("description")
-                .and_then(|n| n.as_str().map(|s| s.value().to_string()))
-            {
-                if !desc.is_empty() {
-                    parts.push(desc);
-                }
-            }
-
-            // Inputs
-            if let Some(inputs_node) = table.get("inputs") {
-                if let Some(inputs_table) = inputs_node.as_table() {
-                    let entries = inputs_table.entries().read();
-                    let input_strs: Vec<String> = entries
-                        .iter()
-                        .map(|(k, v)| {
-                            let concept = v
-                                .as_str()
-                                .map(|s| s.value().to_string())
-                                .unwrap_or_else(|| "?".to_string());
-                            format!("`{}`: {}", k.value(), concept)
-                        })
-                        .collect();
-                    if !input_strs.is_empty() {
-                        parts.push(format!("**Inputs:** {}", input_strs.join(", ")));
-                    }
-                }
-            }
+        ReferenceKind::Pipe => pipe_hover(&resolved.ref_name, &resolved.target_node),
+        ReferenceKind::Concept => concept_hover(&resolved.ref_name, &resolved.target_node),
+    }
+}
 
-            // Output
-            if let Some(output) = table
-                .get("output")
-                .and_then(|n| n.as_str().map(|s| s.value().to_string()))
-            {
-                if !output.is_empty() {
-                    parts.push(format!("**Output:** `{}`", output));
-                }
-            }
+/// Build Markdown hover content for a template variable.
+///
+/// A bare variable shows the input it binds to — its concept, with the
+/// concept's description and fields. A dotted variable shows the structure
+/// field its last segment names, with the field's type and description.
+/// Returns `None` when the variable names no input or no field.
+pub(crate) fn build_template_variable_hover(
+    variable: &TemplateVariable,
+    concepts: &[ConceptCandidate],
+    natives: &NativeConcepts,
+) -> Option<String> {
+    let (root, fields) = variable.path.split_first()?;
+    let (_, mut concept) = declared_inputs(&variable.pipe)
+        .into_iter()
+        .find(|(name, _)| name == root)?;
+
+    let Some((last, between)) = fields.split_last() else {
+        let mut parts = vec![format!("**{root}** — input `{concept}`")];
+        let name = strip_concept_qualifiers(&concept);
+        if let Some(candidate) = concepts.iter().find(|candidate| candidate.name == name) {
+            let content = match candidate.concept.as_str() {
+                Some(description) => description.value().to_string(),
+                None => build_mthds_hover_content(&ResolvedReference {
+                    kind: ReferenceKind::Concept,
+                    ref_name: name,
+                    target_node: candidate.concept.clone(),
+                }),
+            };
+            parts.push(content);
+        } else if let Some(native) = natives.find(&name) {
+            parts.push(native_concept_hover(native, natives));
         }
-        ReferenceKind::Concept => {
-            // Header: **ConceptName**
-            parts.push(format!("**{}**", resolved.ref_name));
-
-            // Description
-            if let Some(desc) = table
-                .get("description")
-                .and_then(|n| n.as_str().map(|s| s.value().to_string()))
-            {
-                if !desc.is_empty() {
-                    parts.push(desc);
-                }
-            }
-
-            // Refines
-            if let Some(refines) = table
-                .get("refines")
-                .and_then(|n| n.as_str().map(|s| s.value().to_string()))
-            {
-                if !refines.is_empty() {
-                    parts.push(format!("**Refines:** `{}`", refines));
-                }
-            }
+        return Some(parts.join("\n\n"));
+    };
 
-            // Structure fields
-            if let Some(structure_node) = table.get("structure") {
-                if let Some(structure_table) = structure_node.as_table() {
-                    let entries = structure_table.entries().read();
-                    let field_names: Vec<String> = entries
-                        .iter()
-                        .map(|(k, _)| format!("`{}`", k.value()))
-                        .collect();
-                    if !field_names.is_empty() {
-                        parts.push(format!("**Fields:** {}", field_names.join(", ")));
-                    }
-                }
-            }
-        }
+    for segment in between {
+        concept = concept_fields(&concept, concepts, natives)
+            .into_iter()
+            .find(|field| field.name == *segment)?
+            .concept?;
     }
-
-    parts.join("\n\n")
+    let field = concept_fields(&concept, concepts, natives)
+        .into_iter()
+        .find(|field| field.name == *last)?;
+    let mut parts = vec![format!(
+        "**{}** — field `{}`",
+        variable.path.join("."),
+        field.detail
+    )];
+    parts.extend(field.description.filter(|d| !d.is_empty()));
+    Some(parts.join("\n\n"))
 }
 
-/// Build a simple hover for a model field value.
+/// Build the hover for a model field value.
 ///
 /// Recognizes the prefix convention (`$` preset, `@` alias, `~` waterfall,
 /// `#` handle) and shows a short, readable label.
 ///
 /// When `pipe_type` is provided (e.g. `"PipeLLM"`), strips the `"Pipe"` prefix
 /// and prepends it to give context: `**gpt-4o** — LLM model preset`.
-pub(crate) fn build_model_hover(value: &str, pipe_type: Option<&str>) -> String {
-    let (kind, name) = match value.chars().next() {
-        Some('$') => ("preset", &value[1..]),
-        Some('@') => ("alias", &value[1..]),
-        Some('~') => ("waterfall", &value[1..]),
-        Some('#') => ("handle", &value[1..]),
-        _ => ("", value),
+///
+/// With a model deck, adds what the deck declares: a preset's description and
+/// settings, a waterfall's models, the chain of names the reference resolves
+/// through and the provider and settings of the handle it ends at — or that
+/// the deck does not declare it.
+pub(crate) fn build_model_hover(
+    value: &str,
+    pipe_type: Option<&str>,
+    models: &ModelDeck,
+) -> String {
+    let found = models.lookup(value);
+    let (kind, name) = match split_model_ref(value) {
+        (Some(kind), name) => (kind.label(), name),
+        (None, name) => (found.map_or("", |(kind, _)| kind.label()), name),
     };
     let type_prefix = pipe_type
         .and_then(|t| t.strip_prefix("Pipe"))
         .filter(|s| !s.is_empty());
-    match (type_prefix, kind) {
+    let header = match (type_prefix, kind) {
         (Some(prefix), "") => format!("**{}** — {} model", name, prefix),
         (Some(prefix), _) => format!("**{}** — {} model {}", name, prefix, kind),
         (_, "") => format!("**{}** — model", name),
         (_, _) => format!("**{}** — model {}", name, kind),
-    }
-}
-
-/// Build Markdown hover content for a native (built-in) concept.
-pub(crate) fn build_native_concept_hover(concept: &NativeConcept) -> String {
-    let mut parts: Vec<String> = Vec::new();
-
-    parts.push(format!("**{}** *(native)*", concept.name));
-    parts.push(concept.description.to_string());
+    };
 
-    if !concept.fields.is_empty() {
-        let field_strs: Vec<String> = concept
-            .fields
+    let mut parts = vec![header];
+    let Some((_, entry)) = found else {
+        if !models.is_empty() {
+            parts.push("*Not declared in the model deck.*".to_string());
+        }
+        return parts.join("\n\n");
+    };
+    match entry {
+        DeckEntry::Preset(preset) => {
+            parts.extend(preset.description.clone());
+            if !preset.settings.is_empty() {
+                parts.push(format!("**Settings:** {}", settings_list(&preset.settings)));
+            }
+        }
+        DeckEntry::Waterfall(tried) => {
+            let tried = tried.iter().map(|model| format!("`{model}`")).join(", ");
+            parts.push(format!("**Tries:** {tried}"));
+        }
+        DeckEntry::Alias(_) | DeckEntry::Handle(_) => {}
+    }
+    let (chain, handle) = models.resolve(value);
+    if chain.len() > 1 {
+        let chain = chain[1..]
             .iter()
-            .map(|(name, ty)| format!("`{}`: {}", name, ty))
-            .collect();
-        parts.push(format!("**Fields:** {}", field_strs.join(", ")));
+            .map(|name| format!("`{name}`"))
+            .join(" → ");
+        parts.push(format!("**Resolves to:** {chain}"));
+    }
+    if let Some(handle) = handle {
+        parts.push(match handle.kind {
+            Some(kind) => format!(
+                "**Provider:** `{}` ({} model)",
+                handle.provider,
+                kind.label()
+            ),
+            None => format!("**Provider:** `{}`", handle.provider),
+        });
+        if !handle.settings.is_empty() {
+            parts.push(format!("**Model:** {}", settings_list(&handle.settings)));
+        }
     }
-
     parts.join("\n\n")
 }
 
+/// `key = value` settings as inline code, comma-separated.
+fn settings_list(settings: &[(String, String)]) -> String {
+    settings
+        .iter()
+        .map(|(key, value)| format!("`{key} = {value}`"))
+        .join(", ")
+}
+
 fn is_primitive(kind: SyntaxKind) -> bool {
     matches!(
         kind,
@@ -1,15 +1,16 @@
 use std::sync::Arc;
 
-use super::{semantic_tokens, update_configuration};
+use super::{mthds_move::REFACTOR_MOVE, semantic_tokens, update_configuration, FIX_ALL};
 use crate::config::InitConfig;
 use crate::world::WorkspaceState;
 use crate::World;
 use lsp_async_stub::{rpc::Error, Context, Params};
 use lsp_types::{
-    CompletionOptions, DocumentLinkOptions, FoldingRangeProviderCapability,
-    HoverProviderCapability, InitializedParams, OneOf, PositionEncodingKind, RenameOptions,
-    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
-    SemanticTokensServerCapabilities, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
+    CodeActionKind, CodeActionOptions, CodeActionProviderCapability, CompletionOptions,
+    DocumentLinkOptions, FoldingRangeProviderCapability, HoverProviderCapability,
+    InitializedParams, OneOf, PositionEncodingKind, RenameOptions, SemanticTokensFullOptions,
+    SemanticTokensLegend, SemanticTokensOptions, SemanticTokensServerCapabilities,
+    ServerCapabilities, ServerInfo, SignatureHelpOptions, TextDocumentSyncCapability,
     TextDocumentSyncKind, WorkDoneProgressOptions, WorkspaceFoldersServerCapabilities,
     WorkspaceServerCapabilities,
 };
@@ -80,6 +81,17 @@ pub async fn initialize<E: Environment>(
                 prepare_provider: Some(true),
                 work_done_progress_options: Default::default(),
             })),
+            code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                code_action_kinds: Some(vec![
+                    CodeActionKind::QUICKFIX,
+                    CodeActionKind::REFACTOR_REWRITE,
+                    CodeActionKind::REFACTOR_EXTRACT,
+                    REFACTOR_MOVE.into(),
+                    FIX_ALL.into(),
+                ]),
+                resolve_provider: Some(false),
+                work_done_progress_options: Default::default(),
+            })),
             folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
             document_symbol_provider: Some(OneOf::Left(true)),
             document_formatting_provider: Some(OneOf::Left(true)),
@@ -93,10 +105,17 @@ pub async fn initialize<E: Environment>(
                     "{".into(),
                     ",".into(),
                     "\"".into(),
+                    "@".into(),
                 ]),
                 ..Default::default()
             }),
             definition_provider: Some(OneOf::Left(true)),
+            inlay_hint_provider: Some(OneOf::Left(true)),
+            signature_help_provider: Some(SignatureHelpOptions {
+                trigger_characters: Some(vec!["{".into(), ",".into()]),
+                retrigger_characters: None,
+                work_done_progress_options: Default::default(),
+            }),
             document_link_provider: Some(DocumentLinkOptions {
                 resolve_provider: None,
                 work_done_progress_options: Default::default(),
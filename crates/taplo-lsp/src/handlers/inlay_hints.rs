@@ -0,0 +1,166 @@
+//! Inlay hints for `.mthds` files: the concept each step's `result` holds,
+//! the signature of the pipe a step or branch calls, and the item concept a
+//! `batch_as` name is bound to.
+//!
+//! Callees are looked up with [`resolve_pipe_call`], the resolution hover
+//! uses, so a hint appears exactly where hovering the `pipe` value would show
+//! the pipe.
+
+use super::{
+    mthds_actions::{declared_inputs, item_concept},
+    mthds_resolution::resolve_pipe_call,
+};
+use crate::world::World;
+use lsp_async_stub::{
+    rpc::Error,
+    util::{LspExt, Range},
+    Context, Params,
+};
+use lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, InlayHintParams};
+use pipelex_common::tools::bundle::string_of;
+use taplo::{
+    dom::Node,
+    rowan::{TextRange, TextSize},
+};
+use taplo_common::environment::Environment;
+
+/// One inlay hint, placed right after the string value it annotates.
+pub(crate) struct Hint {
+    pub(crate) offset: TextSize,
+    pub(crate) label: String,
+    pub(crate) kind: InlayHintKind,
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn inlay_hints<E: Environment>(
+    context: Context<World<E>>,
+    params: Params<InlayHintParams>,
+) -> Result<Option<Vec<InlayHint>>, Error> {
+    let p = params.required()?;
+
+    let document_uri = p.text_document.uri;
+    if !document_uri.as_str().ends_with(".mthds") {
+        return Ok(None);
+    }
+
+    let workspaces = context.workspaces.read().await;
+    let ws = workspaces.by_document(&document_uri);
+    let doc = match ws.document(&document_uri) {
+        Ok(d) => d,
+        Err(error) => {
+            tracing::debug!(%error, "failed to get document from workspace");
+            return Ok(None);
+        }
+    };
+
+    let Some(range) = doc.mapper.text_range(Range::from_lsp(p.range)) else {
+        return Ok(None);
+    };
+
+    Ok(Some(
+        mthds_inlay_hints(&doc.dom, range)
+            .into_iter()
+            .filter_map(|hint| {
+                Some(InlayHint {
+                    position: doc.mapper.position(hint.offset)?.into_lsp(),
+                    label: InlayHintLabel::String(hint.label),
+                    kind: Some(hint.kind),
+                    text_edits: None,
+                    tooltip: None,
+                    padding_left: None,
+                    padding_right: None,
+                    data: None,
+                })
+            })
+            .collect(),
+    ))
+}
+
+/// The hints of the steps of every `PipeSequence` and the branches of every
+/// `PipeParallel` that fall within `range`:
+///
+/// - `(input: Concept, …)` after `pipe = "name"`, from the callee's `inputs`;
+/// - `: Concept` after `result = "name"`, from the callee's `output`;
+/// - `: Concept` after `batch_as = "name"`, from the callee's input of that
+///   name, or else one item of the list the step batches over.
+///
+/// Calls to pipes that are not defined in the document get no hints.
+pub(crate) fn mthds_inlay_hints(dom: &Node, range: TextRange) -> Vec<Hint> {
+    let mut hints = Vec::new();
+    let pipes = dom.get("pipe");
+    let Some(pipes) = pipes.as_table() else {
+        return hints;
+    };
+    let entries = pipes.entries().read();
+    for (_, pipe) in entries.iter() {
+        // The working-memory names a step sees: the pipe's inputs and the
+        // results of earlier steps.
+        let mut visible = declared_inputs(pipe);
+        for key in ["steps", "branches"] {
+            let calls = pipe.get(key);
+            let Some(calls) = calls.as_array() else {
+                continue;
+            };
+            for call in calls.items().read().iter() {
+                let callee = resolve_pipe_call(dom, &call.get("pipe"));
+                let callee_inputs = callee.as_ref().map(declared_inputs).unwrap_or_default();
+
+                if let (Some(_), Some(end)) = (&callee, string_end(&call.get("pipe"))) {
+                    let signature = callee_inputs
+                        .iter()
+                        .map(|(name, concept)| format!("{name}: {concept}"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    hints.push(Hint {
+                        offset: end,
+                        label: format!("({signature})"),
+                        kind: InlayHintKind::PARAMETER,
+                    });
+                }
+
+                let batch_as = call.get("batch_as");
+                if let (Some(item), Some(end)) = (string_of(&batch_as), string_end(&batch_as)) {
+                    let concept = lookup(&callee_inputs, &item).or_else(|| {
+                        let list = string_of(&call.get("batch_over"))?;
+                        lookup(&visible, &list).map(|concept| item_concept(&concept))
+                    });
+                    if let Some(concept) = concept {
+                        hints.push(Hint {
+                            offset: end,
+                            label: format!(": {concept}"),
+                            kind: InlayHintKind::TYPE,
+                        });
+                    }
+                }
+
+                let result = call.get("result");
+                let output = callee.as_ref().and_then(|c| string_of(&c.get("output")));
+                if let (Some(output), Some(end)) = (output, string_end(&result)) {
+                    hints.push(Hint {
+                        offset: end,
+                        label: format!(": {output}"),
+                        kind: InlayHintKind::TYPE,
+                    });
+                    if let Some(name) = string_of(&result) {
+                        visible.push((name, output));
+                    }
+                }
+            }
+        }
+    }
+    hints.retain(|hint| range.contains_inclusive(hint.offset));
+    hints
+}
+
+fn lookup(names: &[(String, String)], name: &str) -> Option<String> {
+    names
+        .iter()
+        .rev()
+        .find(|(candidate, _)| candidate == name)
+        .map(|(_, concept)| concept.clone())
+}
+
+fn string_end(node: &Node) -> Option<TextSize> {
+    node.as_str()?;
+    Some(node.text_ranges(false).next()?.end())
+}
@@ -0,0 +1,642 @@
+//! MTHDS-specific code actions: creating the pipe or concept an unresolved
+//! reference names, declaring a template variable in `inputs`, expanding a
+//! structure field shorthand into a full field table, and extracting steps of
+//! a sequence into a new one.
+//!
+//! Each action is computed from the document alone and returned as a [`Fix`],
+//! so the handler maps it to LSP edits like any lint fix. None of them is
+//! `safe`: they insert placeholders (`"TODO"` descriptions, `Text` inputs)
+//! that still need writing.
+
+use pipelex_common::tools::{
+    bundle::{string_of, BundleIndex, RefKind, Reference},
+    fixes::{Edit, Fix},
+    jinja::{self, pipe_templates, variables::variables, TemplateSource},
+};
+use taplo::{
+    dom::{
+        node::DomNode,
+        rewrite::{PendingPatchKind, Rewrite},
+        Node,
+    },
+    formatter,
+    rowan::{TextRange, TextSize},
+    syntax::{SyntaxElement, SyntaxKind, SyntaxNode},
+    util::escape,
+};
+
+/// The concept given to inputs and outputs nothing else can be inferred for.
+const PLACEHOLDER_CONCEPT: &str = "Text";
+
+/// "Create pipe stub" or "Create concept" for the unresolved reference whose
+/// string spans `range` (the range of its `mthds/undefined-*` diagnostic).
+///
+/// References qualified with another domain are left alone: their
+/// definition belongs to another bundle.
+pub(crate) fn create_definition(dom: &Node, range: TextRange) -> Option<Fix> {
+    let index = BundleIndex::from_dom(dom);
+    let reference = index
+        .references
+        .iter()
+        .find(|reference| reference.range == range)?;
+    if reference.domain.is_some() && reference.domain != index.domain {
+        return None;
+    }
+    match reference.kind {
+        RefKind::Pipe => create_pipe_stub(dom, reference),
+        RefKind::Concept => create_concept(dom, reference),
+    }
+}
+
+/// "Add `variable` to `inputs`" when `offset` is on a template variable the
+/// enclosing pipe does not declare.
+pub(crate) fn add_missing_input(dom: &Node, offset: TextSize) -> Option<Fix> {
+    let pipes = dom.get("pipe");
+    let pipes = pipes.as_table()?;
+    let entries = pipes.entries().read();
+    for (_, pipe) in entries.iter() {
+        for (_, template) in pipe_templates(pipe) {
+            let Some(source) = TemplateSource::from_node(&template) else {
+                continue;
+            };
+            let Some(variable) = variables(&jinja::parse(&source.text).template)
+                .into_iter()
+                .find(|variable| {
+                    source
+                        .document_range(variable.range)
+                        .contains_inclusive(offset)
+                })
+                .map(|variable| variable.name().to_string())
+            else {
+                continue;
+            };
+            let inputs = pipe.get("inputs");
+            if !inputs.get(variable.as_str()).is_invalid() {
+                return None;
+            }
+            let entry = format!("{} = \"{PLACEHOLDER_CONCEPT}\"", toml_key(&variable));
+            let (range, new_text) = if inputs.is_invalid() {
+                declare_inputs(pipe, &entry)?
+            } else {
+                append_entry(&inputs, &entry)?
+            };
+            return Some(Fix::replace(
+                format!("Add `{variable}` to `inputs`"),
+                false,
+                range,
+                new_text,
+            ));
+        }
+    }
+    None
+}
+
+/// "Convert to a `{ type, description, required }` table" when `offset` is on
+/// a `field = "description"` shorthand in a concept's `structure`. The
+/// shorthand stands for an optional text field.
+pub(crate) fn expand_structure_field(dom: &Node, offset: TextSize) -> Option<Fix> {
+    let concepts = dom.get("concept");
+    let concepts = concepts.as_table()?;
+    let entries = concepts.entries().read();
+    for (_, concept) in entries.iter() {
+        let structure = concept.get("structure");
+        let Some(structure) = structure.as_table() else {
+            continue;
+        };
+        for (key, field) in structure.entries().read().iter() {
+            if field.as_str().is_none() {
+                continue;
+            }
+            let Some(value) = field.syntax() else {
+                continue;
+            };
+            let on_field =
+                entry_of(value).is_some_and(|entry| entry.text_range().contains_inclusive(offset));
+            if !on_field {
+                continue;
+            }
+            return Some(Fix::replace(
+                format!(
+                    "Convert `{}` to a `{{ type, description, required }}` table",
+                    key.value()
+                ),
+                false,
+                value.text_range(),
+                format!("{{ type = \"text\", description = {value}, required = false }}"),
+            ));
+        }
+    }
+    None
+}
+
+/// "Extract steps into a new PipeSequence" when `range` covers two or more
+/// entries of a `steps` array.
+///
+/// The new sequence takes the working-memory names the steps consume and
+/// were not produced among them, and outputs what the last step's pipe does.
+/// The steps are replaced by one call to it, keeping the last step's
+/// `result`. The extraction is refused when a later step consumes the
+/// `result` of another extracted step, which the new sequence would hide.
+pub(crate) fn extract_steps(
+    dom: &Node,
+    range: TextRange,
+    options: &formatter::Options,
+) -> Option<Fix> {
+    let pipes = dom.get("pipe");
+    let pipes = pipes.as_table()?;
+    let entries = pipes.entries().read();
+    let (sequence, pipe) =
+        entries.iter().find_map(|(key, pipe)| {
+            let steps = pipe.get("steps");
+            let in_range =
+                steps.as_array()?.items().read().iter().any(|step| {
+                    step_range(step).is_some_and(|step| step.intersect(range).is_some())
+                });
+            in_range.then(|| (key.value().to_string(), pipe.clone()))
+        })?;
+    let inputs = declared_inputs(&pipe);
+    let steps: Vec<Node> = pipe
+        .get("steps")
+        .as_array()?
+        .items()
+        .read()
+        .iter()
+        .cloned()
+        .collect();
+
+    let selected: Vec<usize> = (0..steps.len())
+        .filter(|&i| step_range(&steps[i]).is_some_and(|step| step.intersect(range).is_some()))
+        .collect();
+    let (&first, &last) = (selected.first()?, selected.last()?);
+    if first == last {
+        return None;
+    }
+    let extracted = &steps[first..=last];
+
+    // What the extracted steps need from before them, in order of use.
+    let visible = visible_names(dom, inputs, &steps[..first]);
+    let mut produced: Vec<String> = Vec::new();
+    let mut consumed: Vec<(String, String)> = Vec::new();
+    for step in extracted {
+        for (name, concept) in step_consumes(dom, step) {
+            if produced.contains(&name) || consumed.iter().any(|(known, _)| *known == name) {
+                continue;
+            }
+            let concept = visible
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map_or(concept, |(_, concept)| concept.clone());
+            consumed.push((name, concept));
+        }
+        produced.extend(string_of(&step.get("result")));
+    }
+
+    let result = string_of(&extracted[extracted.len() - 1].get("result"));
+    let hidden: Vec<&String> = produced
+        .iter()
+        .filter(|name| Some(*name) != result.as_ref())
+        .collect();
+    let hides_a_result = steps[last + 1..].iter().any(|step| {
+        step_consumes(dom, step)
+            .iter()
+            .any(|(name, _)| hidden.contains(&name))
+    });
+    if hides_a_result {
+        return None;
+    }
+
+    let output = string_of(&extracted[extracted.len() - 1].get("pipe"))
+        .and_then(|pipe| pipe_output(dom, &pipe))
+        .unwrap_or_else(|| PLACEHOLDER_CONCEPT.to_string());
+
+    let mut name = format!("{sequence}_part");
+    let mut suffix = 1;
+    while pipes.get(name.as_str()).is_some() {
+        suffix += 1;
+        name = format!("{sequence}_part_{suffix}");
+    }
+
+    let mut lines = vec![
+        format!("[pipe.{}]", toml_key(&name)),
+        "type = \"PipeSequence\"".to_string(),
+        "description = \"TODO\"".to_string(),
+    ];
+    if !consumed.is_empty() {
+        let inputs: Vec<String> = consumed
+            .iter()
+            .map(|(name, concept)| format!("{} = \"{}\"", toml_key(name), escape(concept)))
+            .collect();
+        lines.push(format!("inputs = {{ {} }}", inputs.join(", ")));
+    }
+    lines.push(format!("output = \"{}\"", escape(&output)));
+    lines.push("steps = [".to_string());
+    for step in extracted {
+        lines.push(format!("{},", step.syntax()?));
+    }
+    lines.push("]".to_string());
+    let table = formatter::format(&lines.join("\n"), options.clone());
+
+    let call = match &result {
+        Some(result) => format!(
+            "{{ pipe = \"{}\", result = \"{}\" }}",
+            escape(&name),
+            escape(result)
+        ),
+        None => format!("{{ pipe = \"{}\" }}", escape(&name)),
+    };
+
+    let source = dom.syntax()?.to_string();
+    let (end, separator) = document_end(&source);
+    let mut rewrite = Rewrite::new(dom.clone()).ok()?;
+    rewrite
+        .replace_range(
+            step_range(&steps[first])?.cover(step_range(&steps[last])?),
+            &call,
+        )
+        .ok()?
+        .replace_range(TextRange::empty(end), &format!("{separator}{table}"))
+        .ok()?;
+
+    Some(Fix {
+        title: format!(
+            "Extract {} steps into a new PipeSequence `{name}`",
+            extracted.len()
+        ),
+        safe: false,
+        // The rewrite keeps its patches last first.
+        edits: rewrite
+            .patches()
+            .iter()
+            .rev()
+            .filter_map(|patch| match &patch.kind {
+                PendingPatchKind::Replace(new_text) => Some(Edit {
+                    range: patch.range,
+                    new_text: new_text.to_string(),
+                }),
+                _ => None,
+            })
+            .collect(),
+    })
+}
+
+fn create_pipe_stub(dom: &Node, reference: &Reference) -> Option<Fix> {
+    let (inputs, output) = infer_signature(dom, reference.range);
+    let output = output.unwrap_or_else(|| PLACEHOLDER_CONCEPT.to_string());
+
+    let mut lines = vec![
+        format!("[pipe.{}]", toml_key(&reference.name)),
+        "type = \"PipeLLM\"".to_string(),
+        "description = \"TODO\"".to_string(),
+    ];
+    if !inputs.is_empty() {
+        let inputs: Vec<String> = inputs
+            .iter()
+            .map(|(name, concept)| format!("{} = \"{}\"", toml_key(name), escape(concept)))
+            .collect();
+        lines.push(format!("inputs = {{ {} }}", inputs.join(", ")));
+    }
+    lines.push(format!("output = \"{}\"", escape(&output)));
+    lines.push("prompt = \"TODO\"".to_string());
+
+    let (at, separator) = document_end(&dom.syntax()?.to_string());
+    Some(Fix::replace(
+        format!("Create pipe stub `[pipe.{}]`", reference.name),
+        false,
+        TextRange::empty(at),
+        format!("{separator}{}\n", lines.join("\n")),
+    ))
+}
+
+/// A `[concept.<name>]` table, placed before the first pipe table so concepts
+/// stay above the pipes using them (at the end of the document otherwise).
+fn create_concept(dom: &Node, reference: &Reference) -> Option<Fix> {
+    let root = dom.syntax()?.as_node()?.clone();
+    let source = root.to_string();
+    let table = format!(
+        "[concept.{}]\ndescription = \"TODO\"\n",
+        toml_key(&reference.name)
+    );
+    let title = format!("Create concept `{}`", reference.name);
+
+    let first_pipe = root.children().find(|child| {
+        matches!(
+            child.kind(),
+            SyntaxKind::TABLE_HEADER | SyntaxKind::TABLE_ARRAY_HEADER
+        ) && child
+            .children()
+            .find(|key| key.kind() == SyntaxKind::KEY)
+            .is_some_and(|key| {
+                let key: String = key.to_string().split_whitespace().collect();
+                key == "pipe" || key.starts_with("pipe.")
+            })
+    });
+    let Some(first_pipe) = first_pipe else {
+        let (at, separator) = document_end(&source);
+        return Some(Fix::replace(
+            title,
+            false,
+            TextRange::empty(at),
+            format!("{separator}{table}"),
+        ));
+    };
+
+    let at = section_start(&source, first_pipe.text_range().start().into());
+    Some(Fix::replace(
+        title,
+        false,
+        TextRange::empty(TextSize::from(at as u32)),
+        format!("{table}\n"),
+    ))
+}
+
+/// The inputs and output a pipe called at `range` needs, from what its caller
+/// makes available and expects.
+///
+/// - A sequence step sees the sequence's inputs and the `result` of earlier
+///   steps; it produces what a later step takes under its `result` name, or
+///   the sequence's output when it is the last step. A `batch_over` step gets
+///   one item under its `batch_as` name instead of the list.
+/// - A batch branch gets one item of the batch's list under
+///   `input_item_name` and produces one item of the batch's output.
+/// - Parallel branches and condition outcomes see their caller's inputs; an
+///   outcome produces the condition's output.
+pub(crate) fn infer_signature(
+    dom: &Node,
+    range: TextRange,
+) -> (Vec<(String, String)>, Option<String>) {
+    let pipes = dom.get("pipe");
+    let Some(pipes) = pipes.as_table() else {
+        return (Vec::new(), None);
+    };
+    let entries = pipes.entries().read();
+    for (_, pipe) in entries.iter() {
+        let inputs = declared_inputs(pipe);
+        let output = pipe.get("output").as_str().map(|s| s.value().to_string());
+
+        if let Some(steps) = pipe.get("steps").as_array() {
+            let steps = steps.items().read();
+            if let Some(position) = steps
+                .iter()
+                .position(|step| is_at(&step.get("pipe"), range))
+            {
+                return sequence_step_signature(dom, inputs, output, &steps, position);
+            }
+        }
+
+        if let Some(branches) = pipe.get("branches").as_array() {
+            if branches
+                .items()
+                .read()
+                .iter()
+                .any(|branch| is_at(&branch.get("pipe"), range))
+            {
+                return (inputs, None);
+            }
+        }
+
+        if is_at(&pipe.get("branch_pipe_code"), range) {
+            let list = pipe.get("input_list_name");
+            let list = list.as_str().map(|s| s.value().to_string());
+            let item = pipe.get("input_item_name");
+            let item = item.as_str().map(|s| s.value().to_string());
+            let mut branch_inputs = Vec::new();
+            for (name, concept) in inputs {
+                match (&list, &item) {
+                    (Some(list), Some(item)) if *list == name => {
+                        branch_inputs.push((item.clone(), item_concept(&concept)));
+                    }
+                    _ => branch_inputs.push((name, concept)),
+                }
+            }
+            return (branch_inputs, output.map(|output| item_concept(&output)));
+        }
+
+        let outcomes = pipe.get("outcomes");
+        let in_outcomes = outcomes.as_table().is_some_and(|outcomes| {
+            outcomes
+                .entries()
+                .read()
+                .iter()
+                .any(|(_, outcome)| is_at(outcome, range))
+        });
+        if in_outcomes || is_at(&pipe.get("default_outcome"), range) {
+            return (inputs, output);
+        }
+    }
+    (Vec::new(), None)
+}
+
+fn sequence_step_signature(
+    dom: &Node,
+    inputs: Vec<(String, String)>,
+    output: Option<String>,
+    steps: &[Node],
+    position: usize,
+) -> (Vec<(String, String)>, Option<String>) {
+    let mut inputs = visible_names(dom, inputs, &steps[..position]);
+
+    let step = &steps[position];
+    if let (Some(list), Some(item)) = (
+        string_of(&step.get("batch_over")),
+        string_of(&step.get("batch_as")),
+    ) {
+        if let Some(slot) = inputs.iter_mut().find(|(name, _)| *name == list) {
+            *slot = (item, item_concept(&slot.1));
+        }
+    }
+
+    let produced = string_of(&step.get("result")).and_then(|result| {
+        steps[position + 1..].iter().find_map(|later| {
+            let pipe = string_of(&later.get("pipe"))?;
+            string_of(
+                &dom.get("pipe")
+                    .get(pipe.as_str())
+                    .get("inputs")
+                    .get(result.as_str()),
+            )
+        })
+    });
+    let output = produced.or(if position + 1 == steps.len() {
+        output
+    } else {
+        None
+    });
+    (inputs, output)
+}
+
+/// The working-memory names, with their concepts, that a step of a sequence
+/// sees after `earlier` steps ran: the sequence's `inputs` and the `result`
+/// of each earlier step whose pipe is defined in the document.
+pub(crate) fn visible_names(
+    dom: &Node,
+    mut names: Vec<(String, String)>,
+    earlier: &[Node],
+) -> Vec<(String, String)> {
+    for step in earlier {
+        let (Some(result), Some(concept)) = (
+            string_of(&step.get("result")),
+            string_of(&step.get("pipe")).and_then(|pipe| pipe_output(dom, &pipe)),
+        ) else {
+            continue;
+        };
+        names.retain(|(name, _)| *name != result);
+        names.push((result, concept));
+    }
+    names
+}
+
+/// Insert `entry` after the last entry of an existing `inputs` table.
+fn append_entry(inputs: &Node, entry: &str) -> Option<(TextRange, String)> {
+    let table = inputs.as_table()?;
+    let syntax = inputs.syntax()?.as_node()?;
+    let inline = syntax.kind() == SyntaxKind::INLINE_TABLE;
+    let last = table
+        .entries()
+        .read()
+        .iter()
+        .filter_map(|(_, value)| value.syntax()?.as_token().cloned())
+        .max_by_key(|value| value.text_range().end());
+
+    Some(match (last, inline) {
+        (Some(last), true) => (
+            TextRange::empty(last.text_range().end()),
+            format!(", {entry}"),
+        ),
+        (Some(last), false) => {
+            // After a trailing comment, if any.
+            let mut end = last.text_range().end();
+            let mut next = last.next_token();
+            while let Some(token) = next.filter(|token| token.kind() != SyntaxKind::NEWLINE) {
+                end = token.text_range().end();
+                next = token.next_token();
+            }
+            (TextRange::empty(end), format!("\n{entry}"))
+        }
+        (None, true) => (syntax.text_range(), format!("{{ {entry} }}")),
+        (None, false) => (
+            TextRange::empty(syntax.text_range().end()),
+            format!("\n{entry}"),
+        ),
+    })
+}
+
+/// Add an `inputs` table with `entry` to a pipe that has none, before its
+/// `output` (after its header when it has no `output`).
+fn declare_inputs(pipe: &Node, entry: &str) -> Option<(TextRange, String)> {
+    let declaration = format!("inputs = {{ {entry} }}");
+    if let Some(output) = pipe.get("output").syntax().and_then(entry_of) {
+        return Some((
+            TextRange::empty(output.text_range().start()),
+            format!("{declaration}\n"),
+        ));
+    }
+    let header = pipe
+        .syntax()?
+        .as_node()
+        .filter(|syntax| syntax.kind() == SyntaxKind::TABLE_HEADER)?;
+    Some((
+        TextRange::empty(header.text_range().end()),
+        format!("\n{declaration}"),
+    ))
+}
+
+/// The end of `source`, and what to write there so that a new table is
+/// separated from the last one by a blank line.
+pub(crate) fn document_end(source: &str) -> (TextSize, &'static str) {
+    let separator = if source.is_empty() || source.ends_with("\n\n") {
+        ""
+    } else if source.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    (TextSize::of(source), separator)
+}
+
+/// The `key = value` entry a value belongs to.
+fn entry_of(value: &SyntaxElement) -> Option<SyntaxNode> {
+    value
+        .ancestors()
+        .find(|node| node.kind() == SyntaxKind::ENTRY)
+}
+
+/// The start of the line `offset` is on, moved up over the comment lines right
+/// above it (they document what follows).
+pub(crate) fn section_start(source: &str, offset: usize) -> usize {
+    let mut start = source[..offset]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    while start > 0 {
+        let previous = source[..start - 1]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        if !source[previous..start].trim_start().starts_with('#') {
+            break;
+        }
+        start = previous;
+    }
+    start
+}
+
+/// The working-memory names a sequence step reads, with the concept its pipe
+/// declares for each (when the pipe is defined in the document). A
+/// `batch_over` step reads the list, not the `batch_as` item.
+fn step_consumes(dom: &Node, step: &Node) -> Vec<(String, String)> {
+    let batch_as = string_of(&step.get("batch_as"));
+    let mut names = Vec::new();
+    if let Some(list) = string_of(&step.get("batch_over")) {
+        names.push((list, PLACEHOLDER_CONCEPT.to_string()));
+    }
+    if let Some(pipe) = string_of(&step.get("pipe")) {
+        for (name, concept) in declared_inputs(&dom.get("pipe").get(pipe.as_str())) {
+            if batch_as.as_ref() != Some(&name) {
+                names.push((name, concept));
+            }
+        }
+    }
+    names
+}
+
+fn step_range(step: &Node) -> Option<TextRange> {
+    step.syntax().map(SyntaxElement::text_range)
+}
+
+pub(crate) fn declared_inputs(pipe: &Node) -> Vec<(String, String)> {
+    let inputs = pipe.get("inputs");
+    let Some(inputs) = inputs.as_table() else {
+        return Vec::new();
+    };
+    let entries = inputs.entries().read();
+    entries
+        .iter()
+        .filter_map(|(key, concept)| Some((key.value().to_string(), string_of(concept)?)))
+        .collect()
+}
+
+fn pipe_output(dom: &Node, pipe: &str) -> Option<String> {
+    string_of(&dom.get("pipe").get(pipe).get("output"))
+}
+
+fn is_at(node: &Node, range: TextRange) -> bool {
+    node.as_str().is_some() && node.text_ranges(false).next() == Some(range)
+}
+
+/// The concept of one item of a list concept (`"Page[]"` → `"Page"`).
+pub(crate) fn item_concept(concept: &str) -> String {
+    concept
+        .split_once('[')
+        .map_or(concept, |(item, _)| item)
+        .to_string()
+}
+
+fn toml_key(name: &str) -> String {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        name.to_string()
+    } else {
+        format!("\"{}\"", escape(name))
+    }
+}
@@ -0,0 +1,555 @@
+//! Completion of the references of `.mthds` files: the pipes of the bundle
+//! inside `pipe`, `main_pipe`, `branch_pipe_code`, `outcomes` and
+//! `default_outcome` strings, its concepts plus the native ones inside
+//! `output`, `inputs`, `refines`, `concept_ref` and `item_concept_ref`
+//! strings, the model deck's references inside `model` strings, and in
+//! prompt templates the pipe's inputs and the fields of their concepts.
+//!
+//! Pipe candidates are ranked by how well they fit the call site, using the
+//! signature [`infer_signature`] derives from it: a pipe producing the
+//! concept the caller expects comes first, and among those, one whose inputs
+//! the caller can all provide.
+
+use super::{
+    hover::{build_model_hover, build_mthds_hover_content},
+    mthds_actions::{declared_inputs, infer_signature},
+    mthds_resolution::{ReferenceKind, ResolvedReference},
+    mthds_templates::{concept_fields, template_reference_at, TemplateReference},
+    sibling_files,
+    signature_help::{signature_information, Signature},
+};
+use crate::world::{DocumentState, WorkspaceState};
+use lsp_async_stub::util::LspExt;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Documentation, InsertTextFormat,
+    MarkupContent, MarkupKind, TextEdit, Url,
+};
+use pipelex_common::tools::{
+    bundle::{string_of, strip_concept_qualifiers, BundleIndex, ModelReference},
+    hover::native_concept_hover,
+    models::ModelDeck,
+    native_concepts::NativeConcepts,
+};
+use taplo::{
+    dom::Node,
+    rowan::{TextRange, TextSize},
+};
+use taplo_common::environment::Environment;
+
+/// A pipe of the bundle that a reference can name.
+pub(crate) struct PipeCandidate {
+    pub(crate) name: String,
+    /// The `domain` of the file defining it.
+    pub(crate) domain: Option<String>,
+    pub(crate) pipe: Node,
+    /// Whether it is defined in the document being completed.
+    pub(crate) local: bool,
+}
+
+/// A concept of the bundle that a reference can name.
+pub(crate) struct ConceptCandidate {
+    pub(crate) name: String,
+    /// The `domain` of the file defining it.
+    pub(crate) domain: Option<String>,
+    /// Its table, or its description for the `Name = "description"`
+    /// shorthand.
+    pub(crate) concept: Node,
+    /// Whether it is defined in the document being completed.
+    pub(crate) local: bool,
+}
+
+/// The concept reference string the cursor is inside of.
+pub(crate) struct ConceptReference {
+    /// The range of the string, quotes included.
+    pub(crate) range: TextRange,
+    pub(crate) value: String,
+    /// The key holding it: `output`, `inputs`, `refines`, `concept_ref` or
+    /// `item_concept_ref`.
+    pub(crate) key: &'static str,
+}
+
+/// The completions of the pipe, concept or model reference, or of the
+/// template variable, at `offset`, with the definitions of the document and
+/// of the other files of its bundle, and the workspace's model deck. `None`
+/// when the cursor is in none of them.
+pub(crate) async fn mthds_completions<E: Environment>(
+    env: &E,
+    ws: &WorkspaceState<E>,
+    uri: &Url,
+    doc: &DocumentState,
+    offset: TextSize,
+) -> Option<Vec<CompletionItem>> {
+    let template = template_reference_at(&doc.dom, offset);
+    let pipe = pipe_reference_at(&doc.dom, offset);
+    let concept = concept_reference_at(&doc.dom, offset);
+    if let Some(reference) = model_reference_at(&doc.dom, offset) {
+        let range = doc
+            .mapper
+            .range(string_contents(reference.range))?
+            .into_lsp();
+        let items = model_completions(&reference, &ws.models);
+        return Some(
+            items
+                .into_iter()
+                .map(|item| CompletionItem {
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                        range,
+                        new_text: item.label.clone(),
+                    })),
+                    ..item
+                })
+                .collect(),
+        );
+    }
+    if template.is_none() && pipe.is_none() && concept.is_none() {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    if let Some(path) = env.to_file_path_normalized(uri) {
+        siblings = sibling_files(env, ws, &path).await;
+    }
+    let concepts = || {
+        let mut candidates = concept_candidates(&doc.dom, true);
+        for sibling in &siblings {
+            candidates.extend(concept_candidates(&sibling.dom, false));
+        }
+        candidates
+    };
+
+    // The range the items replace: the typed part of a template variable, or
+    // the contents of a reference string.
+    let (range, items) = if let Some(reference) = template {
+        let items = template_completions(&reference, &concepts(), &ws.natives);
+        (reference.partial, items)
+    } else if let Some(range) = pipe {
+        let mut candidates = pipe_candidates(&doc.dom, true);
+        for sibling in &siblings {
+            candidates.extend(pipe_candidates(&sibling.dom, false));
+        }
+        let items = pipe_completions(&doc.dom, range, &candidates);
+        (string_contents(range), items)
+    } else {
+        let reference = concept?;
+        let items = concept_completions(&doc.dom, &reference, &concepts(), &ws.natives);
+        (string_contents(reference.range), items)
+    };
+
+    let range = doc.mapper.range(range)?.into_lsp();
+    Some(
+        items
+            .into_iter()
+            .map(|mut item| CompletionItem {
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: item
+                        .insert_text
+                        .take()
+                        .unwrap_or_else(|| item.label.clone()),
+                })),
+                ..item
+            })
+            .collect(),
+    )
+}
+
+/// The range of the pipe reference string the cursor is inside of, quotes
+/// included: the top-level `main_pipe`, and in a pipe the `pipe` of its
+/// `steps` and `branches`, its `branch_pipe_code`, `default_pipe_code`,
+/// `default_outcome` and the values of its `outcomes`.
+pub(crate) fn pipe_reference_at(dom: &Node, offset: TextSize) -> Option<TextRange> {
+    let mut references = vec![dom.get("main_pipe")];
+    let pipes = dom.get("pipe");
+    if let Some(pipes) = pipes.as_table() {
+        for (_, pipe) in pipes.entries().read().iter() {
+            for key in ["steps", "branches"] {
+                if let Some(calls) = pipe.get(key).as_array() {
+                    references.extend(calls.items().read().iter().map(|call| call.get("pipe")));
+                }
+            }
+            for key in ["branch_pipe_code", "default_pipe_code", "default_outcome"] {
+                references.push(pipe.get(key));
+            }
+            if let Some(outcomes) = pipe.get("outcomes").as_table() {
+                references.extend(outcomes.entries().read().iter().map(|(_, v)| v.clone()));
+            }
+        }
+    }
+    references.into_iter().find_map(|reference| {
+        reference.as_str()?;
+        let range = reference.text_ranges(false).next()?;
+        (range.start() < offset && offset < range.end()).then_some(range)
+    })
+}
+
+/// The pipes `dom` defines.
+pub(crate) fn pipe_candidates(dom: &Node, local: bool) -> Vec<PipeCandidate> {
+    let domain = BundleIndex::from_dom(dom).domain;
+    let pipes = dom.get("pipe");
+    let Some(pipes) = pipes.as_table() else {
+        return Vec::new();
+    };
+    let entries = pipes.entries().read();
+    entries
+        .iter()
+        .map(|(key, pipe)| PipeCandidate {
+            name: key.value().to_string(),
+            domain: domain.clone(),
+            pipe: pipe.clone(),
+            local,
+        })
+        .collect()
+}
+
+/// One completion item per candidate for the pipe reference at `range`,
+/// without text edits, its `sort_text` carrying the rank. Pipes of another
+/// domain are qualified with it, and the pipe the reference sits in is left
+/// out.
+pub(crate) fn pipe_completions(
+    dom: &Node,
+    range: TextRange,
+    candidates: &[PipeCandidate],
+) -> Vec<CompletionItem> {
+    let domain = BundleIndex::from_dom(dom).domain;
+    let (available, expected) = infer_signature(dom, range);
+    // A reference with no caller around it (`main_pipe`) ranks nothing.
+    let ranked = expected.is_some() || !available.is_empty();
+
+    candidates
+        .iter()
+        .filter(|candidate| {
+            !(candidate.local
+                && candidate
+                    .pipe
+                    .text_ranges(true)
+                    .next()
+                    .is_some_and(|pipe| pipe.contains_range(range)))
+        })
+        .map(|candidate| {
+            let label = match &candidate.domain {
+                Some(other) if domain.as_ref() != Some(other) => {
+                    format!("{other}.{}", candidate.name)
+                }
+                _ => candidate.name.clone(),
+            };
+            let inputs = declared_inputs(&candidate.pipe);
+            let output = string_of(&candidate.pipe.get("output"));
+
+            let produces = matches!(
+                (&expected, &output),
+                (Some(expected), Some(output)) if same_concept(expected, output)
+            );
+            let receives = inputs.iter().all(|(name, concept)| {
+                available
+                    .iter()
+                    .any(|(seen, seen_concept)| seen == name && same_concept(seen_concept, concept))
+            });
+            let rank = match (produces, receives) {
+                _ if !ranked => 0,
+                (true, true) => 0,
+                (true, false) => 1,
+                (false, true) => 2,
+                (false, false) => 3,
+            };
+
+            let signature = Signature {
+                pipe: label.clone(),
+                description: None,
+                inputs,
+                output,
+                active: None,
+            };
+            let documentation = build_mthds_hover_content(&ResolvedReference {
+                kind: ReferenceKind::Pipe,
+                ref_name: label.clone(),
+                target_node: candidate.pipe.clone(),
+            });
+            CompletionItem {
+                label: label.clone(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(signature_information(&signature).label),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: documentation,
+                })),
+                sort_text: Some(format!("{rank}{label}")),
+                filter_text: Some(label),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// The concept reference string the cursor is inside of: the `output` and
+/// `inputs` values of a pipe, and the `refines` of a concept and the
+/// `concept_ref` / `item_concept_ref` of its structure fields.
+pub(crate) fn concept_reference_at(dom: &Node, offset: TextSize) -> Option<ConceptReference> {
+    let mut references = Vec::new();
+    let pipes = dom.get("pipe");
+    if let Some(pipes) = pipes.as_table() {
+        for (_, pipe) in pipes.entries().read().iter() {
+            references.push(("output", pipe.get("output")));
+            if let Some(inputs) = pipe.get("inputs").as_table() {
+                references.extend(
+                    inputs
+                        .entries()
+                        .read()
+                        .iter()
+                        .map(|(_, concept)| ("inputs", concept.clone())),
+                );
+            }
+        }
+    }
+    let concepts = dom.get("concept");
+    if let Some(concepts) = concepts.as_table() {
+        for (_, concept) in concepts.entries().read().iter() {
+            references.push(("refines", concept.get("refines")));
+            if let Some(structure) = concept.get("structure").as_table() {
+                for (_, field) in structure.entries().read().iter() {
+                    references.push(("concept_ref", field.get("concept_ref")));
+                    references.push(("item_concept_ref", field.get("item_concept_ref")));
+                }
+            }
+        }
+    }
+    references.into_iter().find_map(|(key, reference)| {
+        let value = reference.as_str()?.value().to_string();
+        let range = reference.text_ranges(false).next()?;
+        (range.start() < offset && offset < range.end()).then_some(ConceptReference {
+            range,
+            value,
+            key,
+        })
+    })
+}
+
+/// The concepts `dom` defines.
+pub(crate) fn concept_candidates(dom: &Node, local: bool) -> Vec<ConceptCandidate> {
+    let domain = BundleIndex::from_dom(dom).domain;
+    let concepts = dom.get("concept");
+    let Some(concepts) = concepts.as_table() else {
+        return Vec::new();
+    };
+    let entries = concepts.entries().read();
+    entries
+        .iter()
+        .map(|(key, concept)| ConceptCandidate {
+            name: key.value().to_string(),
+            domain: domain.clone(),
+            concept: concept.clone(),
+            local,
+        })
+        .collect()
+}
+
+/// The completion items of `reference`, listed in this order:
+///
+/// - for an `output` or `inputs` value already naming a concept, that
+///   concept as a list (`Name[]`) and as a fixed-size list (`Name[N]`);
+/// - the concepts of the document's domain, then the native concepts in
+///   registry order, then the concepts of other domains, qualified with
+///   their domain.
+///
+/// A native concept is inserted as `native.Name` when the value already
+/// starts with `native.` or the bundle defines a concept of the same name.
+/// The concept a `refines` sits in is left out.
+pub(crate) fn concept_completions(
+    dom: &Node,
+    reference: &ConceptReference,
+    candidates: &[ConceptCandidate],
+    natives: &NativeConcepts,
+) -> Vec<CompletionItem> {
+    let domain = BundleIndex::from_dom(dom).domain;
+    let mut items = Vec::new();
+
+    let base = reference
+        .value
+        .split_once('[')
+        .map_or(reference.value.as_str(), |(base, _)| base);
+    if matches!(reference.key, "output" | "inputs") && !base.is_empty() {
+        items.push(CompletionItem {
+            label: format!("{base}[]"),
+            kind: Some(CompletionItemKind::SNIPPET),
+            detail: Some(format!("A list of {base} of any length")),
+            sort_text: Some(format!("0{base}[]")),
+            ..Default::default()
+        });
+        items.push(CompletionItem {
+            label: format!("{base}[N]"),
+            kind: Some(CompletionItemKind::SNIPPET),
+            detail: Some(format!("A list of exactly N {base}")),
+            insert_text: Some(format!("{base}[${{1:2}}]")),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            sort_text: Some(format!("0{base}[N]")),
+            ..Default::default()
+        });
+    }
+
+    for candidate in candidates {
+        if candidate.local
+            && reference.key == "refines"
+            && candidate
+                .concept
+                .text_ranges(true)
+                .next()
+                .is_some_and(|concept| concept.contains_range(reference.range))
+        {
+            continue;
+        }
+        let (label, rank) = match &candidate.domain {
+            Some(other) if domain.as_ref() != Some(other) => {
+                (format!("{other}.{}", candidate.name), 3)
+            }
+            _ => (candidate.name.clone(), 1),
+        };
+        let documentation = match candidate.concept.as_str() {
+            Some(description) => description.value().to_string(),
+            None => build_mthds_hover_content(&ResolvedReference {
+                kind: ReferenceKind::Concept,
+                ref_name: label.clone(),
+                target_node: candidate.concept.clone(),
+            }),
+        };
+        items.push(concept_item(label, rank, documentation));
+    }
+
+    for (index, native) in natives.concepts.iter().enumerate() {
+        let shadowed = candidates
+            .iter()
+            .any(|candidate| candidate.name == native.name);
+        let label = if shadowed || reference.value.starts_with("native.") {
+            format!("native.{}", native.name)
+        } else {
+            native.name.clone()
+        };
+        let mut item = concept_item(label, 2, native_concept_hover(native, natives));
+        item.detail = Some("native concept".to_string());
+        // In registry order.
+        item.sort_text = Some(format!("2{index:03}"));
+        items.push(item);
+    }
+
+    items
+}
+
+fn concept_item(label: String, rank: u8, documentation: String) -> CompletionItem {
+    CompletionItem {
+        label: label.clone(),
+        kind: Some(CompletionItemKind::CLASS),
+        documentation: Some(Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: documentation,
+        })),
+        sort_text: Some(format!("{rank}{label}")),
+        filter_text: Some(label),
+        ..Default::default()
+    }
+}
+
+/// The `model` reference string the cursor is inside of, written on one line.
+pub(crate) fn model_reference_at(dom: &Node, offset: TextSize) -> Option<ModelReference> {
+    BundleIndex::from_dom(dom)
+        .model_references
+        .into_iter()
+        .find(|reference| {
+            reference.value_range.is_some()
+                && reference.range.start() < offset
+                && offset < reference.range.end()
+        })
+}
+
+/// One completion item per reference the deck declares that `reference`'s
+/// pipe can run: presets, then aliases, waterfalls and handles. References
+/// whose kind of model the deck does not tell are always offered.
+pub(crate) fn model_completions(
+    reference: &ModelReference,
+    models: &ModelDeck,
+) -> Vec<CompletionItem> {
+    models
+        .references()
+        .into_iter()
+        .filter_map(|(label, ref_kind)| {
+            let kind = models.kind_of(&label);
+            if matches!((kind, reference.kind), (Some(kind), Some(wanted)) if kind != wanted) {
+                return None;
+            }
+            let rank = ref_kind as u8;
+            let detail = match kind {
+                Some(kind) => format!("{} model {}", kind.label(), ref_kind.label()),
+                None => format!("model {}", ref_kind.label()),
+            };
+            Some(CompletionItem {
+                label: label.clone(),
+                kind: Some(CompletionItemKind::CONSTANT),
+                detail: Some(detail),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: build_model_hover(&label, None, models),
+                })),
+                sort_text: Some(format!("{rank}{label}")),
+                filter_text: Some(label),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// The completions of a template variable: the pipe's inputs for its first
+/// segment, and then the fields of the concept the path leads to.
+pub(crate) fn template_completions(
+    reference: &TemplateReference,
+    concepts: &[ConceptCandidate],
+    natives: &NativeConcepts,
+) -> Vec<CompletionItem> {
+    let inputs = declared_inputs(&reference.pipe);
+    let Some((root, fields)) = reference.path.split_first() else {
+        return inputs
+            .into_iter()
+            .map(|(name, concept)| CompletionItem {
+                label: name,
+                kind: Some(CompletionItemKind::VARIABLE),
+                detail: Some(concept),
+                ..Default::default()
+            })
+            .collect();
+    };
+
+    let Some((_, mut concept)) = inputs.into_iter().find(|(name, _)| name == root) else {
+        return Vec::new();
+    };
+    for segment in fields {
+        let next = concept_fields(&concept, concepts, natives)
+            .into_iter()
+            .find(|field| field.name == *segment)
+            .and_then(|field| field.concept);
+        let Some(next) = next else {
+            return Vec::new();
+        };
+        concept = next;
+    }
+    concept_fields(&concept, concepts, natives)
+        .into_iter()
+        .map(|field| CompletionItem {
+            label: field.name,
+            kind: Some(CompletionItemKind::FIELD),
+            detail: Some(field.detail),
+            documentation: field.description.map(Documentation::String),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// The contents of a single-line string value, without its quotes.
+fn string_contents(range: TextRange) -> TextRange {
+    TextRange::new(
+        range.start() + TextSize::from(1),
+        range.end() - TextSize::from(1),
+    )
+}
+
+/// Whether two concept references name the same concept with the same
+/// multiplicity, whatever their domain qualifier.
+fn same_concept(a: &str, b: &str) -> bool {
+    let multiplicity = |concept: &str| concept.find('[').map(|i| concept[i..].to_string());
+    strip_concept_qualifiers(a) == strip_concept_qualifiers(b) && multiplicity(a) == multiplicity(b)
+}
@@ -0,0 +1,432 @@
+//! The "Move to file" refactoring: moving a `[pipe.<name>]` or
+//! `[concept.<name>]` table, with its sub-tables and the comments above it,
+//! into another file of the bundle.
+//!
+//! When the target file declares another `domain`, references are
+//! requalified on both sides: the moved table's references to what stays
+//! behind gain the source domain, and references to the moved definition
+//! across the bundle point at the target domain (or lose their qualifier in
+//! files of the target domain).
+
+use super::{
+    file_url_from_path,
+    mthds_actions::{document_end, section_start},
+    sibling_files, BundleFile,
+};
+use crate::world::{DocumentState, WorkspaceState};
+use lsp_async_stub::util::LspExt;
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CreateFile, DocumentChangeOperation,
+    DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier, ResourceOp, TextDocumentEdit,
+    TextEdit, Url, WorkspaceEdit,
+};
+use pipelex_common::tools::{
+    bundle::{BundleIndex, RefKind, Reference},
+    fixes::Edit,
+    native_concepts::NativeConcepts,
+};
+use taplo::{
+    dom::{node::DomNode, Node},
+    rowan::{TextRange, TextSize},
+    syntax::{SyntaxKind, SyntaxNode},
+};
+use taplo_common::environment::Environment;
+
+/// The kind of the "Move to file" actions.
+pub(crate) const REFACTOR_MOVE: &str = "refactor.move";
+
+/// One "Move to file" action per other file of the bundle, plus one moving
+/// the section at `offset` to a new file named after it.
+pub(crate) async fn move_to_file_actions<E: Environment>(
+    env: &E,
+    ws: &WorkspaceState<E>,
+    uri: &Url,
+    doc: &DocumentState,
+    offset: TextSize,
+) -> Vec<CodeActionOrCommand> {
+    let Some(section) = section_at(&doc.dom, offset) else {
+        return Vec::new();
+    };
+    let Some(path) = env.to_file_path_normalized(uri) else {
+        return Vec::new();
+    };
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+
+    let siblings = sibling_files(env, ws, &path).await;
+
+    let source = BundleFile {
+        uri: uri.clone(),
+        dom: doc.dom.clone(),
+        mapper: doc.mapper.clone(),
+    };
+    let source_domain = BundleIndex::from_dom(&doc.dom).domain;
+    let key = section.key();
+    let mut actions = Vec::new();
+
+    for (target_at, target) in siblings.iter().enumerate() {
+        let target_domain = BundleIndex::from_dom(&target.dom).domain;
+        let Some(text) = moved_text(&doc.dom, &section, target_domain.as_deref(), &ws.natives)
+        else {
+            continue;
+        };
+        let Some(removal) = section_removal(&doc.dom, &section) else {
+            continue;
+        };
+        let Some(append) = append_section(&target.dom, &text) else {
+            continue;
+        };
+
+        let mut changes = vec![(&source, vec![removal]), (target, vec![append])];
+        if let (Some(from), Some(to)) = (&source_domain, &target_domain) {
+            if from != to {
+                let requalified = |file: &BundleFile, skip| {
+                    requalify_references(&file.dom, section.kind, &section.name, from, to, skip)
+                };
+                changes[0]
+                    .1
+                    .extend(requalified(&source, Some(section.range)));
+                changes[1].1.extend(requalified(target, None));
+                for (at, other) in siblings.iter().enumerate() {
+                    let edits = requalified(other, None);
+                    if at != target_at && !edits.is_empty() {
+                        changes.push((other, edits));
+                    }
+                }
+            }
+        }
+
+        let Some(operations) = changes
+            .into_iter()
+            .map(|(file, edits)| text_document_edit(file, &edits))
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        actions.push(move_action(
+            format!("Move `{key}` to `{}`", file_name(&target.uri)),
+            operations,
+        ));
+    }
+
+    let new_name = new_file_name(&section);
+    let new_path = dir.join(&new_name);
+    let exists = siblings
+        .iter()
+        .any(|sibling| env.to_file_path_normalized(&sibling.uri).as_ref() == Some(&new_path));
+    if let (false, Some(new_uri)) = (exists || new_path == path, file_url_from_path(&new_path)) {
+        let text = moved_text(&doc.dom, &section, source_domain.as_deref(), &ws.natives);
+        let removal = section_removal(&doc.dom, &section)
+            .and_then(|removal| text_document_edit(&source, &[removal]));
+        if let (Some(text), Some(removal)) = (text, removal) {
+            let content = new_file_content(source_domain.as_deref(), &text);
+            actions.push(move_action(
+                format!("Move `{key}` to a new file `{new_name}`"),
+                vec![
+                    removal,
+                    DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                        uri: new_uri.clone(),
+                        options: None,
+                        annotation_id: None,
+                    })),
+                    DocumentChangeOperation::Edit(TextDocumentEdit {
+                        text_document: OptionalVersionedTextDocumentIdentifier {
+                            uri: new_uri,
+                            version: None,
+                        },
+                        edits: vec![OneOf::Left(TextEdit {
+                            range: lsp_types::Range::default(),
+                            new_text: content,
+                        })],
+                    }),
+                ],
+            ));
+        }
+    }
+
+    actions
+}
+
+fn move_action(title: String, operations: Vec<DocumentChangeOperation>) -> CodeActionOrCommand {
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title,
+        kind: Some(CodeActionKind::from(REFACTOR_MOVE)),
+        edit: Some(WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+fn text_document_edit(file: &BundleFile, edits: &[Edit]) -> Option<DocumentChangeOperation> {
+    let edits = edits
+        .iter()
+        .map(|edit| {
+            Some(OneOf::Left(TextEdit {
+                range: file.mapper.range(edit.range)?.into_lsp(),
+                new_text: edit.new_text.clone(),
+            }))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(DocumentChangeOperation::Edit(TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier {
+            uri: file.uri.clone(),
+            version: None,
+        },
+        edits,
+    }))
+}
+
+fn file_name(uri: &Url) -> &str {
+    uri.path_segments()
+        .and_then(Iterator::last)
+        .unwrap_or_else(|| uri.as_str())
+}
+
+/// A pipe or concept table together with its sub-tables and the comments
+/// right above it.
+#[derive(Debug, Clone)]
+pub(crate) struct Section {
+    pub(crate) kind: RefKind,
+    pub(crate) name: String,
+    /// From the first attached comment to the next table's comments (or the
+    /// end of the document).
+    pub(crate) range: TextRange,
+}
+
+impl Section {
+    /// `pipe.<name>` or `concept.<name>`.
+    pub(crate) fn key(&self) -> String {
+        let root = match self.kind {
+            RefKind::Pipe => "pipe",
+            RefKind::Concept => "concept",
+        };
+        format!("{root}.{}", self.name)
+    }
+}
+
+/// The section of the pipe or concept table `offset` is in.
+///
+/// Only tables opened by their own `[pipe.<name>]` header qualify, and only
+/// when their sub-tables follow it directly, so the section is one block of
+/// text.
+pub(crate) fn section_at(dom: &Node, offset: TextSize) -> Option<Section> {
+    let root = dom.syntax()?.as_node()?.clone();
+    let source = root.to_string();
+    let headers: Vec<(Vec<String>, SyntaxNode)> = root
+        .children()
+        .filter(|child| {
+            matches!(
+                child.kind(),
+                SyntaxKind::TABLE_HEADER | SyntaxKind::TABLE_ARRAY_HEADER
+            )
+        })
+        .map(|header| (key_segments(&header), header))
+        .collect();
+
+    let current = headers
+        .iter()
+        .rposition(|(_, header)| header.text_range().start() <= offset)?;
+    let (kind, name) = match headers[current].0.as_slice() {
+        [root, name, ..] if root == "pipe" => (RefKind::Pipe, name.clone()),
+        [root, name, ..] if root == "concept" => (RefKind::Concept, name.clone()),
+        _ => return None,
+    };
+    let belongs =
+        |segments: &[String]| segments.len() >= 2 && segments[..2] == headers[current].0[..2];
+
+    let first = headers.iter().position(|(segments, _)| belongs(segments))?;
+    if headers[first].0.len() != 2 || headers[first].1.kind() != SyntaxKind::TABLE_HEADER {
+        return None;
+    }
+    let after = headers[first..]
+        .iter()
+        .position(|(segments, _)| !belongs(segments))
+        .map(|position| first + position);
+    if let Some(after) = after {
+        if headers[after..]
+            .iter()
+            .any(|(segments, _)| belongs(segments))
+        {
+            return None;
+        }
+    }
+
+    let start = section_start(&source, headers[first].1.text_range().start().into());
+    let end = after.map_or(source.len(), |after| {
+        section_start(&source, headers[after].1.text_range().start().into())
+    });
+    Some(Section {
+        kind,
+        name,
+        range: TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32)),
+    })
+}
+
+/// The edit deleting `section` from its document. The last section of a
+/// document takes the blank lines above it along.
+pub(crate) fn section_removal(dom: &Node, section: &Section) -> Option<Edit> {
+    let source = dom.syntax()?.to_string();
+    let mut start = usize::from(section.range.start());
+    if usize::from(section.range.end()) == source.len() {
+        while start > 1 && source[..start].ends_with("\n\n") {
+            start -= 1;
+        }
+    }
+    Some(Edit {
+        range: TextRange::new(TextSize::from(start as u32), section.range.end()),
+        new_text: String::new(),
+    })
+}
+
+/// The text of `section` as it reads once moved to a file of
+/// `target_domain`, with its own references requalified.
+pub(crate) fn moved_text(
+    dom: &Node,
+    section: &Section,
+    target_domain: Option<&str>,
+    natives: &NativeConcepts,
+) -> Option<String> {
+    let source = dom.syntax()?.to_string();
+    let index = BundleIndex::from_dom(dom);
+    let mut text = source[section.range].trim_end().to_string();
+    text.push('\n');
+
+    let (Some(from), Some(to)) = (index.domain.as_deref(), target_domain) else {
+        return Some(text);
+    };
+    if from == to {
+        return Some(text);
+    }
+
+    let mut edits: Vec<Edit> = index
+        .references
+        .iter()
+        .filter(|reference| section.range.contains_range(reference.range))
+        .filter_map(|reference| {
+            let domain = reference.domain.as_deref().unwrap_or(from);
+            let itself = reference.kind == section.kind && reference.name == section.name;
+            let native = reference.kind == RefKind::Concept
+                && reference.domain.is_none()
+                && natives.find(&reference.name).is_some();
+            let qualified = if native {
+                return None;
+            } else if domain == to || (itself && domain == from) {
+                reference.name.clone()
+            } else if domain == from {
+                format!("{from}.{}", reference.name)
+            } else {
+                return None;
+            };
+            requalify(reference, &qualified)
+        })
+        .collect();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start()));
+    for edit in edits {
+        let range = edit.range - section.range.start();
+        text.replace_range(std::ops::Range::<usize>::from(range), &edit.new_text);
+    }
+    Some(text)
+}
+
+/// The edits pointing the references of `dom` to the definition of
+/// `kind`/`name` in domain `from` at its new home in domain `to`, leaving the
+/// references inside `skip` (the moved section itself) alone.
+pub(crate) fn requalify_references(
+    dom: &Node,
+    kind: RefKind,
+    name: &str,
+    from: &str,
+    to: &str,
+    skip: Option<TextRange>,
+) -> Vec<Edit> {
+    let index = BundleIndex::from_dom(dom);
+    let file_domain = index.domain.as_deref();
+    index
+        .references
+        .iter()
+        .filter(|reference| reference.kind == kind && reference.name == name)
+        .filter(|reference| !skip.is_some_and(|skip| skip.contains_range(reference.range)))
+        .filter(|reference| reference.domain.as_deref().or(file_domain) == Some(from))
+        .filter_map(|reference| {
+            let qualified = if file_domain == Some(to) {
+                name.to_string()
+            } else {
+                format!("{to}.{name}")
+            };
+            requalify(reference, &qualified)
+        })
+        .collect()
+}
+
+/// The file name a section moved to a new file gets: its name in
+/// `snake_case`.
+pub(crate) fn new_file_name(section: &Section) -> String {
+    let mut file_name = String::new();
+    for (i, c) in section.name.char_indices() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !file_name.ends_with('_') {
+                file_name.push('_');
+            }
+            file_name.push(c.to_ascii_lowercase());
+        } else {
+            file_name.push(c);
+        }
+    }
+    file_name.push_str(".mthds");
+    file_name
+}
+
+/// The content of a new file of domain `domain` holding `text`.
+pub(crate) fn new_file_content(domain: Option<&str>, text: &str) -> String {
+    match domain {
+        Some(domain) => format!("domain = \"{}\"\n\n{text}", taplo::util::escape(domain)),
+        None => text.to_string(),
+    }
+}
+
+/// The edit appending `text` to the document of `dom`.
+pub(crate) fn append_section(dom: &Node, text: &str) -> Option<Edit> {
+    let (end, separator) = document_end(&dom.syntax()?.to_string());
+    Some(Edit {
+        range: TextRange::empty(end),
+        new_text: format!("{separator}{text}"),
+    })
+}
+
+/// Rewrite the `domain.name` part of a reference (keeping any multiplicity
+/// suffix), when it is written verbatim and changes.
+fn requalify(reference: &Reference, qualified: &str) -> Option<Edit> {
+    let name_range = reference.name_range?;
+    let current = match &reference.domain {
+        Some(domain) => format!("{domain}.{}", reference.name),
+        None => reference.name.clone(),
+    };
+    (current != qualified).then(|| Edit {
+        range: TextRange::new(
+            reference.range.start() + TextSize::from(1),
+            name_range.end(),
+        ),
+        new_text: qualified.to_string(),
+    })
+}
+
+/// The dotted segments of a table header's key, unquoted.
+fn key_segments(header: &SyntaxNode) -> Vec<String> {
+    header
+        .children()
+        .find(|node| node.kind() == SyntaxKind::KEY)
+        .into_iter()
+        .flat_map(|key| key.children_with_tokens())
+        .filter_map(|token| token.into_token())
+        .filter(|token| token.kind() == SyntaxKind::IDENT)
+        .map(|token| {
+            token
+                .text()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string()
+        })
+        .collect()
+}
@@ -1,6 +1,9 @@
 use crate::query::{PositionInfo, Query};
+use pipelex_common::tools::bundle::strip_concept_qualifiers;
+pub(crate) use pipelex_common::tools::native_concepts::NativeConcepts;
 use taplo::{
     dom::{KeyOrIndex, Keys, Node},
+    rowan::TextSize,
     syntax::SyntaxKind::{self, IDENT, STRING, STRING_LITERAL},
 };
 
@@ -32,106 +35,6 @@ impl ReferenceKind {
     }
 }
 
-// ---------------------------------------------------------------------------
-// Native concept registry
-// ---------------------------------------------------------------------------
-
-pub(crate) struct NativeConcept {
-    pub name: &'static str,
-    pub description: &'static str,
-    pub fields: &'static [(&'static str, &'static str)],
-}
-
-static NATIVE_CONCEPTS: &[NativeConcept] = &[
-    NativeConcept {
-        name: "Text",
-        description: "Plain text content.",
-        fields: &[("text", "str")],
-    },
-    NativeConcept {
-        name: "Number",
-        description: "A numeric value (integer or float).",
-        fields: &[("number", "int | float")],
-    },
-    NativeConcept {
-        name: "YesNo",
-        description: "The answer to a yes/no question.",
-        fields: &[("yes_no", "bool")],
-    },
-    NativeConcept {
-        name: "Date",
-        description: "A calendar date, optionally with a time of day.",
-        fields: &[("date", "date"), ("time", "time?")],
-    },
-    NativeConcept {
-        name: "Time",
-        description: "A time of day, optionally with a UTC offset.",
-        fields: &[("time", "time")],
-    },
-    NativeConcept {
-        name: "Image",
-        description: "An image with URL and optional metadata.",
-        fields: &[
-            ("url", "str"),
-            ("filename", "str?"),
-            ("caption", "str?"),
-            ("mime_type", "str?"),
-        ],
-    },
-    NativeConcept {
-        name: "Document",
-        description: "A document file (e.g. PDF) with URL and metadata.",
-        fields: &[("url", "str"), ("filename", "str?"), ("mime_type", "str?")],
-    },
-    NativeConcept {
-        name: "Html",
-        description: "HTML content with an inner HTML string and CSS class.",
-        fields: &[("inner_html", "str"), ("css_class", "str")],
-    },
-    NativeConcept {
-        name: "TextAndImages",
-        description: "Composite content holding text and associated images.",
-        fields: &[("text", "TextContent?"), ("images", "list[ImageContent]?")],
-    },
-    NativeConcept {
-        name: "Page",
-        description: "A single page extracted from a document.",
-        fields: &[
-            ("text_and_images", "TextAndImagesContent"),
-            ("page_view", "ImageContent?"),
-        ],
-    },
-    NativeConcept {
-        name: "JSON",
-        description: "A JSON object.",
-        fields: &[("json_obj", "dict")],
-    },
-    NativeConcept {
-        name: "SearchResult",
-        description: "A web search result with answer and sources.",
-        fields: &[("answer", "str"), ("sources", "list[DocumentContent]")],
-    },
-    NativeConcept {
-        name: "Anything",
-        description: "Accepts any content type.",
-        fields: &[],
-    },
-    NativeConcept {
-        name: "Dynamic",
-        description: "Dynamic content with user-defined fields.",
-        fields: &[],
-    },
-    NativeConcept {
-        name: "Composite",
-        description: "A named composition of contents.",
-        fields: &[],
-    },
-];
-
-pub(crate) fn find_native_concept(name: &str) -> Option<&'static NativeConcept> {
-    NATIVE_CONCEPTS.iter().find(|c| c.name == name)
-}
-
 /// Find a STRING or STRING_LITERAL `PositionInfo` from the query's before/after.
 pub(crate) fn find_string_position_info(query: &Query) -> Option<&PositionInfo> {
     query
@@ -204,7 +107,7 @@ pub(crate) fn classify_reference(query: &Query) -> Option<ClassifiedReference> {
 
     let kind = if matches!(
         key_text.as_str(),
-        "pipe" | "main_pipe" | "default_pipe_code"
+        "pipe" | "main_pipe" | "default_pipe_code" | "branch_pipe_code"
     ) {
         ReferenceKind::Pipe
     } else if matches!(key_text.as_str(), "output" | "refines") {
@@ -238,9 +141,9 @@ pub(crate) fn classify_reference(query: &Query) -> Option<ClassifiedReference> {
 /// Resolve a reference at the cursor position in the DOM.
 ///
 /// Checks if the cursor is on a STRING token inside a reference field
-/// (`pipe`, `main_pipe`, `default_pipe_code`, `output`, `refines`, or an
-/// `inputs = { ... }` inline table value), extracts the reference name,
-/// and looks up the corresponding `pipe.<name>` or `concept.<name>` in the DOM.
+/// (`pipe`, `main_pipe`, `default_pipe_code`, `branch_pipe_code`, `output`,
+/// `refines`, or an `inputs = { ... }` inline table value), extracts the
+/// reference name, and looks up the corresponding `pipe.<name>` or `concept.<name>` in the DOM.
 pub(crate) fn resolve_reference(dom: &Node, query: &Query) -> Option<ResolvedReference> {
     let classified = classify_reference(query)?;
 
@@ -263,26 +166,14 @@ pub(crate) fn resolve_reference(dom: &Node, query: &Query) -> Option<ResolvedRef
     })
 }
 
-/// Strip domain prefix and multiplicity suffix from a concept reference string.
-///
-/// Examples:
-/// - `"Slide[]"` → `"Slide"`
-/// - `"Page[5]"` → `"Page"`
-/// - `"images.Photo"` → `"Photo"`
-/// - `"legal.Contract[]"` → `"Contract"`
-/// - `"Analysis"` → `"Analysis"` (unchanged)
-fn strip_concept_qualifiers(name: &str) -> String {
-    // Strip multiplicity suffix: everything from '[' onwards
-    let without_mult = match name.find('[') {
-        Some(pos) => &name[..pos],
-        None => name,
-    };
-    // Strip domain prefix: everything up to and including the last '.'
-    let without_domain = match without_mult.rfind('.') {
-        Some(pos) => &without_mult[pos + 1..],
-        None => without_mult,
-    };
-    without_domain.to_string()
+/// Resolve the pipe a `pipe = "name"` value calls, exactly as
+/// [`resolve_reference`] resolves it with the cursor on that value.
+pub(crate) fn resolve_pipe_call(dom: &Node, value: &Node) -> Option<Node> {
+    value.as_str()?;
+    let start = value.text_ranges(false).next()?.start();
+    let query = Query::at(dom, start + TextSize::from(1));
+    let resolved = resolve_reference(dom, &query)?;
+    matches!(resolved.kind, ReferenceKind::Pipe).then_some(resolved.target_node)
 }
 
 /// Check whether a syntax token sits inside an `inputs = { … }` inline table.
@@ -319,53 +210,3 @@ pub(crate) fn is_inside_inputs_inline_table(token: &taplo::syntax::SyntaxToken)
         .filter_map(|t| t.into_token())
         .any(|t| t.kind() == SyntaxKind::IDENT && t.text() == "inputs")
 }
-
-#[cfg(test)]
-mod tests {
-    use super::strip_concept_qualifiers;
-
-    #[test]
-    fn strip_bare_brackets() {
-        assert_eq!(strip_concept_qualifiers("[]"), "");
-    }
-
-    #[test]
-    fn strip_lone_dot() {
-        assert_eq!(strip_concept_qualifiers("."), "");
-    }
-
-    #[test]
-    fn strip_specific_count_only() {
-        assert_eq!(strip_concept_qualifiers("[5]"), "");
-    }
-
-    #[test]
-    fn strip_domain_and_brackets() {
-        assert_eq!(strip_concept_qualifiers("domain.[]"), "");
-    }
-
-    #[test]
-    fn strip_normal_concept_unchanged() {
-        assert_eq!(strip_concept_qualifiers("Analysis"), "Analysis");
-    }
-
-    #[test]
-    fn strip_indefinite_multiplicity() {
-        assert_eq!(strip_concept_qualifiers("Slide[]"), "Slide");
-    }
-
-    #[test]
-    fn strip_specific_multiplicity() {
-        assert_eq!(strip_concept_qualifiers("Page[5]"), "Page");
-    }
-
-    #[test]
-    fn strip_domain_prefix() {
-        assert_eq!(strip_concept_qualifiers("images.Photo"), "Photo");
-    }
-
-    #[test]
-    fn strip_domain_prefix_and_multiplicity() {
-        assert_eq!(strip_concept_qualifiers("legal.Contract[]"), "Contract");
-    }
-}
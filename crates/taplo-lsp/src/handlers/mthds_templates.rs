@@ -0,0 +1,273 @@
+//! The variables of the prompt templates of `.mthds` pipes — `@name`,
+//! `$name` and names inside `{{ … }}` / `{% … %}`, with their `.field`
+//! segments — and the concept fields they reach, shared by completion and
+//! hover. Templates are read with the Jinja lexer and parser of
+//! `pipelex-common`, so these features see the same templates and variables
+//! as lint and previews.
+
+use super::mthds_completion::ConceptCandidate;
+use pipelex_common::tools::{
+    bundle::{string_of, strip_concept_qualifiers},
+    jinja::{
+        self,
+        lexer::{is_name, Token, TokenKind},
+        pipe_templates,
+        variables::variables,
+        TemplateSource,
+    },
+    native_concepts::NativeConcepts,
+};
+use taplo::{
+    dom::{node::DomNode, Node},
+    rowan::{TextRange, TextSize},
+};
+
+/// The template variable the cursor is typing, in a template of a pipe.
+pub(crate) struct TemplateReference {
+    /// The pipe whose `inputs` the variable names.
+    pub(crate) pipe: Node,
+    /// The segments before the one being typed: `["doc", "author"]` for
+    /// `@doc.author.na`.
+    pub(crate) path: Vec<String>,
+    /// The range of the segment being typed, up to the cursor.
+    pub(crate) partial: TextRange,
+}
+
+/// The template variable the cursor is typing: a name right after an `@` or
+/// `$` sigil, or anywhere a name may start inside a `{{ … }}` or `{% … %}`
+/// tag, possibly followed by `.field` segments.
+pub(crate) fn template_reference_at(dom: &Node, offset: TextSize) -> Option<TemplateReference> {
+    let (pipe, source) = template_at(dom, offset)?;
+    let at = source.template_offset(offset)?;
+    let text = &source.text;
+    let tokens = jinja::lexer::tokenize(text).0;
+
+    let (path, partial_start) =
+        shorthand_path(text, &tokens, at).or_else(|| expression_path(text, &tokens, at))?;
+    Some(TemplateReference {
+        pipe,
+        path,
+        partial: TextRange::new(source.document_offset(partial_start), offset),
+    })
+}
+
+/// The segments of the shorthand the cursor is typing, and where the segment
+/// being typed starts: a shorthand token the cursor is inside of or right
+/// after (a trailing `.` included), or a lone sigil.
+fn shorthand_path(text: &str, tokens: &[Token], at: TextSize) -> Option<(Vec<String>, TextSize)> {
+    let token = tokens
+        .iter()
+        .find(|token| token.range.start() < at && at <= token.range.end() + TextSize::from(1))?;
+    let typed = match token.kind {
+        TokenKind::Shorthand
+            if at <= token.range.end()
+                || text[usize::from(token.range.end())..].starts_with('.') =>
+        {
+            &text[usize::from(token.range.start()) + 1..usize::from(at)]
+        }
+        TokenKind::Text => {
+            // A sigil with nothing typed after it yet.
+            let before = &text[usize::from(token.range.start())..usize::from(at)];
+            let lead = before.strip_suffix(['@', '$'])?;
+            if lead.ends_with(is_name) {
+                return None;
+            }
+            ""
+        }
+        _ => return None,
+    };
+    let mut segments: Vec<String> = typed.split('.').map(str::to_string).collect();
+    let partial = segments.pop()?;
+    Some((segments, at - TextSize::of(partial.as_str())))
+}
+
+/// The segments of the `name.field…` path the cursor is typing inside a
+/// tag, and where the segment being typed starts. Strings, filter names and
+/// test names are not variables.
+fn expression_path(text: &str, tokens: &[Token], at: TextSize) -> Option<(Vec<String>, TextSize)> {
+    let before: Vec<&Token> = tokens
+        .iter()
+        .filter(|token| token.range.start() < at)
+        .collect();
+    let open = before.iter().rposition(|token| {
+        matches!(
+            token.kind,
+            TokenKind::ExpressionOpen | TokenKind::StatementOpen
+        )
+    })?;
+    let inside = &before[open + 1..];
+    if inside.iter().any(|token| {
+        matches!(
+            token.kind,
+            TokenKind::ExpressionClose | TokenKind::StatementClose
+        )
+    }) || before[open].range.end() > at
+    {
+        return None;
+    }
+
+    // The name being typed, when the cursor touches one.
+    let (partial_start, mut rest) = match inside.split_last() {
+        Some((last, rest)) if last.range.end() >= at => match last.kind {
+            TokenKind::Name => (last.range.start(), rest),
+            TokenKind::Operator => (at, inside),
+            _ => return None,
+        },
+        _ => (at, inside),
+    };
+    let mut segments = Vec::new();
+    while let [earlier @ .., name, dot] = rest {
+        if name.kind != TokenKind::Name || dot.text(text) != "." {
+            break;
+        }
+        segments.push(name.text(text).to_string());
+        rest = earlier;
+    }
+    // A field of something else than a variable, a filter or test name, or
+    // the name of a statement.
+    if matches!(
+        rest.last().map(|token| token.text(text)),
+        Some("." | "|" | "is")
+    ) || (rest.is_empty() && before[open].kind == TokenKind::StatementOpen)
+    {
+        return None;
+    }
+    segments.reverse();
+    Some((segments, partial_start))
+}
+
+/// A template variable under the cursor.
+pub(crate) struct TemplateVariable {
+    /// The pipe whose `inputs` the variable names.
+    pub(crate) pipe: Node,
+    /// The segments up to the one under the cursor: `["doc", "author"]` with
+    /// the cursor on `author` in `@doc.author.name`.
+    pub(crate) path: Vec<String>,
+    /// The range of those segments.
+    pub(crate) range: TextRange,
+}
+
+/// The template variable under the cursor, as far as the segment the cursor
+/// is on. Names bound in the template itself, like loop variables, are not
+/// template variables.
+pub(crate) fn template_variable_at(dom: &Node, offset: TextSize) -> Option<TemplateVariable> {
+    let (pipe, source) = template_at(dom, offset)?;
+    let at = source.template_offset(offset)?;
+    let parsed = jinja::parse(&source.text);
+    variables(&parsed.template)
+        .into_iter()
+        .find_map(|variable| {
+            let on = variable
+                .path
+                .iter()
+                .position(|segment| segment.range.contains_inclusive(at))?;
+            let range = variable.path[0].range.cover(variable.path[on].range);
+            Some(TemplateVariable {
+                pipe: pipe.clone(),
+                path: variable.path[..=on]
+                    .iter()
+                    .map(|segment| segment.name.clone())
+                    .collect(),
+                range: source.document_range(range),
+            })
+        })
+}
+
+/// The pipe and the template the cursor is inside of.
+fn template_at(dom: &Node, offset: TextSize) -> Option<(Node, TemplateSource)> {
+    let pipes = dom.get("pipe");
+    let pipes = pipes.as_table()?;
+    let entries = pipes.entries().read();
+    for (_, pipe) in entries.iter() {
+        for (_, template) in pipe_templates(pipe) {
+            let Some(range) = template.syntax().map(|syntax| syntax.text_range()) else {
+                continue;
+            };
+            if range.start() < offset && offset < range.end() {
+                return Some((pipe.clone(), TemplateSource::from_node(&template)?));
+            }
+        }
+    }
+    None
+}
+
+/// A field of a concept's content.
+pub(crate) struct ConceptField {
+    pub(crate) name: String,
+    /// Its type as written: `text`, `list`, a concept reference, or the
+    /// Python-like type of a native field.
+    pub(crate) detail: String,
+    pub(crate) description: Option<String>,
+    /// The concept of a `concept` field, whose own fields follow a `.`.
+    pub(crate) concept: Option<String>,
+}
+
+/// The fields of `concept`: the `structure` of a bundle concept (or of what
+/// it refines, when it has none), or the fields of a native concept, inherited
+/// ones included. A list concept has no fields.
+pub(crate) fn concept_fields(
+    concept: &str,
+    concepts: &[ConceptCandidate],
+    natives: &NativeConcepts,
+) -> Vec<ConceptField> {
+    let mut concept = concept.to_string();
+    // Bounded, so a `refines` cycle cannot loop forever.
+    for _ in 0..8 {
+        if concept.contains('[') {
+            return Vec::new();
+        }
+        let name = strip_concept_qualifiers(&concept);
+        let Some(candidate) = concepts.iter().find(|candidate| candidate.name == name) else {
+            return natives
+                .fields(&name)
+                .into_iter()
+                .map(|(name, ty)| ConceptField {
+                    name: name.to_string(),
+                    detail: ty.to_string(),
+                    description: None,
+                    concept: None,
+                })
+                .collect();
+        };
+        let structure = candidate.concept.get("structure");
+        if let Some(structure) = structure.as_table() {
+            let entries = structure.entries().read();
+            return entries
+                .iter()
+                .map(|(key, field)| structure_field(key.value(), field))
+                .collect();
+        }
+        let Some(refines) = string_of(&candidate.concept.get("refines")) else {
+            return Vec::new();
+        };
+        concept = refines;
+    }
+    Vec::new()
+}
+
+/// A `structure` entry: the `field = "description"` shorthand for a text
+/// field, or a `{ type, description, concept_ref, … }` table.
+fn structure_field(name: &str, field: &Node) -> ConceptField {
+    if let Some(description) = string_of(field) {
+        return ConceptField {
+            name: name.to_string(),
+            detail: "text".to_string(),
+            description: Some(description),
+            concept: None,
+        };
+    }
+    let concept = string_of(&field.get("concept_ref"));
+    let item = string_of(&field.get("item_concept_ref"));
+    let ty = string_of(&field.get("type")).unwrap_or_else(|| "text".to_string());
+    let detail = match (&concept, &item) {
+        (Some(concept), _) => concept.clone(),
+        (None, Some(item)) => format!("{item}[]"),
+        (None, None) => ty,
+    };
+    ConceptField {
+        name: name.to_string(),
+        detail,
+        description: string_of(&field.get("description")),
+        concept,
+    }
+}
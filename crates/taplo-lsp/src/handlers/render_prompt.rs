@@ -0,0 +1,82 @@
+use lsp_async_stub::{rpc::Error, util::LspExt, Context, Params};
+use pipelex_common::tools::{
+    jinja::render::{TagStyle, TextFormat},
+    render_prompt::{render_prompts, RenderOptions},
+};
+use taplo_common::environment::Environment;
+
+use super::sibling_files;
+use crate::{
+    lsp_ext::request::{RenderError, RenderPromptParams, RenderPromptResponse, RenderedPrompt},
+    world::World,
+};
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn render_prompt<E: Environment>(
+    context: Context<World<E>>,
+    params: Params<RenderPromptParams>,
+) -> Result<RenderPromptResponse, Error> {
+    let p = params.required()?;
+    let failure = |error: String| RenderPromptResponse {
+        prompts: Vec::new(),
+        error: Some(error),
+    };
+
+    let tag_style = match p.tag_style.as_deref() {
+        Some(name) => match TagStyle::from_name(name) {
+            Some(style) => Some(style),
+            None => return Ok(failure(format!("unknown tag style `{name}`"))),
+        },
+        None => None,
+    };
+    let text_format = match p.text_format.as_deref() {
+        Some(name) => match TextFormat::from_name(name) {
+            Some(format) => Some(format),
+            None => return Ok(failure(format!("unknown text format `{name}`"))),
+        },
+        None => None,
+    };
+
+    let workspaces = context.workspaces.read().await;
+    let ws = workspaces.by_document(&p.document_uri);
+    let doc = match ws.document(&p.document_uri) {
+        Ok(d) => d,
+        Err(error) => return Ok(failure(error.to_string())),
+    };
+
+    let mut siblings = Vec::new();
+    if let Some(path) = context.env.to_file_path_normalized(&p.document_uri) {
+        for sibling in sibling_files(&context.env, ws, &path).await {
+            siblings.push(sibling.dom);
+        }
+    }
+
+    let options = RenderOptions {
+        inputs: p.inputs.as_ref(),
+        tag_style,
+        text_format,
+    };
+    match render_prompts(&doc.dom, &siblings, &p.pipe, &ws.natives, options) {
+        Ok(prompts) => Ok(RenderPromptResponse {
+            prompts: prompts
+                .into_iter()
+                .map(|prompt| RenderedPrompt {
+                    field: prompt.field.to_string(),
+                    text: prompt.text,
+                    errors: prompt
+                        .errors
+                        .into_iter()
+                        .filter_map(|error| {
+                            Some(RenderError {
+                                range: doc.mapper.range(error.range)?.into_lsp(),
+                                message: error.message,
+                            })
+                        })
+                        .collect(),
+                })
+                .collect(),
+            error: None,
+        }),
+        Err(error) => Ok(failure(error.to_string())),
+    }
+}
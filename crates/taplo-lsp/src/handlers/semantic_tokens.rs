@@ -1,19 +1,22 @@
+use super::{goto_definition::sibling_files, mthds_actions::declared_inputs};
 use crate::World;
-use lsp_async_stub::{
-    rpc::Error,
-    util::{relative_range, LspExt, Mapper},
-    Context, Params,
-};
+use lsp_async_stub::{rpc::Error, util::Mapper, Context, Params};
 use lsp_types::{
-    Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
-    SemanticTokensParams, SemanticTokensResult,
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensParams,
+    SemanticTokensResult,
+};
+use pipelex_common::tools::{
+    bundle::{BundleIndex, RefKind, Reference},
+    jinja::{self, lexer::TokenKind, pipe_templates, variables::variables, TemplateSource},
+    native_concepts::NativeConcepts,
 };
 use taplo::{
-    dom::node::DomNode,
+    dom::{node::DomNode, Node},
+    rowan::{TextRange, TextSize},
     syntax::{
         SyntaxElement,
-        SyntaxKind::{ARRAY, IDENT, INLINE_TABLE},
-        SyntaxNode, SyntaxToken,
+        SyntaxKind::{self, ARRAY, IDENT, INLINE_TABLE},
+        SyntaxNode,
     },
 };
 use taplo_common::environment::Environment;
@@ -40,42 +43,109 @@ pub(crate) async fn semantic_tokens<E: Environment>(
         }
     };
 
+    let syntax = doc.dom.syntax().unwrap().as_node().unwrap().clone();
+    let mut builder = SemanticTokensBuilder::new(&doc.mapper);
+    add_toml_tokens(&mut builder, &syntax);
+
+    if p.text_document.uri.as_str().ends_with(".mthds") {
+        let mut siblings = Vec::new();
+        if let Some(path) = context.env.to_file_path_normalized(&p.text_document.uri) {
+            for sibling in sibling_files(&context.env, ws, &path).await {
+                siblings.push(BundleIndex::from_dom(&sibling.dom));
+            }
+        }
+        for token in mthds_tokens(&doc.dom, &siblings, &ws.natives) {
+            builder.add_token(token.range, token.ty, &token.modifiers);
+        }
+    }
+
     Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
         result_id: None,
-        data: create_tokens(doc.dom.syntax().unwrap().as_node().unwrap(), &doc.mapper),
+        data: builder.build(),
     })))
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u32)]
 pub enum TokenType {
     TomlArrayKey,
     TomlTableKey,
+    /// A pipe reference, e.g. a step's `pipe` or a `main_pipe`.
+    MthdsPipeName,
+    /// A concept reference, e.g. an `output` or an `inputs` value.
+    MthdsConcept,
+    /// The `domain.` qualifier of a pipe or concept reference.
+    MthdsDomain,
+    /// The `[]` / `[N]` suffix of a concept reference.
+    MthdsMultiplicity,
+    /// A template variable: `@name`, `$name` or an input named in `{{ … }}`.
+    MthdsDataVariable,
+    /// `{{`, `}}`, `{%`, `%}`, `{#` and `#}` inside a prompt.
+    MthdsJinjaDelimiter,
+    /// A Jinja keyword inside a prompt, e.g. `for` or `endif`.
+    MthdsJinjaKeyword,
+    /// A `model` or `model_to_structure` value.
+    MthdsModelRef,
 }
 
 impl TokenType {
     pub const LEGEND: &'static [SemanticTokenType] = &[
         SemanticTokenType::new("tomlArrayKey"),
         SemanticTokenType::new("tomlTableKey"),
+        SemanticTokenType::new("mthdsPipeName"),
+        SemanticTokenType::new("mthdsConcept"),
+        SemanticTokenType::new("mthdsDomain"),
+        SemanticTokenType::new("mthdsMultiplicity"),
+        SemanticTokenType::new("mthdsDataVariable"),
+        SemanticTokenType::new("mthdsJinjaDelimiter"),
+        SemanticTokenType::new("mthdsJinjaKeyword"),
+        SemanticTokenType::new("mthdsModelRef"),
     ];
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u32)]
 pub enum TokenModifier {
     ReadOnly,
+    /// A native concept (or its `native.` qualifier).
+    Native,
+    /// Set along with `Native`, for themes that only know the standard
+    /// modifiers.
+    DefaultLibrary,
+    /// A reference to a pipe, concept or input the bundle does not define.
+    Unresolved,
+    /// A `$preset` model reference.
+    Preset,
+    /// An `@alias` model reference.
+    Alias,
+    /// A `~waterfall` model reference.
+    Waterfall,
+    /// A `#handle` model reference.
+    Handle,
 }
 
 impl TokenModifier {
-    pub const MODIFIERS: &'static [SemanticTokenModifier] = &[SemanticTokenModifier::READONLY];
+    pub const MODIFIERS: &'static [SemanticTokenModifier] = &[
+        SemanticTokenModifier::READONLY,
+        SemanticTokenModifier::new("native"),
+        SemanticTokenModifier::DEFAULT_LIBRARY,
+        SemanticTokenModifier::new("unresolved"),
+        SemanticTokenModifier::new("preset"),
+        SemanticTokenModifier::new("alias"),
+        SemanticTokenModifier::new("waterfall"),
+        SemanticTokenModifier::new("handle"),
+    ];
 }
 
 #[tracing::instrument(skip_all)]
 pub fn create_tokens(syntax: &SyntaxNode, mapper: &Mapper) -> Vec<SemanticToken> {
     let mut builder = SemanticTokensBuilder::new(mapper);
+    add_toml_tokens(&mut builder, syntax);
+    builder.build()
+}
 
+fn add_toml_tokens(builder: &mut SemanticTokensBuilder, syntax: &SyntaxNode) {
     for element in syntax.descendants_with_tokens() {
         match element {
             SyntaxElement::Node(_node) => {}
@@ -89,7 +159,7 @@ pub fn create_tokens(syntax: &SyntaxNode, mapper: &Mapper) -> Vec<SemanticToken>
                         .is_some_and(|t| t.kind() == INLINE_TABLE);
 
                     if is_table_key {
-                        builder.add_token(&token, TokenType::TomlTableKey, &[]);
+                        builder.add_token(token.text_range(), TokenType::TomlTableKey, &[]);
                         continue;
                     }
 
@@ -101,20 +171,280 @@ pub fn create_tokens(syntax: &SyntaxNode, mapper: &Mapper) -> Vec<SemanticToken>
                         .is_some_and(|t| t.kind() == ARRAY);
 
                     if is_array_key {
-                        builder.add_token(&token, TokenType::TomlArrayKey, &[]);
+                        builder.add_token(token.text_range(), TokenType::TomlArrayKey, &[]);
                     }
                 }
             }
         }
     }
+}
 
-    builder.build()
+/// A semantic token of an `.mthds` document, before encoding.
+#[derive(Debug)]
+pub(crate) struct MthdsToken {
+    pub(crate) range: TextRange,
+    pub(crate) ty: TokenType,
+    pub(crate) modifiers: Vec<TokenModifier>,
+}
+
+const JINJA_KEYWORDS: &[&str] = &[
+    "and",
+    "as",
+    "block",
+    "call",
+    "elif",
+    "else",
+    "endblock",
+    "endcall",
+    "endfilter",
+    "endfor",
+    "endif",
+    "endmacro",
+    "endraw",
+    "endset",
+    "endwith",
+    "extends",
+    "false",
+    "filter",
+    "for",
+    "from",
+    "if",
+    "import",
+    "in",
+    "include",
+    "is",
+    "macro",
+    "none",
+    "not",
+    "or",
+    "raw",
+    "recursive",
+    "set",
+    "true",
+    "with",
+];
+
+/// The MTHDS tokens of `dom`, resolving its references against the document
+/// itself and the indexes of its `siblings` in the same bundle:
+///
+/// - pipe and concept references, split into their domain qualifier, name and
+///   multiplicity suffix; native concepts carry `native` and
+///   `defaultLibrary`, and references the bundle does not define carry
+///   `unresolved`;
+/// - in prompt templates, the variables they read — `@name` / `$name`
+///   shorthands and names inside `{{ … }}` / `{% … %}`, `unresolved` when
+///   the pipe has no such input — the Jinja delimiters and the keywords;
+/// - `model` and `model_to_structure` values, with a modifier for their
+///   `$`, `@`, `~` or `#` prefix.
+///
+/// References into other domains point outside the bundle and are never
+/// marked `unresolved`. Model values written with escapes or across lines
+/// are skipped, as their text does not map onto the source byte for byte.
+pub(crate) fn mthds_tokens(
+    dom: &Node,
+    siblings: &[BundleIndex],
+    natives: &NativeConcepts,
+) -> Vec<MthdsToken> {
+    let index = BundleIndex::from_dom(dom);
+    let mut tokens = Vec::new();
+    for reference in &index.references {
+        reference_tokens(&mut tokens, reference, &index, siblings, natives);
+    }
+
+    let pipes = dom.get("pipe");
+    if let Some(pipes) = pipes.as_table() {
+        let entries = pipes.entries().read();
+        for (_, pipe) in entries.iter() {
+            let inputs = declared_inputs(pipe);
+            for (_, template) in pipe_templates(pipe) {
+                template_tokens(&mut tokens, &template, &inputs);
+            }
+            for key in ["model", "model_to_structure"] {
+                let model = pipe.get(key);
+                if model.as_table().is_some() {
+                    model_tokens(&mut tokens, &model.get("model"));
+                } else {
+                    model_tokens(&mut tokens, &model);
+                }
+            }
+        }
+    }
+
+    tokens.sort_by_key(|token| token.range.start());
+    tokens
+}
+
+fn reference_tokens(
+    tokens: &mut Vec<MthdsToken>,
+    reference: &Reference,
+    index: &BundleIndex,
+    siblings: &[BundleIndex],
+    natives: &NativeConcepts,
+) {
+    let Some(name_range) = reference.name_range else {
+        return;
+    };
+    let defined = || {
+        std::iter::once(index)
+            .chain(siblings)
+            .any(|index| index.defines(reference.kind, &reference.name))
+    };
+    let native = reference.kind == RefKind::Concept
+        && natives.find(&reference.name).is_some()
+        && match reference.domain.as_deref() {
+            Some("native") => true,
+            None => !defined(),
+            Some(_) => false,
+        };
+    let local = match reference.domain.as_deref() {
+        None => true,
+        Some("native") => reference.kind == RefKind::Concept,
+        Some(domain) => index.domain.as_deref() == Some(domain),
+    };
+    let mut modifiers = Vec::new();
+    if native {
+        modifiers.extend([TokenModifier::Native, TokenModifier::DefaultLibrary]);
+    } else if local && !defined() {
+        modifiers.push(TokenModifier::Unresolved);
+    }
+
+    if let Some(domain) = &reference.domain {
+        let qualifier_modifiers = if native {
+            vec![TokenModifier::Native, TokenModifier::DefaultLibrary]
+        } else {
+            Vec::new()
+        };
+        tokens.push(MthdsToken {
+            range: TextRange::at(
+                reference.range.start() + TextSize::from(1),
+                TextSize::of(domain.as_str()),
+            ),
+            ty: TokenType::MthdsDomain,
+            modifiers: qualifier_modifiers,
+        });
+    }
+    tokens.push(MthdsToken {
+        range: name_range,
+        ty: match reference.kind {
+            RefKind::Pipe => TokenType::MthdsPipeName,
+            RefKind::Concept => TokenType::MthdsConcept,
+        },
+        modifiers,
+    });
+    // Everything between the name and the closing quote is the multiplicity.
+    let suffix_end = reference.range.end() - TextSize::from(1);
+    if name_range.end() < suffix_end {
+        tokens.push(MthdsToken {
+            range: TextRange::new(name_range.end(), suffix_end),
+            ty: TokenType::MthdsMultiplicity,
+            modifiers: Vec::new(),
+        });
+    }
+}
+
+/// The tokens of a prompt template: the context variables it reads, its
+/// Jinja delimiters and its keywords. Names the template binds itself, like
+/// loop variables, and filter names are left alone.
+fn template_tokens(tokens: &mut Vec<MthdsToken>, template: &Node, inputs: &[(String, String)]) {
+    let Some(source) = TemplateSource::from_node(template) else {
+        return;
+    };
+    let parsed = jinja::parse(&source.text);
+    let mut push = |range: TextRange, ty: TokenType, modifiers: Vec<TokenModifier>| {
+        tokens.push(MthdsToken {
+            range: source.document_range(range),
+            ty,
+            modifiers,
+        });
+    };
+
+    for token in &parsed.tokens {
+        let range = token.range;
+        match token.kind {
+            TokenKind::ExpressionOpen
+            | TokenKind::ExpressionClose
+            | TokenKind::StatementOpen
+            | TokenKind::StatementClose => push(range, TokenType::MthdsJinjaDelimiter, Vec::new()),
+            TokenKind::Comment => {
+                let delimiter = TextSize::from(2);
+                push(
+                    TextRange::at(range.start(), delimiter),
+                    TokenType::MthdsJinjaDelimiter,
+                    Vec::new(),
+                );
+                if token.text(&source.text).len() >= 4 && token.text(&source.text).ends_with("#}") {
+                    push(
+                        TextRange::new(range.end() - delimiter, range.end()),
+                        TokenType::MthdsJinjaDelimiter,
+                        Vec::new(),
+                    );
+                }
+            }
+            TokenKind::Name if JINJA_KEYWORDS.contains(&token.text(&source.text)) => {
+                push(range, TokenType::MthdsJinjaKeyword, Vec::new());
+            }
+            _ => {}
+        }
+    }
+
+    for variable in variables(&parsed.template) {
+        let declared = inputs.iter().any(|(input, _)| input == variable.name());
+        push(
+            variable.range,
+            TokenType::MthdsDataVariable,
+            if declared {
+                Vec::new()
+            } else {
+                vec![TokenModifier::Unresolved]
+            },
+        );
+    }
+}
+
+/// The token of a model reference, with the modifier of its prefix.
+fn model_tokens(tokens: &mut Vec<MthdsToken>, model: &Node) {
+    let Some((value, range)) = verbatim_contents(model) else {
+        return;
+    };
+    let modifiers = match value.chars().next() {
+        Some('$') => vec![TokenModifier::Preset],
+        Some('@') => vec![TokenModifier::Alias],
+        Some('~') => vec![TokenModifier::Waterfall],
+        Some('#') => vec![TokenModifier::Handle],
+        Some(_) => Vec::new(),
+        None => return,
+    };
+    tokens.push(MthdsToken {
+        range,
+        ty: TokenType::MthdsModelRef,
+        modifiers,
+    });
+}
+
+/// The value of a one-line string without escapes and the range of its
+/// contents, quotes excluded.
+fn verbatim_contents(node: &Node) -> Option<(String, TextRange)> {
+    let value = node.as_str()?.value().to_string();
+    let syntax = node.syntax()?;
+    let text = syntax.to_string();
+    let verbatim = matches!(
+        syntax.kind(),
+        SyntaxKind::STRING | SyntaxKind::STRING_LITERAL
+    ) && text.get(1..text.len().saturating_sub(1)) == Some(value.as_str());
+    if !verbatim {
+        return None;
+    }
+    let range = syntax.text_range();
+    let range = TextRange::new(
+        range.start() + TextSize::from(1),
+        range.end() - TextSize::from(1),
+    );
+    Some((value, range))
 }
 
 struct SemanticTokensBuilder<'b> {
-    tokens: Vec<SemanticToken>,
+    tokens: Vec<(TextRange, TokenType, u32)>,
     mapper: &'b Mapper,
-    last_range: Option<Range>,
 }
 
 impl<'b> SemanticTokensBuilder<'b> {
@@ -122,39 +452,46 @@ impl<'b> SemanticTokensBuilder<'b> {
         Self {
             tokens: Vec::new(),
             mapper,
-            last_range: None,
         }
     }
 
-    fn add_token(
-        &mut self,
-        token: &SyntaxToken,
-        ty: TokenType,
-        modifiers: &[SemanticTokenModifier],
-    ) {
-        let range = self.mapper.range(token.text_range()).unwrap();
-
-        let relative = relative_range(
-            range,
-            lsp_async_stub::util::Range::from_lsp(self.last_range.unwrap_or_default()),
-        );
-
-        #[allow(clippy::cast_possible_truncation)]
-        self.tokens.push(SemanticToken {
-            delta_line: relative.start.line as u32,
-            delta_start: relative.start.character as u32,
-            length: (relative.end.character - relative.start.character) as u32,
-            token_type: ty as u32,
-            token_modifiers_bitset: modifiers.iter().enumerate().fold(0, |mut total, (i, _)| {
-                total += 1 << i;
-                total
-            }),
-        });
-
-        self.last_range = Some(range.into_lsp());
+    fn add_token(&mut self, range: TextRange, ty: TokenType, modifiers: &[TokenModifier]) {
+        let bitset = modifiers
+            .iter()
+            .fold(0, |bits, modifier| bits | 1 << *modifier as u32);
+        self.tokens.push((range, ty, bitset));
     }
 
-    fn build(self) -> Vec<SemanticToken> {
-        self.tokens
+    /// Encode the tokens relative to each other, in document order. Tokens
+    /// spanning several lines cannot be encoded and are dropped.
+    fn build(mut self) -> Vec<SemanticToken> {
+        self.tokens.sort_by_key(|(range, ..)| range.start());
+
+        let mut data = Vec::with_capacity(self.tokens.len());
+        let (mut line, mut character) = (0, 0);
+        for (range, ty, bitset) in self.tokens {
+            let Some(range) = self.mapper.range(range) else {
+                continue;
+            };
+            if range.start.line != range.end.line {
+                continue;
+            }
+            let delta_line = range.start.line - line;
+            let delta_start = if delta_line == 0 {
+                range.start.character - character
+            } else {
+                range.start.character
+            };
+            #[allow(clippy::cast_possible_truncation)]
+            data.push(SemanticToken {
+                delta_line: delta_line as u32,
+                delta_start: delta_start as u32,
+                length: (range.end.character - range.start.character) as u32,
+                token_type: ty as u32,
+                token_modifiers_bitset: bitset,
+            });
+            (line, character) = (range.start.line, range.start.character);
+        }
+        data
     }
 }
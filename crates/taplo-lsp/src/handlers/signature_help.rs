@@ -0,0 +1,229 @@
+//! Signature help for the calls of `.mthds` pipes: inside a `steps` or
+//! `branches` entry (`{ pipe = "name", … }`) and on the `branch_pipe_code`
+//! of a `PipeBatch`, the called pipe's inputs and output, with the input the
+//! cursor is about highlighted.
+
+use super::{
+    mthds_actions::{declared_inputs, visible_names},
+    mthds_resolution::resolve_pipe_call,
+};
+use crate::world::World;
+use lsp_async_stub::{
+    rpc::Error,
+    util::{LspExt, Position},
+    Context, Params,
+};
+use lsp_types::{
+    Documentation, ParameterInformation, ParameterLabel, SignatureHelp, SignatureHelpParams,
+    SignatureInformation,
+};
+use pipelex_common::tools::bundle::string_of;
+use taplo::{dom::Node, rowan::TextSize};
+use taplo_common::environment::Environment;
+
+/// The signature of the pipe called at the cursor.
+pub(crate) struct Signature {
+    pub(crate) pipe: String,
+    pub(crate) description: Option<String>,
+    pub(crate) inputs: Vec<(String, String)>,
+    pub(crate) output: Option<String>,
+    /// The index in `inputs` of the active parameter.
+    pub(crate) active: Option<usize>,
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn signature_help<E: Environment>(
+    context: Context<World<E>>,
+    params: Params<SignatureHelpParams>,
+) -> Result<Option<SignatureHelp>, Error> {
+    let p = params.required()?;
+
+    let document_uri = p.text_document_position_params.text_document.uri;
+    if !document_uri.as_str().ends_with(".mthds") {
+        return Ok(None);
+    }
+
+    let workspaces = context.workspaces.read().await;
+    let ws = workspaces.by_document(&document_uri);
+    let doc = match ws.document(&document_uri) {
+        Ok(d) => d,
+        Err(error) => {
+            tracing::debug!(%error, "failed to get document from workspace");
+            return Ok(None);
+        }
+    };
+
+    let position = p.text_document_position_params.position;
+    let Some(offset) = doc.mapper.offset(Position::from_lsp(position)) else {
+        tracing::error!(?position, "document position not found");
+        return Ok(None);
+    };
+
+    Ok(mthds_signature_at(&doc.dom, offset).map(|signature| {
+        let active = signature.active.and_then(|i| u32::try_from(i).ok());
+        SignatureHelp {
+            signatures: vec![signature_information(&signature)],
+            active_signature: Some(0),
+            active_parameter: active,
+        }
+    }))
+}
+
+/// The signature of the pipe called by the entry the cursor is in:
+///
+/// - a `steps` entry of a `PipeSequence` or a `branches` entry of a
+///   `PipeParallel`, anywhere between its braces;
+/// - the `branch_pipe_code`, `input_list_name` or `input_item_name` entry of
+///   a `PipeBatch`.
+///
+/// On a `batch_over` / `batch_as` entry (or `input_list_name` /
+/// `input_item_name`), the active parameter is the input receiving each
+/// item. Elsewhere it is the first input the call does not see yet — one that
+/// is neither an input of the caller nor an earlier step's `result` — so the
+/// missing wiring stands out. Calls to pipes not defined in the document get
+/// no signature, as they get no hover.
+pub(crate) fn mthds_signature_at(dom: &Node, offset: TextSize) -> Option<Signature> {
+    let pipes = dom.get("pipe");
+    let pipes = pipes.as_table()?;
+    let entries = pipes.entries().read();
+    for (_, pipe) in entries.iter() {
+        let inputs = declared_inputs(pipe);
+
+        for key in ["steps", "branches"] {
+            let calls = pipe.get(key);
+            let Some(calls) = calls.as_array() else {
+                continue;
+            };
+            let calls = calls.items().read();
+            let Some(position) = calls.iter().position(|call| {
+                call.as_table().is_some()
+                    && call
+                        .text_ranges(true)
+                        .next()
+                        .is_some_and(|range| range.contains(offset))
+            }) else {
+                continue;
+            };
+            let call = &calls[position];
+            let mut visible = if key == "steps" {
+                visible_names(dom, inputs, &calls[..position])
+            } else {
+                inputs
+            };
+            let item = string_of(&call.get("batch_as"));
+            if let Some(item) = &item {
+                visible.push((item.clone(), String::new()));
+            }
+            let on_batch = ["batch_over", "batch_as"]
+                .iter()
+                .any(|key| entry_contains(call, key, offset));
+            return signature(
+                dom,
+                &call.get("pipe"),
+                &visible,
+                item.filter(|_| on_batch).as_deref(),
+            );
+        }
+
+        let batch_entries = ["branch_pipe_code", "input_list_name", "input_item_name"];
+        if !batch_entries
+            .iter()
+            .any(|key| entry_contains(pipe, key, offset))
+        {
+            continue;
+        }
+        let list = string_of(&pipe.get("input_list_name"));
+        let item = string_of(&pipe.get("input_item_name"));
+        let mut visible = inputs;
+        if let Some(item) = &item {
+            visible.retain(|(name, _)| Some(name) != list.as_ref());
+            visible.push((item.clone(), String::new()));
+        }
+        let on_batch = batch_entries[1..]
+            .iter()
+            .any(|key| entry_contains(pipe, key, offset));
+        return signature(
+            dom,
+            &pipe.get("branch_pipe_code"),
+            &visible,
+            item.filter(|_| on_batch).as_deref(),
+        );
+    }
+    None
+}
+
+/// The LSP signature of `signature`: `name(input: Concept, …) -> Output`,
+/// with one parameter per input, located by its offsets in the label.
+pub(crate) fn signature_information(signature: &Signature) -> SignatureInformation {
+    let mut label = format!("{}(", signature.pipe);
+    let mut parameters = Vec::new();
+    for (i, (name, concept)) in signature.inputs.iter().enumerate() {
+        if i > 0 {
+            label.push_str(", ");
+        }
+        let start = utf16_len(&label);
+        label.push_str(name);
+        label.push_str(": ");
+        label.push_str(concept);
+        parameters.push(ParameterInformation {
+            label: ParameterLabel::LabelOffsets([start, utf16_len(&label)]),
+            documentation: None,
+        });
+    }
+    label.push(')');
+    if let Some(output) = &signature.output {
+        label.push_str(" -> ");
+        label.push_str(output);
+    }
+    SignatureInformation {
+        label,
+        documentation: signature.description.clone().map(Documentation::String),
+        parameters: Some(parameters),
+        active_parameter: signature.active.and_then(|i| u32::try_from(i).ok()),
+    }
+}
+
+/// The signature of the pipe the `value` of a call names, `None` when it is
+/// not defined in the document.
+fn signature(
+    dom: &Node,
+    value: &Node,
+    visible: &[(String, String)],
+    item: Option<&str>,
+) -> Option<Signature> {
+    let callee = resolve_pipe_call(dom, value)?;
+    let inputs = declared_inputs(&callee);
+    let active = match item {
+        Some(item) => inputs.iter().position(|(name, _)| name == item),
+        None => inputs
+            .iter()
+            .position(|(name, _)| !visible.iter().any(|(seen, _)| seen == name)),
+    };
+    Some(Signature {
+        pipe: string_of(value)?,
+        description: string_of(&callee.get("description")).filter(|d| !d.is_empty()),
+        inputs,
+        output: string_of(&callee.get("output")),
+        active,
+    })
+}
+
+/// Whether `offset` is within the `key = value` entry of `table`.
+fn entry_contains(table: &Node, key: &str, offset: TextSize) -> bool {
+    let Some(table) = table.as_table() else {
+        return false;
+    };
+    let entries = table.entries().read();
+    let contains = entries.iter().any(|(k, value)| {
+        k.value() == key
+            && k.text_ranges()
+                .chain(value.text_ranges(true))
+                .reduce(|a, b| a.cover(b))
+                .is_some_and(|range| range.contains_inclusive(offset))
+    });
+    contains
+}
+
+fn utf16_len(s: &str) -> u32 {
+    u32::try_from(s.encode_utf16().count()).unwrap_or(u32::MAX)
+}
@@ -0,0 +1,91 @@
+use crate::handlers::{diagnostic_fixes, fix_all_edits, fix_data};
+use lsp_async_stub::util::Mapper;
+use lsp_types::{Diagnostic, Position, Range};
+use pipelex_common::tools::{
+    bundle::BundleIndex, models::ModelDeck, native_concepts::NativeConcepts, rules::check_bundle,
+};
+use taplo::parser::parse;
+use taplo_common::config::LintOptions;
+
+const SOURCE: &str = r#"domain = "d"
+
+[pipe.review]
+type = "PipeSequence"
+description = "Review"
+output = "Txt"
+steps = [{ pipe = "sumarize" }]
+
+[pipe.summarize]
+type = "PipeLLM"
+description = "Summarize"
+output = "Text"
+"#;
+
+/// The rule diagnostics of `SOURCE`, as the server publishes them.
+fn published() -> Vec<Diagnostic> {
+    let dom = parse(SOURCE).into_dom();
+    check_bundle(
+        &BundleIndex::from_dom(&dom),
+        &[],
+        NativeConcepts::embedded(),
+        &ModelDeck::default(),
+        &LintOptions::default(),
+    )
+    .into_iter()
+    .map(|violation| Diagnostic {
+        data: fix_data(&violation.fixes),
+        ..Default::default()
+    })
+    .collect()
+}
+
+#[test]
+fn fixes_round_trip_through_diagnostic_data() {
+    let diagnostics = published();
+    let titles: Vec<Vec<String>> = diagnostics
+        .iter()
+        .map(|d| {
+            diagnostic_fixes(d)
+                .into_iter()
+                .map(|fix| fix.title)
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        titles,
+        [
+            vec!["Replace with `Text`".to_string()],
+            vec!["Replace with `summarize`".to_string()],
+        ]
+    );
+    assert!(diagnostic_fixes(&Diagnostic::default()).is_empty());
+}
+
+#[test]
+fn fix_all_applies_each_fix_once() {
+    let dom = parse(SOURCE).into_dom();
+    let mapper = Mapper::new_utf16(SOURCE, false);
+    let mut fixes: Vec<_> = published().iter().flat_map(diagnostic_fixes).collect();
+    // The same fix twice (e.g. a diagnostic sent back twice) must not be
+    // applied twice.
+    fixes.push(fixes[0].clone());
+
+    let edits = fix_all_edits(&dom, &mapper, &fixes);
+    let edits: Vec<(Range, &str)> = edits
+        .iter()
+        .map(|edit| (edit.range, edit.new_text.as_str()))
+        .collect();
+    assert_eq!(
+        edits,
+        [
+            (
+                Range::new(Position::new(5, 10), Position::new(5, 13)),
+                "Text"
+            ),
+            (
+                Range::new(Position::new(6, 19), Position::new(6, 27)),
+                "summarize"
+            ),
+        ]
+    );
+}
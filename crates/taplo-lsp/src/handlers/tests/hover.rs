@@ -1,10 +1,12 @@
 use super::{offset_inside_string, offset_inside_string_after, parse_and_query};
 use crate::handlers::{
-    hover::{build_model_hover, build_mthds_hover_content, build_native_concept_hover},
-    mthds_resolution::{
-        classify_reference, find_native_concept, is_model_field, resolve_reference, ReferenceKind,
-    },
+    hover::{build_model_hover, build_mthds_hover_content},
+    mthds_resolution::{classify_reference, is_model_field, resolve_reference, ReferenceKind},
 };
+use pipelex_common::tools::{
+    hover::native_concept_hover, models::ModelDeck, native_concepts::NativeConcepts,
+};
+use std::path::Path;
 
 macro_rules! fixture {
     ($name:literal) => {
@@ -258,8 +260,10 @@ prompt = "hello"
     assert!(matches!(classified.kind, ReferenceKind::Concept));
     assert_eq!(classified.ref_name, "Text");
 
-    let native = find_native_concept(&classified.ref_name).expect("Text is a native concept");
-    let content = build_native_concept_hover(native);
+    let native = NativeConcepts::embedded()
+        .find(&classified.ref_name)
+        .expect("Text is a native concept");
+    let content = native_concept_hover(native, NativeConcepts::embedded());
 
     assert!(
         content.contains("**Text** *(native)*"),
@@ -289,8 +293,10 @@ prompt = "Answer yes or no."
     let classified = classify_reference(&query).expect("should classify");
     assert_eq!(classified.ref_name, "YesNo");
 
-    let native = find_native_concept(&classified.ref_name).expect("YesNo is native");
-    let content = build_native_concept_hover(native);
+    let native = NativeConcepts::embedded()
+        .find(&classified.ref_name)
+        .expect("YesNo is native");
+    let content = native_concept_hover(native, NativeConcepts::embedded());
 
     assert!(content.contains("**YesNo** *(native)*"), "got: {content}");
     assert!(
@@ -319,8 +325,10 @@ prompt = "Extract the date."
     let classified = classify_reference(&query).expect("should classify");
     assert_eq!(classified.ref_name, "Date");
 
-    let native = find_native_concept(&classified.ref_name).expect("Date is native");
-    let content = build_native_concept_hover(native);
+    let native = NativeConcepts::embedded()
+        .find(&classified.ref_name)
+        .expect("Date is native");
+    let content = native_concept_hover(native, NativeConcepts::embedded());
 
     assert!(content.contains("**Date** *(native)*"), "got: {content}");
     assert!(
@@ -351,12 +359,12 @@ fn test_native_concept_registry_matches_current_language_surface() {
         "Composite",
     ] {
         assert!(
-            find_native_concept(name).is_some(),
+            NativeConcepts::embedded().find(name).is_some(),
             "{name} should be native"
         );
     }
     assert!(
-        find_native_concept("ImgGenPrompt").is_none(),
+        NativeConcepts::embedded().find("ImgGenPrompt").is_none(),
         "ImgGenPrompt is no longer native"
     );
 }
@@ -377,8 +385,10 @@ output = "Page[]"
     let classified = classify_reference(&query).expect("should classify");
     assert_eq!(classified.ref_name, "Page");
 
-    let native = find_native_concept(&classified.ref_name).expect("Page is native");
-    let content = build_native_concept_hover(native);
+    let native = NativeConcepts::embedded()
+        .find(&classified.ref_name)
+        .expect("Page is native");
+    let content = native_concept_hover(native, NativeConcepts::embedded());
 
     assert!(content.contains("**Page** *(native)*"), "got: {content}");
     assert!(
@@ -415,8 +425,10 @@ prompt = "Analyze."
     assert!(matches!(classified.kind, ReferenceKind::Concept));
     assert_eq!(classified.ref_name, "Document");
 
-    let native = find_native_concept(&classified.ref_name).expect("Document is native");
-    let content = build_native_concept_hover(native);
+    let native = NativeConcepts::embedded()
+        .find(&classified.ref_name)
+        .expect("Document is native");
+    let content = native_concept_hover(native, NativeConcepts::embedded());
 
     assert!(
         content.contains("**Document** *(native)*"),
@@ -447,8 +459,10 @@ prompt = "Process."
         "domain prefix should be stripped"
     );
 
-    let native = find_native_concept(&classified.ref_name).expect("Image is native");
-    let content = build_native_concept_hover(native);
+    let native = NativeConcepts::embedded()
+        .find(&classified.ref_name)
+        .expect("Image is native");
+    let content = native_concept_hover(native, NativeConcepts::embedded());
 
     assert!(content.contains("**Image** *(native)*"), "got: {content}");
     assert!(
@@ -473,8 +487,10 @@ prompt = "Pass."
 
     let (_dom, query) = parse_and_query(src, offset);
     let classified = classify_reference(&query).expect("should classify");
-    let native = find_native_concept(&classified.ref_name).expect("Anything is native");
-    let content = build_native_concept_hover(native);
+    let native = NativeConcepts::embedded()
+        .find(&classified.ref_name)
+        .expect("Anything is native");
+    let content = native_concept_hover(native, NativeConcepts::embedded());
 
     assert!(
         content.contains("**Anything** *(native)*"),
@@ -487,6 +503,41 @@ prompt = "Pass."
     assert!(!content.contains("**Fields:**"), "Anything has no fields");
 }
 
+#[test]
+fn test_hover_native_concept_from_a_registry_file() {
+    let natives = NativeConcepts::from_toml(
+        r#"
+mthds_version = "1.0"
+
+[[concept]]
+name = "Text"
+description = "Plain text content."
+fields = { text = "str" }
+
+[[concept]]
+name = "Markdown"
+description = "Text written in Markdown."
+refines = "Text"
+fields = { flavor = "str" }
+"#,
+    )
+    .unwrap();
+    let native = natives
+        .find("Markdown")
+        .expect("Markdown is in the registry");
+    let content = native_concept_hover(native, &natives);
+
+    assert!(
+        content.contains("**Markdown** *(native)*"),
+        "got: {content}"
+    );
+    assert!(content.contains("**Refines:** `Text`"), "got: {content}");
+    assert!(
+        content.contains("**Fields:** `text`: str, `flavor`: str"),
+        "inherited fields come first, got: {content}"
+    );
+}
+
 // ---------------------------------------------------------------------------
 // Model field hover tests
 // ---------------------------------------------------------------------------
@@ -551,19 +602,19 @@ prompt = "hello"
 
 #[test]
 fn test_build_model_hover_with_pipe_type() {
-    let content = build_model_hover("$gpt-4o", Some("PipeLLM"));
+    let content = build_model_hover("$gpt-4o", Some("PipeLLM"), &ModelDeck::default());
     assert_eq!(content, "**gpt-4o** — LLM model preset", "got: {content}");
 }
 
 #[test]
 fn test_build_model_hover_without_pipe_type() {
-    let content = build_model_hover("$gpt-4o", None);
+    let content = build_model_hover("$gpt-4o", None, &ModelDeck::default());
     assert_eq!(content, "**gpt-4o** — model preset", "got: {content}");
 }
 
 #[test]
 fn test_build_model_hover_alias() {
-    let content = build_model_hover("@my-alias", Some("PipeExtract"));
+    let content = build_model_hover("@my-alias", Some("PipeExtract"), &ModelDeck::default());
     assert_eq!(
         content, "**my-alias** — Extract model alias",
         "got: {content}"
@@ -572,14 +623,77 @@ fn test_build_model_hover_alias() {
 
 #[test]
 fn test_build_model_hover_bare_model() {
-    let content = build_model_hover("claude-3-opus", Some("PipeLLM"));
+    let content = build_model_hover("claude-3-opus", Some("PipeLLM"), &ModelDeck::default());
     assert_eq!(content, "**claude-3-opus** — LLM model", "got: {content}");
 }
 
+fn model_deck() -> ModelDeck {
+    let mut deck = ModelDeck::default();
+    deck.add_file(
+        Path::new("deck/base_deck.toml"),
+        r#"
+[aliases]
+best-claude = "claude-4.5-sonnet"
+
+[waterfalls]
+smart_llm = ["best-claude", "gpt-4o"]
+
+[llm.presets]
+writing-factual = { model = "@best-claude", temperature = 0.1, description = "Factual writing" }
+"#,
+    )
+    .unwrap();
+    deck.add_file(
+        Path::new("backends/anthropic.toml"),
+        "[\"claude-4.5-sonnet\"]\nmodel_type = \"llm\"\nmodel_id = \"claude-sonnet-4-5\"\n",
+    )
+    .unwrap();
+    deck
+}
+
+#[test]
+fn test_build_model_hover_resolves_through_the_deck() {
+    let content = build_model_hover("$writing-factual", Some("PipeLLM"), &model_deck());
+    assert_eq!(
+        content,
+        "**writing-factual** — LLM model preset\n\n\
+Factual writing\n\n\
+**Settings:** `temperature = 0.1`\n\n\
+**Resolves to:** `@best-claude` → `claude-4.5-sonnet`\n\n\
+**Provider:** `anthropic` (LLM model)\n\n\
+**Model:** `model_id = \"claude-sonnet-4-5\"`",
+    );
+}
+
+#[test]
+fn test_build_model_hover_waterfall_and_unprefixed_handle() {
+    let deck = model_deck();
+    let content = build_model_hover("~smart_llm", None, &deck);
+    assert!(
+        content.contains("**Tries:** `best-claude`, `gpt-4o`"),
+        "got: {content}"
+    );
+    let content = build_model_hover("claude-4.5-sonnet", Some("PipeLLM"), &deck);
+    assert!(
+        content
+            .starts_with("**claude-4.5-sonnet** — LLM model handle\n\n**Provider:** `anthropic`"),
+        "got: {content}"
+    );
+}
+
+#[test]
+fn test_build_model_hover_unknown_to_the_deck() {
+    let content = build_model_hover("$missing", Some("PipeLLM"), &model_deck());
+    assert_eq!(
+        content,
+        "**missing** — LLM model preset\n\n*Not declared in the model deck.*"
+    );
+}
+
 #[test]
 fn test_build_model_hover_pipe_type_without_prefix() {
     // If type doesn't start with "Pipe", no prefix is shown
-    let content = build_model_hover("$gpt-4o", Some("SomethingElse"));
+    let content = build_model_hover("$gpt-4o", Some("SomethingElse"), &ModelDeck::default());
     assert_eq!(content, "**gpt-4o** — model preset", "got: {content}");
 }
 
@@ -643,3 +757,107 @@ prompt = "hello"
         "specific count only should not classify as a valid reference"
     );
 }
+
+#[test]
+fn test_classify_branch_pipe_code_as_pipe() {
+    let src = "[pipe.all]\ntype = \"PipeBatch\"\nbranch_pipe_code = \"describe\"\n\n[pipe.describe]\ntype = \"PipeLLM\"\n";
+    let offset = offset_inside_string(src, r#"branch_pipe_code = "describe""#);
+
+    let (dom, query) = parse_and_query(src, offset);
+    let resolved = resolve_reference(&dom, &query).expect("should resolve the batch branch");
+    assert!(matches!(resolved.kind, ReferenceKind::Pipe));
+    assert_eq!(resolved.ref_name, "describe");
+}
+
+/// Hover content for the template variable at the first occurrence of
+/// `target`, cursor on its `on`-th byte.
+fn template_hover(src: &str, target: &str, on: usize) -> Option<(String, String)> {
+    use crate::handlers::{
+        hover::build_template_variable_hover, mthds_completion::concept_candidates,
+        mthds_templates::template_variable_at,
+    };
+    let offset = src.find(target).unwrap() + on;
+    let dom = taplo::parser::parse(src).into_dom();
+    let variable = template_variable_at(&dom, taplo::rowan::TextSize::from(offset as u32))?;
+    let content = build_template_variable_hover(
+        &variable,
+        &concept_candidates(&dom, true),
+        NativeConcepts::embedded(),
+    )?;
+    Some((src[variable.range].to_string(), content))
+}
+
+#[test]
+fn test_hover_template_variable_shows_input_concept() {
+    let src = fixture!("template_variables.mthds");
+    let (range, content) = template_hover(src, "@feature_analysis", 3).unwrap();
+
+    assert_eq!(range, "feature_analysis");
+    assert!(
+        content.contains("**feature_analysis** — input `Analysis`"),
+        "got: {content}"
+    );
+    assert!(content.contains("A structured analysis of a feature"));
+    assert!(content.contains("**Fields:** `summary`, `reasoning`, `counterpart`"));
+}
+
+#[test]
+fn test_hover_template_field_shows_type_and_description() {
+    let src = fixture!("template_variables.mthds");
+    let (range, content) = template_hover(src, "reasoning }}", 2).unwrap();
+
+    assert_eq!(range, "opposite_concept.reasoning");
+    assert!(
+        content.contains("**opposite_concept.reasoning** — field `text`"),
+        "got: {content}"
+    );
+    assert!(content.contains("Why the feature matters"));
+}
+
+#[test]
+fn test_hover_template_root_segment_of_dotted_variable() {
+    let src = fixture!("template_variables.mthds");
+    let (range, content) = template_hover(src, "opposite_concept.reasoning", 2).unwrap();
+
+    assert_eq!(range, "opposite_concept");
+    assert!(content.contains("input `Analysis`"), "got: {content}");
+}
+
+#[test]
+fn test_hover_template_nested_field_through_refines_to_native() {
+    let src = fixture!("template_variables.mthds");
+    let (range, content) = template_hover(src, "text }}", 1).unwrap();
+
+    assert_eq!(range, "opposite_concept.counterpart.text");
+    assert!(content.contains("field `str`"), "got: {content}");
+}
+
+#[test]
+fn test_hover_template_native_input() {
+    let src = fixture!("template_variables.mthds");
+    let (_, content) = template_hover(src, "$cover", 2).unwrap();
+
+    assert!(content.contains("input `Image`"), "got: {content}");
+    assert!(content.contains("**Image** *(native)*"));
+}
+
+#[test]
+fn test_hover_template_unknown_variable_or_field_shows_nothing() {
+    let src = fixture!("template_variables.mthds");
+    assert!(template_hover(src, "@unknown", 2).is_none());
+    assert!(template_hover(src, "missing }}", 2).is_none());
+    assert!(template_hover(src, "Critique", 2).is_none());
+}
+
+#[test]
+fn test_hover_template_skips_names_the_template_binds() {
+    let src = r#"[pipe.list]
+type = "PipeLLM"
+inputs = { items = "Text[]" }
+output = "Text"
+prompt = "{% for item in items %}{{ item }}{% endfor %}"
+"#;
+    assert!(template_hover(src, "item }}", 1).is_none());
+    let (range, _) = template_hover(src, "items %}", 1).unwrap();
+    assert_eq!(range, "items");
+}
@@ -0,0 +1,87 @@
+use super::{PARALLEL, SEQUENCE};
+use crate::handlers::inlay_hints::mthds_inlay_hints;
+use lsp_types::InlayHintKind;
+use taplo::{
+    parser::parse,
+    rowan::{TextRange, TextSize},
+};
+
+/// `source` with each hint's label inserted where the editor would show it.
+fn rendered(source: &str, range: TextRange) -> String {
+    let mut hints = mthds_inlay_hints(&parse(source).into_dom(), range);
+    hints.sort_by_key(|hint| std::cmp::Reverse(hint.offset));
+    let mut out = source.to_string();
+    for hint in hints {
+        out.insert_str(usize::from(hint.offset), &hint.label);
+    }
+    out
+}
+
+fn whole(source: &str) -> TextRange {
+    TextRange::up_to(TextSize::of(source))
+}
+
+#[test]
+fn annotates_results_and_callee_signatures() {
+    let out = rendered(SEQUENCE, whole(SEQUENCE));
+    assert!(
+        out.contains(
+            r#"{ pipe = "create_prompt"(topic: Text, style: Text), result = "prompt": Prompt }"#
+        ),
+        "{out}"
+    );
+    assert!(
+        out.contains(r#"pipe = "describe_page"(topic: Text, page: Page)"#),
+        "{out}"
+    );
+    assert!(
+        out.contains(r#"result = "descriptions": Description }"#),
+        "{out}"
+    );
+}
+
+#[test]
+fn annotates_batch_as_with_the_item_concept() {
+    let out = rendered(SEQUENCE, whole(SEQUENCE));
+    assert!(out.contains(r#"batch_as = "page": Page,"#), "{out}");
+}
+
+#[test]
+fn batch_as_falls_back_to_an_item_of_the_list() {
+    let source = SEQUENCE.replace(r#"page = "Page" }"#, r#"item = "Page" }"#);
+    let out = rendered(&source, whole(&source));
+    assert!(out.contains(r#"batch_as = "page": Page,"#), "{out}");
+}
+
+#[test]
+fn skips_calls_to_pipes_not_defined_in_the_document() {
+    let out = rendered(SEQUENCE, whole(SEQUENCE));
+    assert!(
+        out.contains(r#"{ pipe = "elsewhere", result = "other" }"#),
+        "{out}"
+    );
+}
+
+#[test]
+fn annotates_parallel_branches() {
+    let out = rendered(PARALLEL, whole(PARALLEL));
+    assert!(
+        out.contains(r#"{ pipe = "shorten"(text: Text), result = "short": Text }"#),
+        "{out}"
+    );
+}
+
+#[test]
+fn only_returns_hints_within_the_range() {
+    let start = SEQUENCE.find("{ pipe = \"describe_page\"").unwrap();
+    let end = start + SEQUENCE[start..].find('\n').unwrap();
+    let range = TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32));
+    let hints = mthds_inlay_hints(&parse(SEQUENCE).into_dom(), range);
+    let labels: Vec<_> = hints.iter().map(|hint| hint.label.as_str()).collect();
+    assert_eq!(
+        labels,
+        ["(topic: Text, page: Page)", ": Page", ": Description"]
+    );
+    assert_eq!(hints[0].kind, InlayHintKind::PARAMETER);
+    assert_eq!(hints[1].kind, InlayHintKind::TYPE);
+}
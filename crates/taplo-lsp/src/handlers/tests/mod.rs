@@ -29,5 +29,70 @@ fn offset_inside_string_after(source: &str, section_header: &str, target: &str)
     (quote_pos + 1) as u32
 }
 
+/// The byte offset of the first `target` in `source`. Panics if `target` is
+/// not found.
+fn offset_of(source: &str, target: &str) -> TextSize {
+    TextSize::from(source.find(target).unwrap() as u32)
+}
+
+/// A sequence calling pipes of the document, one of them over a batch, and a
+/// pipe it does not define. `create_prompt` needs an input the sequence does
+/// not provide.
+const SEQUENCE: &str = r#"domain = "d"
+
+[pipe.write]
+type = "PipeSequence"
+inputs = { topic = "Text", pages = "Page[]" }
+output = "Article"
+steps = [
+    { pipe = "create_prompt", result = "prompt" },
+    { pipe = "draft", result = "article" },
+    { pipe = "describe_page", batch_over = "pages", batch_as = "page", result = "descriptions" },
+    { pipe = "elsewhere", result = "other" },
+]
+
+[pipe.create_prompt]
+type = "PipeLLM"
+description = "Write a prompt about a topic"
+inputs = { topic = "Text", style = "Text" }
+output = "Prompt"
+prompt = "Write about @topic in the style of @style"
+
+[pipe.draft]
+type = "PipeLLM"
+inputs = { prompt = "Prompt" }
+output = "Article"
+prompt = "@prompt"
+
+[pipe.describe_page]
+type = "PipeLLM"
+inputs = { topic = "Text", page = "Page" }
+output = "Description"
+prompt = "Describe $page for @topic"
+"#;
+
+/// A parallel pipe with a single branch.
+const PARALLEL: &str = r#"domain = "d"
+
+[pipe.both]
+type = "PipeParallel"
+inputs = { text = "Text" }
+output = "Report"
+branches = [{ pipe = "shorten", result = "short" }]
+
+[pipe.shorten]
+type = "PipeLLM"
+inputs = { text = "Text" }
+output = "Text"
+prompt = "Shorten @text"
+"#;
+
+mod code_actions;
 mod goto_definition;
 mod hover;
+mod inlay_hints;
+mod mthds_actions;
+mod mthds_completion;
+mod mthds_move;
+mod semantic_tokens;
+mod signature_help;
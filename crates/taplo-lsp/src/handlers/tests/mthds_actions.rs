@@ -0,0 +1,303 @@
+use super::offset_of;
+use crate::handlers::mthds_actions::{
+    add_missing_input, create_definition, expand_structure_field, extract_steps,
+};
+use pipelex_common::tools::{
+    fixes::{apply_fixes, Fix},
+    format::mthds_format_options,
+};
+use taplo::{
+    parser::parse,
+    rowan::{TextRange, TextSize},
+};
+
+/// The source after applying `fix`.
+fn applied(source: &str, fix: &Fix) -> String {
+    apply_fixes(&parse(source).into_dom(), [fix]).unwrap().0
+}
+
+/// The range of the string `"<value>"` following `after` in `source`.
+fn string_range(source: &str, after: &str, value: &str) -> TextRange {
+    let from = source.find(after).unwrap();
+    let quoted = format!("\"{value}\"");
+    let start = from + source[from..].find(&quoted).unwrap();
+    TextRange::at(TextSize::from(start as u32), TextSize::of(quoted.as_str()))
+}
+
+const REVIEW: &str = r#"domain = "d"
+
+[concept.Summary]
+description = "A summary"
+
+# The entry point.
+[pipe.review]
+type = "PipeSequence"
+description = "Review"
+inputs = { document = "Document" }
+output = "Report"
+steps = [
+    { pipe = "summarize", result = "summary" },
+    { pipe = "draft", result = "report" },
+]
+
+[pipe.summarize]
+type = "PipeLLM"
+description = "Summarize"
+inputs = { document = "Document" }
+output = "Summary"
+prompt = "Summarize @document"
+"#;
+
+#[test]
+fn pipe_stub_infers_its_signature_from_the_calling_step() {
+    let dom = parse(REVIEW).into_dom();
+    let fix = create_definition(&dom, string_range(REVIEW, "steps", "draft")).unwrap();
+    assert_eq!(fix.title, "Create pipe stub `[pipe.draft]`");
+    let expected = format!(
+        "{REVIEW}\n{}",
+        r#"[pipe.draft]
+type = "PipeLLM"
+description = "TODO"
+inputs = { document = "Document", summary = "Summary" }
+output = "Report"
+prompt = "TODO"
+"#
+    );
+    assert_eq!(applied(REVIEW, &fix), expected);
+}
+
+#[test]
+fn pipe_stub_of_a_batch_branch_takes_one_item() {
+    let source = r#"domain = "d"
+
+[pipe.each]
+type = "PipeBatch"
+description = "Each"
+inputs = { pages = "Page[]" }
+output = "Summary[]"
+branch_pipe_code = "summarize_page"
+input_list_name = "pages"
+input_item_name = "page""#;
+    let dom = parse(source).into_dom();
+    let fix = create_definition(&dom, string_range(source, "branch", "summarize_page")).unwrap();
+    assert!(
+        applied(source, &fix).ends_with(
+            "input_item_name = \"page\"\n\n[pipe.summarize_page]\ntype = \"PipeLLM\"\ndescription = \"TODO\"\ninputs = { page = \"Page\" }\noutput = \"Summary\"\nprompt = \"TODO\"\n"
+        ),
+        "{}",
+        applied(source, &fix)
+    );
+}
+
+#[test]
+fn concept_is_created_above_the_first_pipe_and_its_comments() {
+    let dom = parse(REVIEW).into_dom();
+    let fix = create_definition(&dom, string_range(REVIEW, "[pipe.review]", "Report")).unwrap();
+    assert_eq!(fix.title, "Create concept `Report`");
+    assert!(applied(REVIEW, &fix).contains(
+        "description = \"A summary\"\n\n[concept.Report]\ndescription = \"TODO\"\n\n# The entry point.\n[pipe.review]"
+    ));
+}
+
+#[test]
+fn references_to_other_domains_are_not_created() {
+    let source = "domain = \"d\"\nmain_pipe = \"legal.review\"\n";
+    let dom = parse(source).into_dom();
+    assert!(create_definition(&dom, string_range(source, "main_pipe", "legal.review")).is_none());
+}
+
+#[test]
+fn undeclared_template_variable_is_added_to_inputs() {
+    let source = REVIEW.replace("Summarize @document", "Summarize @document in $style.tone");
+    let dom = parse(&source).into_dom();
+    let fix = add_missing_input(&dom, offset_of(&source, "tone")).unwrap();
+    assert_eq!(fix.title, "Add `style` to `inputs`");
+    assert!(applied(&source, &fix).contains(
+        r#"inputs = { document = "Document", style = "Text" }
+output = "Summary""#
+    ));
+
+    // Declared already.
+    assert!(add_missing_input(&dom, offset_of(&source, "@document")).is_none());
+    // Not in a template.
+    assert!(add_missing_input(&dom, offset_of(&source, "Summarize\"")).is_none());
+}
+
+#[test]
+fn expression_variables_and_template_tables_are_added_to_inputs() {
+    let source = r#"[pipe.compose]
+type = "PipeCompose"
+inputs = { order = "Order" }
+output = "Text"
+
+[pipe.compose.template]
+template = "{% for line in order.lines %}{{ line }} for {{ customer.name }}{% endfor %}"
+"#;
+    let dom = parse(source).into_dom();
+    let fix = add_missing_input(&dom, offset_of(source, "customer")).unwrap();
+    assert_eq!(fix.title, "Add `customer` to `inputs`");
+    assert!(applied(source, &fix).contains(r#"inputs = { order = "Order", customer = "Text" }"#));
+    assert!(
+        add_missing_input(&dom, offset_of(source, "line }}")).is_none(),
+        "loop variables are not inputs"
+    );
+}
+
+#[test]
+fn inputs_are_declared_before_the_output() {
+    let source = r#"[pipe.ask]
+type = "PipeLLM"
+description = "Ask"
+output = "Text"
+prompt = """
+Answer @question (not user@example.com).
+"""
+"#;
+    let dom = parse(source).into_dom();
+    assert!(add_missing_input(&dom, offset_of(source, "example")).is_none());
+    let fix = add_missing_input(&dom, offset_of(source, "@question")).unwrap();
+    assert!(applied(source, &fix)
+        .contains("description = \"Ask\"\ninputs = { question = \"Text\" }\noutput = \"Text\""));
+}
+
+#[test]
+fn inputs_tables_get_a_new_line() {
+    let source = r#"[pipe.ask]
+type = "PipeLLM"
+description = "Ask"
+output = "Text"
+prompt = "@question about @topic"
+
+[pipe.ask.inputs]
+question = "Text" # what to answer
+"#;
+    let dom = parse(source).into_dom();
+    let fix = add_missing_input(&dom, offset_of(source, "@topic")).unwrap();
+    assert!(
+        applied(source, &fix).ends_with("question = \"Text\" # what to answer\ntopic = \"Text\"\n")
+    );
+}
+
+#[test]
+fn structure_field_shorthand_expands_to_a_table() {
+    let source = r#"[concept.Invoice]
+description = "An invoice"
+
+[concept.Invoice.structure]
+number = "The invoice number"
+total = { type = "number", description = "The total" }
+"#;
+    let dom = parse(source).into_dom();
+    let fix = expand_structure_field(&dom, offset_of(source, "number =")).unwrap();
+    assert_eq!(
+        fix.title,
+        "Convert `number` to a `{ type, description, required }` table"
+    );
+    assert!(applied(source, &fix).contains(
+        r#"number = { type = "text", description = "The invoice number", required = false }"#
+    ));
+    assert!(expand_structure_field(&dom, offset_of(source, "total =")).is_none());
+    assert!(expand_structure_field(&dom, offset_of(source, "An invoice")).is_none());
+}
+
+const PIPELINE: &str = r#"domain = "d"
+
+[pipe.review]
+type = "PipeSequence"
+description = "Review"
+inputs = { contract = "Contract" }
+output = "Report"
+steps = [
+  { pipe = "extract", result = "clauses" },
+  { pipe = "assess", result = "risks" },
+  { pipe = "draft", result = "report" },
+]
+
+[pipe.extract]
+type = "PipeExtract"
+description = "Extract"
+inputs = { contract = "Contract" }
+output = "Clause[]"
+
+[pipe.assess]
+type = "PipeLLM"
+description = "Assess"
+inputs = { clauses = "Clause[]", policy = "Policy" }
+output = "Risk[]"
+prompt = "@clauses against @policy"
+
+[pipe.draft]
+type = "PipeLLM"
+description = "Draft"
+inputs = { risks = "Risk[]" }
+output = "Report"
+prompt = "@risks"
+"#;
+
+/// The range from the start of `from` to the end of `to` in `source`.
+fn selection(source: &str, from: &str, to: &str) -> TextRange {
+    let start = source.find(from).unwrap();
+    let end = source.find(to).unwrap() + to.len();
+    TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32))
+}
+
+#[test]
+fn steps_are_extracted_into_a_new_sequence() {
+    let dom = parse(PIPELINE).into_dom();
+    let range = selection(PIPELINE, "{ pipe = \"extract\"", "\"risks\" }");
+    let fix = extract_steps(&dom, range, &mthds_format_options()).unwrap();
+    assert_eq!(
+        fix.title,
+        "Extract 2 steps into a new PipeSequence `review_part`"
+    );
+    assert!(
+        fix.edits
+            .windows(2)
+            .all(|pair| pair[0].range.end() <= pair[1].range.start()),
+        "{:?}",
+        fix.edits
+    );
+
+    let extracted = applied(PIPELINE, &fix);
+    assert!(extracted.contains(
+        r#"steps = [
+  { pipe = "review_part", result = "risks" },
+  { pipe = "draft", result = "report" },
+]"#
+    ));
+    assert!(
+        extracted.ends_with(
+            r#"prompt = "@risks"
+
+[pipe.review_part]
+type = "PipeSequence"
+description = "TODO"
+inputs = { contract = "Contract", policy = "Policy" }
+output = "Risk[]"
+steps = [
+  { pipe = "extract", result = "clauses" },
+  { pipe = "assess", result = "risks" },
+]
+"#
+        ),
+        "{extracted}"
+    );
+}
+
+#[test]
+fn extraction_needs_several_steps_and_keeps_later_results_visible() {
+    let dom = parse(PIPELINE).into_dom();
+    let options = mthds_format_options();
+    let one_step = selection(PIPELINE, "{ pipe = \"assess\"", "\"risks\" }");
+    assert!(extract_steps(&dom, one_step, &options).is_none());
+
+    // Extracting the first two steps hides `clauses` behind `risks`, which
+    // is fine until a later step reads it.
+    let hiding = PIPELINE.replace(
+        "inputs = { risks = \"Risk[]\" }",
+        "inputs = { risks = \"Risk[]\", clauses = \"Clause[]\" }",
+    );
+    let dom = parse(&hiding).into_dom();
+    let range = selection(&hiding, "{ pipe = \"extract\"", "\"risks\" }");
+    assert!(extract_steps(&dom, range, &options).is_none());
+}
@@ -0,0 +1,440 @@
+use super::{offset_inside_string, offset_inside_string_after};
+use crate::handlers::mthds_completion::{
+    concept_candidates, concept_completions, concept_reference_at, model_completions,
+    model_reference_at, pipe_candidates, pipe_completions, pipe_reference_at, template_completions,
+};
+use crate::handlers::mthds_templates::template_reference_at;
+use lsp_types::{CompletionItem, Documentation, InsertTextFormat};
+use pipelex_common::tools::{models::ModelDeck, native_concepts::NativeConcepts};
+use std::path::Path;
+use taplo::{parser::parse, rowan::TextSize};
+
+/// The completions inside the `target` string following `after`, with the
+/// pipes of `source` and of `siblings`, best ranked first.
+fn completions(source: &str, after: &str, target: &str, siblings: &[&str]) -> Vec<CompletionItem> {
+    let dom = parse(source).into_dom();
+    let offset = offset_inside_string_after(source, after, target);
+    let range = pipe_reference_at(&dom, offset.into()).expect("cursor inside a pipe reference");
+    let mut candidates = pipe_candidates(&dom, true);
+    for sibling in siblings {
+        candidates.extend(pipe_candidates(&parse(sibling).into_dom(), false));
+    }
+    let mut items = pipe_completions(&dom, range, &candidates);
+    items.sort_by(|a, b| a.sort_text.cmp(&b.sort_text));
+    items
+}
+
+fn labels(items: &[CompletionItem]) -> Vec<&str> {
+    items.iter().map(|item| item.label.as_str()).collect()
+}
+
+const CANDIDATES: &str = r#"domain = "d"
+main_pipe = "write"
+
+[pipe.write]
+type = "PipeSequence"
+inputs = { topic = "Text" }
+output = "Article"
+steps = [
+    { pipe = "", result = "prompt" },
+    { pipe = "draft", result = "article" },
+]
+
+[pipe.draft]
+type = "PipeLLM"
+description = "Draft an article"
+inputs = { prompt = "Prompt" }
+output = "Article"
+prompt = "@prompt"
+
+[pipe.make_prompt]
+type = "PipeLLM"
+inputs = { topic = "Text" }
+output = "Prompt"
+prompt = "Prompt about @topic"
+
+[pipe.ask_prompt]
+type = "PipeLLM"
+inputs = { subject = "Text" }
+output = "Prompt"
+prompt = "Prompt about @subject"
+"#;
+
+#[test]
+fn offers_the_pipes_of_the_document_and_its_bundle() {
+    let sibling = "domain = \"d\"\n\n[pipe.outline]\ntype = \"PipeLLM\"\noutput = \"Text\"\n";
+    let items = completions(CANDIDATES, "steps", "\"\"", &[sibling]);
+    let mut names = labels(&items);
+    names.sort_unstable();
+    assert_eq!(names, ["ask_prompt", "draft", "make_prompt", "outline"]);
+}
+
+#[test]
+fn leaves_out_the_enclosing_pipe() {
+    let items = completions(CANDIDATES, "steps", "\"\"", &[]);
+    assert!(!labels(&items).contains(&"write"));
+}
+
+#[test]
+fn ranks_by_output_then_inputs() {
+    let items = completions(CANDIDATES, "steps", "\"\"", &[]);
+    assert_eq!(labels(&items), ["make_prompt", "ask_prompt", "draft"]);
+}
+
+#[test]
+fn carries_type_description_and_signature() {
+    let items = completions(CANDIDATES, "steps", "\"\"", &[]);
+    let draft = items.iter().find(|item| item.label == "draft").unwrap();
+    assert_eq!(
+        draft.detail.as_deref(),
+        Some("draft(prompt: Prompt) -> Article")
+    );
+    let Some(Documentation::MarkupContent(documentation)) = &draft.documentation else {
+        panic!("markdown documentation expected");
+    };
+    assert!(documentation.value.contains("**draft** `PipeLLM`"));
+    assert!(documentation.value.contains("Draft an article"));
+}
+
+#[test]
+fn qualifies_pipes_of_another_domain() {
+    let sibling = "domain = \"other\"\n\n[pipe.outline]\ntype = \"PipeLLM\"\noutput = \"Text\"\n";
+    let items = completions(CANDIDATES, "steps", "\"\"", &[sibling]);
+    assert!(labels(&items).contains(&"other.outline"));
+}
+
+#[test]
+fn completes_main_pipe_and_condition_outcomes() {
+    let items = completions(CANDIDATES, "main_pipe", "\"write\"", &[]);
+    assert!(labels(&items).contains(&"write"));
+
+    let source = r#"domain = "d"
+
+[pipe.route]
+type = "PipeCondition"
+inputs = { text = "Text" }
+output = "Text"
+expression = "text.kind"
+default_outcome = "fallback"
+outcomes = { short = "" }
+
+[pipe.fallback]
+type = "PipeLLM"
+inputs = { text = "Text" }
+output = "Text"
+prompt = "@text"
+"#;
+    let items = completions(source, "outcomes", "\"\"", &[]);
+    assert_eq!(labels(&items), ["fallback"]);
+    let items = completions(source, "default_outcome", "\"fallback\"", &[]);
+    assert_eq!(labels(&items), ["fallback"]);
+}
+
+#[test]
+fn no_completion_outside_pipe_references() {
+    let dom = parse(CANDIDATES).into_dom();
+    let offset = offset_inside_string(CANDIDATES, "\"Article\"");
+    assert!(pipe_reference_at(&dom, offset.into()).is_none());
+}
+
+/// The concept completions inside the `target` string following `after`,
+/// with the concepts of `source` and of `siblings`, best ranked first.
+fn concept_items(
+    source: &str,
+    after: &str,
+    target: &str,
+    siblings: &[&str],
+) -> Vec<CompletionItem> {
+    let dom = parse(source).into_dom();
+    let offset = offset_inside_string_after(source, after, target);
+    let reference =
+        concept_reference_at(&dom, offset.into()).expect("cursor inside a concept reference");
+    let mut candidates = concept_candidates(&dom, true);
+    for sibling in siblings {
+        candidates.extend(concept_candidates(&parse(sibling).into_dom(), false));
+    }
+    let mut items = concept_completions(&dom, &reference, &candidates, NativeConcepts::embedded());
+    items.sort_by(|a, b| a.sort_text.cmp(&b.sort_text));
+    items
+}
+
+const CONCEPTS: &str = r#"domain = "d"
+
+[concept]
+Summary = "A short summary"
+
+[concept.Report]
+description = "A written report"
+refines = "Text"
+
+[concept.Report.structure]
+title = { type = "text", description = "Title" }
+sections = { type = "list", item_type = "concept", item_concept_ref = "" }
+
+[pipe.summarize]
+type = "PipeLLM"
+inputs = { report = "Report" }
+output = ""
+prompt = "Summarize @report"
+"#;
+
+#[test]
+fn offers_bundle_then_native_then_other_domain_concepts() {
+    let sibling = "domain = \"legal\"\n\n[concept.Contract]\ndescription = \"A contract\"\n";
+    let items = concept_items(CONCEPTS, "output", "\"\"", &[sibling]);
+    let names = labels(&items);
+    assert_eq!(names[..2], ["Report", "Summary"]);
+    assert_eq!(names[2], "Text");
+    assert!(names.contains(&"Image"));
+    assert_eq!(names.last(), Some(&"legal.Contract"));
+}
+
+#[test]
+fn documents_concepts_with_their_hover_content() {
+    let items = concept_items(CONCEPTS, "output", "\"\"", &[]);
+    let documentation = |label: &str| {
+        let item = items.iter().find(|item| item.label == label).unwrap();
+        let Some(Documentation::MarkupContent(documentation)) = &item.documentation else {
+            panic!("markdown documentation expected");
+        };
+        documentation.value.clone()
+    };
+    assert!(documentation("Report").contains("**Refines:** `Text`"));
+    assert_eq!(documentation("Summary"), "A short summary");
+    assert!(documentation("Image").contains("*(native)*"));
+}
+
+#[test]
+fn offers_multiplicity_for_inputs_and_output() {
+    let items = concept_items(CONCEPTS, "inputs", "\"Report\"", &[]);
+    assert_eq!(labels(&items)[..2], ["Report[N]", "Report[]"]);
+    let fixed = &items[0];
+    assert_eq!(fixed.insert_text.as_deref(), Some("Report[${1:2}]"));
+    assert_eq!(fixed.insert_text_format, Some(InsertTextFormat::SNIPPET));
+}
+
+#[test]
+fn no_multiplicity_for_refines_and_structure_references() {
+    let items = concept_items(CONCEPTS, "refines", "\"Text\"", &[]);
+    assert!(!labels(&items).iter().any(|label| label.ends_with(']')));
+    let items = concept_items(CONCEPTS, "item_concept_ref", "\"\"", &[]);
+    assert!(
+        labels(&items).contains(&"Report"),
+        "a structure may nest its own concept"
+    );
+}
+
+#[test]
+fn leaves_out_the_refining_concept() {
+    let items = concept_items(CONCEPTS, "refines", "\"Text\"", &[]);
+    assert!(!labels(&items).contains(&"Report"));
+}
+
+#[test]
+fn qualifies_native_concepts_when_asked_or_shadowed() {
+    let source = CONCEPTS.replace("output = \"\"", "output = \"native.\"");
+    let items = concept_items(&source, "output", "\"native.\"", &[]);
+    assert!(labels(&items).contains(&"native.Image"));
+
+    let shadowing = "domain = \"d\"\n\n[concept.Image]\ndescription = \"A picture\"\n";
+    let items = concept_items(CONCEPTS, "output", "\"\"", &[shadowing]);
+    assert!(labels(&items).contains(&"native.Image"));
+    assert!(labels(&items).contains(&"Image"));
+}
+
+/// The template completions at the end of the first occurrence of `typed`
+/// in `source`, with the concepts of `source`.
+fn template_items(source: &str, typed: &str) -> Option<Vec<CompletionItem>> {
+    let offset = source.find(typed).unwrap() + typed.len();
+    let dom = parse(source).into_dom();
+    let reference = template_reference_at(&dom, TextSize::from(offset as u32))?;
+    Some(template_completions(
+        &reference,
+        &concept_candidates(&dom, true),
+        NativeConcepts::embedded(),
+    ))
+}
+
+const TEMPLATE: &str = r#"domain = "d"
+
+[concept.Report]
+description = "A written report"
+
+[concept.Report.structure]
+title = "The title"
+author = { type = "concept", concept_ref = "Person", description = "Who wrote it" }
+
+[concept.Person]
+description = "Someone"
+refines = "Text"
+
+[pipe.review]
+type = "PipeLLM"
+inputs = { report = "Report", cover = "Image", pages = "Page[]" }
+output = "Text"
+system_prompt = "You review reports, like @re"
+prompt = """
+Review {{ rep }} by @report.author.te, titled @report.ti.
+The cover: $cover.ur
+Pages: @pages.x
+Mail me at someone@rep
+"""
+"#;
+
+#[test]
+fn completes_input_names_after_a_sigil_or_inside_braces() {
+    let items = template_items(TEMPLATE, "like @re").unwrap();
+    assert_eq!(labels(&items), ["report", "cover", "pages"]);
+    assert_eq!(items[0].detail.as_deref(), Some("Report"));
+
+    let items = template_items(TEMPLATE, "{{ rep").unwrap();
+    assert_eq!(labels(&items), ["report", "cover", "pages"]);
+}
+
+#[test]
+fn completes_structure_fields_after_a_dot() {
+    let items = template_items(TEMPLATE, "@report.ti").unwrap();
+    assert_eq!(labels(&items), ["title", "author"]);
+    assert_eq!(items[1].detail.as_deref(), Some("Person"));
+    assert_eq!(
+        items[1].documentation,
+        Some(Documentation::String("Who wrote it".to_string()))
+    );
+}
+
+#[test]
+fn follows_concept_fields_and_refines_to_native_fields() {
+    let items = template_items(TEMPLATE, "@report.author.te").unwrap();
+    assert_eq!(labels(&items), ["text"]);
+}
+
+#[test]
+fn completes_native_concept_fields() {
+    let items = template_items(TEMPLATE, "$cover.ur").unwrap();
+    assert_eq!(labels(&items), ["url", "filename", "caption", "mime_type"]);
+}
+
+#[test]
+fn lists_have_no_fields() {
+    assert!(template_items(TEMPLATE, "@pages.x").unwrap().is_empty());
+}
+
+#[test]
+fn ignores_plain_text_and_email_addresses() {
+    assert!(template_items(TEMPLATE, "Review").is_none());
+    assert!(template_items(TEMPLATE, "someone@rep").is_none());
+}
+
+#[test]
+fn completes_in_statements_after_dots_and_lone_sigils() {
+    let source = r#"domain = "d"
+
+[concept.Report]
+description = "A written report"
+
+[concept.Report.structure]
+title = "The title"
+
+[pipe.review]
+type = "PipeLLM"
+inputs = { report = "Report" }
+output = "Text"
+prompt = "{% if rep %}Title: @report. Escaped \"{{ report.ti }}\" from @ and {{ 'rep' | up }}{% endif %}"
+"#;
+    let items = template_items(source, "{% if rep").unwrap();
+    assert_eq!(labels(&items), ["report"]);
+    let items = template_items(source, "@report.").unwrap();
+    assert_eq!(labels(&items), ["title"]);
+    let items = template_items(source, "{{ report.ti").unwrap();
+    assert_eq!(labels(&items), ["title"]);
+    let items = template_items(source, "from @").unwrap();
+    assert_eq!(labels(&items), ["report"]);
+
+    assert!(
+        template_items(source, "{{ 'rep").is_none(),
+        "inside a string"
+    );
+    assert!(template_items(source, "| up").is_none(), "a filter name");
+    assert!(
+        template_items(source, "{% if").is_none(),
+        "a statement name"
+    );
+}
+
+#[test]
+fn replaces_only_the_segment_being_typed() {
+    let dom = parse(TEMPLATE).into_dom();
+    let offset = TEMPLATE.find("@report.ti").unwrap() + "@report.ti".len();
+    let reference = template_reference_at(&dom, TextSize::from(offset as u32)).unwrap();
+    assert_eq!(reference.path, ["report"]);
+    assert_eq!(&TEMPLATE[reference.partial], "ti");
+}
+
+const MODEL_DECK: &str = r#"
+[aliases]
+best-claude = "claude-sonnet"
+
+[waterfalls]
+smart_llm = ["best-claude"]
+unknown_mix = ["mystery"]
+
+[llm.presets]
+writing-factual = { model = "@best-claude", temperature = 0.1 }
+
+[img_gen.presets]
+gen-image = { model = "flux-pro" }
+"#;
+
+const BACKEND: &str = r#"
+[claude-sonnet]
+model_type = "llm"
+
+[flux-pro]
+model_type = "img_gen"
+"#;
+
+/// The model completions inside the first `target` string.
+fn model_items(source: &str, target: &str) -> Vec<CompletionItem> {
+    let dom = parse(source).into_dom();
+    let reference = model_reference_at(&dom, offset_inside_string(source, target).into())
+        .expect("cursor inside a model reference");
+    let mut deck = ModelDeck::default();
+    deck.add_file(Path::new("deck.toml"), MODEL_DECK).unwrap();
+    deck.add_file(Path::new("backends/acme.toml"), BACKEND)
+        .unwrap();
+    let mut items = model_completions(&reference, &deck);
+    items.sort_by(|a, b| a.sort_text.cmp(&b.sort_text));
+    items
+}
+
+#[test]
+fn offers_the_deck_models_the_pipe_type_can_run() {
+    let source = r#"domain = "d"
+
+[pipe.write]
+type = "PipeLLM"
+output = "Text"
+model = "$w"
+
+[pipe.draw]
+type = "PipeImgGen"
+output = "Image"
+model = { model = "" }
+"#;
+    let items = model_items(source, "model = \"");
+    assert_eq!(
+        labels(&items),
+        [
+            "$writing-factual",
+            "@best-claude",
+            "~smart_llm",
+            "~unknown_mix",
+            "#claude-sonnet",
+        ]
+    );
+    assert_eq!(items[0].detail.as_deref(), Some("LLM model preset"));
+    assert_eq!(items[3].detail.as_deref(), Some("model waterfall"));
+
+    let items = model_items(source, "model = { model = \"");
+    assert_eq!(labels(&items), ["$gen-image", "~unknown_mix", "#flux-pro"]);
+}
@@ -0,0 +1,178 @@
+use super::offset_of;
+use crate::handlers::mthds_move::{
+    append_section, moved_text, new_file_name, requalify_references, section_at, section_removal,
+    Section,
+};
+use pipelex_common::tools::{
+    bundle::RefKind,
+    fixes::{apply_fixes, Edit, Fix},
+    native_concepts::NativeConcepts,
+};
+use taplo::{
+    dom::Node,
+    parser::parse,
+    rowan::{TextRange, TextSize},
+};
+
+const LEGAL: &str = r#"domain = "legal"
+
+[concept.Clause]
+description = "A clause"
+
+# Reviews a contract.
+# Second line.
+[pipe.review]
+type = "PipeSequence"
+description = "Review"
+inputs = { contract = "Contract" }
+output = "Text"
+steps = [{ pipe = "extract" }, { pipe = "review" }]
+
+[pipe.review.extra]
+note = "kept with the pipe"
+
+# Extracts clauses.
+[pipe.extract]
+type = "PipeExtract"
+description = "Extract"
+inputs = { contract = "Contract" }
+output = "Clause[]"
+"#;
+
+fn dom(source: &str) -> Node {
+    parse(source).into_dom()
+}
+
+fn edited(source: &str, edits: Vec<Edit>) -> String {
+    let fix = Fix {
+        title: String::new(),
+        safe: false,
+        edits,
+    };
+    apply_fixes(&dom(source), [&fix]).unwrap().0
+}
+
+fn review(source: &str) -> Section {
+    section_at(&dom(source), offset_of(source, "type = \"PipeSequence\"")).unwrap()
+}
+
+#[test]
+fn section_spans_comments_and_sub_tables() {
+    let section = review(LEGAL);
+    assert_eq!(section.key(), "pipe.review");
+    assert!(
+        LEGAL[section.range].starts_with("# Reviews a contract.\n# Second line.\n[pipe.review]")
+    );
+    assert!(LEGAL[section.range].ends_with("note = \"kept with the pipe\"\n\n"));
+
+    // On a sub-table, the section is still the whole pipe.
+    let from_sub_table = section_at(&dom(LEGAL), offset_of(LEGAL, "note")).unwrap();
+    assert_eq!(from_sub_table.range, section.range);
+
+    // Root entries belong to no section.
+    assert!(section_at(&dom(LEGAL), offset_of(LEGAL, "legal")).is_none());
+}
+
+#[test]
+fn scattered_sub_tables_are_not_moved() {
+    let source = format!("{LEGAL}\n[pipe.review.late]\nnote = \"elsewhere\"\n");
+    assert!(section_at(&dom(&source), offset_of(&source, "PipeSequence")).is_none());
+}
+
+#[test]
+fn removal_leaves_the_neighbours_tidy() {
+    let removed = edited(
+        LEGAL,
+        vec![section_removal(&dom(LEGAL), &review(LEGAL)).unwrap()],
+    );
+    assert!(removed.contains("description = \"A clause\"\n\n# Extracts clauses.\n[pipe.extract]"));
+
+    let extract = section_at(&dom(LEGAL), offset_of(LEGAL, "PipeExtract")).unwrap();
+    let removed = edited(LEGAL, vec![section_removal(&dom(LEGAL), &extract).unwrap()]);
+    assert!(
+        removed.ends_with("note = \"kept with the pipe\"\n"),
+        "{removed}"
+    );
+}
+
+#[test]
+fn moved_text_is_requalified_for_another_domain() {
+    let moved = moved_text(
+        &dom(LEGAL),
+        &review(LEGAL),
+        Some("audit"),
+        NativeConcepts::embedded(),
+    )
+    .unwrap();
+    assert!(moved.starts_with("# Reviews a contract."));
+    // What stays behind gains the source domain; the pipe's reference to
+    // itself and native concepts stay bare.
+    assert!(moved.contains(r#"inputs = { contract = "legal.Contract" }"#));
+    assert!(moved.contains(r#"output = "Text""#));
+    assert!(moved.contains(r#"steps = [{ pipe = "legal.extract" }, { pipe = "review" }]"#));
+    assert!(moved.ends_with("note = \"kept with the pipe\"\n"));
+
+    let same_domain = moved_text(
+        &dom(LEGAL),
+        &review(LEGAL),
+        Some("legal"),
+        NativeConcepts::embedded(),
+    )
+    .unwrap();
+    assert!(same_domain.contains(r#"{ pipe = "extract" }"#));
+}
+
+#[test]
+fn references_follow_the_moved_definition() {
+    let extract = section_at(&dom(LEGAL), offset_of(LEGAL, "PipeExtract")).unwrap();
+    let source_edits = requalify_references(
+        &dom(LEGAL),
+        RefKind::Pipe,
+        "extract",
+        "legal",
+        "audit",
+        Some(extract.range),
+    );
+    assert!(edited(LEGAL, source_edits)
+        .contains(r#"steps = [{ pipe = "audit.extract" }, { pipe = "review" }]"#));
+
+    let audit = "domain = \"audit\"\nmain_pipe = \"legal.extract\"\n";
+    let target_edits = requalify_references(
+        &dom(audit),
+        RefKind::Pipe,
+        "extract",
+        "legal",
+        "audit",
+        None,
+    );
+    assert_eq!(
+        edited(audit, target_edits),
+        "domain = \"audit\"\nmain_pipe = \"extract\"\n"
+    );
+
+    let concepts = "domain = \"other\"\n\n[pipe.p]\ntype = \"PipeLLM\"\ndescription = \"p\"\noutput = \"legal.Clause[]\"\n";
+    let edits = requalify_references(
+        &dom(concepts),
+        RefKind::Concept,
+        "Clause",
+        "legal",
+        "audit",
+        None,
+    );
+    assert!(edited(concepts, edits).contains(r#"output = "audit.Clause[]""#));
+}
+
+#[test]
+fn appended_sections_are_separated_by_a_blank_line() {
+    let target = "domain = \"audit\"";
+    let append = append_section(&dom(target), "[pipe.x]\n").unwrap();
+    assert_eq!(append.range, TextRange::empty(TextSize::of(target)));
+    assert_eq!(append.new_text, "\n\n[pipe.x]\n");
+}
+
+#[test]
+fn new_files_are_named_in_snake_case() {
+    let concept = section_at(&dom(LEGAL), offset_of(LEGAL, "A clause")).unwrap();
+    assert_eq!(new_file_name(&concept), "clause.mthds");
+    assert_eq!(new_file_name(&review(LEGAL)), "review.mthds");
+}
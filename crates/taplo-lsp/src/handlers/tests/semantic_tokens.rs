@@ -0,0 +1,238 @@
+use crate::handlers::semantic_tokens::{mthds_tokens, TokenModifier, TokenType};
+use pipelex_common::tools::{bundle::BundleIndex, native_concepts::NativeConcepts};
+use taplo::parser::parse;
+
+/// The tokens of `source` as `(text, type, modifiers)`, in document order.
+fn tokens(source: &str, siblings: &[BundleIndex]) -> Vec<(String, TokenType, Vec<TokenModifier>)> {
+    mthds_tokens(
+        &parse(source).into_dom(),
+        siblings,
+        NativeConcepts::embedded(),
+    )
+    .into_iter()
+    .map(|token| (source[token.range].to_string(), token.ty, token.modifiers))
+    .collect()
+}
+
+fn of_type(source: &str, ty: TokenType) -> Vec<(String, Vec<TokenModifier>)> {
+    tokens(source, &[])
+        .into_iter()
+        .filter(|(_, token_type, _)| *token_type == ty)
+        .map(|(text, _, modifiers)| (text, modifiers))
+        .collect()
+}
+
+const BUNDLE: &str = r#"domain = "legal"
+main_pipe = "review"
+
+[concept]
+Contract = "A signed agreement"
+
+[pipe.review]
+type = "PipeSequence"
+inputs = { contract = "legal.Contract", pages = "Page[]" }
+output = "Clause[5]"
+steps = [
+    { pipe = "extract", result = "clauses" },
+    { pipe = "missing", result = "other" },
+    { pipe = "tax.compute", result = "tax" },
+]
+
+[pipe.extract]
+type = "PipeLLM"
+inputs = { contract = "Contract" }
+output = "native.Text"
+model = "$writing-factual"
+prompt = "Extract clauses from @contract for @reader"
+"#;
+
+#[test]
+fn splits_references_into_domain_name_and_multiplicity() {
+    let all = tokens(BUNDLE, &[]);
+    let line: Vec<_> = all
+        .iter()
+        .filter(|(text, ..)| ["legal", "Contract", "Page", "[]"].contains(&text.as_str()))
+        .map(|(text, ty, _)| (text.as_str(), *ty))
+        .collect();
+    assert_eq!(
+        line,
+        [
+            ("legal", TokenType::MthdsDomain),
+            ("Contract", TokenType::MthdsConcept),
+            ("Page", TokenType::MthdsConcept),
+            ("[]", TokenType::MthdsMultiplicity),
+            ("Contract", TokenType::MthdsConcept),
+        ]
+    );
+    assert!(all.contains(&("[5]".to_string(), TokenType::MthdsMultiplicity, Vec::new())));
+}
+
+#[test]
+fn marks_native_concepts() {
+    let concepts = of_type(BUNDLE, TokenType::MthdsConcept);
+    let native = vec![TokenModifier::Native, TokenModifier::DefaultLibrary];
+    assert!(concepts.contains(&("Page".to_string(), native.clone())));
+    assert!(concepts.contains(&("Text".to_string(), native.clone())));
+    assert!(concepts.contains(&("Contract".to_string(), Vec::new())));
+
+    let domains = of_type(BUNDLE, TokenType::MthdsDomain);
+    assert!(domains.contains(&("native".to_string(), native)));
+}
+
+#[test]
+fn marks_references_the_bundle_does_not_define() {
+    let pipes = of_type(BUNDLE, TokenType::MthdsPipeName);
+    assert_eq!(
+        pipes,
+        [
+            ("review".to_string(), Vec::new()),
+            ("extract".to_string(), Vec::new()),
+            ("missing".to_string(), vec![TokenModifier::Unresolved]),
+            ("compute".to_string(), Vec::new()),
+        ],
+        "references into other domains are not checked"
+    );
+    assert!(of_type(BUNDLE, TokenType::MthdsConcept)
+        .contains(&("Clause".to_string(), vec![TokenModifier::Unresolved])));
+}
+
+#[test]
+fn resolves_references_against_sibling_files() {
+    let sibling = parse(
+        r#"domain = "legal"
+
+[concept]
+Clause = "A clause"
+
+[pipe.missing]
+type = "PipeLLM"
+output = "Text"
+prompt = "Hello"
+"#,
+    )
+    .into_dom();
+    let all = tokens(BUNDLE, &[BundleIndex::from_dom(&sibling)]);
+    let unresolved: Vec<_> = all
+        .iter()
+        .filter(|(_, _, modifiers)| modifiers.contains(&TokenModifier::Unresolved))
+        .map(|(text, ..)| text.as_str())
+        .collect();
+    assert_eq!(unresolved, ["@reader"]);
+    assert!(all.contains(&("missing".to_string(), TokenType::MthdsPipeName, Vec::new())));
+    assert!(all.contains(&("Clause".to_string(), TokenType::MthdsConcept, Vec::new())));
+}
+
+#[test]
+fn marks_undeclared_template_variables() {
+    assert_eq!(
+        of_type(BUNDLE, TokenType::MthdsDataVariable),
+        [
+            ("@contract".to_string(), Vec::new()),
+            ("@reader".to_string(), vec![TokenModifier::Unresolved]),
+        ]
+    );
+}
+
+#[test]
+fn tokenizes_jinja_delimiters_keywords_and_inputs() {
+    let source = r#"domain = "d"
+
+[pipe.list]
+type = "PipeLLM"
+inputs = { items = "Item[]", doc = "Document" }
+output = "Text"
+prompt = """
+{# items first #}
+{%- for item in items %}- {{ item.name }} of {{ doc.title | upper }}{% endfor -%}
+"""
+"#;
+    assert_eq!(
+        of_type(source, TokenType::MthdsJinjaDelimiter)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect::<Vec<_>>(),
+        ["{#", "#}", "{%-", "%}", "{{", "}}", "{{", "}}", "{%", "-%}"]
+    );
+    assert_eq!(
+        of_type(source, TokenType::MthdsJinjaKeyword)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect::<Vec<_>>(),
+        ["for", "in", "endfor"]
+    );
+    assert_eq!(
+        of_type(source, TokenType::MthdsDataVariable)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect::<Vec<_>>(),
+        ["items", "doc.title"],
+        "loop variables and filters are not inputs"
+    );
+}
+
+#[test]
+fn maps_template_tokens_through_escapes_and_all_template_fields() {
+    let source = r#"domain = "d"
+
+[pipe.pick]
+type = "PipeCondition"
+inputs = { score = "Number" }
+output = "Text"
+expression_template = "{{ \"high\" if score > limit }}{% raw %}{{ score }}{% endraw %}"
+"#;
+    assert_eq!(
+        of_type(source, TokenType::MthdsDataVariable),
+        [
+            ("score".to_string(), Vec::new()),
+            ("limit".to_string(), vec![TokenModifier::Unresolved]),
+        ],
+        "a raw block holds no variables"
+    );
+    assert_eq!(
+        of_type(source, TokenType::MthdsJinjaKeyword)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect::<Vec<_>>(),
+        ["if", "raw", "endraw"]
+    );
+}
+
+#[test]
+fn marks_model_references_by_prefix() {
+    let source = r##"domain = "d"
+
+[pipe.a]
+type = "PipeLLM"
+output = "Text"
+model = "@best-claude"
+model_to_structure = { model = "~fallbacks", temperature = 0.2 }
+prompt = "Hi"
+
+[pipe.b]
+type = "PipeImgGen"
+output = "Image"
+model = "#gpt-image"
+
+[pipe.c]
+type = "PipeLLM"
+output = "Text"
+model = "$writing-factual"
+prompt = "Hi"
+
+[pipe.d]
+type = "PipeLLM"
+output = "Text"
+model = "claude-4.6-opus"
+prompt = "Hi"
+"##;
+    assert_eq!(
+        of_type(source, TokenType::MthdsModelRef),
+        [
+            ("@best-claude".to_string(), vec![TokenModifier::Alias]),
+            ("~fallbacks".to_string(), vec![TokenModifier::Waterfall]),
+            ("#gpt-image".to_string(), vec![TokenModifier::Handle]),
+            ("$writing-factual".to_string(), vec![TokenModifier::Preset]),
+            ("claude-4.6-opus".to_string(), Vec::new()),
+        ]
+    );
+}
@@ -0,0 +1,120 @@
+use super::{offset_inside_string_after, PARALLEL, SEQUENCE};
+use crate::handlers::signature_help::{mthds_signature_at, signature_information};
+use lsp_types::ParameterLabel;
+use taplo::parser::parse;
+
+#[test]
+fn shows_the_callee_inputs_and_output_inside_a_step() {
+    let dom = parse(SEQUENCE).into_dom();
+    let offset = offset_inside_string_after(SEQUENCE, "\"create_prompt\"", "result = \"").into();
+    let signature = mthds_signature_at(&dom, offset).expect("signature inside the step");
+    let information = signature_information(&signature);
+
+    assert_eq!(
+        information.label,
+        "create_prompt(topic: Text, style: Text) -> Prompt"
+    );
+    let parameters = information.parameters.unwrap();
+    let labels: Vec<_> = parameters
+        .iter()
+        .map(|p| match p.label {
+            ParameterLabel::LabelOffsets([start, end]) => {
+                &information.label[start as usize..end as usize]
+            }
+            ParameterLabel::Simple(_) => unreachable!(),
+        })
+        .collect();
+    assert_eq!(labels, ["topic: Text", "style: Text"]);
+    assert!(information.documentation.is_some());
+}
+
+#[test]
+fn highlights_the_first_input_the_step_does_not_see() {
+    let dom = parse(SEQUENCE).into_dom();
+    let offset = offset_inside_string_after(SEQUENCE, "\"create_prompt\"", "result = \"").into();
+    let signature = mthds_signature_at(&dom, offset).unwrap();
+    assert_eq!(signature.active, Some(1), "`style` is not provided");
+}
+
+#[test]
+fn earlier_results_count_as_seen() {
+    let dom = parse(SEQUENCE).into_dom();
+    let offset = offset_inside_string_after(SEQUENCE, "\"draft\"", "result = \"").into();
+    let signature = mthds_signature_at(&dom, offset).unwrap();
+    assert_eq!(signature.pipe, "draft");
+    assert_eq!(signature.active, None);
+}
+
+#[test]
+fn highlights_the_item_input_on_batch_entries() {
+    let dom = parse(SEQUENCE).into_dom();
+    let offset = offset_inside_string_after(SEQUENCE, "\"describe_page\"", "\"page\"").into();
+    let signature = mthds_signature_at(&dom, offset).unwrap();
+    assert_eq!(signature.pipe, "describe_page");
+    assert_eq!(signature.active, Some(1));
+}
+
+#[test]
+fn no_signature_outside_calls() {
+    let dom = parse(SEQUENCE).into_dom();
+    assert!(mthds_signature_at(
+        &dom,
+        offset_inside_string_after(SEQUENCE, "[pipe.write]", "output = \"").into()
+    )
+    .is_none());
+    assert!(mthds_signature_at(
+        &dom,
+        offset_inside_string_after(SEQUENCE, "[pipe.draft]", "prompt = \"").into()
+    )
+    .is_none());
+}
+
+#[test]
+fn applies_to_parallel_branches() {
+    let dom = parse(PARALLEL).into_dom();
+    let signature = mthds_signature_at(
+        &dom,
+        offset_inside_string_after(PARALLEL, "branches", "result = \"").into(),
+    )
+    .unwrap();
+    assert_eq!(
+        signature_information(&signature).label,
+        "shorten(text: Text) -> Text"
+    );
+    assert_eq!(signature.active, None);
+}
+
+#[test]
+fn applies_to_pipe_batch_entries() {
+    let source = r#"domain = "d"
+
+[pipe.all]
+type = "PipeBatch"
+inputs = { pages = "Page[]" }
+output = "Description[]"
+branch_pipe_code = "describe"
+input_list_name = "pages"
+input_item_name = "page"
+
+[pipe.describe]
+type = "PipeLLM"
+inputs = { page = "Page" }
+output = "Description"
+prompt = "Describe $page"
+"#;
+    let dom = parse(source).into_dom();
+    let on_item = mthds_signature_at(
+        &dom,
+        offset_inside_string_after(source, "input_item_name", "\"page\"").into(),
+    )
+    .expect("signature on input_item_name");
+    assert_eq!(on_item.pipe, "describe");
+    assert_eq!(on_item.active, Some(0));
+
+    let on_branch = mthds_signature_at(
+        &dom,
+        offset_inside_string_after(source, "branch_pipe_code", "\"describe\"").into(),
+    )
+    .expect("signature on branch_pipe_code");
+    assert_eq!(on_branch.active, None, "the item is provided by the batch");
+}
@@ -49,6 +49,9 @@ pub fn create_server<E: Environment>() -> Server<World<E>> {
         .on_request::<request::SemanticTokensFullRequest, _>(handlers::semantic_tokens)
         .on_request::<request::PrepareRenameRequest, _>(handlers::prepare_rename)
         .on_request::<request::Rename, _>(handlers::rename)
+        .on_request::<request::CodeActionRequest, _>(handlers::code_action)
+        .on_request::<request::InlayHintRequest, _>(handlers::inlay_hints)
+        .on_request::<request::SignatureHelpRequest, _>(handlers::signature_help)
         .on_notification::<notification::Initialized, _>(handlers::initialized)
         .on_notification::<notification::DidOpenTextDocument, _>(handlers::document_open)
         .on_notification::<notification::DidChangeTextDocument, _>(handlers::document_change)
@@ -61,6 +64,8 @@ pub fn create_server<E: Environment>() -> Server<World<E>> {
         .on_request::<lsp_ext::request::ConvertToTomlRequest, _>(handlers::convert_to_toml)
         .on_request::<lsp_ext::request::ListSchemasRequest, _>(handlers::list_schemas)
         .on_request::<lsp_ext::request::AssociatedSchemaRequest, _>(handlers::associated_schema)
+        .on_request::<lsp_ext::request::PipeFixtureRequest, _>(handlers::pipe_fixture)
+        .on_request::<lsp_ext::request::RenderPromptRequest, _>(handlers::render_prompt)
         .on_notification::<lsp_ext::notification::AssociateSchema, _>(handlers::associate_schema)
         .build()
 }
@@ -1,4 +1,4 @@
-use lsp_types::{request::Request, Url};
+use lsp_types::{request::Request, Range, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -102,3 +102,84 @@ impl Request for AssociatedSchemaRequest {
     type Result = AssociatedSchemaResponse;
     const METHOD: &'static str = "taplo/associatedSchema";
 }
+
+/// A sample input fixture for a pipe of an MTHDS document.
+pub enum PipeFixtureRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipeFixtureParams {
+    pub document_uri: Url,
+    /// The code of the pipe.
+    pub pipe: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipeFixtureResponse {
+    /// The working memory holding the pipe's inputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixture: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Request for PipeFixtureRequest {
+    type Params = PipeFixtureParams;
+    type Result = PipeFixtureResponse;
+    const METHOD: &'static str = "taplo/pipeFixture";
+}
+
+/// The prompts of a pipe of an MTHDS document, rendered with sample inputs.
+pub enum RenderPromptRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderPromptParams {
+    pub document_uri: Url,
+    /// The code of the pipe.
+    pub pipe: String,
+    /// The working memory to render with, the placeholder fixture of the
+    /// pipe if omitted.
+    #[serde(default)]
+    pub inputs: Option<Value>,
+    /// Overrides the tag style of the pipe, e.g. `xml`.
+    #[serde(default)]
+    pub tag_style: Option<String>,
+    /// Overrides the text format of the pipe, e.g. `markdown`.
+    #[serde(default)]
+    pub text_format: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderPromptResponse {
+    pub prompts: Vec<RenderedPrompt>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderedPrompt {
+    /// The pipe field holding the template, e.g. `prompt`.
+    pub field: String,
+    pub text: String,
+    pub errors: Vec<RenderError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderError {
+    pub message: String,
+    /// The range in the document.
+    pub range: Range,
+}
+
+impl Request for RenderPromptRequest {
+    type Params = RenderPromptParams;
+    type Result = RenderPromptResponse;
+    const METHOD: &'static str = "taplo/renderPrompt";
+}
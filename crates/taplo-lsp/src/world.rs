@@ -7,15 +7,19 @@ use arc_swap::ArcSwap;
 use lsp_async_stub::{rpc, util::Mapper, Context, RequestWriter};
 use lsp_types::Url;
 use once_cell::sync::Lazy;
+use pipelex_common::tools::{
+    models::ModelDeck, native_concepts::NativeConcepts, rules::unknown_rule_ids,
+};
 use regex::Regex;
 use serde_json::json;
-use std::{str, sync::Arc, time::Duration};
+use std::{path::Path, str, sync::Arc, time::Duration};
 use taplo::{dom::Node, parser::Parse};
 use taplo_common::{
-    config::Config,
+    config::{Config, LintOptions},
     environment::Environment,
     schema::{
         associations::{priority, source, AssociationRule, SchemaAssociation},
+        builtins::{MthdsSchemaVersion, MTHDS_VERSION},
         Schemas,
     },
     AsyncRwLock, HashMap, IndexMap,
@@ -112,6 +116,10 @@ pub struct WorkspaceState<E: Environment> {
     pub(crate) taplo_config: Config,
     pub(crate) schemas: Schemas<E>,
     pub(crate) config: LspConfig,
+    /// The model deck of `taplo_config`, read again with it.
+    pub(crate) models: Arc<ModelDeck>,
+    /// The native concept registry of `taplo_config`, read again with it.
+    pub(crate) natives: Arc<NativeConcepts>,
 }
 
 impl<E: Environment> WorkspaceState<E> {
@@ -133,6 +141,8 @@ impl<E: Environment> WorkspaceState<E> {
             taplo_config: Default::default(),
             schemas: Schemas::new(env, Some(client)),
             config: LspConfig::default(),
+            models: Arc::default(),
+            natives: Arc::default(),
         }
     }
 }
@@ -144,6 +154,14 @@ impl<E: Environment> WorkspaceState<E> {
             .ok_or_else(rpc::Error::invalid_params)
     }
 
+    /// The MTHDS lint rule levels for a document: the config file's
+    /// `[lint.rules]` and matching `[[rule]]`s, then the editor's `lint` settings.
+    pub(crate) fn lint_options(&self, path: &Path) -> LintOptions {
+        let mut options = self.taplo_config.lint_options(path);
+        options.update(&self.config.lint);
+        options
+    }
+
     #[tracing::instrument(skip_all, fields(%self.root))]
     pub(crate) async fn initialize(
         &mut self,
@@ -166,12 +184,13 @@ impl<E: Environment> WorkspaceState<E> {
         self.schemas.clear_caches();
 
         // Clear config/catalog/LSP associations but keep document-level ones
-        // (directives, $schema) and manual associations to avoid re-parsing
+        // (directives, $schema, mthds_version) and manual associations to avoid re-parsing
         // all open documents and losing programmatic schema bindings.
         self.schemas.associations().retain(|(_, assoc)| {
             let source_val = assoc.meta["source"].as_str().unwrap_or("");
             source_val == source::DIRECTIVE
                 || source_val == source::SCHEMA_FIELD
+                || source_val == source::MTHDS_VERSION
                 || source_val == source::MANUAL
         });
         self.schemas.associations().add_builtins();
@@ -184,6 +203,18 @@ impl<E: Environment> WorkspaceState<E> {
         self.schemas
             .associations()
             .add_from_config(&self.taplo_config);
+        if let Some(version) = self
+            .taplo_config
+            .mthds_version()
+            .and_then(MthdsSchemaVersion::find)
+            .filter(|version| version.deprecated)
+        {
+            tracing::warn!(
+                version = version.version,
+                latest = MTHDS_VERSION,
+                "configured MTHDS version is deprecated"
+            );
+        }
 
         for (pattern, schema_url) in &self.config.schema.associations {
             let pattern = match Regex::new(pattern) {
@@ -271,6 +302,21 @@ impl<E: Environment> WorkspaceState<E> {
         self.taplo_config.rule.extend(self.config.rules.clone());
         self.taplo_config.prepare(env, &root_path)?;
 
+        for options in std::iter::once(&self.taplo_config.global_options)
+            .chain(self.taplo_config.rule.iter().map(|rule| &rule.options))
+            .filter_map(|options| options.lint.as_ref())
+            .chain(std::iter::once(&self.config.lint))
+        {
+            for id in unknown_rule_ids(options) {
+                tracing::warn!(rule = id, "unknown lint rule in configuration");
+            }
+        }
+
+        self.models = Arc::new(ModelDeck::load(env, &self.taplo_config.model_deck_paths()).await);
+        self.natives = Arc::new(
+            NativeConcepts::load(env, self.taplo_config.native_concepts_path().as_deref()).await,
+        );
+
         tracing::debug!("using config: {:#?}", self.taplo_config);
 
         Ok(())
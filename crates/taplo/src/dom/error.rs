@@ -21,6 +21,20 @@ pub enum Error {
     Query(#[from] QueryError),
 }
 
+impl Error {
+    /// A stable, machine-readable code for the error variant.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::UnexpectedSyntax { .. } => "semantic/unexpected-syntax",
+            Error::InvalidEscapeSequence { .. } => "semantic/invalid-escape-sequence",
+            Error::ConflictingKeys { .. } => "semantic/conflicting-keys",
+            Error::ExpectedTable { .. } => "semantic/expected-table",
+            Error::ExpectedArrayOfTables { .. } => "semantic/expected-array-of-tables",
+            Error::Query(_) => "semantic/invalid-query",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Error)]
 pub enum QueryError {
     #[error("the key or index was not found")]
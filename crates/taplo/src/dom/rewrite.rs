@@ -48,6 +48,14 @@ impl Rewrite {
                     }
                 }
             }
+            Patch::Replace { range, to } => {
+                self.check_overlap(range)?;
+
+                self.patches.push(PendingPatch {
+                    range,
+                    kind: PendingPatchKind::Replace(to),
+                })
+            }
         }
 
         self.patches
@@ -60,15 +68,19 @@ impl Rewrite {
         &self.patches
     }
 
-    fn check_overlap(&self, range: TextRange) -> Result<(), Error> {
-        for patch in self.patches() {
-            if patch.range.contains_range(range)
+    /// Whether a patch of `range` would overlap one of the existing patches.
+    pub fn overlaps(&self, range: TextRange) -> bool {
+        self.patches().iter().any(|patch| {
+            patch.range.contains_range(range)
                 || range.contains_range(patch.range)
                 || patch.range.contains(range.start())
                 || patch.range.contains(range.end())
-            {
-                return Err(Error::Overlap);
-            }
+        })
+    }
+
+    fn check_overlap(&self, range: TextRange) -> Result<(), Error> {
+        if self.overlaps(range) {
+            return Err(Error::Overlap);
         }
 
         Ok(())
@@ -82,6 +94,14 @@ impl Rewrite {
             to: to.into(),
         })
     }
+
+    /// Replace the text in `range` with `to`; an empty range inserts `to`.
+    pub fn replace_range(&mut self, range: TextRange, to: &str) -> Result<&mut Self, Error> {
+        self.add(Patch::Replace {
+            range,
+            to: to.into(),
+        })
+    }
 }
 
 impl core::fmt::Display for Rewrite {
@@ -103,6 +123,7 @@ impl core::fmt::Display for Rewrite {
 #[derive(Debug)]
 pub enum Patch {
     RenameKeys { key: Arc<str>, to: Arc<str> },
+    Replace { range: TextRange, to: Arc<str> },
 }
 
 #[derive(Debug)]
@@ -197,4 +218,21 @@ mod tests {
 
         assert_eq!(expected_toml, patches.to_string());
     }
+
+    #[test]
+    fn replace_range_rejects_overlaps() {
+        use rowan::{TextRange, TextSize};
+
+        let root = parse("a = 1\nb = 2\n").into_dom();
+        let range =
+            |start: u32, end: u32| TextRange::new(TextSize::from(start), TextSize::from(end));
+
+        let mut patches = Rewrite::new(root).unwrap();
+
+        patches.replace_range(range(4, 5), "10").unwrap();
+        patches.replace_range(range(6, 6), "# b\n").unwrap();
+        assert!(patches.replace_range(range(4, 5), "11").is_err());
+
+        assert_eq!("a = 10\n# b\nb = 2\n", patches.to_string());
+    }
 }
@@ -22,6 +22,11 @@ pub struct Error {
     pub message: String,
 }
 
+impl Error {
+    /// The stable diagnostic code shared by every syntax error.
+    pub const CODE: &'static str = "syntax/parse-error";
+}
+
 impl core::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} ({:?})", self.message, self.range)
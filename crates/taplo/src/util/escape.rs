@@ -116,6 +116,49 @@ pub fn unescape(s: &str) -> Result<String, usize> {
     Ok(new_s + lexer.remainder())
 }
 
+/// Same as [unescape], but also returns where each byte of the unescaped
+/// string comes from: the offset in `s` of the byte itself, or of the escape
+/// sequence that produced it. The offsets have one more entry than the
+/// string, the length of `s`, so that the end of any range can be mapped too.
+///
+/// If it fails, the index of failure is returned.
+pub fn unescape_with_offsets(s: &str) -> Result<(String, Vec<usize>), usize> {
+    let mut new_s = String::with_capacity(s.len());
+    let mut offsets = Vec::with_capacity(s.len() + 1);
+    let mut lexer: Lexer<Escape> = Lexer::new(s);
+
+    while let Some(t) = lexer.next() {
+        let start = lexer.span().start;
+        let unescaped = match t {
+            Backspace => '\u{0008}',
+            Tab => '\u{0009}',
+            LineFeed => '\u{000A}',
+            FormFeed => '\u{000C}',
+            CarriageReturn => '\u{000D}',
+            Quote => '\u{0022}',
+            Backslash => '\u{005C}',
+            Newline => continue,
+            Unicode | UnicodeLarge => std::char::from_u32(
+                u32::from_str_radix(&lexer.slice()[2..], 16).map_err(|_| start)?,
+            )
+            .ok_or(start)?,
+            Unknown => return Err(lexer.span().end),
+            UnEscaped => {
+                new_s += lexer.slice();
+                offsets.extend(lexer.span());
+                continue;
+            }
+        };
+        new_s.push(unescaped);
+        offsets.extend(std::iter::repeat(start).take(unescaped.len_utf8()));
+    }
+
+    let remainder_start = s.len() - lexer.remainder().len();
+    new_s += lexer.remainder();
+    offsets.extend(remainder_start..=s.len());
+    Ok((new_s, offsets))
+}
+
 /// Same as unescape, but doesn't create a new
 /// unescaped string, and returns all invalid escape indices.
 pub fn check_escape(s: &str) -> Result<(), Vec<usize>> {
@@ -175,3 +218,26 @@ pub fn check_escape(s: &str) -> Result<(), Vec<usize>> {
         Err(invalid)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_with_offsets_maps_escapes_to_their_start() {
+        let source = r#"a\tb\u00e9c"#;
+        let (value, offsets) = unescape_with_offsets(source).unwrap();
+        assert_eq!(value, unescape(source).unwrap());
+        assert_eq!(value, "a\tb\u{e9}c");
+        assert_eq!(offsets, [0, 1, 3, 4, 4, 10, 11]);
+    }
+
+    #[test]
+    fn unescape_with_offsets_skips_line_ending_backslashes() {
+        let source = "one \\\n   two";
+        let (value, offsets) = unescape_with_offsets(source).unwrap();
+        assert_eq!(value, unescape(source).unwrap());
+        assert_eq!(&source[offsets[value.find('t').unwrap()]..], "two");
+        assert_eq!(offsets.last(), Some(&source.len()));
+    }
+}
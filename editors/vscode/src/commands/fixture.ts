@@ -0,0 +1,70 @@
+import * as vscode from "vscode";
+import { BaseLanguageClient } from "vscode-languageclient";
+import { findPipeHeaders } from "../pipelex/pipeTestProvider";
+import { findMainPipeName } from "../pipelex/terminalRunner";
+
+export function register(ctx: vscode.ExtensionContext, c: BaseLanguageClient) {
+  ctx.subscriptions.push(
+    vscode.commands.registerTextEditorCommand(
+      "pipelex.generateInputs",
+      async editor => {
+        const document = editor?.document;
+        if (!document || document.uri.scheme !== "file") {
+          return;
+        }
+
+        const text = document.getText();
+        const mainPipe = findMainPipeName(text);
+        const pipes = findPipeHeaders(text).map(header => header.name);
+        if (pipes.length === 0) {
+          vscode.window.showWarningMessage("This bundle defines no pipe.");
+          return;
+        }
+        // The main pipe first, as the one usually run.
+        pipes.sort((a, b) => Number(b === mainPipe) - Number(a === mainPipe));
+
+        const pipe =
+          pipes.length === 1
+            ? pipes[0]
+            : await vscode.window.showQuickPick(pipes, {
+                placeHolder: "Pipe to generate inputs for",
+              });
+        if (!pipe) {
+          return;
+        }
+
+        const res = await c.sendRequest<{ fixture?: unknown; error?: string }>(
+          "taplo/pipeFixture",
+          { documentUri: document.uri.toString(), pipe }
+        );
+        if (res.error || res.fixture === undefined) {
+          vscode.window.showErrorMessage(
+            `Could not generate inputs: ${res.error ?? "no fixture"}`
+          );
+          return;
+        }
+
+        // `inputs.json` next to the bundle is what "Run Bundle" passes to Pipelex.
+        const target = vscode.Uri.joinPath(document.uri, "..", "inputs.json");
+        const exists = await vscode.workspace.fs.stat(target).then(
+          () => true,
+          () => false
+        );
+        if (exists) {
+          const overwrite = await vscode.window.showWarningMessage(
+            "inputs.json already exists. Overwrite it?",
+            { modal: true },
+            "Overwrite"
+          );
+          if (overwrite !== "Overwrite") {
+            return;
+          }
+        }
+
+        const content = JSON.stringify(res.fixture, null, 2) + "\n";
+        await vscode.workspace.fs.writeFile(target, new TextEncoder().encode(content));
+        await vscode.window.showTextDocument(target);
+      }
+    )
+  );
+}
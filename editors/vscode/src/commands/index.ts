@@ -1,5 +1,6 @@
 import * as vscode from "vscode";
 import * as conversionCommands from "./conversion";
+import * as fixtureCommands from "./fixture";
 import * as schemaCommands from "./schema";
 import { BaseLanguageClient } from "vscode-languageclient";
 
@@ -9,5 +10,6 @@ export function registerCommands(
   c: BaseLanguageClient
 ) {
   conversionCommands.register(ctx, c);
+  fixtureCommands.register(ctx, c);
   schemaCommands.register(ctx, c);
 }
@@ -13,7 +13,7 @@
  * import forms):
  *
  * ```js
- * import { initialize, lintMthds, formatMthds } from "@pipelex/tools-wasm";
+ * import { initialize, lintMthds, formatMthds, conceptSchema } from "@pipelex/tools-wasm";
  *
  * await initialize();
  * const { diagnostics } = lintMthds(mthdsSource);
@@ -28,7 +28,7 @@ import loadPipelexTools from "../../../crates/pipelex-tools-wasm/Cargo.toml";
 // three in sync.
 
 /** Which analysis produced a `Diagnostic` — mirror of `pipelex-tools`' closed kind set. */
-export type DiagnosticKind = "syntax" | "semantic" | "schema";
+export type DiagnosticKind = "syntax" | "semantic" | "schema" | "rule";
 
 /** Source span of a `Diagnostic` — byte offsets plus 1-based line/column coordinates. */
 export interface DiagnosticRange {
@@ -40,17 +40,42 @@ export interface DiagnosticRange {
   end_col: number;
 }
 
+/** Replace the text in `range` with `new_text` — an empty range inserts. */
+export interface TextEdit {
+  range: DiagnosticRange;
+  new_text: string;
+}
+
+/**
+ * A machine-applicable fix of a `Diagnostic` — mirror of pipelex's
+ * `DiagnosticFix`. `safe` fixes are the ones `plxt lint --fix` applies; its
+ * `edits` never overlap and are in document order.
+ */
+export interface Fix {
+  title: string;
+  safe: boolean;
+  edits: TextEdit[];
+}
+
 /**
  * One structured lint/format diagnostic — mirror of pipelex's `Diagnostic`.
- * `severity` stays an open string (the engine does not close the vocabulary);
- * `location` and `range` are `null` when the analysis cannot attribute a span.
+ * `severity` stays an open string (the engine does not close the vocabulary;
+ * today it is `"error"`, `"warning"` or `"info"`, only `"rule"` diagnostics
+ * and `"schema/deprecated"` warnings going below `"error"`). `code` is a stable code prefixed by the stage (e.g.
+ * `"syntax/parse-error"`, `"schema/required"`, `"mthds/undefined-pipe"`) and
+ * `code_description` the URL of its documentation; `location` and `range` are
+ * `null` when the analysis cannot attribute a span, and `fixes` is empty unless
+ * the diagnostic has machine-applicable fixes.
  */
 export interface Diagnostic {
   kind: DiagnosticKind;
   severity: string;
+  code: string;
+  code_description: string | null;
   message: string;
   location: string | null;
   range: DiagnosticRange | null;
+  fixes: Fix[];
 }
 
 /** Result of {@link lintMthds} — the diagnostics of one linted `.mthds` file (empty == clean). */
@@ -121,3 +146,16 @@ export function formatMthds(
 ): FormatResult {
   return loaded().format_mthds(content, options);
 }
+
+/**
+ * Translate the concepts of one MTHDS document into a JSON Schema (draft
+ * 2020-12) document with one `$defs` entry per concept. With `concept`, only
+ * that concept and the concepts it references are exported, and the schema's
+ * `$ref` points at it. Throws on a syntax error or an unknown concept.
+ */
+export function conceptSchema(
+  content: string,
+  concept?: string
+): Record<string, unknown> {
+  return loaded().concept_schema(content, concept);
+}
@@ -24,6 +24,7 @@ const tools = require("../dist/index.js") as {
   initialize(): Promise<void>;
   lintMthds(content: string): LintResult;
   formatMthds(content: string, options?: FormatMthdsOptions): FormatResult;
+  conceptSchema(content: string, concept?: string): Record<string, any>;
 };
 
 /** Repo root, resolved from this package dir (`<root>/js/tools-wasm`). */
@@ -90,6 +91,9 @@ describe("lint wire shape", () => {
       // location/range are part of the wire shape even when null — the
       // JSON-compatible serializer must not drop them as `undefined`.
       expect(Object.keys(diagnostic).sort()).toEqual([
+        "code",
+        "code_description",
+        "fixes",
         "kind",
         "location",
         "message",
@@ -99,11 +103,54 @@ describe("lint wire shape", () => {
     }
   });
 
+  it("reports MTHDS rule violations with their rule id", () => {
+    const content =
+      'domain = "d"\n\n[pipe.p]\ntype = "PipeLLM"\ndescription = "p"\noutput = "Summary"\n';
+    const { diagnostics } = tools.lintMthds(content);
+    expect(diagnostics).toHaveLength(1);
+    expect(diagnostics[0].kind).toBe("rule");
+    expect(diagnostics[0].code).toBe("mthds/undefined-concept");
+    expect(diagnostics[0].severity).toBe("warning");
+  });
+
+  it("honors plxt-ignore suppression comments", () => {
+    const content =
+      '# plxt-ignore-file: mthds/undefined-concept\ndomain = "d"\n\n[pipe.p]\ntype = "PipeLLM"\ndescription = "p"\noutput = "Summary"\n';
+    expect(tools.lintMthds(content).diagnostics).toEqual([]);
+  });
+
+  it("attaches a safe fix to a misspelled reference", () => {
+    const content =
+      'domain = "d"\n\n[concept.Summary]\ndescription = "s"\n\n[pipe.p]\ntype = "PipeLLM"\ndescription = "p"\noutput = "Sumary"\n';
+    const diagnostic = tools
+      .lintMthds(content)
+      .diagnostics.find((d) => d.code === "mthds/undefined-concept");
+    expect(diagnostic?.fixes).toEqual([
+      {
+        title: "Replace with `Summary`",
+        safe: true,
+        edits: [
+          {
+            range: expect.objectContaining({
+              start_offset: 105,
+              end_offset: 111,
+            }),
+            new_text: "Summary",
+          },
+        ],
+      },
+    ]);
+  });
+
   it("reports a syntax error with a range and a null location", () => {
     const { diagnostics } = tools.lintMthds("key = ");
     expect(diagnostics.length).toBeGreaterThan(0);
     const diagnostic: Diagnostic = diagnostics[0];
     expect(diagnostic.kind).toBe("syntax");
+    expect(diagnostic.code).toBe("syntax/parse-error");
+    expect(diagnostic.code_description).toMatch(
+      /diagnostic-codes\.md#syntaxparse-error$/
+    );
     expect(diagnostic.location).toBeNull();
     expect(Object.prototype.hasOwnProperty.call(diagnostic, "location")).toBe(
       true
@@ -123,6 +170,7 @@ describe("lint wire shape", () => {
     expect(diagnostics.length).toBeGreaterThan(0);
     for (const diagnostic of diagnostics) {
       expect(diagnostic.kind).toBe("semantic");
+      expect(diagnostic.code).toBe("semantic/conflicting-keys");
     }
   });
 
@@ -181,3 +229,31 @@ describe("format", () => {
     ).toThrow();
   });
 });
+
+describe("concept schema", () => {
+  const content =
+    'domain = "d"\n\n[concept.Greeting]\ndescription = "A greeting"\n\n[concept.Greeting.structure]\ntext = { type = "text", description = "The words", required = true }\n';
+
+  it("exports every concept of the document", () => {
+    const schema = tools.conceptSchema(content);
+    expect(schema.$schema).toBe("https://json-schema.org/draft/2020-12/schema");
+    expect(schema.$defs.Greeting).toEqual({
+      title: "Greeting",
+      description: "A greeting",
+      type: "object",
+      properties: { text: { type: "string", description: "The words" } },
+      required: ["text"],
+      additionalProperties: false,
+    });
+  });
+
+  it("exports a single concept by name", () => {
+    expect(tools.conceptSchema(content, "Greeting").$ref).toBe(
+      "#/$defs/Greeting"
+    );
+  });
+
+  it("throws on an unknown concept", () => {
+    expect(() => tools.conceptSchema(content, "Missing")).toThrow(/Missing/);
+  });
+});